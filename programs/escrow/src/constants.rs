@@ -1 +1,41 @@
-// We don't actually have any constants in this program
+// Longest submission (a hash or URI) a bounty claim can store
+pub const MAX_SUBMISSION_LENGTH: usize = 200;
+
+// Longest confirmation window a listing can have: a year
+pub const MAX_CONFIRMATION_WINDOW_SECS: i64 = 60 * 60 * 24 * 365;
+
+// Winner recorded for a draw, where each player gets their stake back.
+// Kept out of the range of player slots, which are numbered from 1
+pub const DRAW: u8 = u8::MAX;
+
+// Most players a game can have
+pub const MAX_PLAYERS: usize = 8;
+
+// Highest rake a game can take from the pot, in basis points
+pub const MAX_RAKE_BPS: u16 = 1_000;
+
+// Longest challenge period a game can have: a year
+pub const MAX_CHALLENGE_PERIOD_SECS: i64 = 60 * 60 * 24 * 365;
+
+// Starts every result attestation, so a signature over one can't be passed off as a signature
+// over any other message
+pub const RESULT_ATTESTATION_TAG: [u8; 25] = *b"escrow result attestation";
+
+// Most referees a game's quorum can have
+pub const MAX_REFEREES: usize = 5;
+
+// Longest a game matched from a queue can run for before it can be cancelled: a year
+pub const MAX_GAME_DURATION_SECS: i64 = 60 * 60 * 24 * 365;
+
+// Most players a matchmaking queue can hold at once
+pub const MAX_QUEUE_LENGTH: usize = 16;
+
+// Most rounds a series can record. A series needs enough room for every player
+// to win all but one of the rounds they need, before someone wins the deciding one
+pub const MAX_ROUNDS: usize = 16;
+
+// Elo rating every player profile starts with
+pub const STARTING_RATING: i32 = 1_200;
+
+// Most a player's Elo rating can move in one game
+pub const RATING_K_FACTOR: i64 = 32;
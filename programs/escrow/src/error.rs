@@ -47,4 +47,297 @@ pub enum ErrorCode {
 
     #[msg("Lamports transfer failed")]
     LamportsTransferFailed,
+
+    // Offer errors added after the duel errors, so existing error codes don't change
+    #[msg("Failed to return surplus tokens from vault")]
+    FailedSurplusTransfer,
+
+    #[msg("Receipt token is missing, invalid, or not held by the signer")]
+    InvalidReceipt,
+
+    #[msg("Only the maker, or the receipt holder for offers with a receipt, may refund this offer")]
+    NotOfferOwner,
+
+    #[msg("Proceeds account does not belong to the offer's owner")]
+    InvalidProceedsAccount,
+
+    // Loan errors
+    #[msg("Loan has already been funded")]
+    LoanAlreadyFunded,
+
+    #[msg("Loan has not been funded")]
+    LoanNotFunded,
+
+    #[msg("Loan is past its due date")]
+    LoanOverdue,
+
+    #[msg("Loan is not past its due date yet")]
+    LoanNotOverdue,
+
+    // Call option errors
+    #[msg("Option has already been sold")]
+    OptionAlreadySold,
+
+    #[msg("Option has expired")]
+    OptionExpired,
+
+    #[msg("Option has been sold and has not expired yet")]
+    OptionNotExpired,
+
+    // HTLC errors
+    #[msg("Preimage does not match the hashlock")]
+    InvalidPreimage,
+
+    #[msg("HTLC has already been claimed")]
+    HtlcAlreadyClaimed,
+
+    #[msg("HTLC has not been claimed")]
+    HtlcNotClaimed,
+
+    #[msg("HTLC timelock has passed")]
+    HtlcExpired,
+
+    #[msg("HTLC timelock has not passed yet")]
+    HtlcNotExpired,
+
+    // Stream errors
+    #[msg("Stream must start at or after the Unix epoch, end after it starts, and end after the current time")]
+    InvalidStreamSchedule,
+
+    #[msg("Token streams need the token mint, token program, and token accounts, lamport streams need none of them")]
+    InvalidStreamAccounts,
+
+    #[msg("Nothing has accrued to withdraw yet")]
+    NothingToWithdraw,
+
+    #[msg("Stream has already ended")]
+    StreamEnded,
+
+    // Crowdfunding errors
+    #[msg("Campaign deadline has passed")]
+    CampaignEnded,
+
+    #[msg("Campaign deadline has not passed yet")]
+    CampaignNotEnded,
+
+    #[msg("Campaign has not reached its target")]
+    CampaignTargetNotReached,
+
+    #[msg("Campaign has reached its target, so contributions can't be refunded")]
+    CampaignTargetReached,
+
+    #[msg("Contribution receipt is from an earlier campaign with the same id")]
+    StaleContribution,
+
+    #[msg("Contribution receipt is still needed by its campaign")]
+    ContributionStillActive,
+
+    // Bounty errors
+    #[msg("Bounty deadline has passed")]
+    BountyEnded,
+
+    #[msg("Bounty deadline has not passed yet")]
+    BountyNotEnded,
+
+    #[msg("Awards would add up to more than the bounty's reward")]
+    BountyRewardExceeded,
+
+    #[msg("Submission is too long")]
+    SubmissionTooLong,
+
+    #[msg("Claim is from an earlier bounty with the same id")]
+    StaleClaim,
+
+    #[msg("Claim can still be awarded")]
+    ClaimStillActive,
+
+    // Marketplace errors
+    #[msg("Listing is not in the right state for this action")]
+    InvalidListingStatus,
+
+    #[msg("Only the buyer can confirm receipt before the confirmation window has passed")]
+    ConfirmationWindowOpen,
+
+    #[msg("The confirmation window has passed")]
+    ConfirmationWindowClosed,
+
+    // Game rake errors
+    #[msg("Rake is higher than the maximum allowed")]
+    RakeTooHigh,
+
+    // Multiplayer game errors
+    #[msg("Games need between 2 and 8 players")]
+    InvalidPlayerCount,
+
+    #[msg("Result must rank distinct player slots, with payouts adding up to 100%")]
+    InvalidResult,
+
+    // Token game errors
+    #[msg("Token games need the stake mint, token program, and token accounts, lamport games need none of them")]
+    InvalidGameAccounts,
+
+    // Optimistic settlement errors
+    #[msg("Challenge period must be positive and at most a year")]
+    InvalidChallengeSettings,
+
+    #[msg("Results for this game must be proposed, and can be challenged, rather than finalized directly")]
+    ResultMustBeProposed,
+
+    #[msg("This game is finalized directly by its authority")]
+    ChallengesNotEnabled,
+
+    #[msg("A result has already been proposed for this game")]
+    ResultAlreadyProposed,
+
+    #[msg("No result has been proposed for this game")]
+    NoProposedResult,
+
+    #[msg("The challenge window has closed")]
+    ChallengeWindowClosed,
+
+    #[msg("The challenge window is still open")]
+    ChallengeWindowOpen,
+
+    #[msg("The proposed result has already been challenged")]
+    AlreadyChallenged,
+
+    #[msg("The proposed result hasn't been challenged")]
+    NotChallenged,
+
+    // Referee quorum errors
+    #[msg("Referees must be distinct, with a threshold between 1 and the number of referees")]
+    InvalidRefereeQuorum,
+
+    #[msg("This game has no referee quorum")]
+    QuorumNotEnabled,
+
+    #[msg("Referee has already voted for this result")]
+    AlreadyVoted,
+
+    #[msg("Not enough referees have agreed on this result")]
+    QuorumNotReached,
+
+    #[msg("Votes can still be counted for this game")]
+    VoteStillActive,
+
+    // Result attestation errors
+    #[msg("Result attestation must be verified by the Ed25519 program, signed by the game's attester, in the previous instruction")]
+    InvalidAttestation,
+
+    // Deposit deadline errors
+    #[msg("Deposit deadline must be in the future, and no later than the expiry")]
+    InvalidDepositDeadline,
+
+    #[msg("The deposit deadline has passed")]
+    DepositDeadlinePassed,
+
+    // Open game errors
+    #[msg("Games need at least one named player, the rest can be left open")]
+    NoNamedPlayer,
+
+    #[msg("Every seat in this game is taken")]
+    GameFull,
+
+    // Matchmaking queue errors
+    #[msg("Queues need a positive game duration of at most a year")]
+    InvalidGameDuration,
+
+    #[msg("This queue is full")]
+    QueueFull,
+
+    #[msg("Player is already in this queue")]
+    AlreadyQueued,
+
+    #[msg("Player isn't in this queue")]
+    NotQueued,
+
+    #[msg("Not enough players are waiting in this queue to start a game")]
+    NotEnoughPlayersQueued,
+
+    // Series errors
+    #[msg("Series must fit in the most rounds a game can record, and can't be settled optimistically or by a referee quorum")]
+    InvalidSeries,
+
+    #[msg("This game isn't a series")]
+    NotASeries,
+
+    #[msg("Series are paid out by recording rounds")]
+    PaidOutByRounds,
+
+    // Side bet errors
+    #[msg("Side bets aren't taken on games with challenges or rounds")]
+    SideBetsNotSupported,
+
+    #[msg("Side bets close once every player has deposited")]
+    SideBetsClosed,
+
+    #[msg("Bettor has already backed a different player")]
+    BackedAnotherPlayer,
+
+    #[msg("Side bets can't be claimed until the game is finalized or cancelled")]
+    SideBetsNotSettled,
+
+    // More offer errors
+    #[msg("Surplus account is missing or isn't the one chosen when making the offer")]
+    InvalidSurplusAccount,
+
+    #[msg("Receipts can only be closed once their offer has been taken or refunded")]
+    OfferStillOpen,
+
+    // More loan errors
+    #[msg("Loan duration must be greater than zero, and short enough to give a valid due date")]
+    InvalidLoanDuration,
+
+    #[msg("Insufficient collateral in borrower's account")]
+    InsufficientCollateral,
+
+    #[msg("Insufficient principal in lender's account")]
+    InsufficientPrincipal,
+
+    #[msg("Insufficient balance in borrower's account to repay principal plus interest")]
+    InsufficientRepayment,
+
+    // Vault errors
+    #[msg("Vault holds less than the escrow has recorded")]
+    VaultShortfall,
+
+    // Nonce errors
+    #[msg("No more nonces can be handed out")]
+    NoncesExhausted,
+
+    // More bounty errors
+    #[msg("Award deadline must be after the submission deadline")]
+    InvalidAwardDeadline,
+
+    #[msg("Bounty's award deadline has passed")]
+    AwardDeadlinePassed,
+
+    #[msg("Bounty's award deadline has not passed yet")]
+    AwardDeadlineNotPassed,
+
+    // More marketplace errors
+    #[msg("The seller or arbiter still has time to act on this listing")]
+    ResponseWindowOpen,
+
+    // More game errors
+    #[msg("A game can have challenge settings or a referee quorum, not both")]
+    ChallengeWithQuorum,
+
+    #[msg("This game has no attester, so can't be finalized with an attestation")]
+    AttestationsNotEnabled,
+
+    // More matchmaking queue errors
+    #[msg("Players are still waiting in this queue")]
+    QueueNotEmpty,
+
+    // More crowdfunding errors
+    #[msg("Campaign has contributions, which must be withdrawn or refunded instead")]
+    CampaignHasContributions,
+
+    // Game record errors
+    #[msg("Game has already been paid out")]
+    GameOver,
+
+    #[msg("Game hasn't been paid out yet")]
+    GameNotOver,
 }
@@ -6,12 +6,21 @@ use solana_kite::{
     create_associated_token_account, create_token_mint, deploy_program, get_pda_and_bump,
     mint_tokens_to_account, send_transaction_from_instructions, SolanaKiteError,
 };
+use solana_program::clock::Clock;
 use solana_program::hash::hashv;
 use solana_pubkey::Pubkey;
 use solana_signer::Signer;
 use std::cell::Cell;
 use std::str::FromStr;
 
+use anchor_lang::{AccountDeserialize, AnchorSerialize};
+use anchor_spl::token_2022::spl_token_2022;
+use anchor_spl::token_2022::spl_token_2022::extension::{
+    transfer_fee::instruction::initialize_transfer_fee_config, ExtensionType, StateWithExtensions,
+};
+
+use crate::state::GameOptions;
+
 pub const PROGRAM_ID: &str = "8jR5GeNzeweq35Uo84kGP3v1NcBaZWH5u62k7PxN4T2y";
 
 /// Standard token unit for token A (1 token = 1_000_000_000 lamports for 9 decimals)
@@ -31,7 +40,7 @@ pub struct EscrowTestEnvironment {
     /// The escrow program ID
     pub program_id: Pubkey,
     /// The mint authority that can create and mint tokens
-    pub _mint_authority: Keypair,
+    pub mint_authority: Keypair,
     /// Token mint A (the first token in escrow trades)
     pub token_mint_a: Pubkey,
     /// Token mint B (the second token in escrow trades)
@@ -89,6 +98,7 @@ pub struct EscrowTestEnvironment {
 ///     token_mint_a: env.token_mint_a.pubkey(),
 ///     token_mint_b: env.token_mint_b.pubkey(),
 ///     maker_token_account_a: env.alice_token_account_a,
+///     proceeds_account: env.alice_token_account_b,
 ///     offer_account,
 ///     vault: spl_associated_token_account::get_associated_token_address(&offer_account, &env.token_mint_a.pubkey()),
 /// };
@@ -169,7 +179,7 @@ pub fn setup_escrow_test() -> EscrowTestEnvironment {
     EscrowTestEnvironment {
         litesvm,
         program_id,
-        _mint_authority: mint_authority,
+        mint_authority,
         token_mint_a,
         token_mint_b,
         alice,
@@ -181,12 +191,206 @@ pub fn setup_escrow_test() -> EscrowTestEnvironment {
     }
 }
 
+/// Creates a Token Extensions mint with 9 decimals charging `fee_basis_points` on transfers
+pub fn create_transfer_fee_mint(
+    litesvm: &mut LiteSVM,
+    mint_authority: &Keypair,
+    fee_basis_points: u16,
+) -> Pubkey {
+    let mint = Keypair::new();
+    let space = ExtensionType::try_calculate_account_len::<spl_token_2022::state::Mint>(&[
+        ExtensionType::TransferFeeConfig,
+    ])
+    .unwrap();
+    let instructions = vec![
+        anchor_lang::solana_program::system_instruction::create_account(
+            &mint_authority.pubkey(),
+            &mint.pubkey(),
+            litesvm.minimum_balance_for_rent_exemption(space),
+            space as u64,
+            &spl_token_2022::ID,
+        ),
+        initialize_transfer_fee_config(
+            &spl_token_2022::ID,
+            &mint.pubkey(),
+            None,
+            None,
+            fee_basis_points,
+            u64::MAX,
+        )
+        .unwrap(),
+        spl_token_2022::instruction::initialize_mint2(
+            &spl_token_2022::ID,
+            &mint.pubkey(),
+            &mint_authority.pubkey(),
+            None,
+            9,
+        )
+        .unwrap(),
+    ];
+    send_transaction_from_instructions(
+        litesvm,
+        instructions,
+        &[mint_authority, &mint],
+        &mint_authority.pubkey(),
+    )
+    .unwrap();
+    mint.pubkey()
+}
+
+/// Creates the owner's Token Extensions ATA for a mint made by `create_transfer_fee_mint`
+/// and mints `amount` to it
+pub fn create_token_extensions_account(
+    litesvm: &mut LiteSVM,
+    owner: &Pubkey,
+    mint: &Pubkey,
+    mint_authority: &Keypair,
+    amount: u64,
+) -> Pubkey {
+    let token_account = anchor_spl::associated_token::get_associated_token_address_with_program_id(
+        owner,
+        mint,
+        &spl_token_2022::ID,
+    );
+    let mut instructions = vec![
+        spl_associated_token_account::instruction::create_associated_token_account(
+            &mint_authority.pubkey(),
+            owner,
+            mint,
+            &spl_token_2022::ID,
+        ),
+    ];
+    if amount > 0 {
+        instructions.push(
+            spl_token_2022::instruction::mint_to(
+                &spl_token_2022::ID,
+                mint,
+                &token_account,
+                &mint_authority.pubkey(),
+                &[],
+                amount,
+            )
+            .unwrap(),
+        );
+    }
+    send_transaction_from_instructions(
+        litesvm,
+        instructions,
+        &[mint_authority],
+        &mint_authority.pubkey(),
+    )
+    .unwrap();
+    token_account
+}
+
+pub fn get_token_extensions_balance(litesvm: &LiteSVM, token_account: &Pubkey) -> u64 {
+    let account = litesvm.get_account(token_account).unwrap();
+    StateWithExtensions::<spl_token_2022::state::Account>::unpack(&account.data)
+        .unwrap()
+        .base
+        .amount
+}
+
 pub fn get_program_id() -> Pubkey {
     Pubkey::from_str(PROGRAM_ID).unwrap()
 }
 
+/// Returns a player's profile PDA
+pub fn get_player_profile(player: &Pubkey) -> Pubkey {
+    let (player_profile, _player_profile_bump) = get_pda_and_bump(
+        &[b"profile".as_ref().into(), player.as_ref().into()],
+        &get_program_id(),
+    );
+    player_profile
+}
+
+/// Returns the profile PDA of each player, in the order given
+pub fn get_player_profiles(players: &[Pubkey]) -> Vec<Pubkey> {
+    players.iter().map(get_player_profile).collect()
+}
+
+/// Returns a game's nonce, which the addresses of its vote records and side pool include
+pub fn get_game_nonce(litesvm: &LiteSVM, game: &Pubkey) -> u64 {
+    let game_account = litesvm.get_account(game).unwrap();
+    crate::state::Game::try_deserialize(&mut game_account.data.as_slice())
+        .unwrap()
+        .nonce
+}
+
+/// Returns the winner a paid out game recorded, the slot of the player who came first or DRAW
+pub fn get_game_winner(litesvm: &LiteSVM, game: &Pubkey) -> u8 {
+    let game_account = litesvm.get_account(game).unwrap();
+    crate::state::Game::try_deserialize(&mut game_account.data.as_slice())
+        .unwrap()
+        .winner
+}
+
+/// Returns the PDA that hands out nonces for the offer, option, game, campaign or bounty
+/// at `account`
+pub fn get_nonce_counter(account: &Pubkey) -> Pubkey {
+    let (nonce_counter, _nonce_counter_bump) = get_pda_and_bump(
+        &[b"nonce_counter".as_ref().into(), account.as_ref().into()],
+        &get_program_id(),
+    );
+    nonce_counter
+}
+
+/// Returns the receipt mint PDA for the offer at `offer`, with the nonce it was made with.
+/// The first offer made at an address gets nonce 1
+pub fn get_receipt_mint(offer: &Pubkey, nonce: u64) -> Pubkey {
+    let (receipt_mint, _receipt_mint_bump) = get_pda_and_bump(
+        &[
+            b"receipt".as_ref().into(),
+            offer.as_ref().into(),
+            nonce.to_le_bytes().as_ref().into(),
+        ],
+        &get_program_id(),
+    );
+    receipt_mint
+}
+
+/// Returns the option mint PDA for the option at `call_option`, with the nonce it was written
+/// with. The first option written at an address gets nonce 1
+pub fn get_option_mint(call_option: &Pubkey, nonce: u64) -> Pubkey {
+    let (option_mint, _option_mint_bump) = get_pda_and_bump(
+        &[
+            b"option_mint".as_ref().into(),
+            call_option.as_ref().into(),
+            nonce.to_le_bytes().as_ref().into(),
+        ],
+        &get_program_id(),
+    );
+    option_mint
+}
+
+/// Returns the vote record PDA for a result of a game, as it currently stands
+pub fn get_vote_record(litesvm: &LiteSVM, game: &Pubkey, result_hash: &[u8; 32]) -> Pubkey {
+    let (vote_record, _vote_record_bump) = get_pda_and_bump(
+        &[
+            b"vote".as_ref().into(),
+            game.as_ref().into(),
+            get_game_nonce(litesvm, game).to_le_bytes().as_ref().into(),
+            result_hash.as_ref().into(),
+        ],
+        &get_program_id(),
+    );
+    vote_record
+}
+
+/// Returns the current unix timestamp of the test environment's clock
+pub fn get_unix_timestamp(litesvm: &LiteSVM) -> i64 {
+    litesvm.get_sysvar::<Clock>().unix_timestamp
+}
+
+/// Moves the test environment's clock to the given unix timestamp, eg to pass a deadline
+pub fn warp_to_unix_timestamp(litesvm: &mut LiteSVM, unix_timestamp: i64) {
+    let mut clock = litesvm.get_sysvar::<Clock>();
+    clock.unix_timestamp = unix_timestamp;
+    litesvm.set_sysvar::<Clock>(&clock);
+}
+
 thread_local! {
-    static OFFER_ID_COUNTER: Cell<u64> = Cell::new(1);
+    static OFFER_ID_COUNTER: Cell<u64> = const { Cell::new(1) };
 }
 
 /// Generates a unique offer ID for testing
@@ -216,6 +420,11 @@ pub fn get_refund_offer_discriminator() -> Vec<u8> {
     hashv(&[discriminator_input]).to_bytes()[..8].to_vec()
 }
 
+pub fn get_close_receipt_discriminator() -> Vec<u8> {
+    let discriminator_input = b"global:close_receipt";
+    hashv(&[discriminator_input]).to_bytes()[..8].to_vec()
+}
+
 pub fn get_create_game_discriminator() -> Vec<u8> {
     let discriminator_input = b"global:create_game";
     hashv(&[discriminator_input]).to_bytes()[..8].to_vec()
@@ -236,16 +445,264 @@ pub fn get_cancel_game_discriminator() -> Vec<u8> {
     hashv(&[discriminator_input]).to_bytes()[..8].to_vec()
 }
 
+pub fn get_request_loan_discriminator() -> Vec<u8> {
+    let discriminator_input = b"global:request_loan";
+    hashv(&[discriminator_input]).to_bytes()[..8].to_vec()
+}
+
+pub fn get_fund_loan_discriminator() -> Vec<u8> {
+    let discriminator_input = b"global:fund_loan";
+    hashv(&[discriminator_input]).to_bytes()[..8].to_vec()
+}
+
+pub fn get_repay_loan_discriminator() -> Vec<u8> {
+    let discriminator_input = b"global:repay_loan";
+    hashv(&[discriminator_input]).to_bytes()[..8].to_vec()
+}
+
+pub fn get_claim_collateral_discriminator() -> Vec<u8> {
+    let discriminator_input = b"global:claim_collateral";
+    hashv(&[discriminator_input]).to_bytes()[..8].to_vec()
+}
+
+pub fn get_write_option_discriminator() -> Vec<u8> {
+    let discriminator_input = b"global:write_option";
+    hashv(&[discriminator_input]).to_bytes()[..8].to_vec()
+}
+
+pub fn get_buy_option_discriminator() -> Vec<u8> {
+    let discriminator_input = b"global:buy_option";
+    hashv(&[discriminator_input]).to_bytes()[..8].to_vec()
+}
+
+pub fn get_exercise_option_discriminator() -> Vec<u8> {
+    let discriminator_input = b"global:exercise_option";
+    hashv(&[discriminator_input]).to_bytes()[..8].to_vec()
+}
+
+pub fn get_reclaim_option_discriminator() -> Vec<u8> {
+    let discriminator_input = b"global:reclaim_option";
+    hashv(&[discriminator_input]).to_bytes()[..8].to_vec()
+}
+
+pub fn get_create_htlc_discriminator() -> Vec<u8> {
+    let discriminator_input = b"global:create_htlc";
+    hashv(&[discriminator_input]).to_bytes()[..8].to_vec()
+}
+
+pub fn get_claim_htlc_discriminator() -> Vec<u8> {
+    let discriminator_input = b"global:claim_htlc";
+    hashv(&[discriminator_input]).to_bytes()[..8].to_vec()
+}
+
+pub fn get_refund_htlc_discriminator() -> Vec<u8> {
+    let discriminator_input = b"global:refund_htlc";
+    hashv(&[discriminator_input]).to_bytes()[..8].to_vec()
+}
+
+pub fn get_create_stream_discriminator() -> Vec<u8> {
+    let discriminator_input = b"global:create_stream";
+    hashv(&[discriminator_input]).to_bytes()[..8].to_vec()
+}
+
+pub fn get_withdraw_stream_discriminator() -> Vec<u8> {
+    let discriminator_input = b"global:withdraw_stream";
+    hashv(&[discriminator_input]).to_bytes()[..8].to_vec()
+}
+
+pub fn get_cancel_stream_discriminator() -> Vec<u8> {
+    let discriminator_input = b"global:cancel_stream";
+    hashv(&[discriminator_input]).to_bytes()[..8].to_vec()
+}
+
+pub fn get_create_campaign_discriminator() -> Vec<u8> {
+    let discriminator_input = b"global:create_campaign";
+    hashv(&[discriminator_input]).to_bytes()[..8].to_vec()
+}
+
+pub fn get_contribute_discriminator() -> Vec<u8> {
+    let discriminator_input = b"global:contribute";
+    hashv(&[discriminator_input]).to_bytes()[..8].to_vec()
+}
+
+pub fn get_withdraw_campaign_funds_discriminator() -> Vec<u8> {
+    let discriminator_input = b"global:withdraw_campaign_funds";
+    hashv(&[discriminator_input]).to_bytes()[..8].to_vec()
+}
+
+pub fn get_refund_contribution_discriminator() -> Vec<u8> {
+    let discriminator_input = b"global:refund_contribution";
+    hashv(&[discriminator_input]).to_bytes()[..8].to_vec()
+}
+
+pub fn get_close_contribution_discriminator() -> Vec<u8> {
+    let discriminator_input = b"global:close_contribution";
+    hashv(&[discriminator_input]).to_bytes()[..8].to_vec()
+}
+
+pub fn get_close_campaign_discriminator() -> Vec<u8> {
+    let discriminator_input = b"global:close_campaign";
+    hashv(&[discriminator_input]).to_bytes()[..8].to_vec()
+}
+
+pub fn get_create_bounty_discriminator() -> Vec<u8> {
+    let discriminator_input = b"global:create_bounty";
+    hashv(&[discriminator_input]).to_bytes()[..8].to_vec()
+}
+
+pub fn get_submit_claim_discriminator() -> Vec<u8> {
+    let discriminator_input = b"global:submit_claim";
+    hashv(&[discriminator_input]).to_bytes()[..8].to_vec()
+}
+
+pub fn get_award_claim_discriminator() -> Vec<u8> {
+    let discriminator_input = b"global:award_claim";
+    hashv(&[discriminator_input]).to_bytes()[..8].to_vec()
+}
+
+pub fn get_close_bounty_discriminator() -> Vec<u8> {
+    let discriminator_input = b"global:close_bounty";
+    hashv(&[discriminator_input]).to_bytes()[..8].to_vec()
+}
+
+pub fn get_close_claim_discriminator() -> Vec<u8> {
+    let discriminator_input = b"global:close_claim";
+    hashv(&[discriminator_input]).to_bytes()[..8].to_vec()
+}
+
+pub fn get_create_listing_discriminator() -> Vec<u8> {
+    let discriminator_input = b"global:create_listing";
+    hashv(&[discriminator_input]).to_bytes()[..8].to_vec()
+}
+
+pub fn get_purchase_listing_discriminator() -> Vec<u8> {
+    let discriminator_input = b"global:purchase_listing";
+    hashv(&[discriminator_input]).to_bytes()[..8].to_vec()
+}
+
+pub fn get_mark_shipped_discriminator() -> Vec<u8> {
+    let discriminator_input = b"global:mark_shipped";
+    hashv(&[discriminator_input]).to_bytes()[..8].to_vec()
+}
+
+pub fn get_confirm_receipt_discriminator() -> Vec<u8> {
+    let discriminator_input = b"global:confirm_receipt";
+    hashv(&[discriminator_input]).to_bytes()[..8].to_vec()
+}
+
+pub fn get_open_dispute_discriminator() -> Vec<u8> {
+    let discriminator_input = b"global:open_dispute";
+    hashv(&[discriminator_input]).to_bytes()[..8].to_vec()
+}
+
+pub fn get_reclaim_payment_discriminator() -> Vec<u8> {
+    let discriminator_input = b"global:reclaim_payment";
+    hashv(&[discriminator_input]).to_bytes()[..8].to_vec()
+}
+
+pub fn get_resolve_dispute_discriminator() -> Vec<u8> {
+    let discriminator_input = b"global:resolve_dispute";
+    hashv(&[discriminator_input]).to_bytes()[..8].to_vec()
+}
+
+pub fn get_propose_result_discriminator() -> Vec<u8> {
+    let discriminator_input = b"global:propose_result";
+    hashv(&[discriminator_input]).to_bytes()[..8].to_vec()
+}
+
+pub fn get_challenge_result_discriminator() -> Vec<u8> {
+    let discriminator_input = b"global:challenge_result";
+    hashv(&[discriminator_input]).to_bytes()[..8].to_vec()
+}
+
+pub fn get_resolve_challenge_discriminator() -> Vec<u8> {
+    let discriminator_input = b"global:resolve_challenge";
+    hashv(&[discriminator_input]).to_bytes()[..8].to_vec()
+}
+
+pub fn get_settle_game_discriminator() -> Vec<u8> {
+    let discriminator_input = b"global:settle_game";
+    hashv(&[discriminator_input]).to_bytes()[..8].to_vec()
+}
+
+pub fn get_vote_result_discriminator() -> Vec<u8> {
+    let discriminator_input = b"global:vote_result";
+    hashv(&[discriminator_input]).to_bytes()[..8].to_vec()
+}
+
+pub fn get_close_vote_record_discriminator() -> Vec<u8> {
+    let discriminator_input = b"global:close_vote_record";
+    hashv(&[discriminator_input]).to_bytes()[..8].to_vec()
+}
+
+pub fn get_finalize_with_attestation_discriminator() -> Vec<u8> {
+    let discriminator_input = b"global:finalize_with_attestation";
+    hashv(&[discriminator_input]).to_bytes()[..8].to_vec()
+}
+
+pub fn get_create_queue_discriminator() -> Vec<u8> {
+    let discriminator_input = b"global:create_queue";
+    hashv(&[discriminator_input]).to_bytes()[..8].to_vec()
+}
+
+pub fn get_enqueue_discriminator() -> Vec<u8> {
+    let discriminator_input = b"global:enqueue";
+    hashv(&[discriminator_input]).to_bytes()[..8].to_vec()
+}
+
+pub fn get_match_players_discriminator() -> Vec<u8> {
+    let discriminator_input = b"global:match_players";
+    hashv(&[discriminator_input]).to_bytes()[..8].to_vec()
+}
+
+pub fn get_dequeue_discriminator() -> Vec<u8> {
+    let discriminator_input = b"global:dequeue";
+    hashv(&[discriminator_input]).to_bytes()[..8].to_vec()
+}
+
+pub fn get_close_queue_discriminator() -> Vec<u8> {
+    let discriminator_input = b"global:close_queue";
+    hashv(&[discriminator_input]).to_bytes()[..8].to_vec()
+}
+
+pub fn get_record_round_discriminator() -> Vec<u8> {
+    let discriminator_input = b"global:record_round";
+    hashv(&[discriminator_input]).to_bytes()[..8].to_vec()
+}
+
+pub fn get_place_side_bet_discriminator() -> Vec<u8> {
+    let discriminator_input = b"global:place_side_bet";
+    hashv(&[discriminator_input]).to_bytes()[..8].to_vec()
+}
+
+pub fn get_claim_side_bet_discriminator() -> Vec<u8> {
+    let discriminator_input = b"global:claim_side_bet";
+    hashv(&[discriminator_input]).to_bytes()[..8].to_vec()
+}
+
+pub fn get_close_game_discriminator() -> Vec<u8> {
+    let discriminator_input = b"global:close_game";
+    hashv(&[discriminator_input]).to_bytes()[..8].to_vec()
+}
+
 pub struct MakeOfferAccounts {
     pub associated_token_program: Pubkey,
     pub token_program: Pubkey,
     pub system_program: Pubkey,
     pub maker: Pubkey,
+    /// `None` leaves the maker paying the rent
+    pub rent_payer: Option<Pubkey>,
     pub token_mint_a: Pubkey,
     pub token_mint_b: Pubkey,
     pub maker_token_account_a: Pubkey,
+    pub proceeds_account: Pubkey,
+    /// `None` leaves surplus tokens going to `maker_token_account_a`
+    pub surplus_account: Option<Pubkey>,
     pub offer_account: Pubkey,
     pub vault: Pubkey,
+    /// Both `None` for an offer without a receipt token
+    pub receipt_mint: Option<Pubkey>,
+    pub maker_receipt_account: Option<Pubkey>,
 }
 
 /// Helper function to create MakeOfferAccounts with standard program IDs
@@ -254,11 +711,16 @@ pub struct MakeOfferAccounts {
 /// program IDs (associated_token_program, token_program, system_program) that
 /// are always the same constants across all tests. Instead of copy-pasting
 /// these three lines in every test, this helper focuses on the variable fields.
+///
+/// The maker pays the rent for the offer; set `rent_payer` on the result to sponsor it.
+/// Likewise set `surplus_account` to send any tokens donated to the vault somewhere else,
+/// and `receipt_mint` and `maker_receipt_account` to mint a receipt token for the offer.
 pub fn build_make_offer_accounts(
     maker: Pubkey,
     token_mint_a: Pubkey,
     token_mint_b: Pubkey,
     maker_token_account_a: Pubkey,
+    proceeds_account: Pubkey,
     offer_account: Pubkey,
     vault: Pubkey,
 ) -> MakeOfferAccounts {
@@ -267,11 +729,16 @@ pub fn build_make_offer_accounts(
         token_program: anchor_spl::token::ID,
         system_program: anchor_lang::system_program::ID,
         maker,
+        rent_payer: None,
         token_mint_a,
         token_mint_b,
         maker_token_account_a,
+        proceeds_account,
+        surplus_account: None,
         offer_account,
         vault,
+        receipt_mint: None,
+        maker_receipt_account: None,
     }
 }
 
@@ -291,11 +758,21 @@ pub fn build_make_offer_instruction(
         AccountMeta::new_readonly(accounts.token_program, false),
         AccountMeta::new_readonly(accounts.system_program, false),
         AccountMeta::new(accounts.maker, true),
+        optional_signer_account_meta(accounts.rent_payer),
         AccountMeta::new_readonly(accounts.token_mint_a, false),
         AccountMeta::new_readonly(accounts.token_mint_b, false),
         AccountMeta::new(accounts.maker_token_account_a, false),
+        AccountMeta::new_readonly(accounts.proceeds_account, false),
+        optional_account_meta(accounts.surplus_account),
         AccountMeta::new(accounts.offer_account, false),
         AccountMeta::new(accounts.vault, false),
+        optional_account_meta(
+            accounts
+                .receipt_mint
+                .map(|_| get_nonce_counter(&accounts.offer_account)),
+        ),
+        optional_account_meta(accounts.receipt_mint),
+        optional_account_meta(accounts.maker_receipt_account),
     ];
 
     Instruction {
@@ -305,31 +782,75 @@ pub fn build_make_offer_instruction(
     }
 }
 
+/// Anchor uses the program ID in place of optional accounts that aren't provided
+pub fn optional_account_meta(account: Option<Pubkey>) -> AccountMeta {
+    match account {
+        Some(account) => AccountMeta::new(account, false),
+        None => AccountMeta::new_readonly(get_program_id(), false),
+    }
+}
+
+/// Like `optional_account_meta`, for optional accounts that must sign
+pub fn optional_signer_account_meta(account: Option<Pubkey>) -> AccountMeta {
+    match account {
+        Some(account) => AccountMeta::new(account, true),
+        None => AccountMeta::new_readonly(get_program_id(), false),
+    }
+}
+
+/// Like `optional_account_meta`, for optional accounts the instruction doesn't write to
+pub fn optional_readonly_account_meta(account: Option<Pubkey>) -> AccountMeta {
+    AccountMeta::new_readonly(account.unwrap_or_else(get_program_id), false)
+}
+
+/// Accounts holding the stakes of token games. Leave them out with `None` for lamport games
+#[derive(Clone, Copy)]
+pub struct GameTokenAccounts {
+    pub token_program: Pubkey,
+    pub stake_mint: Pubkey,
+    pub vault: Pubkey,
+}
+
 pub struct CreateGameAccounts {
     pub authority: Pubkey,
     pub system_program: Pubkey,
+    pub treasury: Pubkey,
     pub game: Pubkey,
+    pub token_accounts: Option<GameTokenAccounts>,
 }
 
 pub fn build_create_game_instruction(
     id: u64,
-    player_a: Pubkey,
-    player_b: Pubkey,
-    stake_lamports: u64,
+    players: &[Pubkey],
+    stake_amount: u64,
     expiry_ts: i64,
+    rake_bps: u16,
+    options: GameOptions,
     accounts: CreateGameAccounts,
 ) -> Instruction {
     let mut data = get_create_game_discriminator();
     data.extend_from_slice(&id.to_le_bytes());
-    data.extend_from_slice(player_a.as_ref());
-    data.extend_from_slice(player_b.as_ref());
-    data.extend_from_slice(&stake_lamports.to_le_bytes());
+    // Borsh vectors are a u32 length followed by the items
+    data.extend_from_slice(&(players.len() as u32).to_le_bytes());
+    for player in players {
+        data.extend_from_slice(player.as_ref());
+    }
+    data.extend_from_slice(&stake_amount.to_le_bytes());
     data.extend_from_slice(&expiry_ts.to_le_bytes());
+    data.extend_from_slice(&rake_bps.to_le_bytes());
+    options.serialize(&mut data).unwrap();
 
+    let token_accounts = accounts.token_accounts.as_ref();
     let metas = vec![
         AccountMeta::new(accounts.authority, true),
+        optional_readonly_account_meta(token_accounts.map(|_| spl_associated_token_account::ID)),
+        optional_readonly_account_meta(token_accounts.map(|t| t.token_program)),
         AccountMeta::new_readonly(accounts.system_program, false),
+        AccountMeta::new_readonly(accounts.treasury, false),
         AccountMeta::new(accounts.game, false),
+        AccountMeta::new(get_nonce_counter(&accounts.game), false),
+        optional_readonly_account_meta(token_accounts.map(|t| t.stake_mint)),
+        optional_account_meta(token_accounts.map(|t| t.vault)),
     ];
 
     Instruction { program_id: get_program_id(), accounts: metas, data }
@@ -339,216 +860,991 @@ pub struct DepositAccounts {
     pub player: Pubkey,
     pub system_program: Pubkey,
     pub game: Pubkey,
+    pub token_accounts: Option<GameTokenAccounts>,
+    /// Where the player's stake comes from, for token games
+    pub player_token_account: Option<Pubkey>,
 }
 
 pub fn build_deposit_instruction(amount: u64, accounts: DepositAccounts) -> Instruction {
     let mut data = get_deposit_discriminator();
     data.extend_from_slice(&amount.to_le_bytes());
+    let token_accounts = accounts.token_accounts.as_ref();
     let metas = vec![
         AccountMeta::new(accounts.player, true),
+        optional_readonly_account_meta(token_accounts.map(|t| t.token_program)),
         AccountMeta::new_readonly(accounts.system_program, false),
         AccountMeta::new(accounts.game, false),
+        optional_readonly_account_meta(token_accounts.map(|t| t.stake_mint)),
+        optional_account_meta(accounts.player_token_account),
+        optional_account_meta(token_accounts.map(|t| t.vault)),
+        AccountMeta::new(get_player_profile(&accounts.player), false),
     ];
     Instruction { program_id: get_program_id(), accounts: metas, data }
 }
 
+/// Serializes a game result: player slots in finishing order, and each place's payout in basis points
+pub fn serialize_game_result(ranking: &[u8], payout_bps: &[u16]) -> Vec<u8> {
+    let mut data = Vec::new();
+    data.extend_from_slice(&(ranking.len() as u32).to_le_bytes());
+    data.extend_from_slice(ranking);
+    data.extend_from_slice(&(payout_bps.len() as u32).to_le_bytes());
+    for bps in payout_bps {
+        data.extend_from_slice(&bps.to_le_bytes());
+    }
+    data
+}
+
 pub struct FinalizeGameAccounts {
     pub authority: Pubkey,
-    pub winner_account: Pubkey,
+    /// The treasury, or its token account for token games
+    pub treasury: Pubkey,
     pub system_program: Pubkey,
     pub game: Pubkey,
+    pub token_accounts: Option<GameTokenAccounts>,
+    /// Votes collected in earlier transactions, for games with a referee quorum
+    pub vote_record: Option<Pubkey>,
+    /// The game's side pool, for games spectators have bet on
+    pub side_pool: Option<Pubkey>,
+    /// Where each player is paid, in slot order: wallets, or token accounts for token games
+    pub player_accounts: Vec<Pubkey>,
+    /// Each player's profile, in slot order
+    pub player_profiles: Vec<Pubkey>,
+    /// Referees signing the transaction, for games with a referee quorum
+    pub signing_referees: Vec<Pubkey>,
 }
 
-pub fn build_finalize_game_instruction(winner: u8, accounts: FinalizeGameAccounts) -> Instruction {
+pub fn build_finalize_game_instruction(
+    ranking: &[u8],
+    payout_bps: &[u16],
+    accounts: FinalizeGameAccounts,
+) -> Instruction {
     let mut data = get_finalize_game_discriminator();
-    data.push(winner);
-    let metas = vec![
+    data.extend_from_slice(&serialize_game_result(ranking, payout_bps));
+    let token_accounts = accounts.token_accounts.as_ref();
+    let mut metas = vec![
         AccountMeta::new(accounts.authority, true),
-        AccountMeta::new(accounts.winner_account, false),
+        AccountMeta::new(accounts.treasury, false),
+        optional_readonly_account_meta(token_accounts.map(|t| t.token_program)),
         AccountMeta::new_readonly(accounts.system_program, false),
         AccountMeta::new(accounts.game, false),
+        optional_readonly_account_meta(token_accounts.map(|t| t.stake_mint)),
+        optional_account_meta(token_accounts.map(|t| t.vault)),
+        optional_account_meta(accounts.vote_record),
+        optional_account_meta(accounts.side_pool),
     ];
+    metas.extend(
+        accounts
+            .player_accounts
+            .iter()
+            .chain(&accounts.player_profiles)
+            .map(|account| AccountMeta::new(*account, false)),
+    );
+    metas.extend(
+        accounts
+            .signing_referees
+            .iter()
+            .map(|referee| AccountMeta::new_readonly(*referee, true)),
+    );
     Instruction { program_id: get_program_id(), accounts: metas, data }
 }
 
 pub struct CancelGameAccounts {
     pub caller: Pubkey,
-    pub player_a_account: Pubkey,
-    pub player_b_account: Pubkey,
     pub system_program: Pubkey,
     pub game: Pubkey,
+    pub token_accounts: Option<GameTokenAccounts>,
+    /// Gets the bond back, for challenged games
+    pub challenger: Option<Pubkey>,
+    /// The game's side pool, for games spectators have bet on
+    pub side_pool: Option<Pubkey>,
+    /// Where each player is refunded, in slot order: wallets, or token accounts for token games
+    pub player_accounts: Vec<Pubkey>,
 }
 
 pub fn build_cancel_game_instruction(accounts: CancelGameAccounts) -> Instruction {
     let data = get_cancel_game_discriminator();
-    let metas = vec![
+    let token_accounts = accounts.token_accounts.as_ref();
+    let mut metas = vec![
         AccountMeta::new(accounts.caller, true),
-        AccountMeta::new(accounts.player_a_account, false),
-        AccountMeta::new(accounts.player_b_account, false),
+        optional_readonly_account_meta(token_accounts.map(|t| t.token_program)),
         AccountMeta::new_readonly(accounts.system_program, false),
         AccountMeta::new(accounts.game, false),
+        optional_readonly_account_meta(token_accounts.map(|t| t.stake_mint)),
+        optional_account_meta(token_accounts.map(|t| t.vault)),
+        optional_account_meta(accounts.challenger),
+        optional_account_meta(accounts.side_pool),
     ];
+    metas.extend(
+        accounts
+            .player_accounts
+            .iter()
+            .map(|player| AccountMeta::new(*player, false)),
+    );
     Instruction { program_id: get_program_id(), accounts: metas, data }
 }
 
-pub struct TakeOfferAccounts {
-    pub associated_token_program: Pubkey,
-    pub token_program: Pubkey,
+pub struct VoteResultAccounts {
+    pub referee: Pubkey,
     pub system_program: Pubkey,
-    pub taker: Pubkey,
-    pub maker: Pubkey,
-    pub token_mint_a: Pubkey,
-    pub token_mint_b: Pubkey,
-    pub taker_token_account_a: Pubkey,
-    pub taker_token_account_b: Pubkey,
-    pub maker_token_account_b: Pubkey,
-    pub offer_account: Pubkey,
-    pub vault: Pubkey,
+    pub game: Pubkey,
+    pub vote_record: Pubkey,
 }
 
-pub fn build_take_offer_instruction(accounts: TakeOfferAccounts) -> Instruction {
-    let instruction_data = get_take_offer_discriminator();
-
-    let account_metas = vec![
-        AccountMeta::new_readonly(accounts.associated_token_program, false),
-        AccountMeta::new_readonly(accounts.token_program, false),
+pub fn build_vote_result_instruction(
+    ranking: &[u8],
+    payout_bps: &[u16],
+    accounts: VoteResultAccounts,
+) -> Instruction {
+    let mut data = get_vote_result_discriminator();
+    data.extend_from_slice(&serialize_game_result(ranking, payout_bps));
+    let metas = vec![
+        AccountMeta::new(accounts.referee, true),
         AccountMeta::new_readonly(accounts.system_program, false),
-        AccountMeta::new(accounts.taker, true),
-        AccountMeta::new(accounts.maker, false),
-        AccountMeta::new_readonly(accounts.token_mint_a, false),
-        AccountMeta::new_readonly(accounts.token_mint_b, false),
-        AccountMeta::new(accounts.taker_token_account_a, false),
-        AccountMeta::new(accounts.taker_token_account_b, false),
-        AccountMeta::new(accounts.maker_token_account_b, false),
-        AccountMeta::new(accounts.offer_account, false),
-        AccountMeta::new(accounts.vault, false),
+        AccountMeta::new_readonly(accounts.game, false),
+        AccountMeta::new(accounts.vote_record, false),
     ];
+    Instruction { program_id: get_program_id(), accounts: metas, data }
+}
 
-    Instruction {
-        program_id: get_program_id(),
-        accounts: account_metas,
-        data: instruction_data,
+pub fn build_close_vote_record_instruction(
+    rent_payer: Pubkey,
+    game: Pubkey,
+    vote_record: Pubkey,
+) -> Instruction {
+    let data = get_close_vote_record_discriminator();
+    let metas = vec![
+        AccountMeta::new(rent_payer, false),
+        AccountMeta::new_readonly(game, false),
+        AccountMeta::new(vote_record, false),
+    ];
+    Instruction { program_id: get_program_id(), accounts: metas, data }
+}
+
+/// Serializes the message a game's attester signs to attest to a result
+pub fn serialize_result_attestation(
+    game_id: u64,
+    nonce: u64,
+    ranking: &[u8],
+    payout_bps: &[u16],
+) -> Vec<u8> {
+    let mut data = Vec::new();
+    data.extend_from_slice(&crate::constants::RESULT_ATTESTATION_TAG);
+    data.extend_from_slice(get_program_id().as_ref());
+    data.extend_from_slice(&game_id.to_le_bytes());
+    data.extend_from_slice(&nonce.to_le_bytes());
+    data.extend_from_slice(&serialize_game_result(ranking, payout_bps));
+    data
+}
+
+/// Builds an Ed25519 program instruction verifying `signer`'s signature over `message`,
+/// with the public key, signature, and message all held in the instruction's own data
+pub fn build_ed25519_instruction(signer: &Keypair, message: &[u8]) -> Instruction {
+    // Two header bytes, then seven u16 offsets
+    let public_key_offset: u16 = 2 + 14;
+    let signature_offset = public_key_offset + 32;
+    let message_offset = signature_offset + 64;
+
+    let mut data = vec![1, 0];
+    for offset in [
+        signature_offset,
+        u16::MAX,
+        public_key_offset,
+        u16::MAX,
+        message_offset,
+        message.len() as u16,
+        u16::MAX,
+    ] {
+        data.extend_from_slice(&offset.to_le_bytes());
     }
+    data.extend_from_slice(signer.pubkey().as_ref());
+    data.extend_from_slice(signer.sign_message(message).as_ref());
+    data.extend_from_slice(message);
+
+    Instruction { program_id: solana_sdk_ids::ed25519_program::ID, accounts: vec![], data }
 }
 
-pub struct RefundOfferAccounts {
-    pub token_program: Pubkey,
+pub struct FinalizeWithAttestationAccounts {
+    pub relayer: Pubkey,
+    pub authority: Pubkey,
+    /// The treasury, or its token account for token games
+    pub treasury: Pubkey,
     pub system_program: Pubkey,
-    pub maker: Pubkey,
-    pub token_mint_a: Pubkey,
-    pub maker_token_account_a: Pubkey,
-    pub offer_account: Pubkey,
-    pub vault: Pubkey,
+    pub game: Pubkey,
+    pub token_accounts: Option<GameTokenAccounts>,
+    /// The game's side pool, for games spectators have bet on
+    pub side_pool: Option<Pubkey>,
+    /// Where each player is paid, in slot order: wallets, or token accounts for token games
+    pub player_accounts: Vec<Pubkey>,
+    /// Each player's profile, in slot order
+    pub player_profiles: Vec<Pubkey>,
 }
 
-pub fn build_refund_offer_instruction(accounts: RefundOfferAccounts) -> Instruction {
-    let instruction_data = get_refund_offer_discriminator();
+pub fn build_finalize_with_attestation_instruction(
+    ranking: &[u8],
+    payout_bps: &[u16],
+    accounts: FinalizeWithAttestationAccounts,
+) -> Instruction {
+    let mut data = get_finalize_with_attestation_discriminator();
+    data.extend_from_slice(&serialize_game_result(ranking, payout_bps));
+    let token_accounts = accounts.token_accounts.as_ref();
+    let mut metas = vec![
+        AccountMeta::new_readonly(accounts.relayer, true),
+        AccountMeta::new(accounts.authority, false),
+        AccountMeta::new(accounts.treasury, false),
+        optional_readonly_account_meta(token_accounts.map(|t| t.token_program)),
+        AccountMeta::new_readonly(accounts.system_program, false),
+        AccountMeta::new_readonly(solana_instructions_sysvar::ID, false),
+        AccountMeta::new(accounts.game, false),
+        optional_readonly_account_meta(token_accounts.map(|t| t.stake_mint)),
+        optional_account_meta(token_accounts.map(|t| t.vault)),
+        optional_account_meta(accounts.side_pool),
+    ];
+    metas.extend(
+        accounts
+            .player_accounts
+            .iter()
+            .chain(&accounts.player_profiles)
+            .map(|account| AccountMeta::new(*account, false)),
+    );
+    Instruction { program_id: get_program_id(), accounts: metas, data }
+}
 
-    let account_metas = vec![
-        AccountMeta::new_readonly(accounts.token_program, false),
+pub struct ProposeResultAccounts {
+    pub authority: Pubkey,
+    pub game: Pubkey,
+}
+
+pub fn build_propose_result_instruction(
+    ranking: &[u8],
+    payout_bps: &[u16],
+    accounts: ProposeResultAccounts,
+) -> Instruction {
+    let mut data = get_propose_result_discriminator();
+    data.extend_from_slice(&serialize_game_result(ranking, payout_bps));
+    let metas = vec![
+        AccountMeta::new_readonly(accounts.authority, true),
+        AccountMeta::new(accounts.game, false),
+    ];
+    Instruction { program_id: get_program_id(), accounts: metas, data }
+}
+
+pub struct ChallengeResultAccounts {
+    pub player: Pubkey,
+    pub system_program: Pubkey,
+    pub game: Pubkey,
+}
+
+pub fn build_challenge_result_instruction(accounts: ChallengeResultAccounts) -> Instruction {
+    let data = get_challenge_result_discriminator();
+    let metas = vec![
+        AccountMeta::new(accounts.player, true),
         AccountMeta::new_readonly(accounts.system_program, false),
-        AccountMeta::new(accounts.maker, true),
-        AccountMeta::new_readonly(accounts.token_mint_a, false),
-        AccountMeta::new(accounts.maker_token_account_a, false),
-        AccountMeta::new(accounts.offer_account, false),
-        AccountMeta::new(accounts.vault, false),
+        AccountMeta::new(accounts.game, false),
     ];
+    Instruction { program_id: get_program_id(), accounts: metas, data }
+}
 
-    Instruction {
-        program_id: get_program_id(),
-        accounts: account_metas,
-        data: instruction_data,
-    }
+pub struct ResolveChallengeAccounts {
+    pub arbiter: Pubkey,
+    pub authority: Pubkey,
+    pub challenger: Pubkey,
+    /// The treasury, or its token account for token games
+    pub treasury: Pubkey,
+    pub system_program: Pubkey,
+    pub game: Pubkey,
+    pub token_accounts: Option<GameTokenAccounts>,
+    /// Where each player is paid, in slot order: wallets, or token accounts for token games
+    pub player_accounts: Vec<Pubkey>,
+    /// Each player's profile, in slot order
+    pub player_profiles: Vec<Pubkey>,
 }
 
-/// Executes a complete make_offer flow: creates PDAs, builds accounts, and executes instruction
-///
-/// This helper eliminates the repetitive pattern of creating offer_account and vault PDAs,
-/// building MakeOfferAccounts, and executing the make_offer instruction that appears in
-/// multiple tests.
-pub fn execute_make_offer(
-    test_env: &mut EscrowTestEnvironment,
-    offer_id: u64,
-    maker: &Keypair,
-    maker_token_account_a: Pubkey,
-    token_a_offered_amount: u64,
-    token_b_wanted_amount: u64,
-) -> Result<(Pubkey, Pubkey), SolanaKiteError> {
-    // Create PDAs
-    let (offer_account, _offer_bump) = get_pda_and_bump(
+pub fn build_resolve_challenge_instruction(
+    ranking: &[u8],
+    payout_bps: &[u16],
+    accounts: ResolveChallengeAccounts,
+) -> Instruction {
+    let mut data = get_resolve_challenge_discriminator();
+    data.extend_from_slice(&serialize_game_result(ranking, payout_bps));
+    let token_accounts = accounts.token_accounts.as_ref();
+    let mut metas = vec![
+        AccountMeta::new_readonly(accounts.arbiter, true),
+        AccountMeta::new(accounts.authority, false),
+        AccountMeta::new(accounts.challenger, false),
+        AccountMeta::new(accounts.treasury, false),
+        optional_readonly_account_meta(token_accounts.map(|t| t.token_program)),
+        AccountMeta::new_readonly(accounts.system_program, false),
+        AccountMeta::new(accounts.game, false),
+        optional_readonly_account_meta(token_accounts.map(|t| t.stake_mint)),
+        optional_account_meta(token_accounts.map(|t| t.vault)),
+    ];
+    metas.extend(
+        accounts
+            .player_accounts
+            .iter()
+            .chain(&accounts.player_profiles)
+            .map(|account| AccountMeta::new(*account, false)),
+    );
+    Instruction { program_id: get_program_id(), accounts: metas, data }
+}
+
+pub struct SettleGameAccounts {
+    pub caller: Pubkey,
+    pub authority: Pubkey,
+    /// The treasury, or its token account for token games
+    pub treasury: Pubkey,
+    pub system_program: Pubkey,
+    pub game: Pubkey,
+    pub token_accounts: Option<GameTokenAccounts>,
+    /// Where each player is paid, in slot order: wallets, or token accounts for token games
+    pub player_accounts: Vec<Pubkey>,
+    /// Each player's profile, in slot order
+    pub player_profiles: Vec<Pubkey>,
+}
+
+pub fn build_settle_game_instruction(accounts: SettleGameAccounts) -> Instruction {
+    let data = get_settle_game_discriminator();
+    let token_accounts = accounts.token_accounts.as_ref();
+    let mut metas = vec![
+        AccountMeta::new_readonly(accounts.caller, true),
+        AccountMeta::new(accounts.authority, false),
+        AccountMeta::new(accounts.treasury, false),
+        optional_readonly_account_meta(token_accounts.map(|t| t.token_program)),
+        AccountMeta::new_readonly(accounts.system_program, false),
+        AccountMeta::new(accounts.game, false),
+        optional_readonly_account_meta(token_accounts.map(|t| t.stake_mint)),
+        optional_account_meta(token_accounts.map(|t| t.vault)),
+    ];
+    metas.extend(
+        accounts
+            .player_accounts
+            .iter()
+            .chain(&accounts.player_profiles)
+            .map(|account| AccountMeta::new(*account, false)),
+    );
+    Instruction { program_id: get_program_id(), accounts: metas, data }
+}
+
+pub struct RecordRoundAccounts {
+    pub authority: Pubkey,
+    /// The treasury, or its token account for token games
+    pub treasury: Pubkey,
+    pub system_program: Pubkey,
+    pub game: Pubkey,
+    pub token_accounts: Option<GameTokenAccounts>,
+    /// Where each player is paid, in slot order, for the round that wins the series
+    pub player_accounts: Vec<Pubkey>,
+    /// Each player's profile, in slot order
+    pub player_profiles: Vec<Pubkey>,
+}
+
+pub fn build_record_round_instruction(winner: u8, accounts: RecordRoundAccounts) -> Instruction {
+    let mut data = get_record_round_discriminator();
+    data.push(winner);
+    let token_accounts = accounts.token_accounts.as_ref();
+    let mut metas = vec![
+        AccountMeta::new(accounts.authority, true),
+        AccountMeta::new(accounts.treasury, false),
+        optional_readonly_account_meta(token_accounts.map(|t| t.token_program)),
+        AccountMeta::new_readonly(accounts.system_program, false),
+        AccountMeta::new(accounts.game, false),
+        optional_readonly_account_meta(token_accounts.map(|t| t.stake_mint)),
+        optional_account_meta(token_accounts.map(|t| t.vault)),
+    ];
+    metas.extend(
+        accounts
+            .player_accounts
+            .iter()
+            .chain(&accounts.player_profiles)
+            .map(|account| AccountMeta::new(*account, false)),
+    );
+    Instruction { program_id: get_program_id(), accounts: metas, data }
+}
+
+pub struct SideBetAccounts {
+    pub bettor: Pubkey,
+    pub system_program: Pubkey,
+    pub game: Pubkey,
+    /// The game's nonce, see get_game_nonce
+    pub game_nonce: u64,
+}
+
+/// Returns a game's side pool PDA, and a bettor's side bet PDA in it
+pub fn get_side_bet_accounts(game: &Pubkey, game_nonce: u64, bettor: &Pubkey) -> (Pubkey, Pubkey) {
+    let (side_pool, _side_pool_bump) = get_pda_and_bump(
         &[
-            b"offer".as_ref().into(),
-            offer_id.to_le_bytes().as_ref().into(),
+            b"side_pool".as_ref().into(),
+            game.as_ref().into(),
+            game_nonce.to_le_bytes().as_ref().into(),
         ],
-        &test_env.program_id,
+        &get_program_id(),
     );
-    let vault = anchor_spl::associated_token::get_associated_token_address(
-        &offer_account,
-        &test_env.token_mint_a,
+    let (side_bet, _side_bet_bump) = get_pda_and_bump(
+        &[b"side_bet".as_ref().into(), side_pool.as_ref().into(), bettor.as_ref().into()],
+        &get_program_id(),
     );
+    (side_pool, side_bet)
+}
 
-    // Build accounts
-    let make_offer_accounts = build_make_offer_accounts(
-        maker.pubkey(),
-        test_env.token_mint_a,
-        test_env.token_mint_b,
-        maker_token_account_a,
-        offer_account,
-        vault,
+pub fn build_place_side_bet_instruction(
+    slot: u8,
+    amount: u64,
+    accounts: SideBetAccounts,
+) -> Instruction {
+    let mut data = get_place_side_bet_discriminator();
+    data.push(slot);
+    data.extend_from_slice(&amount.to_le_bytes());
+    let (side_pool, side_bet) =
+        get_side_bet_accounts(&accounts.game, accounts.game_nonce, &accounts.bettor);
+    let metas = vec![
+        AccountMeta::new(accounts.bettor, true),
+        AccountMeta::new_readonly(accounts.system_program, false),
+        AccountMeta::new(accounts.game, false),
+        AccountMeta::new(side_pool, false),
+        AccountMeta::new(side_bet, false),
+    ];
+    Instruction { program_id: get_program_id(), accounts: metas, data }
+}
+
+/// Closes a paid out game. `player_accounts` are the players' wallets, in slot order,
+/// for games matched from a queue, and empty otherwise
+pub fn build_close_game_instruction(
+    authority: Pubkey,
+    game: Pubkey,
+    player_accounts: Vec<Pubkey>,
+) -> Instruction {
+    let data = get_close_game_discriminator();
+    let mut metas = vec![
+        AccountMeta::new(authority, true),
+        AccountMeta::new(game, false),
+    ];
+    metas.extend(
+        player_accounts
+            .into_iter()
+            .map(|player_account| AccountMeta::new(player_account, false)),
     );
+    Instruction { program_id: get_program_id(), accounts: metas, data }
+}
 
-    // Build and execute instruction
-    let make_offer_instruction = build_make_offer_instruction(
-        offer_id,
-        token_a_offered_amount,
-        token_b_wanted_amount,
-        make_offer_accounts,
+/// Builds a claim, where `side_pool_rent_payer` placed the pool's first bet
+pub fn build_claim_side_bet_instruction(
+    accounts: SideBetAccounts,
+    side_pool_rent_payer: Pubkey,
+) -> Instruction {
+    let data = get_claim_side_bet_discriminator();
+    let (side_pool, side_bet) =
+        get_side_bet_accounts(&accounts.game, accounts.game_nonce, &accounts.bettor);
+    let metas = vec![
+        AccountMeta::new(accounts.bettor, true),
+        AccountMeta::new_readonly(accounts.system_program, false),
+        AccountMeta::new(side_pool, false),
+        AccountMeta::new(side_pool_rent_payer, false),
+        AccountMeta::new(side_bet, false),
+    ];
+    Instruction { program_id: get_program_id(), accounts: metas, data }
+}
+
+pub struct CreateQueueAccounts {
+    pub authority: Pubkey,
+    pub system_program: Pubkey,
+    pub treasury: Pubkey,
+    pub queue: Pubkey,
+}
+
+pub fn build_create_queue_instruction(
+    game_mode: u8,
+    stake_lamports: u64,
+    rake_bps: u16,
+    game_duration: i64,
+    accounts: CreateQueueAccounts,
+) -> Instruction {
+    let mut data = get_create_queue_discriminator();
+    data.push(game_mode);
+    data.extend_from_slice(&stake_lamports.to_le_bytes());
+    data.extend_from_slice(&rake_bps.to_le_bytes());
+    data.extend_from_slice(&game_duration.to_le_bytes());
+    let metas = vec![
+        AccountMeta::new(accounts.authority, true),
+        AccountMeta::new_readonly(accounts.system_program, false),
+        AccountMeta::new_readonly(accounts.treasury, false),
+        AccountMeta::new(accounts.queue, false),
+    ];
+    Instruction { program_id: get_program_id(), accounts: metas, data }
+}
+
+pub struct QueuePlayerAccounts {
+    pub player: Pubkey,
+    pub system_program: Pubkey,
+    pub queue: Pubkey,
+}
+
+pub fn build_enqueue_instruction(accounts: QueuePlayerAccounts) -> Instruction {
+    let data = get_enqueue_discriminator();
+    let metas = vec![
+        AccountMeta::new(accounts.player, true),
+        AccountMeta::new_readonly(accounts.system_program, false),
+        AccountMeta::new(accounts.queue, false),
+        AccountMeta::new(get_player_profile(&accounts.player), false),
+    ];
+    Instruction { program_id: get_program_id(), accounts: metas, data }
+}
+
+pub fn build_dequeue_instruction(accounts: QueuePlayerAccounts) -> Instruction {
+    let data = get_dequeue_discriminator();
+    let metas = vec![
+        AccountMeta::new(accounts.player, true),
+        AccountMeta::new_readonly(accounts.system_program, false),
+        AccountMeta::new(accounts.queue, false),
+    ];
+    Instruction { program_id: get_program_id(), accounts: metas, data }
+}
+
+pub struct CloseQueueAccounts {
+    pub authority: Pubkey,
+    pub queue: Pubkey,
+}
+
+pub fn build_close_queue_instruction(accounts: CloseQueueAccounts) -> Instruction {
+    let data = get_close_queue_discriminator();
+    let metas = vec![
+        AccountMeta::new(accounts.authority, true),
+        AccountMeta::new(accounts.queue, false),
+    ];
+    Instruction { program_id: get_program_id(), accounts: metas, data }
+}
+
+pub struct MatchPlayersAccounts {
+    pub cranker: Pubkey,
+    pub system_program: Pubkey,
+    pub queue: Pubkey,
+    pub game: Pubkey,
+}
+
+pub fn build_match_players_instruction(id: u64, accounts: MatchPlayersAccounts) -> Instruction {
+    let mut data = get_match_players_discriminator();
+    data.extend_from_slice(&id.to_le_bytes());
+    let metas = vec![
+        AccountMeta::new(accounts.cranker, true),
+        AccountMeta::new_readonly(accounts.system_program, false),
+        AccountMeta::new(accounts.queue, false),
+        AccountMeta::new(accounts.game, false),
+        AccountMeta::new(get_nonce_counter(&accounts.game), false),
+    ];
+    Instruction { program_id: get_program_id(), accounts: metas, data }
+}
+
+/// Creates a game between the given players, refereed by the mint authority, and has every player deposit
+///
+/// Returns the game PDA
+pub fn execute_create_and_deposit_game(
+    test_env: &mut EscrowTestEnvironment,
+    game_id: u64,
+    players: &[Keypair],
+    stake_amount: u64,
+    expiry_ts: i64,
+    rake_bps: u16,
+    treasury: Pubkey,
+) -> Result<Pubkey, SolanaKiteError> {
+    let (game, _game_bump) = get_pda_and_bump(
+        &[b"game".as_ref().into(), game_id.to_le_bytes().as_ref().into()],
+        &test_env.program_id,
     );
 
+    let player_pubkeys: Vec<Pubkey> = players.iter().map(|player| player.pubkey()).collect();
+    let create_game_instruction = build_create_game_instruction(
+        game_id,
+        &player_pubkeys,
+        stake_amount,
+        expiry_ts,
+        rake_bps,
+        GameOptions::default(),
+        CreateGameAccounts {
+            authority: test_env.mint_authority.pubkey(),
+            system_program: anchor_lang::system_program::ID,
+            treasury,
+            game,
+            token_accounts: None,
+        },
+    );
     send_transaction_from_instructions(
         &mut test_env.litesvm,
-        vec![make_offer_instruction],
-        &[maker],
-        &maker.pubkey(),
+        vec![create_game_instruction],
+        &[&test_env.mint_authority],
+        &test_env.mint_authority.pubkey(),
     )?;
 
-    Ok((offer_account, vault))
+    for player in players {
+        let deposit_instruction = build_deposit_instruction(
+            stake_amount,
+            DepositAccounts {
+                player: player.pubkey(),
+                system_program: anchor_lang::system_program::ID,
+                game,
+                token_accounts: None,
+                player_token_account: None,
+            },
+        );
+        send_transaction_from_instructions(
+            &mut test_env.litesvm,
+            vec![deposit_instruction],
+            &[player],
+            &player.pubkey(),
+        )?;
+    }
+
+    Ok(game)
 }
 
-/// Executes a complete take_offer flow: builds accounts and executes instruction
-pub fn execute_take_offer(
+/// Like `execute_create_and_deposit_game`, for a game created with `options`, with no rake
+///
+/// Returns the game PDA
+pub fn execute_create_and_deposit_game_with_options(
     test_env: &mut EscrowTestEnvironment,
-    taker: &Keypair,
-    maker: &Keypair,
-    taker_token_account_a: Pubkey,
-    taker_token_account_b: Pubkey,
-    maker_token_account_b: Pubkey,
-    offer_account: Pubkey,
-    vault: Pubkey,
-) -> Result<(), SolanaKiteError> {
-    let take_offer_accounts = TakeOfferAccounts {
-        associated_token_program: anchor_spl::associated_token::ID,
-        token_program: anchor_spl::token::ID,
-        system_program: anchor_lang::system_program::ID,
-        taker: taker.pubkey(),
-        maker: maker.pubkey(),
-        token_mint_a: test_env.token_mint_a,
-        token_mint_b: test_env.token_mint_b,
-        taker_token_account_a,
-        taker_token_account_b,
-        maker_token_account_b,
-        offer_account,
-        vault,
-    };
-
-    let take_offer_instruction = build_take_offer_instruction(take_offer_accounts);
+    game_id: u64,
+    players: &[Keypair],
+    stake_amount: u64,
+    expiry_ts: i64,
+    options: GameOptions,
+) -> Result<Pubkey, SolanaKiteError> {
+    let (game, _game_bump) = get_pda_and_bump(
+        &[b"game".as_ref().into(), game_id.to_le_bytes().as_ref().into()],
+        &test_env.program_id,
+    );
 
+    let player_pubkeys: Vec<Pubkey> = players.iter().map(|player| player.pubkey()).collect();
+    let create_game_instruction = build_create_game_instruction(
+        game_id,
+        &player_pubkeys,
+        stake_amount,
+        expiry_ts,
+        0,
+        options,
+        CreateGameAccounts {
+            authority: test_env.mint_authority.pubkey(),
+            system_program: anchor_lang::system_program::ID,
+            treasury: test_env.mint_authority.pubkey(),
+            game,
+            token_accounts: None,
+        },
+    );
     send_transaction_from_instructions(
         &mut test_env.litesvm,
-        vec![take_offer_instruction],
-        &[taker],
-        &taker.pubkey(),
-    )
+        vec![create_game_instruction],
+        &[&test_env.mint_authority],
+        &test_env.mint_authority.pubkey(),
+    )?;
+
+    for player in players {
+        let deposit_instruction = build_deposit_instruction(
+            stake_amount,
+            DepositAccounts {
+                player: player.pubkey(),
+                system_program: anchor_lang::system_program::ID,
+                game,
+                token_accounts: None,
+                player_token_account: None,
+            },
+        );
+        send_transaction_from_instructions(
+            &mut test_env.litesvm,
+            vec![deposit_instruction],
+            &[player],
+            &player.pubkey(),
+        )?;
+    }
+
+    Ok(game)
+}
+
+/// Like `execute_create_and_deposit_game`, for a game staked in `stake_mint` with no rake.
+/// Each player deposits from their associated token account
+///
+/// Returns the game PDA and its token accounts
+pub fn execute_create_and_deposit_token_game(
+    test_env: &mut EscrowTestEnvironment,
+    game_id: u64,
+    players: &[Keypair],
+    stake_mint: Pubkey,
+    stake_amount: u64,
+    expiry_ts: i64,
+) -> Result<(Pubkey, GameTokenAccounts), SolanaKiteError> {
+    let (game, _game_bump) = get_pda_and_bump(
+        &[b"game".as_ref().into(), game_id.to_le_bytes().as_ref().into()],
+        &test_env.program_id,
+    );
+    let token_accounts = GameTokenAccounts {
+        token_program: anchor_spl::token::ID,
+        stake_mint,
+        vault: anchor_spl::associated_token::get_associated_token_address(&game, &stake_mint),
+    };
+
+    let player_pubkeys: Vec<Pubkey> = players.iter().map(|player| player.pubkey()).collect();
+    let create_game_instruction = build_create_game_instruction(
+        game_id,
+        &player_pubkeys,
+        stake_amount,
+        expiry_ts,
+        0,
+        GameOptions::default(),
+        CreateGameAccounts {
+            authority: test_env.mint_authority.pubkey(),
+            system_program: anchor_lang::system_program::ID,
+            treasury: test_env.mint_authority.pubkey(),
+            game,
+            token_accounts: Some(token_accounts),
+        },
+    );
+    send_transaction_from_instructions(
+        &mut test_env.litesvm,
+        vec![create_game_instruction],
+        &[&test_env.mint_authority],
+        &test_env.mint_authority.pubkey(),
+    )?;
+
+    for player in players {
+        let deposit_instruction = build_deposit_instruction(
+            stake_amount,
+            DepositAccounts {
+                player: player.pubkey(),
+                system_program: anchor_lang::system_program::ID,
+                game,
+                token_accounts: Some(token_accounts),
+                player_token_account: Some(
+                    anchor_spl::associated_token::get_associated_token_address(
+                        &player.pubkey(),
+                        &stake_mint,
+                    ),
+                ),
+            },
+        );
+        send_transaction_from_instructions(
+            &mut test_env.litesvm,
+            vec![deposit_instruction],
+            &[player],
+            &player.pubkey(),
+        )?;
+    }
+
+    Ok((game, token_accounts))
+}
+
+pub struct TakeOfferAccounts {
+    pub token_program: Pubkey,
+    pub system_program: Pubkey,
+    pub taker: Pubkey,
+    pub maker: Pubkey,
+    pub rent_payer: Pubkey,
+    pub token_mint_a: Pubkey,
+    pub token_mint_b: Pubkey,
+    pub recipient: Pubkey,
+    pub taker_token_account_b: Pubkey,
+    pub proceeds_account: Pubkey,
+    /// Only needed when the vault holds more than was offered
+    pub surplus_account: Option<Pubkey>,
+    /// Only needed when the surplus account has been closed
+    pub maker_token_account_a: Option<Pubkey>,
+    pub offer_account: Pubkey,
+    pub vault: Pubkey,
+    /// Both only needed for offers with a receipt token
+    pub receipt_account: Option<Pubkey>,
+    pub receipt_mint: Option<Pubkey>,
+}
+
+pub fn build_take_offer_instruction(accounts: TakeOfferAccounts) -> Instruction {
+    let instruction_data = get_take_offer_discriminator();
+
+    let account_metas = vec![
+        AccountMeta::new_readonly(accounts.token_program, false),
+        AccountMeta::new_readonly(accounts.system_program, false),
+        AccountMeta::new(accounts.taker, true),
+        AccountMeta::new_readonly(accounts.maker, false),
+        AccountMeta::new(accounts.rent_payer, false),
+        AccountMeta::new_readonly(accounts.token_mint_a, false),
+        AccountMeta::new_readonly(accounts.token_mint_b, false),
+        AccountMeta::new(accounts.recipient, false),
+        AccountMeta::new(accounts.taker_token_account_b, false),
+        AccountMeta::new(accounts.proceeds_account, false),
+        optional_account_meta(accounts.surplus_account),
+        optional_account_meta(accounts.maker_token_account_a),
+        AccountMeta::new(accounts.offer_account, false),
+        AccountMeta::new(accounts.vault, false),
+        optional_account_meta(accounts.receipt_account),
+        optional_readonly_account_meta(accounts.receipt_mint),
+    ];
+
+    Instruction {
+        program_id: get_program_id(),
+        accounts: account_metas,
+        data: instruction_data,
+    }
+}
+
+pub struct RefundOfferAccounts {
+    pub token_program: Pubkey,
+    pub system_program: Pubkey,
+    pub owner: Pubkey,
+    pub rent_payer: Pubkey,
+    pub token_mint_a: Pubkey,
+    pub owner_token_account_a: Pubkey,
+    /// `None` sends any surplus to `owner_token_account_a`
+    pub surplus_account: Option<Pubkey>,
+    pub offer_account: Pubkey,
+    pub vault: Pubkey,
+    /// Both `None` for an offer without a receipt token
+    pub receipt_mint: Option<Pubkey>,
+    pub owner_receipt_account: Option<Pubkey>,
+}
+
+pub fn build_refund_offer_instruction(accounts: RefundOfferAccounts) -> Instruction {
+    let instruction_data = get_refund_offer_discriminator();
+
+    let account_metas = vec![
+        AccountMeta::new_readonly(accounts.token_program, false),
+        AccountMeta::new_readonly(accounts.system_program, false),
+        AccountMeta::new(accounts.owner, true),
+        AccountMeta::new(accounts.rent_payer, false),
+        AccountMeta::new_readonly(accounts.token_mint_a, false),
+        AccountMeta::new(accounts.owner_token_account_a, false),
+        optional_account_meta(accounts.surplus_account),
+        AccountMeta::new(accounts.offer_account, false),
+        AccountMeta::new(accounts.vault, false),
+        optional_account_meta(accounts.receipt_mint),
+        optional_account_meta(accounts.owner_receipt_account),
+    ];
+
+    Instruction {
+        program_id: get_program_id(),
+        accounts: account_metas,
+        data: instruction_data,
+    }
+}
+
+pub fn build_close_receipt_instruction(
+    offer_id: u64,
+    holder: Pubkey,
+    offer_account: Pubkey,
+    receipt_mint: Pubkey,
+    receipt_account: Pubkey,
+) -> Instruction {
+    let mut instruction_data = get_close_receipt_discriminator();
+    instruction_data.extend_from_slice(&offer_id.to_le_bytes());
+
+    let account_metas = vec![
+        AccountMeta::new_readonly(anchor_spl::token::ID, false),
+        AccountMeta::new(holder, true),
+        AccountMeta::new_readonly(offer_account, false),
+        AccountMeta::new(receipt_mint, false),
+        AccountMeta::new(receipt_account, false),
+    ];
+
+    Instruction {
+        program_id: get_program_id(),
+        accounts: account_metas,
+        data: instruction_data,
+    }
+}
+
+/// Executes a complete make_offer flow: creates PDAs, builds accounts, and executes instruction
+///
+/// This helper eliminates the repetitive pattern of creating offer_account and vault PDAs,
+/// building MakeOfferAccounts, and executing the make_offer instruction that appears in
+/// multiple tests.
+pub fn execute_make_offer(
+    test_env: &mut EscrowTestEnvironment,
+    offer_id: u64,
+    maker: &Keypair,
+    maker_token_account_a: Pubkey,
+    proceeds_account: Pubkey,
+    token_a_offered_amount: u64,
+    token_b_wanted_amount: u64,
+) -> Result<(Pubkey, Pubkey), SolanaKiteError> {
+    // Create PDAs
+    let (offer_account, _offer_bump) = get_pda_and_bump(
+        &[
+            b"offer".as_ref().into(),
+            offer_id.to_le_bytes().as_ref().into(),
+        ],
+        &test_env.program_id,
+    );
+    let vault = anchor_spl::associated_token::get_associated_token_address(
+        &offer_account,
+        &test_env.token_mint_a,
+    );
+
+    // Build accounts
+    let make_offer_accounts = build_make_offer_accounts(
+        maker.pubkey(),
+        test_env.token_mint_a,
+        test_env.token_mint_b,
+        maker_token_account_a,
+        proceeds_account,
+        offer_account,
+        vault,
+    );
+
+    // Build and execute instruction
+    let make_offer_instruction = build_make_offer_instruction(
+        offer_id,
+        token_a_offered_amount,
+        token_b_wanted_amount,
+        make_offer_accounts,
+    );
+
+    send_transaction_from_instructions(
+        &mut test_env.litesvm,
+        vec![make_offer_instruction],
+        &[maker],
+        &maker.pubkey(),
+    )?;
+
+    Ok((offer_account, vault))
+}
+
+/// Executes a complete take_offer flow: builds accounts and executes instruction
+pub fn execute_take_offer(
+    test_env: &mut EscrowTestEnvironment,
+    taker: &Keypair,
+    maker: &Keypair,
+    recipient: Pubkey,
+    taker_token_account_b: Pubkey,
+    proceeds_account: Pubkey,
+    offer_account: Pubkey,
+) -> Result<(), SolanaKiteError> {
+    let vault = anchor_spl::associated_token::get_associated_token_address(
+        &offer_account,
+        &test_env.token_mint_a,
+    );
+    let take_offer_accounts = TakeOfferAccounts {
+        token_program: anchor_spl::token::ID,
+        system_program: anchor_lang::system_program::ID,
+        taker: taker.pubkey(),
+        maker: maker.pubkey(),
+        rent_payer: maker.pubkey(),
+        token_mint_a: test_env.token_mint_a,
+        token_mint_b: test_env.token_mint_b,
+        recipient,
+        taker_token_account_b,
+        proceeds_account,
+        // Surplus tokens go to the maker's token a account by default
+        surplus_account: Some(anchor_spl::associated_token::get_associated_token_address(
+            &maker.pubkey(),
+            &test_env.token_mint_a,
+        )),
+        maker_token_account_a: None,
+        offer_account,
+        vault,
+        receipt_account: None,
+        receipt_mint: None,
+    };
+
+    let take_offer_instruction = build_take_offer_instruction(take_offer_accounts);
+
+    send_transaction_from_instructions(
+        &mut test_env.litesvm,
+        vec![take_offer_instruction],
+        &[taker],
+        &taker.pubkey(),
+    )
 }
 
 /// Executes a complete refund_offer flow: builds accounts and executes instruction
@@ -562,11 +1858,15 @@ pub fn execute_refund_offer(
     let refund_offer_accounts = RefundOfferAccounts {
         token_program: anchor_spl::token::ID,
         system_program: anchor_lang::system_program::ID,
-        maker: maker.pubkey(),
+        owner: maker.pubkey(),
+        rent_payer: maker.pubkey(),
         token_mint_a: test_env.token_mint_a,
-        maker_token_account_a,
+        owner_token_account_a: maker_token_account_a,
+        surplus_account: None,
         offer_account,
         vault,
+        receipt_mint: None,
+        owner_receipt_account: None,
     };
 
     let refund_instruction = build_refund_offer_instruction(refund_offer_accounts);
@@ -578,3 +1878,1029 @@ pub fn execute_refund_offer(
         &maker.pubkey(),
     )
 }
+
+pub struct RequestLoanAccounts {
+    pub associated_token_program: Pubkey,
+    pub token_program: Pubkey,
+    pub system_program: Pubkey,
+    pub borrower: Pubkey,
+    pub collateral_mint: Pubkey,
+    pub principal_mint: Pubkey,
+    pub borrower_collateral_account: Pubkey,
+    pub loan: Pubkey,
+    pub vault: Pubkey,
+}
+
+pub fn build_request_loan_instruction(
+    id: u64,
+    collateral_amount: u64,
+    principal_amount: u64,
+    interest_amount: u64,
+    duration: i64,
+    accounts: RequestLoanAccounts,
+) -> Instruction {
+    let mut data = get_request_loan_discriminator();
+    data.extend_from_slice(&id.to_le_bytes());
+    data.extend_from_slice(&collateral_amount.to_le_bytes());
+    data.extend_from_slice(&principal_amount.to_le_bytes());
+    data.extend_from_slice(&interest_amount.to_le_bytes());
+    data.extend_from_slice(&duration.to_le_bytes());
+
+    let metas = vec![
+        AccountMeta::new_readonly(accounts.associated_token_program, false),
+        AccountMeta::new_readonly(accounts.token_program, false),
+        AccountMeta::new_readonly(accounts.system_program, false),
+        AccountMeta::new(accounts.borrower, true),
+        AccountMeta::new_readonly(accounts.collateral_mint, false),
+        AccountMeta::new_readonly(accounts.principal_mint, false),
+        AccountMeta::new(accounts.borrower_collateral_account, false),
+        AccountMeta::new(accounts.loan, false),
+        AccountMeta::new(accounts.vault, false),
+    ];
+
+    Instruction { program_id: get_program_id(), accounts: metas, data }
+}
+
+pub struct FundLoanAccounts {
+    pub token_program: Pubkey,
+    pub lender: Pubkey,
+    pub borrower: Pubkey,
+    pub principal_mint: Pubkey,
+    pub lender_principal_account: Pubkey,
+    pub borrower_principal_account: Pubkey,
+    pub loan: Pubkey,
+}
+
+pub fn build_fund_loan_instruction(accounts: FundLoanAccounts) -> Instruction {
+    let data = get_fund_loan_discriminator();
+    let metas = vec![
+        AccountMeta::new_readonly(accounts.token_program, false),
+        AccountMeta::new(accounts.lender, true),
+        AccountMeta::new_readonly(accounts.borrower, false),
+        AccountMeta::new_readonly(accounts.principal_mint, false),
+        AccountMeta::new(accounts.lender_principal_account, false),
+        AccountMeta::new(accounts.borrower_principal_account, false),
+        AccountMeta::new(accounts.loan, false),
+    ];
+    Instruction { program_id: get_program_id(), accounts: metas, data }
+}
+
+pub struct RepayLoanAccounts {
+    pub token_program: Pubkey,
+    pub borrower: Pubkey,
+    pub lender: Pubkey,
+    pub collateral_mint: Pubkey,
+    pub principal_mint: Pubkey,
+    pub borrower_principal_account: Pubkey,
+    pub lender_principal_account: Pubkey,
+    pub borrower_collateral_account: Pubkey,
+    pub loan: Pubkey,
+    pub vault: Pubkey,
+}
+
+pub fn build_repay_loan_instruction(accounts: RepayLoanAccounts) -> Instruction {
+    let data = get_repay_loan_discriminator();
+    let metas = vec![
+        AccountMeta::new_readonly(accounts.token_program, false),
+        AccountMeta::new(accounts.borrower, true),
+        AccountMeta::new_readonly(accounts.lender, false),
+        AccountMeta::new_readonly(accounts.collateral_mint, false),
+        AccountMeta::new_readonly(accounts.principal_mint, false),
+        AccountMeta::new(accounts.borrower_principal_account, false),
+        AccountMeta::new(accounts.lender_principal_account, false),
+        AccountMeta::new(accounts.borrower_collateral_account, false),
+        AccountMeta::new(accounts.loan, false),
+        AccountMeta::new(accounts.vault, false),
+    ];
+    Instruction { program_id: get_program_id(), accounts: metas, data }
+}
+
+pub struct ClaimCollateralAccounts {
+    pub token_program: Pubkey,
+    pub lender: Pubkey,
+    pub borrower: Pubkey,
+    pub collateral_mint: Pubkey,
+    pub lender_collateral_account: Pubkey,
+    pub loan: Pubkey,
+    pub vault: Pubkey,
+}
+
+pub fn build_claim_collateral_instruction(accounts: ClaimCollateralAccounts) -> Instruction {
+    let data = get_claim_collateral_discriminator();
+    let metas = vec![
+        AccountMeta::new_readonly(accounts.token_program, false),
+        AccountMeta::new(accounts.lender, true),
+        AccountMeta::new(accounts.borrower, false),
+        AccountMeta::new_readonly(accounts.collateral_mint, false),
+        AccountMeta::new(accounts.lender_collateral_account, false),
+        AccountMeta::new(accounts.loan, false),
+        AccountMeta::new(accounts.vault, false),
+    ];
+    Instruction { program_id: get_program_id(), accounts: metas, data }
+}
+
+/// Executes request_loan with Alice borrowing token B against token A collateral.
+/// Returns the loan and vault addresses.
+pub fn execute_request_loan(
+    test_env: &mut EscrowTestEnvironment,
+    loan_id: u64,
+    collateral_amount: u64,
+    principal_amount: u64,
+    interest_amount: u64,
+    duration: i64,
+) -> Result<(Pubkey, Pubkey), SolanaKiteError> {
+    let (loan, _loan_bump) = get_pda_and_bump(
+        &[b"loan".as_ref().into(), loan_id.to_le_bytes().as_ref().into()],
+        &test_env.program_id,
+    );
+    let vault =
+        anchor_spl::associated_token::get_associated_token_address(&loan, &test_env.token_mint_a);
+
+    let request_loan_instruction = build_request_loan_instruction(
+        loan_id,
+        collateral_amount,
+        principal_amount,
+        interest_amount,
+        duration,
+        RequestLoanAccounts {
+            associated_token_program: anchor_spl::associated_token::ID,
+            token_program: anchor_spl::token::ID,
+            system_program: anchor_lang::system_program::ID,
+            borrower: test_env.alice.pubkey(),
+            collateral_mint: test_env.token_mint_a,
+            principal_mint: test_env.token_mint_b,
+            borrower_collateral_account: test_env.alice_token_account_a,
+            loan,
+            vault,
+        },
+    );
+    send_transaction_from_instructions(
+        &mut test_env.litesvm,
+        vec![request_loan_instruction],
+        &[&test_env.alice],
+        &test_env.alice.pubkey(),
+    )?;
+
+    Ok((loan, vault))
+}
+
+/// Executes fund_loan with Bob lending to Alice
+pub fn execute_fund_loan(
+    test_env: &mut EscrowTestEnvironment,
+    loan: Pubkey,
+) -> Result<(), SolanaKiteError> {
+    let fund_loan_instruction = build_fund_loan_instruction(FundLoanAccounts {
+        token_program: anchor_spl::token::ID,
+        lender: test_env.bob.pubkey(),
+        borrower: test_env.alice.pubkey(),
+        principal_mint: test_env.token_mint_b,
+        lender_principal_account: test_env.bob_token_account_b,
+        borrower_principal_account: test_env.alice_token_account_b,
+        loan,
+    });
+    send_transaction_from_instructions(
+        &mut test_env.litesvm,
+        vec![fund_loan_instruction],
+        &[&test_env.bob],
+        &test_env.bob.pubkey(),
+    )
+}
+
+/// Executes request_loan then fund_loan, with Alice borrowing from Bob.
+/// Returns the loan and vault addresses.
+pub fn execute_request_and_fund_loan(
+    test_env: &mut EscrowTestEnvironment,
+    loan_id: u64,
+    collateral_amount: u64,
+    principal_amount: u64,
+    interest_amount: u64,
+    duration: i64,
+) -> Result<(Pubkey, Pubkey), SolanaKiteError> {
+    let (loan, vault) = execute_request_loan(
+        test_env,
+        loan_id,
+        collateral_amount,
+        principal_amount,
+        interest_amount,
+        duration,
+    )?;
+    execute_fund_loan(test_env, loan)?;
+
+    Ok((loan, vault))
+}
+
+pub struct WriteOptionAccounts {
+    pub associated_token_program: Pubkey,
+    pub token_program: Pubkey,
+    pub system_program: Pubkey,
+    pub writer: Pubkey,
+    pub underlying_mint: Pubkey,
+    pub quote_mint: Pubkey,
+    pub writer_underlying_account: Pubkey,
+    pub proceeds_account: Pubkey,
+    pub call_option: Pubkey,
+    pub vault: Pubkey,
+    pub option_mint: Pubkey,
+}
+
+pub fn build_write_option_instruction(
+    id: u64,
+    underlying_amount: u64,
+    strike_amount: u64,
+    premium_amount: u64,
+    expiry_ts: i64,
+    accounts: WriteOptionAccounts,
+) -> Instruction {
+    let mut data = get_write_option_discriminator();
+    data.extend_from_slice(&id.to_le_bytes());
+    data.extend_from_slice(&underlying_amount.to_le_bytes());
+    data.extend_from_slice(&strike_amount.to_le_bytes());
+    data.extend_from_slice(&premium_amount.to_le_bytes());
+    data.extend_from_slice(&expiry_ts.to_le_bytes());
+
+    let metas = vec![
+        AccountMeta::new_readonly(accounts.associated_token_program, false),
+        AccountMeta::new_readonly(accounts.token_program, false),
+        AccountMeta::new_readonly(accounts.system_program, false),
+        AccountMeta::new(accounts.writer, true),
+        AccountMeta::new_readonly(accounts.underlying_mint, false),
+        AccountMeta::new_readonly(accounts.quote_mint, false),
+        AccountMeta::new(accounts.writer_underlying_account, false),
+        AccountMeta::new_readonly(accounts.proceeds_account, false),
+        AccountMeta::new(accounts.call_option, false),
+        AccountMeta::new(get_nonce_counter(&accounts.call_option), false),
+        AccountMeta::new(accounts.vault, false),
+        AccountMeta::new(accounts.option_mint, false),
+    ];
+
+    Instruction { program_id: get_program_id(), accounts: metas, data }
+}
+
+pub struct BuyOptionAccounts {
+    pub token_program: Pubkey,
+    pub buyer: Pubkey,
+    pub quote_mint: Pubkey,
+    pub buyer_quote_account: Pubkey,
+    pub buyer_option_account: Pubkey,
+    pub proceeds_account: Pubkey,
+    pub option_mint: Pubkey,
+    pub call_option: Pubkey,
+}
+
+pub fn build_buy_option_instruction(accounts: BuyOptionAccounts) -> Instruction {
+    let data = get_buy_option_discriminator();
+    let metas = vec![
+        AccountMeta::new_readonly(accounts.token_program, false),
+        AccountMeta::new(accounts.buyer, true),
+        AccountMeta::new_readonly(accounts.quote_mint, false),
+        AccountMeta::new(accounts.buyer_quote_account, false),
+        AccountMeta::new(accounts.buyer_option_account, false),
+        AccountMeta::new(accounts.proceeds_account, false),
+        AccountMeta::new(accounts.option_mint, false),
+        AccountMeta::new(accounts.call_option, false),
+    ];
+    Instruction { program_id: get_program_id(), accounts: metas, data }
+}
+
+pub struct ExerciseOptionAccounts {
+    pub token_program: Pubkey,
+    pub holder: Pubkey,
+    pub writer: Pubkey,
+    pub underlying_mint: Pubkey,
+    pub quote_mint: Pubkey,
+    pub option_mint: Pubkey,
+    pub holder_option_account: Pubkey,
+    pub holder_quote_account: Pubkey,
+    pub holder_underlying_account: Pubkey,
+    pub proceeds_account: Pubkey,
+    pub call_option: Pubkey,
+    pub vault: Pubkey,
+}
+
+pub fn build_exercise_option_instruction(accounts: ExerciseOptionAccounts) -> Instruction {
+    let data = get_exercise_option_discriminator();
+    let metas = vec![
+        AccountMeta::new_readonly(accounts.token_program, false),
+        AccountMeta::new(accounts.holder, true),
+        AccountMeta::new(accounts.writer, false),
+        AccountMeta::new_readonly(accounts.underlying_mint, false),
+        AccountMeta::new_readonly(accounts.quote_mint, false),
+        AccountMeta::new(accounts.option_mint, false),
+        AccountMeta::new(accounts.holder_option_account, false),
+        AccountMeta::new(accounts.holder_quote_account, false),
+        AccountMeta::new(accounts.holder_underlying_account, false),
+        AccountMeta::new(accounts.proceeds_account, false),
+        AccountMeta::new(accounts.call_option, false),
+        AccountMeta::new(accounts.vault, false),
+    ];
+    Instruction { program_id: get_program_id(), accounts: metas, data }
+}
+
+pub struct ReclaimOptionAccounts {
+    pub token_program: Pubkey,
+    pub writer: Pubkey,
+    pub underlying_mint: Pubkey,
+    pub writer_underlying_account: Pubkey,
+    pub call_option: Pubkey,
+    pub vault: Pubkey,
+}
+
+pub fn build_reclaim_option_instruction(accounts: ReclaimOptionAccounts) -> Instruction {
+    let data = get_reclaim_option_discriminator();
+    let metas = vec![
+        AccountMeta::new_readonly(accounts.token_program, false),
+        AccountMeta::new(accounts.writer, true),
+        AccountMeta::new_readonly(accounts.underlying_mint, false),
+        AccountMeta::new(accounts.writer_underlying_account, false),
+        AccountMeta::new(accounts.call_option, false),
+        AccountMeta::new(accounts.vault, false),
+    ];
+    Instruction { program_id: get_program_id(), accounts: metas, data }
+}
+
+pub struct CreateHtlcAccounts {
+    pub associated_token_program: Pubkey,
+    pub token_program: Pubkey,
+    pub system_program: Pubkey,
+    pub maker: Pubkey,
+    pub token_mint: Pubkey,
+    pub maker_token_account: Pubkey,
+    pub htlc: Pubkey,
+    pub vault: Pubkey,
+}
+
+pub fn build_create_htlc_instruction(
+    id: u64,
+    counterparty: Pubkey,
+    amount: u64,
+    hashlock: [u8; 32],
+    timelock_ts: i64,
+    accounts: CreateHtlcAccounts,
+) -> Instruction {
+    let mut data = get_create_htlc_discriminator();
+    data.extend_from_slice(&id.to_le_bytes());
+    data.extend_from_slice(counterparty.as_ref());
+    data.extend_from_slice(&amount.to_le_bytes());
+    data.extend_from_slice(&hashlock);
+    data.extend_from_slice(&timelock_ts.to_le_bytes());
+
+    let metas = vec![
+        AccountMeta::new_readonly(accounts.associated_token_program, false),
+        AccountMeta::new_readonly(accounts.token_program, false),
+        AccountMeta::new_readonly(accounts.system_program, false),
+        AccountMeta::new(accounts.maker, true),
+        AccountMeta::new_readonly(accounts.token_mint, false),
+        AccountMeta::new(accounts.maker_token_account, false),
+        AccountMeta::new(accounts.htlc, false),
+        AccountMeta::new(accounts.vault, false),
+    ];
+
+    Instruction { program_id: get_program_id(), accounts: metas, data }
+}
+
+pub struct ClaimHtlcAccounts {
+    pub token_program: Pubkey,
+    pub counterparty: Pubkey,
+    pub maker: Pubkey,
+    pub token_mint: Pubkey,
+    pub recipient: Pubkey,
+    pub htlc: Pubkey,
+    pub vault: Pubkey,
+}
+
+pub fn build_claim_htlc_instruction(
+    preimage: [u8; 32],
+    accounts: ClaimHtlcAccounts,
+) -> Instruction {
+    let mut data = get_claim_htlc_discriminator();
+    data.extend_from_slice(&preimage);
+
+    let metas = vec![
+        AccountMeta::new_readonly(accounts.token_program, false),
+        AccountMeta::new(accounts.counterparty, true),
+        AccountMeta::new(accounts.maker, false),
+        AccountMeta::new_readonly(accounts.token_mint, false),
+        AccountMeta::new(accounts.recipient, false),
+        AccountMeta::new(accounts.htlc, false),
+        AccountMeta::new(accounts.vault, false),
+    ];
+    Instruction { program_id: get_program_id(), accounts: metas, data }
+}
+
+pub struct RefundHtlcAccounts {
+    pub token_program: Pubkey,
+    pub maker: Pubkey,
+    pub token_mint: Pubkey,
+    pub maker_token_account: Pubkey,
+    pub htlc: Pubkey,
+    pub vault: Pubkey,
+}
+
+pub fn build_refund_htlc_instruction(accounts: RefundHtlcAccounts) -> Instruction {
+    let data = get_refund_htlc_discriminator();
+    let metas = vec![
+        AccountMeta::new_readonly(accounts.token_program, false),
+        AccountMeta::new(accounts.maker, true),
+        AccountMeta::new_readonly(accounts.token_mint, false),
+        AccountMeta::new(accounts.maker_token_account, false),
+        AccountMeta::new(accounts.htlc, false),
+        AccountMeta::new(accounts.vault, false),
+    ];
+    Instruction { program_id: get_program_id(), accounts: metas, data }
+}
+
+/// Token accounts for token streams. Leave them out with `None` for lamport streams
+pub struct StreamTokenAccounts {
+    pub token_program: Pubkey,
+    pub token_mint: Pubkey,
+    pub sender_token_account: Pubkey,
+    pub recipient_token_account: Pubkey,
+    pub vault: Pubkey,
+}
+
+pub struct CreateStreamAccounts {
+    pub sender: Pubkey,
+    pub stream: Pubkey,
+    pub token_accounts: Option<StreamTokenAccounts>,
+}
+
+pub fn build_create_stream_instruction(
+    id: u64,
+    recipient: Pubkey,
+    amount: u64,
+    start_ts: i64,
+    end_ts: i64,
+    accounts: CreateStreamAccounts,
+) -> Instruction {
+    let mut data = get_create_stream_discriminator();
+    data.extend_from_slice(&id.to_le_bytes());
+    data.extend_from_slice(recipient.as_ref());
+    data.extend_from_slice(&amount.to_le_bytes());
+    data.extend_from_slice(&start_ts.to_le_bytes());
+    data.extend_from_slice(&end_ts.to_le_bytes());
+
+    let token_accounts = accounts.token_accounts.as_ref();
+    let metas = vec![
+        optional_readonly_account_meta(token_accounts.map(|_| spl_associated_token_account::ID)),
+        optional_readonly_account_meta(token_accounts.map(|t| t.token_program)),
+        AccountMeta::new_readonly(anchor_lang::system_program::ID, false),
+        AccountMeta::new(accounts.sender, true),
+        optional_readonly_account_meta(token_accounts.map(|t| t.token_mint)),
+        optional_account_meta(token_accounts.map(|t| t.sender_token_account)),
+        AccountMeta::new(accounts.stream, false),
+        optional_account_meta(token_accounts.map(|t| t.vault)),
+    ];
+
+    Instruction { program_id: get_program_id(), accounts: metas, data }
+}
+
+pub struct WithdrawStreamAccounts {
+    pub recipient: Pubkey,
+    pub stream: Pubkey,
+    pub token_accounts: Option<StreamTokenAccounts>,
+}
+
+pub fn build_withdraw_stream_instruction(accounts: WithdrawStreamAccounts) -> Instruction {
+    let data = get_withdraw_stream_discriminator();
+    let token_accounts = accounts.token_accounts.as_ref();
+    let metas = vec![
+        optional_readonly_account_meta(token_accounts.map(|t| t.token_program)),
+        AccountMeta::new_readonly(anchor_lang::system_program::ID, false),
+        AccountMeta::new(accounts.recipient, true),
+        optional_readonly_account_meta(token_accounts.map(|t| t.token_mint)),
+        optional_account_meta(token_accounts.map(|t| t.recipient_token_account)),
+        AccountMeta::new(accounts.stream, false),
+        optional_account_meta(token_accounts.map(|t| t.vault)),
+    ];
+    Instruction { program_id: get_program_id(), accounts: metas, data }
+}
+
+pub struct CancelStreamAccounts {
+    pub caller: Pubkey,
+    pub sender: Pubkey,
+    pub recipient: Pubkey,
+    pub stream: Pubkey,
+    pub token_accounts: Option<StreamTokenAccounts>,
+}
+
+pub fn build_cancel_stream_instruction(accounts: CancelStreamAccounts) -> Instruction {
+    let data = get_cancel_stream_discriminator();
+    let token_accounts = accounts.token_accounts.as_ref();
+    let metas = vec![
+        optional_readonly_account_meta(token_accounts.map(|t| t.token_program)),
+        AccountMeta::new_readonly(anchor_lang::system_program::ID, false),
+        AccountMeta::new_readonly(accounts.caller, true),
+        AccountMeta::new(accounts.sender, false),
+        AccountMeta::new(accounts.recipient, false),
+        optional_readonly_account_meta(token_accounts.map(|t| t.token_mint)),
+        optional_account_meta(token_accounts.map(|t| t.sender_token_account)),
+        optional_account_meta(token_accounts.map(|t| t.recipient_token_account)),
+        AccountMeta::new(accounts.stream, false),
+        optional_account_meta(token_accounts.map(|t| t.vault)),
+    ];
+    Instruction { program_id: get_program_id(), accounts: metas, data }
+}
+
+pub struct CreateCampaignAccounts {
+    pub associated_token_program: Pubkey,
+    pub token_program: Pubkey,
+    pub system_program: Pubkey,
+    pub creator: Pubkey,
+    pub token_mint: Pubkey,
+    pub campaign: Pubkey,
+    pub vault: Pubkey,
+}
+
+pub fn build_create_campaign_instruction(
+    id: u64,
+    target_amount: u64,
+    deadline_ts: i64,
+    accounts: CreateCampaignAccounts,
+) -> Instruction {
+    let mut data = get_create_campaign_discriminator();
+    data.extend_from_slice(&id.to_le_bytes());
+    data.extend_from_slice(&target_amount.to_le_bytes());
+    data.extend_from_slice(&deadline_ts.to_le_bytes());
+
+    let metas = vec![
+        AccountMeta::new_readonly(accounts.associated_token_program, false),
+        AccountMeta::new_readonly(accounts.token_program, false),
+        AccountMeta::new_readonly(accounts.system_program, false),
+        AccountMeta::new(accounts.creator, true),
+        AccountMeta::new_readonly(accounts.token_mint, false),
+        AccountMeta::new(accounts.campaign, false),
+        AccountMeta::new(get_nonce_counter(&accounts.campaign), false),
+        AccountMeta::new(accounts.vault, false),
+    ];
+
+    Instruction { program_id: get_program_id(), accounts: metas, data }
+}
+
+pub struct ContributeAccounts {
+    pub token_program: Pubkey,
+    pub system_program: Pubkey,
+    pub contributor: Pubkey,
+    pub token_mint: Pubkey,
+    pub contributor_token_account: Pubkey,
+    pub campaign: Pubkey,
+    pub contribution: Pubkey,
+    pub vault: Pubkey,
+}
+
+pub fn build_contribute_instruction(amount: u64, accounts: ContributeAccounts) -> Instruction {
+    let mut data = get_contribute_discriminator();
+    data.extend_from_slice(&amount.to_le_bytes());
+
+    let metas = vec![
+        AccountMeta::new_readonly(accounts.token_program, false),
+        AccountMeta::new_readonly(accounts.system_program, false),
+        AccountMeta::new(accounts.contributor, true),
+        AccountMeta::new_readonly(accounts.token_mint, false),
+        AccountMeta::new(accounts.contributor_token_account, false),
+        AccountMeta::new(accounts.campaign, false),
+        AccountMeta::new(accounts.contribution, false),
+        AccountMeta::new(accounts.vault, false),
+    ];
+    Instruction { program_id: get_program_id(), accounts: metas, data }
+}
+
+pub struct WithdrawCampaignFundsAccounts {
+    pub token_program: Pubkey,
+    pub creator: Pubkey,
+    pub token_mint: Pubkey,
+    pub proceeds_account: Pubkey,
+    pub campaign: Pubkey,
+    pub vault: Pubkey,
+}
+
+pub fn build_withdraw_campaign_funds_instruction(
+    accounts: WithdrawCampaignFundsAccounts,
+) -> Instruction {
+    let data = get_withdraw_campaign_funds_discriminator();
+    let metas = vec![
+        AccountMeta::new_readonly(accounts.token_program, false),
+        AccountMeta::new(accounts.creator, true),
+        AccountMeta::new_readonly(accounts.token_mint, false),
+        AccountMeta::new(accounts.proceeds_account, false),
+        AccountMeta::new(accounts.campaign, false),
+        AccountMeta::new(accounts.vault, false),
+    ];
+    Instruction { program_id: get_program_id(), accounts: metas, data }
+}
+
+pub struct RefundContributionAccounts {
+    pub token_program: Pubkey,
+    pub contributor: Pubkey,
+    pub token_mint: Pubkey,
+    pub contributor_token_account: Pubkey,
+    pub creator: Pubkey,
+    pub campaign: Pubkey,
+    pub contribution: Pubkey,
+    pub vault: Pubkey,
+}
+
+pub fn build_refund_contribution_instruction(accounts: RefundContributionAccounts) -> Instruction {
+    let data = get_refund_contribution_discriminator();
+    let metas = vec![
+        AccountMeta::new_readonly(accounts.token_program, false),
+        AccountMeta::new(accounts.contributor, true),
+        AccountMeta::new_readonly(accounts.token_mint, false),
+        AccountMeta::new(accounts.contributor_token_account, false),
+        AccountMeta::new(accounts.creator, false),
+        AccountMeta::new(accounts.campaign, false),
+        AccountMeta::new(accounts.contribution, false),
+        AccountMeta::new(accounts.vault, false),
+    ];
+    Instruction { program_id: get_program_id(), accounts: metas, data }
+}
+
+pub fn build_close_contribution_instruction(
+    contributor: Pubkey,
+    campaign: Pubkey,
+    contribution: Pubkey,
+) -> Instruction {
+    let data = get_close_contribution_discriminator();
+    let metas = vec![
+        AccountMeta::new(contributor, true),
+        AccountMeta::new_readonly(campaign, false),
+        AccountMeta::new(contribution, false),
+    ];
+    Instruction { program_id: get_program_id(), accounts: metas, data }
+}
+
+pub struct CloseCampaignAccounts {
+    pub token_program: Pubkey,
+    pub creator: Pubkey,
+    pub token_mint: Pubkey,
+    pub proceeds_account: Pubkey,
+    pub campaign: Pubkey,
+    pub vault: Pubkey,
+}
+
+pub fn build_close_campaign_instruction(accounts: CloseCampaignAccounts) -> Instruction {
+    let data = get_close_campaign_discriminator();
+    let metas = vec![
+        AccountMeta::new_readonly(accounts.token_program, false),
+        AccountMeta::new(accounts.creator, true),
+        AccountMeta::new_readonly(accounts.token_mint, false),
+        AccountMeta::new(accounts.proceeds_account, false),
+        AccountMeta::new(accounts.campaign, false),
+        AccountMeta::new(accounts.vault, false),
+    ];
+    Instruction { program_id: get_program_id(), accounts: metas, data }
+}
+
+pub struct CreateBountyAccounts {
+    pub associated_token_program: Pubkey,
+    pub token_program: Pubkey,
+    pub system_program: Pubkey,
+    pub sponsor: Pubkey,
+    pub token_mint: Pubkey,
+    pub sponsor_token_account: Pubkey,
+    pub bounty: Pubkey,
+    pub vault: Pubkey,
+}
+
+pub fn build_create_bounty_instruction(
+    id: u64,
+    judge: Pubkey,
+    reward_amount: u64,
+    deadline_ts: i64,
+    award_deadline_ts: i64,
+    accounts: CreateBountyAccounts,
+) -> Instruction {
+    let mut data = get_create_bounty_discriminator();
+    data.extend_from_slice(&id.to_le_bytes());
+    data.extend_from_slice(judge.as_ref());
+    data.extend_from_slice(&reward_amount.to_le_bytes());
+    data.extend_from_slice(&deadline_ts.to_le_bytes());
+    data.extend_from_slice(&award_deadline_ts.to_le_bytes());
+
+    let metas = vec![
+        AccountMeta::new_readonly(accounts.associated_token_program, false),
+        AccountMeta::new_readonly(accounts.token_program, false),
+        AccountMeta::new_readonly(accounts.system_program, false),
+        AccountMeta::new(accounts.sponsor, true),
+        AccountMeta::new_readonly(accounts.token_mint, false),
+        AccountMeta::new(accounts.sponsor_token_account, false),
+        AccountMeta::new(accounts.bounty, false),
+        AccountMeta::new(get_nonce_counter(&accounts.bounty), false),
+        AccountMeta::new(accounts.vault, false),
+    ];
+
+    Instruction { program_id: get_program_id(), accounts: metas, data }
+}
+
+pub struct SubmitClaimAccounts {
+    pub system_program: Pubkey,
+    pub claimant: Pubkey,
+    pub bounty: Pubkey,
+    pub claim: Pubkey,
+}
+
+pub fn build_submit_claim_instruction(
+    submission: &str,
+    accounts: SubmitClaimAccounts,
+) -> Instruction {
+    let mut data = get_submit_claim_discriminator();
+    // Borsh strings are a u32 length followed by the bytes
+    data.extend_from_slice(&(submission.len() as u32).to_le_bytes());
+    data.extend_from_slice(submission.as_bytes());
+
+    let metas = vec![
+        AccountMeta::new_readonly(accounts.system_program, false),
+        AccountMeta::new(accounts.claimant, true),
+        AccountMeta::new_readonly(accounts.bounty, false),
+        AccountMeta::new(accounts.claim, false),
+    ];
+    Instruction { program_id: get_program_id(), accounts: metas, data }
+}
+
+pub struct AwardClaimAccounts {
+    pub token_program: Pubkey,
+    pub awarder: Pubkey,
+    pub claimant: Pubkey,
+    pub token_mint: Pubkey,
+    pub claimant_token_account: Pubkey,
+    pub bounty: Pubkey,
+    pub claim: Pubkey,
+    pub vault: Pubkey,
+}
+
+pub fn build_award_claim_instruction(amount: u64, accounts: AwardClaimAccounts) -> Instruction {
+    let mut data = get_award_claim_discriminator();
+    data.extend_from_slice(&amount.to_le_bytes());
+
+    let metas = vec![
+        AccountMeta::new_readonly(accounts.token_program, false),
+        AccountMeta::new_readonly(accounts.awarder, true),
+        AccountMeta::new(accounts.claimant, false),
+        AccountMeta::new_readonly(accounts.token_mint, false),
+        AccountMeta::new(accounts.claimant_token_account, false),
+        AccountMeta::new(accounts.bounty, false),
+        AccountMeta::new(accounts.claim, false),
+        AccountMeta::new(accounts.vault, false),
+    ];
+    Instruction { program_id: get_program_id(), accounts: metas, data }
+}
+
+pub struct CloseBountyAccounts {
+    pub token_program: Pubkey,
+    pub sponsor: Pubkey,
+    pub token_mint: Pubkey,
+    pub sponsor_token_account: Pubkey,
+    pub bounty: Pubkey,
+    pub vault: Pubkey,
+}
+
+pub fn build_close_bounty_instruction(accounts: CloseBountyAccounts) -> Instruction {
+    let data = get_close_bounty_discriminator();
+    let metas = vec![
+        AccountMeta::new_readonly(accounts.token_program, false),
+        AccountMeta::new(accounts.sponsor, true),
+        AccountMeta::new_readonly(accounts.token_mint, false),
+        AccountMeta::new(accounts.sponsor_token_account, false),
+        AccountMeta::new(accounts.bounty, false),
+        AccountMeta::new(accounts.vault, false),
+    ];
+    Instruction { program_id: get_program_id(), accounts: metas, data }
+}
+
+pub fn build_close_claim_instruction(
+    claimant: Pubkey,
+    bounty: Pubkey,
+    claim: Pubkey,
+) -> Instruction {
+    let data = get_close_claim_discriminator();
+    let metas = vec![
+        AccountMeta::new(claimant, true),
+        AccountMeta::new_readonly(bounty, false),
+        AccountMeta::new(claim, false),
+    ];
+    Instruction { program_id: get_program_id(), accounts: metas, data }
+}
+
+pub struct CreateListingAccounts {
+    pub associated_token_program: Pubkey,
+    pub token_program: Pubkey,
+    pub system_program: Pubkey,
+    pub seller: Pubkey,
+    pub token_mint: Pubkey,
+    pub listing: Pubkey,
+    pub vault: Pubkey,
+}
+
+pub fn build_create_listing_instruction(
+    id: u64,
+    price: u64,
+    arbiter: Pubkey,
+    confirmation_window_secs: i64,
+    response_window_secs: i64,
+    accounts: CreateListingAccounts,
+) -> Instruction {
+    let mut data = get_create_listing_discriminator();
+    data.extend_from_slice(&id.to_le_bytes());
+    data.extend_from_slice(&price.to_le_bytes());
+    data.extend_from_slice(arbiter.as_ref());
+    data.extend_from_slice(&confirmation_window_secs.to_le_bytes());
+    data.extend_from_slice(&response_window_secs.to_le_bytes());
+
+    let metas = vec![
+        AccountMeta::new_readonly(accounts.associated_token_program, false),
+        AccountMeta::new_readonly(accounts.token_program, false),
+        AccountMeta::new_readonly(accounts.system_program, false),
+        AccountMeta::new(accounts.seller, true),
+        AccountMeta::new_readonly(accounts.token_mint, false),
+        AccountMeta::new(accounts.listing, false),
+        AccountMeta::new(accounts.vault, false),
+    ];
+
+    Instruction { program_id: get_program_id(), accounts: metas, data }
+}
+
+pub struct PurchaseListingAccounts {
+    pub token_program: Pubkey,
+    pub buyer: Pubkey,
+    pub token_mint: Pubkey,
+    pub buyer_token_account: Pubkey,
+    pub listing: Pubkey,
+    pub vault: Pubkey,
+}
+
+pub fn build_purchase_listing_instruction(accounts: PurchaseListingAccounts) -> Instruction {
+    let data = get_purchase_listing_discriminator();
+    let metas = vec![
+        AccountMeta::new_readonly(accounts.token_program, false),
+        AccountMeta::new_readonly(accounts.buyer, true),
+        AccountMeta::new_readonly(accounts.token_mint, false),
+        AccountMeta::new(accounts.buyer_token_account, false),
+        AccountMeta::new(accounts.listing, false),
+        AccountMeta::new(accounts.vault, false),
+    ];
+    Instruction { program_id: get_program_id(), accounts: metas, data }
+}
+
+pub struct MarkShippedAccounts {
+    pub seller: Pubkey,
+    pub listing: Pubkey,
+}
+
+pub fn build_mark_shipped_instruction(accounts: MarkShippedAccounts) -> Instruction {
+    let data = get_mark_shipped_discriminator();
+    let metas = vec![
+        AccountMeta::new_readonly(accounts.seller, true),
+        AccountMeta::new(accounts.listing, false),
+    ];
+    Instruction { program_id: get_program_id(), accounts: metas, data }
+}
+
+pub struct ConfirmReceiptAccounts {
+    pub token_program: Pubkey,
+    pub caller: Pubkey,
+    pub seller: Pubkey,
+    pub token_mint: Pubkey,
+    pub seller_token_account: Pubkey,
+    pub listing: Pubkey,
+    pub vault: Pubkey,
+}
+
+pub fn build_confirm_receipt_instruction(accounts: ConfirmReceiptAccounts) -> Instruction {
+    let data = get_confirm_receipt_discriminator();
+    let metas = vec![
+        AccountMeta::new_readonly(accounts.token_program, false),
+        AccountMeta::new_readonly(accounts.caller, true),
+        AccountMeta::new(accounts.seller, false),
+        AccountMeta::new_readonly(accounts.token_mint, false),
+        AccountMeta::new(accounts.seller_token_account, false),
+        AccountMeta::new(accounts.listing, false),
+        AccountMeta::new(accounts.vault, false),
+    ];
+    Instruction { program_id: get_program_id(), accounts: metas, data }
+}
+
+pub fn build_open_dispute_instruction(buyer: Pubkey, listing: Pubkey) -> Instruction {
+    let data = get_open_dispute_discriminator();
+    let metas = vec![
+        AccountMeta::new_readonly(buyer, true),
+        AccountMeta::new(listing, false),
+    ];
+    Instruction { program_id: get_program_id(), accounts: metas, data }
+}
+
+pub struct ReclaimPaymentAccounts {
+    pub token_program: Pubkey,
+    pub buyer: Pubkey,
+    pub seller: Pubkey,
+    pub token_mint: Pubkey,
+    pub buyer_token_account: Pubkey,
+    pub listing: Pubkey,
+    pub vault: Pubkey,
+}
+
+pub fn build_reclaim_payment_instruction(accounts: ReclaimPaymentAccounts) -> Instruction {
+    let data = get_reclaim_payment_discriminator();
+    let metas = vec![
+        AccountMeta::new_readonly(accounts.token_program, false),
+        AccountMeta::new_readonly(accounts.buyer, true),
+        AccountMeta::new(accounts.seller, false),
+        AccountMeta::new_readonly(accounts.token_mint, false),
+        AccountMeta::new(accounts.buyer_token_account, false),
+        AccountMeta::new(accounts.listing, false),
+        AccountMeta::new(accounts.vault, false),
+    ];
+    Instruction { program_id: get_program_id(), accounts: metas, data }
+}
+
+/// Lists an item from Alice for 2 token B with one day confirmation and response windows,
+/// judged by `arbiter`, and has Bob buy it. Returns the listing and its vault
+pub fn execute_create_and_purchase_listing(
+    test_environment: &mut EscrowTestEnvironment,
+    arbiter: Pubkey,
+) -> (Pubkey, Pubkey) {
+    let listing_id = generate_offer_id();
+    let (listing, _listing_bump) = get_pda_and_bump(
+        &[
+            b"listing".as_ref().into(),
+            listing_id.to_le_bytes().as_ref().into(),
+        ],
+        &test_environment.program_id,
+    );
+    let vault = anchor_spl::associated_token::get_associated_token_address(
+        &listing,
+        &test_environment.token_mint_b,
+    );
+
+    let create_listing_instruction = build_create_listing_instruction(
+        listing_id,
+        2 * TOKEN_B,
+        arbiter,
+        60 * 60 * 24,
+        60 * 60 * 24,
+        CreateListingAccounts {
+            associated_token_program: anchor_spl::associated_token::ID,
+            token_program: anchor_spl::token::ID,
+            system_program: anchor_lang::system_program::ID,
+            seller: test_environment.alice.pubkey(),
+            token_mint: test_environment.token_mint_b,
+            listing,
+            vault,
+        },
+    );
+    send_transaction_from_instructions(
+        &mut test_environment.litesvm,
+        vec![create_listing_instruction],
+        &[&test_environment.alice],
+        &test_environment.alice.pubkey(),
+    )
+    .unwrap();
+
+    let purchase_listing_instruction =
+        build_purchase_listing_instruction(PurchaseListingAccounts {
+            token_program: anchor_spl::token::ID,
+            buyer: test_environment.bob.pubkey(),
+            token_mint: test_environment.token_mint_b,
+            buyer_token_account: test_environment.bob_token_account_b,
+            listing,
+            vault,
+        });
+    send_transaction_from_instructions(
+        &mut test_environment.litesvm,
+        vec![purchase_listing_instruction],
+        &[&test_environment.bob],
+        &test_environment.bob.pubkey(),
+    )
+    .unwrap();
+
+    (listing, vault)
+}
+
+pub struct ResolveDisputeAccounts {
+    pub token_program: Pubkey,
+    pub arbiter: Pubkey,
+    pub seller: Pubkey,
+    pub token_mint: Pubkey,
+    pub buyer_token_account: Pubkey,
+    pub seller_token_account: Pubkey,
+    pub listing: Pubkey,
+    pub vault: Pubkey,
+}
+
+pub fn build_resolve_dispute_instruction(
+    buyer_amount: u64,
+    accounts: ResolveDisputeAccounts,
+) -> Instruction {
+    let mut data = get_resolve_dispute_discriminator();
+    data.extend_from_slice(&buyer_amount.to_le_bytes());
+
+    let metas = vec![
+        AccountMeta::new_readonly(accounts.token_program, false),
+        AccountMeta::new_readonly(accounts.arbiter, true),
+        AccountMeta::new(accounts.seller, false),
+        AccountMeta::new_readonly(accounts.token_mint, false),
+        AccountMeta::new(accounts.buyer_token_account, false),
+        AccountMeta::new(accounts.seller_token_account, false),
+        AccountMeta::new(accounts.listing, false),
+        AccountMeta::new(accounts.vault, false),
+    ];
+    Instruction { program_id: get_program_id(), accounts: metas, data }
+}
@@ -0,0 +1,29 @@
+use anchor_lang::prelude::*;
+
+// Paid out games record their winner until they're closed with close_game, and cancelled
+// games are closed straight away, so these events keep the outcome for indexers afterwards
+
+// A game's result was paid out
+#[event]
+pub struct GameFinalized {
+    pub id: u64,
+    // The slot of the player who came first, or DRAW
+    pub winner: u8,
+}
+
+// A round of a series was won, recorded here too since the game's round_winners
+// are gone once the game is closed
+#[event]
+pub struct RoundRecorded {
+    pub id: u64,
+    // The round's number, starting from 1
+    pub round: u8,
+    // The slot of the player who won the round
+    pub winner: u8,
+}
+
+// A game was cancelled and every deposit refunded
+#[event]
+pub struct GameCancelled {
+    pub id: u64,
+}
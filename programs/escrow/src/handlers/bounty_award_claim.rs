@@ -0,0 +1,98 @@
+use super::shared::transfer_tokens;
+use crate::{
+    error::ErrorCode,
+    state::{Bounty, BountyClaim},
+};
+use anchor_lang::prelude::*;
+use anchor_spl::token_interface::{Mint, TokenAccount, TokenInterface};
+
+#[derive(Accounts)]
+pub struct AwardClaim<'info> {
+    // Work with either the classic token program or
+    // the newer token extensions program
+    pub token_program: Interface<'info, TokenInterface>,
+
+    // Either the sponsor or the judge
+    pub awarder: Signer<'info>,
+
+    // Gets the claim rent back when their claim is awarded
+    #[account(mut)]
+    pub claimant: SystemAccount<'info>,
+
+    pub token_mint: InterfaceAccount<'info, Mint>,
+
+    #[account(
+        mut,
+        token::mint = token_mint,
+        token::authority = claimant,
+        token::token_program = token_program
+    )]
+    pub claimant_token_account: InterfaceAccount<'info, TokenAccount>,
+
+    #[account(
+        mut,
+        has_one = token_mint,
+        constraint = awarder.key() == bounty.sponsor || awarder.key() == bounty.judge
+            @ ErrorCode::Unauthorized,
+        seeds = [b"bounty", bounty.id.to_le_bytes().as_ref()],
+        bump = bounty.bump
+    )]
+    pub bounty: Account<'info, Bounty>,
+
+    // Closed once awarded, so each claim can only be awarded once
+    #[account(
+        mut,
+        close = claimant,
+        has_one = bounty,
+        has_one = claimant,
+        constraint = claim.bounty_nonce == bounty.nonce @ ErrorCode::StaleClaim,
+        seeds = [b"claim", bounty.key().as_ref(), claimant.key().as_ref()],
+        bump = claim.bump
+    )]
+    pub claim: Account<'info, BountyClaim>,
+
+    #[account(
+        mut,
+        associated_token::mint = token_mint,
+        associated_token::authority = bounty,
+        associated_token::token_program = token_program
+    )]
+    pub vault: InterfaceAccount<'info, TokenAccount>,
+}
+
+// Handle the award claim instruction by paying the claimant their share of the reward.
+// To split the reward between several claims, send one award claim instruction for each
+pub fn award_claim(context: Context<AwardClaim>, amount: u64) -> Result<()> {
+    require!(amount > 0, ErrorCode::InvalidAmount);
+
+    let bounty = &context.accounts.bounty;
+    let now = Clock::get()?.unix_timestamp;
+    require!(now <= bounty.award_deadline_ts, ErrorCode::AwardDeadlinePassed);
+
+    let awarded_amount = bounty
+        .awarded_amount
+        .checked_add(amount)
+        .filter(|awarded_amount| *awarded_amount <= bounty.reward_amount)
+        .ok_or(ErrorCode::BountyRewardExceeded)?;
+
+    let bounty_account_seeds = &[
+        b"bounty",
+        &bounty.id.to_le_bytes()[..],
+        &[bounty.bump],
+    ];
+    let signers_seeds = Some(&bounty_account_seeds[..]);
+
+    transfer_tokens(
+        &context.accounts.vault,
+        &context.accounts.claimant_token_account,
+        &amount,
+        &context.accounts.token_mint,
+        &bounty.to_account_info(),
+        &context.accounts.token_program,
+        signers_seeds,
+    )
+    .map_err(|_| ErrorCode::FailedVaultWithdrawal)?;
+
+    context.accounts.bounty.awarded_amount = awarded_amount;
+    Ok(())
+}
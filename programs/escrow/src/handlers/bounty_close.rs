@@ -0,0 +1,85 @@
+use super::shared::{close_token_account, transfer_tokens};
+use crate::{error::ErrorCode, state::Bounty};
+use anchor_lang::prelude::*;
+use anchor_spl::token_interface::{Mint, TokenAccount, TokenInterface};
+
+#[derive(Accounts)]
+pub struct CloseBounty<'info> {
+    // Work with either the classic token program or
+    // the newer token extensions program
+    pub token_program: Interface<'info, TokenInterface>,
+
+    #[account(mut)]
+    pub sponsor: Signer<'info>,
+
+    pub token_mint: InterfaceAccount<'info, Mint>,
+
+    #[account(
+        mut,
+        token::mint = token_mint,
+        token::token_program = token_program
+    )]
+    pub sponsor_token_account: InterfaceAccount<'info, TokenAccount>,
+
+    #[account(
+        mut,
+        close = sponsor,
+        has_one = sponsor,
+        has_one = token_mint,
+        seeds = [b"bounty", bounty.id.to_le_bytes().as_ref()],
+        bump = bounty.bump
+    )]
+    pub bounty: Account<'info, Bounty>,
+
+    #[account(
+        mut,
+        associated_token::mint = token_mint,
+        associated_token::authority = bounty,
+        associated_token::token_program = token_program
+    )]
+    pub vault: InterfaceAccount<'info, TokenAccount>,
+}
+
+// Handle the close bounty instruction, once the award deadline has passed, by:
+// 1. Returning whatever hasn't been awarded from the vault to the sponsor
+// 2. Closing the vault and bounty accounts and returning the rent to the sponsor
+pub fn close_bounty(context: Context<CloseBounty>) -> Result<()> {
+    let now = Clock::get()?.unix_timestamp;
+    require!(
+        now > context.accounts.bounty.award_deadline_ts,
+        ErrorCode::AwardDeadlineNotPassed
+    );
+
+    let bounty_account_seeds = &[
+        b"bounty",
+        &context.accounts.bounty.id.to_le_bytes()[..],
+        &[context.accounts.bounty.bump],
+    ];
+    let signers_seeds = Some(&bounty_account_seeds[..]);
+
+    // We move everything in the vault, including anything else sent to it,
+    // so the vault is empty and can be closed
+    if context.accounts.vault.amount > 0 {
+        transfer_tokens(
+            &context.accounts.vault,
+            &context.accounts.sponsor_token_account,
+            &context.accounts.vault.amount,
+            &context.accounts.token_mint,
+            &context.accounts.bounty.to_account_info(),
+            &context.accounts.token_program,
+            signers_seeds,
+        )
+        .map_err(|_| ErrorCode::FailedRefundTransfer)?;
+    }
+
+    close_token_account(
+        &context.accounts.vault,
+        &context.accounts.sponsor.to_account_info(),
+        &context.accounts.bounty.to_account_info(),
+        &context.accounts.token_program,
+        signers_seeds,
+    )
+    .map_err(|_| ErrorCode::FailedRefundClosure)?;
+
+    Ok(())
+}
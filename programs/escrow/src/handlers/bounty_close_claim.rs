@@ -0,0 +1,42 @@
+use crate::{
+    error::ErrorCode,
+    state::{Bounty, BountyClaim},
+};
+use anchor_lang::prelude::*;
+
+#[derive(Accounts)]
+pub struct CloseClaim<'info> {
+    #[account(mut)]
+    pub claimant: Signer<'info>,
+
+    // The bounty the claim was for, which may since have been closed or replaced
+    /// CHECK: only read to see whether the claim can still be awarded
+    #[account(address = claim.bounty)]
+    pub bounty: UncheckedAccount<'info>,
+
+    #[account(
+        mut,
+        close = claimant,
+        has_one = claimant,
+        seeds = [b"claim", bounty.key().as_ref(), claimant.key().as_ref()],
+        bump = claim.bump
+    )]
+    pub claim: Account<'info, BountyClaim>,
+}
+
+// Handle the close claim instruction by closing a claim that can no longer be awarded,
+// because its bounty has been closed, its award deadline has passed, or its whole reward
+// has gone to other claims, returning the rent to the claimant
+pub fn close_claim(context: Context<CloseClaim>) -> Result<()> {
+    let bounty = &context.accounts.bounty;
+    if bounty.owner == &crate::ID && !bounty.data_is_empty() {
+        let bounty = Bounty::try_deserialize(&mut &bounty.try_borrow_data()?[..])?;
+        require!(
+            bounty.nonce != context.accounts.claim.bounty_nonce
+                || bounty.awarded_amount == bounty.reward_amount
+                || Clock::get()?.unix_timestamp > bounty.award_deadline_ts,
+            ErrorCode::ClaimStillActive
+        );
+    }
+    Ok(())
+}
@@ -0,0 +1,128 @@
+use super::shared::transfer_tokens;
+use crate::{
+    error::ErrorCode,
+    state::{Bounty, NonceCounter},
+};
+use anchor_lang::prelude::*;
+use anchor_spl::{
+    associated_token::AssociatedToken,
+    token_interface::{Mint, TokenAccount, TokenInterface},
+};
+
+#[derive(Accounts)]
+#[instruction(id: u64)]
+pub struct CreateBounty<'info> {
+    // Used to manage associated token accounts
+    // ie where a wallet holds a specific type of token
+    pub associated_token_program: Program<'info, AssociatedToken>,
+
+    // Work with either the classic token program or
+    // the newer token extensions program
+    pub token_program: Interface<'info, TokenInterface>,
+
+    // Used to create accounts
+    pub system_program: Program<'info, System>,
+
+    #[account(mut)]
+    pub sponsor: Signer<'info>,
+
+    #[account(mint::token_program = token_program)]
+    pub token_mint: InterfaceAccount<'info, Mint>,
+
+    #[account(
+        mut,
+        associated_token::mint = token_mint,
+        associated_token::authority = sponsor,
+        associated_token::token_program = token_program
+    )]
+    pub sponsor_token_account: InterfaceAccount<'info, TokenAccount>,
+
+    #[account(
+        init,
+        payer = sponsor,
+        space = Bounty::DISCRIMINATOR.len() + Bounty::INIT_SPACE,
+        seeds = [b"bounty", id.to_le_bytes().as_ref()],
+        bump
+    )]
+    pub bounty: Account<'info, Bounty>,
+
+    // Hands out the bounty's nonce
+    #[account(
+        init_if_needed,
+        payer = sponsor,
+        space = NonceCounter::DISCRIMINATOR.len() + NonceCounter::INIT_SPACE,
+        seeds = [b"nonce_counter", bounty.key().as_ref()],
+        bump
+    )]
+    pub nonce_counter: Account<'info, NonceCounter>,
+
+    #[account(
+        init,
+        payer = sponsor,
+        associated_token::mint = token_mint,
+        associated_token::authority = bounty,
+        associated_token::token_program = token_program
+    )]
+    pub vault: InterfaceAccount<'info, TokenAccount>,
+}
+
+// Handle the create bounty instruction by:
+// 1. Moving the reward from the sponsor's ATA to the vault
+// 2. Saving the details of the bounty to the bounty account
+pub fn create_bounty(
+    context: Context<CreateBounty>,
+    id: u64,
+    judge: Pubkey,
+    reward_amount: u64,
+    deadline_ts: i64,
+    award_deadline_ts: i64,
+) -> Result<()> {
+    require!(reward_amount > 0, ErrorCode::InvalidAmount);
+
+    let now = Clock::get()?.unix_timestamp;
+    require!(deadline_ts > now, ErrorCode::BountyEnded);
+    require!(
+        award_deadline_ts > deadline_ts,
+        ErrorCode::InvalidAwardDeadline
+    );
+
+    transfer_tokens(
+        &context.accounts.sponsor_token_account,
+        &context.accounts.vault,
+        &reward_amount,
+        &context.accounts.token_mint,
+        &context.accounts.sponsor.to_account_info(),
+        &context.accounts.token_program,
+        None,
+    )
+    .map_err(|_| ErrorCode::InsufficientMakerBalance)?;
+
+    // Mints with a transfer fee deliver less than was sent, so the reward is
+    // whatever actually reached the vault
+    let vault = &mut context.accounts.vault;
+    let balance_before = vault.amount;
+    vault.reload()?;
+    let reward_amount = vault
+        .amount
+        .checked_sub(balance_before)
+        .ok_or(ErrorCode::VaultShortfall)?;
+    require!(reward_amount > 0, ErrorCode::InvalidAmount);
+
+    let nonce_counter = &mut context.accounts.nonce_counter;
+    nonce_counter.bump = context.bumps.nonce_counter;
+    let nonce = nonce_counter.next_nonce()?;
+
+    context.accounts.bounty.set_inner(Bounty {
+        id,
+        sponsor: context.accounts.sponsor.key(),
+        judge,
+        token_mint: context.accounts.token_mint.key(),
+        reward_amount,
+        awarded_amount: 0,
+        deadline_ts,
+        award_deadline_ts,
+        nonce,
+        bump: context.bumps.bounty,
+    });
+    Ok(())
+}
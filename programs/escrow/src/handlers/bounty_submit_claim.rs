@@ -0,0 +1,55 @@
+use crate::{
+    constants::MAX_SUBMISSION_LENGTH,
+    error::ErrorCode,
+    state::{Bounty, BountyClaim},
+};
+use anchor_lang::prelude::*;
+
+#[derive(Accounts)]
+pub struct SubmitClaim<'info> {
+    // Used to create accounts
+    pub system_program: Program<'info, System>,
+
+    #[account(mut)]
+    pub claimant: Signer<'info>,
+
+    #[account(
+        seeds = [b"bounty", bounty.id.to_le_bytes().as_ref()],
+        bump = bounty.bump
+    )]
+    pub bounty: Account<'info, Bounty>,
+
+    // One claim per claimant for each bounty. A claim left over from an earlier bounty
+    // with the same id has to be closed with close_claim first
+    #[account(
+        init,
+        payer = claimant,
+        space = BountyClaim::DISCRIMINATOR.len() + BountyClaim::INIT_SPACE,
+        seeds = [b"claim", bounty.key().as_ref(), claimant.key().as_ref()],
+        bump
+    )]
+    pub claim: Account<'info, BountyClaim>,
+}
+
+// Handle the submit claim instruction by saving the claimant's submission to the claim account
+pub fn submit_claim(context: Context<SubmitClaim>, submission: String) -> Result<()> {
+    require!(
+        submission.len() <= MAX_SUBMISSION_LENGTH,
+        ErrorCode::SubmissionTooLong
+    );
+
+    let now = Clock::get()?.unix_timestamp;
+    require!(
+        now <= context.accounts.bounty.deadline_ts,
+        ErrorCode::BountyEnded
+    );
+
+    context.accounts.claim.set_inner(BountyClaim {
+        bounty: context.accounts.bounty.key(),
+        claimant: context.accounts.claimant.key(),
+        submission,
+        bounty_nonce: context.accounts.bounty.nonce,
+        bump: context.bumps.claim,
+    });
+    Ok(())
+}
@@ -0,0 +1,88 @@
+use super::shared::{close_token_account, transfer_tokens};
+use crate::{error::ErrorCode, state::Campaign};
+use anchor_lang::prelude::*;
+use anchor_spl::token_interface::{Mint, TokenAccount, TokenInterface};
+
+#[derive(Accounts)]
+pub struct CloseCampaign<'info> {
+    // Work with either the classic token program or
+    // the newer token extensions program
+    pub token_program: Interface<'info, TokenInterface>,
+
+    #[account(mut)]
+    pub creator: Signer<'info>,
+
+    pub token_mint: InterfaceAccount<'info, Mint>,
+
+    // Where anything sent to the vault outside of contributions goes, chosen by the creator
+    #[account(
+        mut,
+        token::mint = token_mint,
+        token::token_program = token_program
+    )]
+    pub proceeds_account: InterfaceAccount<'info, TokenAccount>,
+
+    #[account(
+        mut,
+        close = creator,
+        has_one = creator,
+        has_one = token_mint,
+        constraint = campaign.raised_amount == 0 @ ErrorCode::CampaignHasContributions,
+        seeds = [b"campaign", campaign.id.to_le_bytes().as_ref()],
+        bump = campaign.bump
+    )]
+    pub campaign: Account<'info, Campaign>,
+
+    #[account(
+        mut,
+        associated_token::mint = token_mint,
+        associated_token::authority = campaign,
+        associated_token::token_program = token_program
+    )]
+    pub vault: InterfaceAccount<'info, TokenAccount>,
+}
+
+// Handle the close campaign instruction, once the deadline has passed without anyone
+// contributing, by:
+// 1. Moving anything sent to the vault outside of contributions to the creator's proceeds account
+// 2. Closing the vault and campaign accounts and returning the rent to the creator
+// Without this, a campaign nobody contributed to would have no last refund to close it
+pub fn close_campaign(context: Context<CloseCampaign>) -> Result<()> {
+    let now = Clock::get()?.unix_timestamp;
+    require!(
+        now > context.accounts.campaign.deadline_ts,
+        ErrorCode::CampaignNotEnded
+    );
+
+    let campaign_account_seeds = &[
+        b"campaign",
+        &context.accounts.campaign.id.to_le_bytes()[..],
+        &[context.accounts.campaign.bump],
+    ];
+    let signers_seeds = Some(&campaign_account_seeds[..]);
+
+    let leftover = context.accounts.vault.amount;
+    if leftover > 0 {
+        transfer_tokens(
+            &context.accounts.vault,
+            &context.accounts.proceeds_account,
+            &leftover,
+            &context.accounts.token_mint,
+            &context.accounts.campaign.to_account_info(),
+            &context.accounts.token_program,
+            signers_seeds,
+        )
+        .map_err(|_| ErrorCode::FailedVaultWithdrawal)?;
+    }
+
+    close_token_account(
+        &context.accounts.vault,
+        &context.accounts.creator.to_account_info(),
+        &context.accounts.campaign.to_account_info(),
+        &context.accounts.token_program,
+        signers_seeds,
+    )
+    .map_err(|_| ErrorCode::FailedVaultClosure)?;
+
+    Ok(())
+}
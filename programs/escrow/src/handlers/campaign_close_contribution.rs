@@ -0,0 +1,40 @@
+use crate::{
+    error::ErrorCode,
+    state::{Campaign, Contribution},
+};
+use anchor_lang::prelude::*;
+
+#[derive(Accounts)]
+pub struct CloseContribution<'info> {
+    #[account(mut)]
+    pub contributor: Signer<'info>,
+
+    // The campaign the receipt was for, which may since have been closed or replaced
+    /// CHECK: only read to see whether the receipt's campaign is still open
+    #[account(address = contribution.campaign)]
+    pub campaign: UncheckedAccount<'info>,
+
+    #[account(
+        mut,
+        close = contributor,
+        has_one = contributor,
+        seeds = [b"contribution", campaign.key().as_ref(), contributor.key().as_ref()],
+        bump = contribution.bump
+    )]
+    pub contribution: Account<'info, Contribution>,
+}
+
+// Handle the close contribution instruction by closing a receipt whose campaign has
+// finished, returning the rent to the contributor. Receipts are left open when the
+// creator withdraws the funds, as a record of who funded the campaign
+pub fn close_contribution(context: Context<CloseContribution>) -> Result<()> {
+    let campaign = &context.accounts.campaign;
+    if campaign.owner == &crate::ID && !campaign.data_is_empty() {
+        let campaign = Campaign::try_deserialize(&mut &campaign.try_borrow_data()?[..])?;
+        require!(
+            campaign.nonce != context.accounts.contribution.campaign_nonce,
+            ErrorCode::ContributionStillActive
+        );
+    }
+    Ok(())
+}
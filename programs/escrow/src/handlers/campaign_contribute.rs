@@ -0,0 +1,111 @@
+use super::shared::transfer_tokens;
+use crate::{
+    error::ErrorCode,
+    state::{Campaign, Contribution},
+};
+use anchor_lang::prelude::*;
+use anchor_spl::token_interface::{Mint, TokenAccount, TokenInterface};
+
+#[derive(Accounts)]
+pub struct Contribute<'info> {
+    // Work with either the classic token program or
+    // the newer token extensions program
+    pub token_program: Interface<'info, TokenInterface>,
+
+    // Used to create accounts
+    pub system_program: Program<'info, System>,
+
+    #[account(mut)]
+    pub contributor: Signer<'info>,
+
+    pub token_mint: InterfaceAccount<'info, Mint>,
+
+    #[account(
+        mut,
+        associated_token::mint = token_mint,
+        associated_token::authority = contributor,
+        associated_token::token_program = token_program
+    )]
+    pub contributor_token_account: InterfaceAccount<'info, TokenAccount>,
+
+    #[account(
+        mut,
+        has_one = token_mint,
+        seeds = [b"campaign", campaign.id.to_le_bytes().as_ref()],
+        bump = campaign.bump
+    )]
+    pub campaign: Account<'info, Campaign>,
+
+    // Created on the first contribution, and added to on later ones. A receipt left over
+    // from an earlier campaign with the same id starts again from zero
+    #[account(
+        init_if_needed,
+        payer = contributor,
+        space = Contribution::DISCRIMINATOR.len() + Contribution::INIT_SPACE,
+        seeds = [b"contribution", campaign.key().as_ref(), contributor.key().as_ref()],
+        bump
+    )]
+    pub contribution: Account<'info, Contribution>,
+
+    #[account(
+        mut,
+        associated_token::mint = token_mint,
+        associated_token::authority = campaign,
+        associated_token::token_program = token_program
+    )]
+    pub vault: InterfaceAccount<'info, TokenAccount>,
+}
+
+// Handle the contribute instruction by:
+// 1. Moving the tokens from the contributor's ATA to the vault
+// 2. Adding the amount that reached the vault to the contributor's receipt and the campaign's total
+pub fn contribute(context: Context<Contribute>, amount: u64) -> Result<()> {
+    require!(amount > 0, ErrorCode::InvalidAmount);
+
+    let now = Clock::get()?.unix_timestamp;
+    require!(
+        now <= context.accounts.campaign.deadline_ts,
+        ErrorCode::CampaignEnded
+    );
+
+    transfer_tokens(
+        &context.accounts.contributor_token_account,
+        &context.accounts.vault,
+        &amount,
+        &context.accounts.token_mint,
+        &context.accounts.contributor.to_account_info(),
+        &context.accounts.token_program,
+        None,
+    )
+    .map_err(|_| ErrorCode::InsufficientTakerBalance)?;
+
+    // Mints with a transfer fee deliver less than was sent, so only count what arrived.
+    // Refunds pay out what's recorded, so this keeps the vault able to cover them
+    let vault = &mut context.accounts.vault;
+    let balance_before = vault.amount;
+    vault.reload()?;
+    let received_amount = vault
+        .amount
+        .checked_sub(balance_before)
+        .ok_or(ErrorCode::VaultShortfall)?;
+
+    let campaign = &mut context.accounts.campaign;
+    let contribution = &mut context.accounts.contribution;
+    if contribution.campaign_nonce != campaign.nonce {
+        contribution.amount = 0;
+    }
+    contribution.campaign = campaign.key();
+    contribution.contributor = context.accounts.contributor.key();
+    contribution.amount = contribution
+        .amount
+        .checked_add(received_amount)
+        .ok_or(ErrorCode::InvalidAmount)?;
+    contribution.campaign_nonce = campaign.nonce;
+    contribution.bump = context.bumps.contribution;
+
+    campaign.raised_amount = campaign
+        .raised_amount
+        .checked_add(received_amount)
+        .ok_or(ErrorCode::InvalidAmount)?;
+    Ok(())
+}
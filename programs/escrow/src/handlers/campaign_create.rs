@@ -0,0 +1,88 @@
+use crate::{
+    error::ErrorCode,
+    state::{Campaign, NonceCounter},
+};
+use anchor_lang::prelude::*;
+use anchor_spl::{
+    associated_token::AssociatedToken,
+    token_interface::{Mint, TokenAccount, TokenInterface},
+};
+
+#[derive(Accounts)]
+#[instruction(id: u64)]
+pub struct CreateCampaign<'info> {
+    // Used to manage associated token accounts
+    // ie where a wallet holds a specific type of token
+    pub associated_token_program: Program<'info, AssociatedToken>,
+
+    // Work with either the classic token program or
+    // the newer token extensions program
+    pub token_program: Interface<'info, TokenInterface>,
+
+    // Used to create accounts
+    pub system_program: Program<'info, System>,
+
+    #[account(mut)]
+    pub creator: Signer<'info>,
+
+    #[account(mint::token_program = token_program)]
+    pub token_mint: InterfaceAccount<'info, Mint>,
+
+    #[account(
+        init,
+        payer = creator,
+        space = Campaign::DISCRIMINATOR.len() + Campaign::INIT_SPACE,
+        seeds = [b"campaign", id.to_le_bytes().as_ref()],
+        bump
+    )]
+    pub campaign: Account<'info, Campaign>,
+
+    // Hands out the campaign's nonce
+    #[account(
+        init_if_needed,
+        payer = creator,
+        space = NonceCounter::DISCRIMINATOR.len() + NonceCounter::INIT_SPACE,
+        seeds = [b"nonce_counter", campaign.key().as_ref()],
+        bump
+    )]
+    pub nonce_counter: Account<'info, NonceCounter>,
+
+    #[account(
+        init,
+        payer = creator,
+        associated_token::mint = token_mint,
+        associated_token::authority = campaign,
+        associated_token::token_program = token_program
+    )]
+    pub vault: InterfaceAccount<'info, TokenAccount>,
+}
+
+// Handle the create campaign instruction by saving the details of the campaign
+// to the campaign account. The vault starts empty, and is filled by contributions
+pub fn create_campaign(
+    context: Context<CreateCampaign>,
+    id: u64,
+    target_amount: u64,
+    deadline_ts: i64,
+) -> Result<()> {
+    require!(target_amount > 0, ErrorCode::InvalidAmount);
+
+    let now = Clock::get()?.unix_timestamp;
+    require!(deadline_ts > now, ErrorCode::CampaignEnded);
+
+    let nonce_counter = &mut context.accounts.nonce_counter;
+    nonce_counter.bump = context.bumps.nonce_counter;
+    let nonce = nonce_counter.next_nonce()?;
+
+    context.accounts.campaign.set_inner(Campaign {
+        id,
+        creator: context.accounts.creator.key(),
+        token_mint: context.accounts.token_mint.key(),
+        target_amount,
+        raised_amount: 0,
+        deadline_ts,
+        nonce,
+        bump: context.bumps.campaign,
+    });
+    Ok(())
+}
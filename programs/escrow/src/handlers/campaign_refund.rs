@@ -0,0 +1,132 @@
+use super::shared::{close_token_account, transfer_tokens};
+use crate::{
+    error::ErrorCode,
+    state::{Campaign, Contribution},
+};
+use anchor_lang::prelude::*;
+use anchor_spl::token_interface::{Mint, TokenAccount, TokenInterface};
+
+#[derive(Accounts)]
+pub struct RefundContribution<'info> {
+    // Work with either the classic token program or
+    // the newer token extensions program
+    pub token_program: Interface<'info, TokenInterface>,
+
+    #[account(mut)]
+    pub contributor: Signer<'info>,
+
+    pub token_mint: InterfaceAccount<'info, Mint>,
+
+    #[account(
+        mut,
+        token::mint = token_mint,
+        token::token_program = token_program
+    )]
+    pub contributor_token_account: InterfaceAccount<'info, TokenAccount>,
+
+    // Gets the rent for the campaign and vault back after the last refund
+    #[account(mut)]
+    pub creator: SystemAccount<'info>,
+
+    #[account(
+        mut,
+        has_one = creator,
+        has_one = token_mint,
+        constraint = campaign.raised_amount < campaign.target_amount
+            @ ErrorCode::CampaignTargetReached,
+        seeds = [b"campaign", campaign.id.to_le_bytes().as_ref()],
+        bump = campaign.bump
+    )]
+    pub campaign: Account<'info, Campaign>,
+
+    #[account(
+        mut,
+        close = contributor,
+        has_one = campaign,
+        has_one = contributor,
+        constraint = contribution.campaign_nonce == campaign.nonce
+            @ ErrorCode::StaleContribution,
+        seeds = [b"contribution", campaign.key().as_ref(), contributor.key().as_ref()],
+        bump = contribution.bump
+    )]
+    pub contribution: Account<'info, Contribution>,
+
+    #[account(
+        mut,
+        associated_token::mint = token_mint,
+        associated_token::authority = campaign,
+        associated_token::token_program = token_program
+    )]
+    pub vault: InterfaceAccount<'info, TokenAccount>,
+}
+
+// Handle the refund contribution instruction, once the deadline has passed
+// without the campaign reaching its target, by:
+// 1. Returning the contributor's tokens from the vault
+// 2. Closing their contribution receipt and returning the rent to them
+// 3. After the last refund, closing the vault and campaign and returning the rent to the creator
+pub fn refund_contribution(context: Context<RefundContribution>) -> Result<()> {
+    let now = Clock::get()?.unix_timestamp;
+    require!(
+        now > context.accounts.campaign.deadline_ts,
+        ErrorCode::CampaignNotEnded
+    );
+
+    let campaign_account_seeds = &[
+        b"campaign",
+        &context.accounts.campaign.id.to_le_bytes()[..],
+        &[context.accounts.campaign.bump],
+    ];
+    let signers_seeds = Some(&campaign_account_seeds[..]);
+
+    transfer_tokens(
+        &context.accounts.vault,
+        &context.accounts.contributor_token_account,
+        &context.accounts.contribution.amount,
+        &context.accounts.token_mint,
+        &context.accounts.campaign.to_account_info(),
+        &context.accounts.token_program,
+        signers_seeds,
+    )
+    .map_err(|_| ErrorCode::FailedRefundTransfer)?;
+
+    let campaign = &mut context.accounts.campaign;
+    campaign.raised_amount = campaign
+        .raised_amount
+        .checked_sub(context.accounts.contribution.amount)
+        .ok_or(ErrorCode::FailedRefundTransfer)?;
+    if campaign.raised_amount > 0 {
+        return Ok(());
+    }
+
+    // Anything sent to the vault outside of contributions goes to whoever takes
+    // the last refund, so the vault is empty and can be closed
+    context.accounts.vault.reload()?;
+    let leftover = context.accounts.vault.amount;
+    if leftover > 0 {
+        transfer_tokens(
+            &context.accounts.vault,
+            &context.accounts.contributor_token_account,
+            &leftover,
+            &context.accounts.token_mint,
+            &context.accounts.campaign.to_account_info(),
+            &context.accounts.token_program,
+            signers_seeds,
+        )
+        .map_err(|_| ErrorCode::FailedRefundTransfer)?;
+    }
+
+    close_token_account(
+        &context.accounts.vault,
+        &context.accounts.creator.to_account_info(),
+        &context.accounts.campaign.to_account_info(),
+        &context.accounts.token_program,
+        signers_seeds,
+    )
+    .map_err(|_| ErrorCode::FailedVaultClosure)?;
+
+    context
+        .accounts
+        .campaign
+        .close(context.accounts.creator.to_account_info())
+}
@@ -0,0 +1,80 @@
+use super::shared::{close_token_account, transfer_tokens};
+use crate::{error::ErrorCode, state::Campaign};
+use anchor_lang::prelude::*;
+use anchor_spl::token_interface::{Mint, TokenAccount, TokenInterface};
+
+#[derive(Accounts)]
+pub struct WithdrawCampaignFunds<'info> {
+    // Work with either the classic token program or
+    // the newer token extensions program
+    pub token_program: Interface<'info, TokenInterface>,
+
+    #[account(mut)]
+    pub creator: Signer<'info>,
+
+    pub token_mint: InterfaceAccount<'info, Mint>,
+
+    // Where the funds go, chosen by the creator
+    #[account(
+        mut,
+        token::mint = token_mint,
+        token::token_program = token_program
+    )]
+    pub proceeds_account: InterfaceAccount<'info, TokenAccount>,
+
+    #[account(
+        mut,
+        close = creator,
+        has_one = creator,
+        has_one = token_mint,
+        constraint = campaign.raised_amount >= campaign.target_amount
+            @ ErrorCode::CampaignTargetNotReached,
+        seeds = [b"campaign", campaign.id.to_le_bytes().as_ref()],
+        bump = campaign.bump
+    )]
+    pub campaign: Account<'info, Campaign>,
+
+    #[account(
+        mut,
+        associated_token::mint = token_mint,
+        associated_token::authority = campaign,
+        associated_token::token_program = token_program
+    )]
+    pub vault: InterfaceAccount<'info, TokenAccount>,
+}
+
+// Handle the withdraw campaign funds instruction, once the target has been reached, by:
+// 1. Moving everything in the vault to the creator's proceeds account
+// 2. Closing the vault and campaign accounts and returning the rent to the creator
+// Contribution receipts are left open as a record of who funded the campaign,
+// until each contributor closes theirs with close_contribution
+pub fn withdraw_campaign_funds(context: Context<WithdrawCampaignFunds>) -> Result<()> {
+    let campaign_account_seeds = &[
+        b"campaign",
+        &context.accounts.campaign.id.to_le_bytes()[..],
+        &[context.accounts.campaign.bump],
+    ];
+    let signers_seeds = Some(&campaign_account_seeds[..]);
+
+    transfer_tokens(
+        &context.accounts.vault,
+        &context.accounts.proceeds_account,
+        &context.accounts.vault.amount,
+        &context.accounts.token_mint,
+        &context.accounts.campaign.to_account_info(),
+        &context.accounts.token_program,
+        signers_seeds,
+    )
+    .map_err(|_| ErrorCode::FailedVaultWithdrawal)?;
+
+    close_token_account(
+        &context.accounts.vault,
+        &context.accounts.creator.to_account_info(),
+        &context.accounts.campaign.to_account_info(),
+        &context.accounts.token_program,
+        signers_seeds,
+    )
+    .map_err(|_| ErrorCode::FailedVaultClosure)?;
+
+    Ok(())
+}
@@ -0,0 +1,96 @@
+use anchor_lang::prelude::*;
+use anchor_spl::token_interface::{Mint, TokenAccount, TokenInterface};
+
+use super::shared::{burn_tokens, close_token_account, thaw_token_account};
+use crate::{error::ErrorCode, state::Offer};
+
+#[derive(Accounts)]
+#[instruction(offer_id: u64)]
+pub struct CloseReceipt<'info> {
+    // Work with either the classic token program or
+    // the newer token extensions program
+    pub token_program: Interface<'info, TokenInterface>,
+
+    // Whoever holds the receipt, who gets the receipt account's rent back
+    #[account(mut)]
+    pub holder: Signer<'info>,
+
+    // The offer the receipt was for. The receipt can't be for the offer currently open at
+    // this address, so a receipt that can still refund or be paid for an offer can't be
+    // closed by mistake. An offer made later with the same id has a different receipt
+    /// CHECK: only used to sign as the receipt mint's freeze authority, and read in the handler
+    #[account(
+        seeds = [b"offer", offer_id.to_le_bytes().as_ref()],
+        bump
+    )]
+    pub offer: UncheckedAccount<'info>,
+
+    #[account(
+        mut,
+        mint::freeze_authority = offer,
+        mint::token_program = token_program
+    )]
+    pub receipt_mint: InterfaceAccount<'info, Mint>,
+
+    #[account(
+        mut,
+        token::mint = receipt_mint,
+        token::authority = holder,
+        token::token_program = token_program
+    )]
+    pub receipt_account: InterfaceAccount<'info, TokenAccount>,
+}
+
+// Handle the close receipt instruction by:
+// 1. Checking the receipt isn't for the offer that's currently open
+// 2. Thawing the receipt account, if it was frozen when the offer was taken
+// 3. Burning the spent receipt, if it hasn't been burned already
+// 4. Closing the receipt account and returning the rent to the holder
+pub fn close_receipt(context: Context<CloseReceipt>, offer_id: u64) -> Result<()> {
+    let offer = &context.accounts.offer;
+    if !offer.data_is_empty() {
+        let open_offer = Offer::try_deserialize(&mut &offer.try_borrow_data()?[..])?;
+        require_keys_neq!(
+            open_offer.receipt_mint,
+            context.accounts.receipt_mint.key(),
+            ErrorCode::OfferStillOpen
+        );
+    }
+
+    let offer_account_seeds = &[
+        b"offer",
+        &offer_id.to_le_bytes()[..],
+        &[context.bumps.offer],
+    ];
+    let receipt_account = &context.accounts.receipt_account;
+    let holder = context.accounts.holder.to_account_info();
+
+    if receipt_account.is_frozen() {
+        thaw_token_account(
+            receipt_account,
+            &context.accounts.receipt_mint,
+            &context.accounts.offer.to_account_info(),
+            &context.accounts.token_program,
+            Some(&offer_account_seeds[..]),
+        )?;
+    }
+
+    if receipt_account.amount > 0 {
+        burn_tokens(
+            receipt_account,
+            &context.accounts.receipt_mint,
+            receipt_account.amount,
+            &holder,
+            &context.accounts.token_program,
+            None,
+        )?;
+    }
+
+    close_token_account(
+        receipt_account,
+        &holder,
+        &holder,
+        &context.accounts.token_program,
+        None,
+    )
+}
@@ -1,78 +1,157 @@
 use anchor_lang::prelude::*;
+use anchor_spl::token_interface::{Mint, TokenAccount, TokenInterface};
 
 use crate::error::ErrorCode;
-use crate::handlers::transfer_lamports;
-use crate::state::Game;
+use crate::events::GameCancelled;
+use crate::handlers::{
+    check_game_payee, close_game_vault, pay_from_game, refund_queue_rent, settle_side_pool,
+    GameVault,
+};
+use crate::constants::DRAW;
+use crate::state::{Game, SidePool};
 
+// Where each player is refunded must be passed in remaining_accounts, writable, in slot order:
+// their wallet for lamport games, or one of their token accounts for token games.
+// Pass any account for open seats nobody took, as they're never paid
 #[derive(Accounts)]
 pub struct CancelGame<'info> {
     #[account(mut)]
     pub caller: Signer<'info>,
 
-    /// CHECK: validated against game.player_a
-    #[account(mut)]
-    pub player_a_account: AccountInfo<'info>,
-
-    /// CHECK: validated against game.player_b
-    #[account(mut)]
-    pub player_b_account: AccountInfo<'info>,
+    // Work with either the classic token program or
+    // the newer token extensions program
+    pub token_program: Option<Interface<'info, TokenInterface>>,
 
     pub system_program: Program<'info, System>,
 
     #[account(
         mut,
         close = caller,
+        constraint = !game.is_over() @ ErrorCode::GameOver,
         seeds = [b"game", game.id.to_le_bytes().as_ref()],
         bump = game.bump,
     )]
     pub game: Account<'info, Game>,
+
+    #[account(
+        address = game.stake_mint @ ErrorCode::InvalidGameAccounts,
+        mint::token_program = token_program
+    )]
+    pub stake_mint: Option<InterfaceAccount<'info, Mint>>,
+
+    #[account(
+        mut,
+        associated_token::mint = stake_mint,
+        associated_token::authority = game,
+        associated_token::token_program = token_program
+    )]
+    pub vault: Option<InterfaceAccount<'info, TokenAccount>>,
+
+    // Gets the bond back if the game was challenged but the arbiter never resolved it
+    #[account(mut, address = game.challenger @ ErrorCode::Unauthorized)]
+    pub challenger: Option<SystemAccount<'info>>,
+
+    // Has every bet refunded, for games spectators have bet on
+    #[account(
+        mut,
+        seeds = [b"side_pool", game.key().as_ref(), game.nonce.to_le_bytes().as_ref()],
+        bump = side_pool.bump,
+    )]
+    pub side_pool: Option<Account<'info, SidePool>>,
 }
 
-pub fn cancel_game(ctx: Context<CancelGame>) -> Result<()> {
-    let game = &mut ctx.accounts.game;
+pub fn cancel_game<'info>(ctx: Context<'_, '_, 'info, 'info, CancelGame<'info>>) -> Result<()> {
+    let game = &ctx.accounts.game;
 
-    // Allow cancel by authority or either player after expiry
+    // Allow cancel by authority or any player after expiry, or straight away once
+    // the deposit deadline has passed without every player depositing
     let now = Clock::get()?.unix_timestamp;
-    require!(now > game.expiry_ts, ErrorCode::NotExpired);
+    require!(
+        now > game.expiry_ts || (now > game.deposit_deadline_ts && !game.all_deposited()),
+        ErrorCode::NotExpired
+    );
 
     let caller = ctx.accounts.caller.key();
     require!(
-        caller == game.authority || caller == game.player_a || caller == game.player_b,
+        caller == game.authority || game.players.iter().any(|slot| slot.player == caller),
         ErrorCode::Unauthorized
     );
 
-    // Validate destination accounts
-    require!(ctx.accounts.player_a_account.key() == game.player_a, ErrorCode::Unauthorized);
-    require!(ctx.accounts.player_b_account.key() == game.player_b, ErrorCode::Unauthorized);
+    // An unchallenged proposed result is settled instead
+    let challenged = game.challenger != Pubkey::default();
+    require!(
+        game.proposed_result.is_none() || challenged,
+        ErrorCode::ResultAlreadyProposed
+    );
+
+    let game_vault = GameVault::for_game(
+        game,
+        &ctx.accounts.stake_mint,
+        &ctx.accounts.vault,
+        &ctx.accounts.token_program,
+    )?;
 
-    let seeds = [b"game".as_ref(), &game.id.to_le_bytes()[..], &[game.bump]];
+    // Validate destination accounts
+    let player_accounts = ctx.remaining_accounts;
+    require!(player_accounts.len() == game.players.len(), ErrorCode::Unauthorized);
+    for (player_account, slot) in player_accounts.iter().zip(&game.players) {
+        if !slot.is_open() {
+            check_game_payee(player_account, &slot.player, game_vault.as_ref())?;
+        }
+    }
 
     // Refund deposited stakes
-    if game.a_deposited {
-        transfer_lamports(
-            &game.to_account_info(),
-            &ctx.accounts.player_a_account,
-            game.stake_lamports,
-            &ctx.accounts.system_program,
-            Some(&seeds),
-        )
-        .map_err(|_| ErrorCode::LamportsTransferFailed)?;
-        game.a_deposited = false;
+    let mut refunds: Vec<u64> = game
+        .players
+        .iter()
+        .map(|slot| if slot.deposited { slot.stake } else { 0 })
+        .collect();
+
+    // Anything else sent to the vault goes to the first player with a seat,
+    // so the vault is empty and can be closed
+    if let Some(game_vault) = &game_vault {
+        let refunded: u64 = refunds.iter().sum();
+        let first = game
+            .players
+            .iter()
+            .position(|slot| !slot.is_open())
+            .ok_or(ErrorCode::NoNamedPlayer)?;
+        let extra = game_vault
+            .vault
+            .amount
+            .checked_sub(refunded)
+            .ok_or(ErrorCode::VaultShortfall)?;
+        refunds[first] += extra;
+    }
+
+    for (player_account, amount) in player_accounts.iter().zip(refunds) {
+        if amount == 0 {
+            continue;
+        }
+        pay_from_game(game, player_account, amount, game_vault.as_ref())?;
     }
 
-    if game.b_deposited {
-        transfer_lamports(
-            &game.to_account_info(),
-            &ctx.accounts.player_b_account,
-            game.stake_lamports,
-            &ctx.accounts.system_program,
-            Some(&seeds),
-        )
-        .map_err(|_| ErrorCode::LamportsTransferFailed)?;
-        game.b_deposited = false;
+    if let Some(game_vault) = &game_vault {
+        close_game_vault(game, game_vault, &ctx.accounts.caller.to_account_info())?;
     }
 
-    game.winner = 0;
+    // Return the bond if the game was challenged but the arbiter never resolved it
+    if challenged {
+        let (Some(challenge_settings), Some(challenger)) =
+            (&game.challenge_settings, &ctx.accounts.challenger)
+        else {
+            return Err(error!(ErrorCode::Unauthorized));
+        };
+        game.sub_lamports(challenge_settings.challenge_bond)
+            .map_err(|_| ErrorCode::LamportsTransferFailed)?;
+        challenger.add_lamports(challenge_settings.challenge_bond)?;
+    }
+
+    refund_queue_rent(game, player_accounts)?;
+
+    settle_side_pool(game, ctx.accounts.side_pool.as_mut(), DRAW)?;
+
+    emit!(GameCancelled { id: game.id });
 
     Ok(())
 }
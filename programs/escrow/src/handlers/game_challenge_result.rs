@@ -0,0 +1,54 @@
+use anchor_lang::prelude::*;
+
+use crate::error::ErrorCode;
+use crate::handlers::transfer_lamports;
+use crate::state::Game;
+
+#[derive(Accounts)]
+pub struct ChallengeResult<'info> {
+    #[account(mut)]
+    pub player: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+
+    #[account(
+        mut,
+        constraint = !game.is_over() @ ErrorCode::GameOver,
+        seeds = [b"game", game.id.to_le_bytes().as_ref()],
+        bump = game.bump,
+    )]
+    pub game: Account<'info, Game>,
+}
+
+// Lets a player dispute the proposed result during the challenge window by posting the bond,
+// which hands the decision to the arbiter
+pub fn challenge_result(ctx: Context<ChallengeResult>) -> Result<()> {
+    let game = &mut ctx.accounts.game;
+
+    let Some(challenge_settings) = &game.challenge_settings else {
+        return Err(error!(ErrorCode::ChallengesNotEnabled));
+    };
+    let player = ctx.accounts.player.key();
+    require!(
+        game.players.iter().any(|slot| slot.player == player),
+        ErrorCode::Unauthorized
+    );
+    require!(game.proposed_result.is_some(), ErrorCode::NoProposedResult);
+    require!(game.challenger == Pubkey::default(), ErrorCode::AlreadyChallenged);
+
+    let now = Clock::get()?.unix_timestamp;
+    require!(now <= game.challenge_deadline_ts, ErrorCode::ChallengeWindowClosed);
+
+    // The bond is held in the game account alongside any lamport stakes
+    transfer_lamports(
+        &ctx.accounts.player.to_account_info(),
+        &game.to_account_info(),
+        challenge_settings.challenge_bond,
+        &ctx.accounts.system_program,
+        None,
+    )
+    .map_err(|_| ErrorCode::LamportsTransferFailed)?;
+    game.challenger = player;
+
+    Ok(())
+}
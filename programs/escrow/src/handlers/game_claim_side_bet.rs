@@ -0,0 +1,76 @@
+use anchor_lang::prelude::*;
+
+use crate::constants::DRAW;
+use crate::error::ErrorCode;
+use crate::state::{SideBet, SidePool};
+
+#[derive(Accounts)]
+pub struct ClaimSideBet<'info> {
+    #[account(mut)]
+    pub bettor: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+
+    // Left open once the game is over, until every bettor has claimed from it
+    #[account(
+        mut,
+        seeds = [
+            b"side_pool",
+            side_pool.game.as_ref(),
+            side_pool.game_nonce.to_le_bytes().as_ref()
+        ],
+        bump = side_pool.bump,
+    )]
+    pub side_pool: Account<'info, SidePool>,
+
+    // Gets the side pool's rent back when the last bet is claimed
+    #[account(mut, address = side_pool.rent_payer @ ErrorCode::Unauthorized)]
+    pub side_pool_rent_payer: SystemAccount<'info>,
+
+    #[account(
+        mut,
+        close = bettor,
+        has_one = bettor @ ErrorCode::Unauthorized,
+        has_one = side_pool @ ErrorCode::Unauthorized,
+        seeds = [b"side_bet", side_pool.key().as_ref(), bettor.key().as_ref()],
+        bump = side_bet.bump,
+    )]
+    pub side_bet: Account<'info, SideBet>,
+}
+
+// Pays out a side bet once the game is over: a share of the whole pool for backing the winner,
+// in proportion to the bet, or the bet back if the game was drawn or cancelled,
+// or nobody backed the winner. Losing bets are just closed. The last claim closes the pool
+pub fn claim_side_bet(ctx: Context<ClaimSideBet>) -> Result<()> {
+    let side_pool = &mut ctx.accounts.side_pool;
+    let side_bet = &ctx.accounts.side_bet;
+    require!(side_pool.outcome != 0, ErrorCode::SideBetsNotSettled);
+
+    let pool_total: u64 = side_pool.slot_totals.iter().sum();
+    let winning_total = match side_pool.outcome {
+        DRAW => 0,
+        winner => side_pool.slot_totals[winner as usize - 1],
+    };
+    let payout = if winning_total == 0 {
+        side_bet.amount
+    } else if side_bet.slot == side_pool.outcome {
+        (side_bet.amount as u128 * pool_total as u128 / winning_total as u128) as u64
+    } else {
+        0
+    };
+
+    // The side pool is owned by this program, so it can be debited directly
+    if payout > 0 {
+        side_pool
+            .sub_lamports(payout)
+            .map_err(|_| ErrorCode::LamportsTransferFailed)?;
+        ctx.accounts.bettor.add_lamports(payout)?;
+    }
+
+    side_pool.open_bets -= 1;
+    if side_pool.open_bets == 0 {
+        return side_pool.close(ctx.accounts.side_pool_rent_payer.to_account_info());
+    }
+
+    Ok(())
+}
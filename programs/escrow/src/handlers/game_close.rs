@@ -0,0 +1,44 @@
+use anchor_lang::prelude::*;
+
+use crate::error::ErrorCode;
+use crate::handlers::{check_game_payee, refund_queue_rent};
+use crate::state::Game;
+
+// For games matched from a queue, each player's wallet must be passed in remaining_accounts,
+// writable, in slot order, since the players paid the game's rent
+#[derive(Accounts)]
+pub struct CloseGame<'info> {
+    #[account(mut)]
+    pub authority: Signer<'info>,
+
+    #[account(
+        mut,
+        close = authority,
+        has_one = authority @ ErrorCode::Unauthorized,
+        constraint = game.is_over() @ ErrorCode::GameNotOver,
+        seeds = [b"game", game.id.to_le_bytes().as_ref()],
+        bump = game.bump,
+    )]
+    pub game: Account<'info, Game>,
+}
+
+// Closes a game that's been paid out, once its record of the outcome is no longer needed,
+// so its id can be used again. The rent goes back to the authority, or to the players
+// for games matched from a queue
+pub fn close_game<'info>(ctx: Context<'_, '_, 'info, 'info, CloseGame<'info>>) -> Result<()> {
+    let game = &ctx.accounts.game;
+    if !game.matched_from_queue {
+        return Ok(());
+    }
+
+    let player_accounts = ctx.remaining_accounts;
+    require!(
+        player_accounts.len() == game.players.len(),
+        ErrorCode::Unauthorized
+    );
+    for (player_account, slot) in player_accounts.iter().zip(&game.players) {
+        check_game_payee(player_account, &slot.player, None)?;
+    }
+
+    refund_queue_rent(game, player_accounts)
+}
@@ -0,0 +1,43 @@
+use anchor_lang::prelude::*;
+
+use crate::error::ErrorCode;
+use crate::state::{Game, VoteRecord};
+
+#[derive(Accounts)]
+pub struct CloseVoteRecord<'info> {
+    // The referee who paid for the vote record gets the rent back
+    #[account(mut, address = vote_record.rent_payer @ ErrorCode::Unauthorized)]
+    pub rent_payer: SystemAccount<'info>,
+
+    // The game voted on, which may since have been closed or replaced
+    /// CHECK: only read to see whether the votes can still be counted
+    #[account(address = vote_record.game)]
+    pub game: UncheckedAccount<'info>,
+
+    #[account(
+        mut,
+        close = rent_payer,
+        seeds = [
+            b"vote",
+            game.key().as_ref(),
+            vote_record.game_nonce.to_le_bytes().as_ref(),
+            vote_record.result_hash.as_ref()
+        ],
+        bump = vote_record.bump,
+    )]
+    pub vote_record: Account<'info, VoteRecord>,
+}
+
+// Closes a vote record once its game is over, whether or not the result it was for won.
+// Anyone can call this, since the rent always goes back to the referee who paid it
+pub fn close_vote_record(ctx: Context<CloseVoteRecord>) -> Result<()> {
+    let game = &ctx.accounts.game;
+    if game.owner == &crate::ID && !game.data_is_empty() {
+        let game = Game::try_deserialize(&mut &game.try_borrow_data()?[..])?;
+        require!(
+            game.nonce != ctx.accounts.vote_record.game_nonce || game.is_over(),
+            ErrorCode::VoteStillActive
+        );
+    }
+    Ok(())
+}
@@ -1,51 +1,189 @@
 use anchor_lang::prelude::*;
+use anchor_spl::{
+    associated_token::AssociatedToken,
+    token_interface::{Mint, TokenAccount, TokenInterface},
+};
 
-use crate::state::Game;
+use crate::constants::{
+    MAX_CHALLENGE_PERIOD_SECS, MAX_PLAYERS, MAX_RAKE_BPS, MAX_REFEREES, MAX_ROUNDS,
+};
+use crate::state::{Game, GameOptions, NonceCounter, PlayerSlot};
 use crate::error::ErrorCode;
 
 #[derive(Accounts)]
-#[instruction(id: u64)]
+#[instruction(id: u64, players: Vec<Pubkey>)]
 pub struct CreateGame<'info> {
     #[account(mut)]
     pub authority: Signer<'info>,
 
+    // Used to create the vault for token games
+    pub associated_token_program: Option<Program<'info, AssociatedToken>>,
+
+    // Work with either the classic token program or
+    // the newer token extensions program
+    pub token_program: Option<Interface<'info, TokenInterface>>,
+
     pub system_program: Program<'info, System>,
 
+    /// CHECK: only stored, as the account the rake is paid to
+    pub treasury: UncheckedAccount<'info>,
+
     #[account(
         init,
         payer = authority,
-        space = Game::DISCRIMINATOR.len() + Game::INIT_SPACE,
+        space = Game::space(players.len()),
         seeds = [b"game", id.to_le_bytes().as_ref()],
         bump
     )]
     pub game: Account<'info, Game>,
+
+    // Hands out the game's nonce
+    #[account(
+        init_if_needed,
+        payer = authority,
+        space = NonceCounter::DISCRIMINATOR.len() + NonceCounter::INIT_SPACE,
+        seeds = [b"nonce_counter", game.key().as_ref()],
+        bump
+    )]
+    pub nonce_counter: Account<'info, NonceCounter>,
+
+    // The token players stake. Leave this and the vault out to stake lamports
+    #[account(mint::token_program = token_program)]
+    pub stake_mint: Option<InterfaceAccount<'info, Mint>>,
+
+    // Holds every player's stake in token games
+    #[account(
+        init,
+        payer = authority,
+        associated_token::mint = stake_mint,
+        associated_token::authority = game,
+        associated_token::token_program = token_program
+    )]
+    pub vault: Option<InterfaceAccount<'info, TokenAccount>>,
 }
 
 pub fn create_game(
     ctx: Context<CreateGame>,
     id: u64,
-    player_a: Pubkey,
-    player_b: Pubkey,
-    stake_lamports: u64,
+    players: Vec<Pubkey>,
+    stake_amount: u64,
     expiry_ts: i64,
+    rake_bps: u16,
+    options: GameOptions,
 ) -> Result<()> {
-    require!(player_a != player_b, ErrorCode::Unauthorized);
-    require!(stake_lamports > 0, ErrorCode::InvalidAmount);
+    require!(
+        (2..=MAX_PLAYERS).contains(&players.len()),
+        ErrorCode::InvalidPlayerCount
+    );
+    // Open seats are left as the default pubkey, but at least one player must be named
+    let open = Pubkey::default();
+    require!(players.iter().any(|player| *player != open), ErrorCode::NoNamedPlayer);
+    for (index, player) in players.iter().enumerate() {
+        require!(
+            *player == open || !players[..index].contains(player),
+            ErrorCode::Unauthorized
+        );
+    }
+    require!(rake_bps <= MAX_RAKE_BPS, ErrorCode::RakeTooHigh);
+    let GameOptions {
+        deposit_deadline_ts,
+        stakes,
+        challenge_settings,
+        referee_quorum,
+        wins_required,
+        attester,
+    } = options;
+    let stakes = stakes.unwrap_or_else(|| vec![stake_amount; players.len()]);
+    require!(
+        stakes.len() == players.len() && stakes.iter().all(|stake| *stake > 0),
+        ErrorCode::InvalidAmount
+    );
+    if let Some(challenge_settings) = &challenge_settings {
+        require!(
+            (1..=MAX_CHALLENGE_PERIOD_SECS).contains(&challenge_settings.challenge_period),
+            ErrorCode::InvalidChallengeSettings
+        );
+    }
+    // A quorum finalizes the game with its votes, which would skip the challenge window
+    require!(
+        challenge_settings.is_none() || referee_quorum.is_none(),
+        ErrorCode::ChallengeWithQuorum
+    );
+    if let Some(referee_quorum) = &referee_quorum {
+        let referees = &referee_quorum.referees;
+        require!(
+            referees.len() <= MAX_REFEREES
+                && (1..=referees.len()).contains(&(referee_quorum.threshold as usize)),
+            ErrorCode::InvalidRefereeQuorum
+        );
+        for (index, referee) in referees.iter().enumerate() {
+            require!(!referees[..index].contains(referee), ErrorCode::InvalidRefereeQuorum);
+        }
+    }
+
+    // Series are decided round by round by the authority alone
+    let wins_required = wins_required.unwrap_or(0);
+    if wins_required > 0 {
+        let most_rounds = players.len() * (wins_required as usize - 1) + 1;
+        require!(
+            most_rounds <= MAX_ROUNDS && challenge_settings.is_none() && referee_quorum.is_none(),
+            ErrorCode::InvalidSeries
+        );
+    }
 
     let now = Clock::get()?.unix_timestamp;
     require!(expiry_ts > now, ErrorCode::InvalidAmount);
+    let deposit_deadline_ts = deposit_deadline_ts.unwrap_or(expiry_ts);
+    require!(
+        deposit_deadline_ts > now && deposit_deadline_ts <= expiry_ts,
+        ErrorCode::InvalidDepositDeadline
+    );
+
+    let stake_mint = match (
+        &ctx.accounts.stake_mint,
+        &ctx.accounts.vault,
+        &ctx.accounts.token_program,
+    ) {
+        (Some(stake_mint), Some(_), Some(_)) => stake_mint.key(),
+        (None, None, None) => Pubkey::default(),
+        _ => return Err(error!(ErrorCode::InvalidGameAccounts)),
+    };
+
+    let nonce_counter = &mut ctx.accounts.nonce_counter;
+    nonce_counter.bump = ctx.bumps.nonce_counter;
+    let nonce = nonce_counter.next_nonce()?;
 
     let bump = ctx.bumps.game;
     ctx.accounts.game.set_inner(Game {
         id,
-        player_a,
-        player_b,
+        players: players
+            .into_iter()
+            .zip(stakes)
+            .map(|(player, stake)| PlayerSlot {
+                player,
+                stake,
+                deposited: false,
+                joined_ts: 0,
+            })
+            .collect(),
         authority: ctx.accounts.authority.key(),
-        stake_lamports,
-        a_deposited: false,
-        b_deposited: false,
+        stake_mint,
         winner: 0,
+        rake_bps,
+        treasury: ctx.accounts.treasury.key(),
         expiry_ts,
+        deposit_deadline_ts,
+        nonce,
+        challenge_settings,
+        referee_quorum,
+        proposed_result: None,
+        challenge_deadline_ts: 0,
+        challenger: Pubkey::default(),
+        wins_required,
+        round_winners: Vec::new(),
+        has_side_pool: false,
+        matched_from_queue: false,
+        attester: attester.unwrap_or_default(),
         bump,
     });
 
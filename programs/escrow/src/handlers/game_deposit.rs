@@ -1,30 +1,86 @@
 use anchor_lang::prelude::*;
+use anchor_spl::token_interface::{Mint, TokenAccount, TokenInterface};
 
 use crate::error::ErrorCode;
-use crate::handlers::transfer_lamports;
-use crate::state::Game;
+use crate::handlers::{transfer_lamports, transfer_tokens};
+use crate::state::{Game, PlayerProfile};
 
 #[derive(Accounts)]
 pub struct Deposit<'info> {
     #[account(mut)]
     pub player: Signer<'info>,
 
+    // Work with either the classic token program or
+    // the newer token extensions program
+    pub token_program: Option<Interface<'info, TokenInterface>>,
+
     pub system_program: Program<'info, System>,
 
     #[account(
         mut,
+        constraint = !game.is_over() @ ErrorCode::GameOver,
         seeds = [b"game", game.id.to_le_bytes().as_ref()],
         bump = game.bump,
     )]
     pub game: Account<'info, Game>,
+
+    #[account(
+        address = game.stake_mint @ ErrorCode::InvalidGameAccounts,
+        mint::token_program = token_program
+    )]
+    pub stake_mint: Option<InterfaceAccount<'info, Mint>>,
+
+    #[account(
+        mut,
+        token::mint = stake_mint,
+        token::authority = player,
+        token::token_program = token_program
+    )]
+    pub player_token_account: Option<InterfaceAccount<'info, TokenAccount>>,
+
+    #[account(
+        mut,
+        associated_token::mint = stake_mint,
+        associated_token::authority = game,
+        associated_token::token_program = token_program
+    )]
+    pub vault: Option<InterfaceAccount<'info, TokenAccount>>,
+
+    // Created on the player's first deposit, and updated whenever a game they're in is finalized
+    #[account(
+        init_if_needed,
+        payer = player,
+        space = PlayerProfile::DISCRIMINATOR.len() + PlayerProfile::INIT_SPACE,
+        seeds = [b"profile", player.key().as_ref()],
+        bump
+    )]
+    pub player_profile: Account<'info, PlayerProfile>,
 }
 
 pub fn deposit(ctx: Context<Deposit>, amount: u64) -> Result<()> {
     let game = &mut ctx.accounts.game;
-    require!(amount == game.stake_lamports, ErrorCode::StakeAmountMismatch);
 
-    if ctx.accounts.player.key() == game.player_a {
-        require!(!game.a_deposited, ErrorCode::AlreadyDeposited);
+    let now = Clock::get()?.unix_timestamp;
+    require!(now <= game.deposit_deadline_ts, ErrorCode::DepositDeadlinePassed);
+
+    // Players without a seat take the first open one
+    let player = ctx.accounts.player.key();
+    let index = match game.players.iter().position(|slot| slot.player == player) {
+        Some(index) => index,
+        None => {
+            let index = game
+                .players
+                .iter()
+                .position(|slot| slot.is_open())
+                .ok_or(ErrorCode::GameFull)?;
+            game.players[index].player = player;
+            index
+        }
+    };
+    require!(!game.players[index].deposited, ErrorCode::AlreadyDeposited);
+    require!(amount == game.players[index].stake, ErrorCode::StakeAmountMismatch);
+
+    if game.stake_mint == Pubkey::default() {
         transfer_lamports(
             &ctx.accounts.player.to_account_info(),
             &game.to_account_info(),
@@ -33,21 +89,41 @@ pub fn deposit(ctx: Context<Deposit>, amount: u64) -> Result<()> {
             None,
         )
         .map_err(|_| ErrorCode::LamportsTransferFailed)?;
-        game.a_deposited = true;
-    } else if ctx.accounts.player.key() == game.player_b {
-        require!(!game.b_deposited, ErrorCode::AlreadyDeposited);
-        transfer_lamports(
+    } else {
+        let (Some(stake_mint), Some(player_token_account), Some(vault), Some(token_program)) = (
+            &ctx.accounts.stake_mint,
+            &ctx.accounts.player_token_account,
+            &mut ctx.accounts.vault,
+            &ctx.accounts.token_program,
+        ) else {
+            return Err(error!(ErrorCode::InvalidGameAccounts));
+        };
+
+        let balance_before = vault.amount;
+        transfer_tokens(
+            player_token_account,
+            vault,
+            &amount,
+            stake_mint,
             &ctx.accounts.player.to_account_info(),
-            &game.to_account_info(),
-            amount,
-            &ctx.accounts.system_program,
+            token_program,
             None,
         )
-        .map_err(|_| ErrorCode::LamportsTransferFailed)?;
-        game.b_deposited = true;
-    } else {
-        return Err(error!(ErrorCode::Unauthorized));
+        .map_err(|_| ErrorCode::InsufficientMakerBalance)?;
+
+        // Mints with a transfer fee deliver less than was sent, so the seat's stake
+        // becomes what the vault actually received and payouts never exceed the vault
+        vault.reload()?;
+        game.players[index].stake = vault
+            .amount
+            .checked_sub(balance_before)
+            .ok_or(ErrorCode::VaultShortfall)?;
     }
+    game.players[index].deposited = true;
+    game.players[index].joined_ts = now;
+
+    let bump = ctx.bumps.player_profile;
+    ctx.accounts.player_profile.set_up(player, bump);
 
     Ok(())
 }
@@ -1,60 +1,140 @@
 use anchor_lang::prelude::*;
+use anchor_spl::token_interface::{Mint, TokenAccount, TokenInterface};
 
 use crate::error::ErrorCode;
-use crate::handlers::transfer_lamports;
-use crate::state::Game;
+use crate::handlers::{pay_out_result, settle_side_pool, split_player_accounts, GameVault};
+use crate::state::{Game, GameResult, SidePool, VoteRecord};
 
+// Where each player is paid must be passed in remaining_accounts, writable, in slot order:
+// their wallet for lamport games, or one of their token accounts for token games.
+// Each player's profile follows, writable, in slot order. For games with a referee quorum,
+// referees signing this transaction follow the profiles
 #[derive(Accounts)]
 pub struct FinalizeGame<'info> {
     #[account(mut)]
     pub authority: Signer<'info>,
 
-    /// CHECK: paid as a SystemAccount; we validate against game players
+    // Where the rake is paid: game.treasury itself, or one of its token accounts for token games
+    /// CHECK: validated against game.treasury
     #[account(mut)]
-    pub winner_account: AccountInfo<'info>,
+    pub treasury: AccountInfo<'info>,
+
+    // Work with either the classic token program or
+    // the newer token extensions program
+    pub token_program: Option<Interface<'info, TokenInterface>>,
 
     pub system_program: Program<'info, System>,
 
     #[account(
         mut,
-        close = authority,
+        constraint = !game.is_over() @ ErrorCode::GameOver,
         seeds = [b"game", game.id.to_le_bytes().as_ref()],
         bump = game.bump,
     )]
     pub game: Account<'info, Game>,
+
+    #[account(
+        address = game.stake_mint @ ErrorCode::InvalidGameAccounts,
+        mint::token_program = token_program
+    )]
+    pub stake_mint: Option<InterfaceAccount<'info, Mint>>,
+
+    #[account(
+        mut,
+        associated_token::mint = stake_mint,
+        associated_token::authority = game,
+        associated_token::token_program = token_program
+    )]
+    pub vault: Option<InterfaceAccount<'info, TokenAccount>>,
+
+    // Votes collected across earlier transactions, for games with a referee quorum.
+    // Closed afterwards with close_vote_record, refunding the referee who paid for it
+    #[account(
+        has_one = game @ ErrorCode::Unauthorized,
+        seeds = [
+            b"vote",
+            game.key().as_ref(),
+            game.nonce.to_le_bytes().as_ref(),
+            vote_record.result_hash.as_ref()
+        ],
+        bump = vote_record.bump,
+    )]
+    pub vote_record: Option<Account<'info, VoteRecord>>,
+
+    // Settled along with the game, for games spectators have bet on
+    #[account(
+        mut,
+        seeds = [b"side_pool", game.key().as_ref(), game.nonce.to_le_bytes().as_ref()],
+        bump = side_pool.bump,
+    )]
+    pub side_pool: Option<Account<'info, SidePool>>,
 }
 
-pub fn finalize_game(ctx: Context<FinalizeGame>, winner: u8) -> Result<()> {
+pub fn finalize_game<'info>(
+    ctx: Context<'_, '_, 'info, 'info, FinalizeGame<'info>>,
+    result: GameResult,
+) -> Result<()> {
     let game = &mut ctx.accounts.game;
 
     require!(ctx.accounts.authority.key() == game.authority, ErrorCode::Unauthorized);
-    require!(game.a_deposited && game.b_deposited, ErrorCode::DepositsIncomplete);
-
-    // Determine winner pubkey
-    let expected_winner = match winner {
-        1 => game.player_a,
-        2 => game.player_b,
-        _ => return Err(error!(ErrorCode::InvalidWinner)),
-    };
-    require!(ctx.accounts.winner_account.key() == expected_winner, ErrorCode::InvalidWinner);
-
-    // Transfer total stake to winner from the game PDA
-    let total = game
-        .stake_lamports
-        .checked_mul(2)
-        .ok_or(error!(ErrorCode::LamportsTransferFailed))?;
-
-    let seeds = [b"game".as_ref(), &game.id.to_le_bytes()[..], &[game.bump]];
-    transfer_lamports(
-        &game.to_account_info(),
-        &ctx.accounts.winner_account,
-        total,
-        &ctx.accounts.system_program,
-        Some(&seeds),
-    )
-    .map_err(|_| ErrorCode::LamportsTransferFailed)?;
-
-    game.winner = winner;
+    require!(game.all_deposited(), ErrorCode::DepositsIncomplete);
+    require!(game.challenge_settings.is_none(), ErrorCode::ResultMustBeProposed);
+    require!(game.wins_required == 0, ErrorCode::PaidOutByRounds);
+
+    let (player_accounts, profile_accounts, signing_referees) =
+        split_player_accounts(game, ctx.remaining_accounts)?;
+
+    // Count distinct referees agreeing on the result, from the vote record or this transaction
+    if let Some(referee_quorum) = &game.referee_quorum {
+        let mut approvals: Vec<Pubkey> = Vec::new();
+        if let Some(vote_record) = &ctx.accounts.vote_record {
+            require!(
+                vote_record.result_hash == result.hash(),
+                ErrorCode::QuorumNotReached
+            );
+            // Only count voters who are still referees for this game
+            approvals.extend(
+                vote_record
+                    .voters
+                    .iter()
+                    .filter(|voter| referee_quorum.referees.contains(voter)),
+            );
+        }
+        for account in [ctx.accounts.authority.as_ref()]
+            .into_iter()
+            .chain(signing_referees)
+        {
+            if account.is_signer
+                && referee_quorum.referees.contains(account.key)
+                && !approvals.contains(account.key)
+            {
+                approvals.push(account.key());
+            }
+        }
+        require!(
+            approvals.len() >= referee_quorum.threshold as usize,
+            ErrorCode::QuorumNotReached
+        );
+    }
+
+    let game_vault = GameVault::for_game(
+        game,
+        &ctx.accounts.stake_mint,
+        &ctx.accounts.vault,
+        &ctx.accounts.token_program,
+    )?;
+
+    pay_out_result(
+        game,
+        &result,
+        player_accounts,
+        profile_accounts,
+        &ctx.accounts.treasury,
+        game_vault.as_ref(),
+        &ctx.accounts.authority.to_account_info(),
+    )?;
+
+    settle_side_pool(game, ctx.accounts.side_pool.as_mut(), Game::winner_for(&result))?;
 
     Ok(())
 }
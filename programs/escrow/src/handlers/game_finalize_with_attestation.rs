@@ -0,0 +1,167 @@
+use anchor_lang::prelude::*;
+use anchor_spl::token_interface::{Mint, TokenAccount, TokenInterface};
+use solana_instructions_sysvar::{get_instruction_relative, ID as INSTRUCTIONS_SYSVAR_ID};
+use solana_sdk_ids::ed25519_program;
+
+use crate::constants::RESULT_ATTESTATION_TAG;
+use crate::error::ErrorCode;
+use crate::handlers::{pay_out_result, settle_side_pool, split_player_accounts, GameVault};
+use crate::state::{Game, GameResult, ResultAttestation, SidePool};
+
+// Ed25519 program instructions start with the number of signatures and a padding byte,
+// followed by the offsets of each signature, public key, and message
+const ED25519_OFFSETS_START: usize = 2;
+const ED25519_OFFSETS_SIZE: usize = 14;
+const ED25519_PUBKEY_SIZE: usize = 32;
+
+// The previous instruction must be an Ed25519 program instruction verifying the attester's
+// signature over the ResultAttestation for this game and result.
+// Where each player is paid must be passed in remaining_accounts, writable, in slot order:
+// their wallet for lamport games, or one of their token accounts for token games.
+// Each player's profile follows, writable, in slot order
+#[derive(Accounts)]
+pub struct FinalizeWithAttestation<'info> {
+    // Anyone can submit the attestation, usually the winner
+    pub relayer: Signer<'info>,
+
+    // Gets the rent back
+    #[account(mut)]
+    pub authority: SystemAccount<'info>,
+
+    // Where the rake is paid: game.treasury itself, or one of its token accounts for token games
+    /// CHECK: validated against game.treasury
+    #[account(mut)]
+    pub treasury: AccountInfo<'info>,
+
+    // Work with either the classic token program or
+    // the newer token extensions program
+    pub token_program: Option<Interface<'info, TokenInterface>>,
+
+    pub system_program: Program<'info, System>,
+
+    /// CHECK: the instructions sysvar, used to find the Ed25519 program instruction
+    #[account(address = INSTRUCTIONS_SYSVAR_ID)]
+    pub instructions: UncheckedAccount<'info>,
+
+    #[account(
+        mut,
+        has_one = authority @ ErrorCode::Unauthorized,
+        constraint = !game.is_over() @ ErrorCode::GameOver,
+        seeds = [b"game", game.id.to_le_bytes().as_ref()],
+        bump = game.bump,
+    )]
+    pub game: Account<'info, Game>,
+
+    #[account(
+        address = game.stake_mint @ ErrorCode::InvalidGameAccounts,
+        mint::token_program = token_program
+    )]
+    pub stake_mint: Option<InterfaceAccount<'info, Mint>>,
+
+    #[account(
+        mut,
+        associated_token::mint = stake_mint,
+        associated_token::authority = game,
+        associated_token::token_program = token_program
+    )]
+    pub vault: Option<InterfaceAccount<'info, TokenAccount>>,
+
+    // Settled along with the game, for games spectators have bet on
+    #[account(
+        mut,
+        seeds = [b"side_pool", game.key().as_ref(), game.nonce.to_le_bytes().as_ref()],
+        bump = side_pool.bump,
+    )]
+    pub side_pool: Option<Account<'info, SidePool>>,
+}
+
+// Pays out a result the game's attester signed off-chain, so the attester's key
+// never has to be on-chain, hold SOL or sign transactions
+pub fn finalize_with_attestation<'info>(
+    ctx: Context<'_, '_, 'info, 'info, FinalizeWithAttestation<'info>>,
+    result: GameResult,
+) -> Result<()> {
+    let game = &mut ctx.accounts.game;
+
+    require!(game.all_deposited(), ErrorCode::DepositsIncomplete);
+    require!(game.challenge_settings.is_none(), ErrorCode::ResultMustBeProposed);
+    require!(game.referee_quorum.is_none(), ErrorCode::QuorumNotReached);
+    require!(game.wins_required == 0, ErrorCode::PaidOutByRounds);
+    require!(game.attester != Pubkey::default(), ErrorCode::AttestationsNotEnabled);
+
+    let mut message = Vec::new();
+    ResultAttestation {
+        tag: RESULT_ATTESTATION_TAG,
+        program_id: crate::ID,
+        game_id: game.id,
+        nonce: game.nonce,
+        result: result.clone(),
+    }
+    .serialize(&mut message)?;
+    verify_attestation(&ctx.accounts.instructions, &game.attester, &message)?;
+
+    let game_vault = GameVault::for_game(
+        game,
+        &ctx.accounts.stake_mint,
+        &ctx.accounts.vault,
+        &ctx.accounts.token_program,
+    )?;
+
+    let (player_accounts, profile_accounts, _) =
+        split_player_accounts(game, ctx.remaining_accounts)?;
+    pay_out_result(
+        game,
+        &result,
+        player_accounts,
+        profile_accounts,
+        &ctx.accounts.treasury,
+        game_vault.as_ref(),
+        &ctx.accounts.authority.to_account_info(),
+    )?;
+
+    settle_side_pool(game, ctx.accounts.side_pool.as_mut(), Game::winner_for(&result))?;
+
+    Ok(())
+}
+
+// Checks the previous instruction has the Ed25519 program verify `signer`'s signature over
+// `message`. The Ed25519 program fails the transaction if the signature is invalid, so this only
+// has to check it verified the right public key and message, held in its own instruction data
+fn verify_attestation(instructions: &AccountInfo, signer: &Pubkey, message: &[u8]) -> Result<()> {
+    let instruction = get_instruction_relative(-1, instructions)
+        .map_err(|_| error!(ErrorCode::InvalidAttestation))?;
+    require!(
+        instruction.program_id == ed25519_program::ID,
+        ErrorCode::InvalidAttestation
+    );
+
+    let data = &instruction.data;
+    require!(
+        data.len() >= ED25519_OFFSETS_START + ED25519_OFFSETS_SIZE && data[0] == 1,
+        ErrorCode::InvalidAttestation
+    );
+    let offset = |index: usize| {
+        let start = ED25519_OFFSETS_START + index * 2;
+        u16::from_le_bytes([data[start], data[start + 1]])
+    };
+    // The signature, public key, and message must all be in this instruction's data,
+    // rather than pointing at data in another instruction
+    require!(
+        [offset(1), offset(3), offset(6)]
+            .iter()
+            .all(|instruction_index| *instruction_index == u16::MAX),
+        ErrorCode::InvalidAttestation
+    );
+
+    let public_key_offset = offset(2) as usize;
+    let message_offset = offset(4) as usize;
+    let message_size = offset(5) as usize;
+    require!(
+        data.get(public_key_offset..public_key_offset + ED25519_PUBKEY_SIZE)
+            == Some(signer.as_ref())
+            && data.get(message_offset..message_offset + message_size) == Some(message),
+        ErrorCode::InvalidAttestation
+    );
+
+    Ok(())
+}
@@ -0,0 +1,94 @@
+use anchor_lang::prelude::*;
+
+use crate::error::ErrorCode;
+use crate::handlers::transfer_lamports;
+use crate::state::{Game, SideBet, SidePool};
+
+#[derive(Accounts)]
+pub struct PlaceSideBet<'info> {
+    #[account(mut)]
+    pub bettor: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+
+    #[account(
+        mut,
+        constraint = !game.is_over() @ ErrorCode::GameOver,
+        seeds = [b"game", game.id.to_le_bytes().as_ref()],
+        bump = game.bump,
+    )]
+    pub game: Account<'info, Game>,
+
+    // Created by the game's first bet, and holds every bet's lamports
+    #[account(
+        init_if_needed,
+        payer = bettor,
+        space = SidePool::DISCRIMINATOR.len() + SidePool::INIT_SPACE,
+        seeds = [b"side_pool", game.key().as_ref(), game.nonce.to_le_bytes().as_ref()],
+        bump
+    )]
+    pub side_pool: Account<'info, SidePool>,
+
+    // Created on the bettor's first bet, and added to on later ones
+    #[account(
+        init_if_needed,
+        payer = bettor,
+        space = SideBet::DISCRIMINATOR.len() + SideBet::INIT_SPACE,
+        seeds = [b"side_bet", side_pool.key().as_ref(), bettor.key().as_ref()],
+        bump
+    )]
+    pub side_bet: Account<'info, SideBet>,
+}
+
+// Bets lamports on the player in `slot` winning the game, until every player has deposited
+pub fn place_side_bet(ctx: Context<PlaceSideBet>, slot: u8, amount: u64) -> Result<()> {
+    let game = &mut ctx.accounts.game;
+    require!(amount > 0, ErrorCode::InvalidAmount);
+    require!(
+        game.challenge_settings.is_none() && game.wins_required == 0,
+        ErrorCode::SideBetsNotSupported
+    );
+    require!(!game.all_deposited(), ErrorCode::SideBetsClosed);
+    require!(
+        (1..=game.players.len()).contains(&(slot as usize)),
+        ErrorCode::InvalidResult
+    );
+
+    let side_pool = &mut ctx.accounts.side_pool;
+    if !game.has_side_pool {
+        side_pool.game = game.key();
+        side_pool.game_nonce = game.nonce;
+        side_pool.slot_totals = vec![0; game.players.len()];
+        side_pool.rent_payer = ctx.accounts.bettor.key();
+        side_pool.bump = ctx.bumps.side_pool;
+        game.has_side_pool = true;
+    }
+
+    let side_bet = &mut ctx.accounts.side_bet;
+    if side_bet.amount == 0 {
+        side_bet.side_pool = side_pool.key();
+        side_bet.bettor = ctx.accounts.bettor.key();
+        side_bet.slot = slot;
+        side_bet.bump = ctx.bumps.side_bet;
+        side_pool.open_bets += 1;
+    }
+    require!(side_bet.slot == slot, ErrorCode::BackedAnotherPlayer);
+
+    transfer_lamports(
+        &ctx.accounts.bettor.to_account_info(),
+        &side_pool.to_account_info(),
+        amount,
+        &ctx.accounts.system_program,
+        None,
+    )
+    .map_err(|_| ErrorCode::LamportsTransferFailed)?;
+
+    side_bet.amount = side_bet
+        .amount
+        .checked_add(amount)
+        .ok_or(ErrorCode::InvalidAmount)?;
+    let slot_total = &mut side_pool.slot_totals[slot as usize - 1];
+    *slot_total = slot_total.checked_add(amount).ok_or(ErrorCode::InvalidAmount)?;
+
+    Ok(())
+}
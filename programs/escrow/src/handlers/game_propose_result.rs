@@ -0,0 +1,40 @@
+use anchor_lang::prelude::*;
+
+use crate::error::ErrorCode;
+use crate::state::{Game, GameResult};
+
+#[derive(Accounts)]
+pub struct ProposeResult<'info> {
+    pub authority: Signer<'info>,
+
+    #[account(
+        mut,
+        has_one = authority @ ErrorCode::Unauthorized,
+        constraint = !game.is_over() @ ErrorCode::GameOver,
+        seeds = [b"game", game.id.to_le_bytes().as_ref()],
+        bump = game.bump,
+    )]
+    pub game: Account<'info, Game>,
+}
+
+// Records the authority's result for an optimistically settled game and opens the challenge window
+pub fn propose_result(ctx: Context<ProposeResult>, result: GameResult) -> Result<()> {
+    let game = &mut ctx.accounts.game;
+
+    let Some(challenge_settings) = &game.challenge_settings else {
+        return Err(error!(ErrorCode::ChallengesNotEnabled));
+    };
+    require!(game.all_deposited(), ErrorCode::DepositsIncomplete);
+    require!(game.proposed_result.is_none(), ErrorCode::ResultAlreadyProposed);
+
+    // Check the result can be paid out now, rather than when it's settled
+    game.payouts(&result)?;
+
+    let now = Clock::get()?.unix_timestamp;
+    game.challenge_deadline_ts = now
+        .checked_add(challenge_settings.challenge_period)
+        .ok_or(ErrorCode::InvalidChallengeSettings)?;
+    game.proposed_result = Some(result);
+
+    Ok(())
+}
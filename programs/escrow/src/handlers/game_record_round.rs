@@ -0,0 +1,102 @@
+use anchor_lang::prelude::*;
+use anchor_spl::token_interface::{Mint, TokenAccount, TokenInterface};
+
+use crate::error::ErrorCode;
+use crate::events::RoundRecorded;
+use crate::handlers::{pay_out_result, split_player_accounts, GameVault};
+use crate::state::{Game, GameResult};
+
+// Where each player is paid must be passed in remaining_accounts, writable, in slot order,
+// when recording the round that wins the series: their wallet for lamport games,
+// or one of their token accounts for token games. Each player's profile follows,
+// writable, in slot order
+#[derive(Accounts)]
+pub struct RecordRound<'info> {
+    #[account(mut)]
+    pub authority: Signer<'info>,
+
+    // Where the rake is paid: game.treasury itself, or one of its token accounts for token games
+    /// CHECK: validated against game.treasury
+    #[account(mut)]
+    pub treasury: AccountInfo<'info>,
+
+    // Work with either the classic token program or
+    // the newer token extensions program
+    pub token_program: Option<Interface<'info, TokenInterface>>,
+
+    pub system_program: Program<'info, System>,
+
+    #[account(
+        mut,
+        has_one = authority @ ErrorCode::Unauthorized,
+        constraint = !game.is_over() @ ErrorCode::GameOver,
+        seeds = [b"game", game.id.to_le_bytes().as_ref()],
+        bump = game.bump,
+    )]
+    pub game: Account<'info, Game>,
+
+    #[account(
+        address = game.stake_mint @ ErrorCode::InvalidGameAccounts,
+        mint::token_program = token_program
+    )]
+    pub stake_mint: Option<InterfaceAccount<'info, Mint>>,
+
+    #[account(
+        mut,
+        associated_token::mint = stake_mint,
+        associated_token::authority = game,
+        associated_token::token_program = token_program
+    )]
+    pub vault: Option<InterfaceAccount<'info, TokenAccount>>,
+}
+
+// Records the winner of a series' next round. Once a player has won enough rounds,
+// they're paid the pot
+pub fn record_round<'info>(
+    ctx: Context<'_, '_, 'info, 'info, RecordRound<'info>>,
+    winner: u8,
+) -> Result<()> {
+    let game = &mut ctx.accounts.game;
+    require!(game.wins_required > 0, ErrorCode::NotASeries);
+    require!(game.all_deposited(), ErrorCode::DepositsIncomplete);
+    require!(
+        (1..=game.players.len()).contains(&(winner as usize)),
+        ErrorCode::InvalidResult
+    );
+
+    game.round_winners.push(winner);
+    emit!(RoundRecorded {
+        id: game.id,
+        round: game.round_winners.len() as u8,
+        winner,
+    });
+    if game.rounds_won(winner) < game.wins_required as usize {
+        return Ok(());
+    }
+
+    let game = &mut ctx.accounts.game;
+    let game_vault = GameVault::for_game(
+        game,
+        &ctx.accounts.stake_mint,
+        &ctx.accounts.vault,
+        &ctx.accounts.token_program,
+    )?;
+
+    let result = GameResult {
+        ranking: vec![winner],
+        payout_bps: vec![10_000],
+    };
+    let (player_accounts, profile_accounts, _) =
+        split_player_accounts(game, ctx.remaining_accounts)?;
+    pay_out_result(
+        game,
+        &result,
+        player_accounts,
+        profile_accounts,
+        &ctx.accounts.treasury,
+        game_vault.as_ref(),
+        &ctx.accounts.authority.to_account_info(),
+    )?;
+
+    Ok(())
+}
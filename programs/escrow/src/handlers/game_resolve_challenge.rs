@@ -0,0 +1,103 @@
+use anchor_lang::prelude::*;
+use anchor_spl::token_interface::{Mint, TokenAccount, TokenInterface};
+
+use crate::error::ErrorCode;
+use crate::handlers::{pay_out_result, split_player_accounts, GameVault};
+use crate::state::{Game, GameResult};
+
+// Where each player is paid must be passed in remaining_accounts, writable, in slot order:
+// their wallet for lamport games, or one of their token accounts for token games.
+// Each player's profile follows, writable, in slot order
+#[derive(Accounts)]
+pub struct ResolveChallenge<'info> {
+    pub arbiter: Signer<'info>,
+
+    // Gets the rent back, and keeps the bond if the challenge fails
+    #[account(mut)]
+    pub authority: SystemAccount<'info>,
+
+    // Gets the bond back if the challenge succeeds
+    #[account(mut, address = game.challenger @ ErrorCode::Unauthorized)]
+    pub challenger: SystemAccount<'info>,
+
+    // Where the rake is paid: game.treasury itself, or one of its token accounts for token games
+    /// CHECK: validated against game.treasury
+    #[account(mut)]
+    pub treasury: AccountInfo<'info>,
+
+    // Work with either the classic token program or
+    // the newer token extensions program
+    pub token_program: Option<Interface<'info, TokenInterface>>,
+
+    pub system_program: Program<'info, System>,
+
+    #[account(
+        mut,
+        has_one = authority @ ErrorCode::Unauthorized,
+        constraint = !game.is_over() @ ErrorCode::GameOver,
+        seeds = [b"game", game.id.to_le_bytes().as_ref()],
+        bump = game.bump,
+    )]
+    pub game: Account<'info, Game>,
+
+    #[account(
+        address = game.stake_mint @ ErrorCode::InvalidGameAccounts,
+        mint::token_program = token_program
+    )]
+    pub stake_mint: Option<InterfaceAccount<'info, Mint>>,
+
+    #[account(
+        mut,
+        associated_token::mint = stake_mint,
+        associated_token::authority = game,
+        associated_token::token_program = token_program
+    )]
+    pub vault: Option<InterfaceAccount<'info, TokenAccount>>,
+}
+
+// Pays out the arbiter's result for a challenged game. The challenger's bond is returned
+// if the arbiter overturns the proposed result
+pub fn resolve_challenge<'info>(
+    ctx: Context<'_, '_, 'info, 'info, ResolveChallenge<'info>>,
+    result: GameResult,
+) -> Result<()> {
+    let game = &mut ctx.accounts.game;
+
+    let Some(challenge_settings) = game.challenge_settings.clone() else {
+        return Err(error!(ErrorCode::ChallengesNotEnabled));
+    };
+    require!(
+        ctx.accounts.arbiter.key() == challenge_settings.arbiter,
+        ErrorCode::Unauthorized
+    );
+    require!(game.challenger != Pubkey::default(), ErrorCode::NotChallenged);
+
+    let game_vault = GameVault::for_game(
+        game,
+        &ctx.accounts.stake_mint,
+        &ctx.accounts.vault,
+        &ctx.accounts.token_program,
+    )?;
+
+    let (player_accounts, profile_accounts, _) =
+        split_player_accounts(game, ctx.remaining_accounts)?;
+    pay_out_result(
+        game,
+        &result,
+        player_accounts,
+        profile_accounts,
+        &ctx.accounts.treasury,
+        game_vault.as_ref(),
+        &ctx.accounts.authority.to_account_info(),
+    )?;
+
+    // A failed challenge leaves the bond in the game account, which goes to the authority
+    // along with the rent when the game is closed
+    if game.proposed_result.as_ref() != Some(&result) {
+        game.sub_lamports(challenge_settings.challenge_bond)
+            .map_err(|_| ErrorCode::LamportsTransferFailed)?;
+        ctx.accounts.challenger.add_lamports(challenge_settings.challenge_bond)?;
+    }
+
+    Ok(())
+}
@@ -0,0 +1,87 @@
+use anchor_lang::prelude::*;
+use anchor_spl::token_interface::{Mint, TokenAccount, TokenInterface};
+
+use crate::error::ErrorCode;
+use crate::handlers::{pay_out_result, split_player_accounts, GameVault};
+use crate::state::Game;
+
+// Where each player is paid must be passed in remaining_accounts, writable, in slot order:
+// their wallet for lamport games, or one of their token accounts for token games.
+// Each player's profile follows, writable, in slot order
+#[derive(Accounts)]
+pub struct SettleGame<'info> {
+    // Anyone can settle a game once its challenge window has passed
+    pub caller: Signer<'info>,
+
+    // Gets the rent back
+    #[account(mut)]
+    pub authority: SystemAccount<'info>,
+
+    // Where the rake is paid: game.treasury itself, or one of its token accounts for token games
+    /// CHECK: validated against game.treasury
+    #[account(mut)]
+    pub treasury: AccountInfo<'info>,
+
+    // Work with either the classic token program or
+    // the newer token extensions program
+    pub token_program: Option<Interface<'info, TokenInterface>>,
+
+    pub system_program: Program<'info, System>,
+
+    #[account(
+        mut,
+        has_one = authority @ ErrorCode::Unauthorized,
+        constraint = !game.is_over() @ ErrorCode::GameOver,
+        seeds = [b"game", game.id.to_le_bytes().as_ref()],
+        bump = game.bump,
+    )]
+    pub game: Account<'info, Game>,
+
+    #[account(
+        address = game.stake_mint @ ErrorCode::InvalidGameAccounts,
+        mint::token_program = token_program
+    )]
+    pub stake_mint: Option<InterfaceAccount<'info, Mint>>,
+
+    #[account(
+        mut,
+        associated_token::mint = stake_mint,
+        associated_token::authority = game,
+        associated_token::token_program = token_program
+    )]
+    pub vault: Option<InterfaceAccount<'info, TokenAccount>>,
+}
+
+// Pays out the proposed result once the challenge window has passed without a challenge
+pub fn settle_game<'info>(ctx: Context<'_, '_, 'info, 'info, SettleGame<'info>>) -> Result<()> {
+    let game = &mut ctx.accounts.game;
+
+    let Some(result) = game.proposed_result.clone() else {
+        return Err(error!(ErrorCode::NoProposedResult));
+    };
+    require!(game.challenger == Pubkey::default(), ErrorCode::AlreadyChallenged);
+
+    let now = Clock::get()?.unix_timestamp;
+    require!(now > game.challenge_deadline_ts, ErrorCode::ChallengeWindowOpen);
+
+    let game_vault = GameVault::for_game(
+        game,
+        &ctx.accounts.stake_mint,
+        &ctx.accounts.vault,
+        &ctx.accounts.token_program,
+    )?;
+
+    let (player_accounts, profile_accounts, _) =
+        split_player_accounts(game, ctx.remaining_accounts)?;
+    pay_out_result(
+        game,
+        &result,
+        player_accounts,
+        profile_accounts,
+        &ctx.accounts.treasury,
+        game_vault.as_ref(),
+        &ctx.accounts.authority.to_account_info(),
+    )?;
+
+    Ok(())
+}
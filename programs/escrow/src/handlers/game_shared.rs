@@ -0,0 +1,282 @@
+use anchor_lang::prelude::*;
+use anchor_spl::token_interface::{
+    transfer_checked, Mint, TokenAccount, TokenInterface, TransferChecked,
+};
+
+use crate::error::ErrorCode;
+use crate::events::GameFinalized;
+use crate::handlers::close_token_account;
+use crate::state::{rating_changes, Game, GameResult, PlayerProfile, SidePool};
+
+// The accounts holding a token game's stakes
+pub struct GameVault<'a, 'info> {
+    pub stake_mint: &'a InterfaceAccount<'info, Mint>,
+    pub vault: &'a InterfaceAccount<'info, TokenAccount>,
+    pub token_program: &'a Interface<'info, TokenInterface>,
+}
+
+impl<'a, 'info> GameVault<'a, 'info> {
+    // The vault accounts for a token game, or None if the game is staked in lamports
+    pub fn for_game(
+        game: &Game,
+        stake_mint: &'a Option<InterfaceAccount<'info, Mint>>,
+        vault: &'a Option<InterfaceAccount<'info, TokenAccount>>,
+        token_program: &'a Option<Interface<'info, TokenInterface>>,
+    ) -> Result<Option<Self>> {
+        if game.stake_mint == Pubkey::default() {
+            return Ok(None);
+        }
+
+        let (Some(stake_mint), Some(vault), Some(token_program)) =
+            (stake_mint, vault, token_program)
+        else {
+            return Err(error!(ErrorCode::InvalidGameAccounts));
+        };
+        Ok(Some(Self {
+            stake_mint,
+            vault,
+            token_program,
+        }))
+    }
+}
+
+// Checks `account` is where `owner` gets paid from a game: their wallet for lamport games,
+// or one of their token accounts for the stake mint in token games
+pub fn check_game_payee(
+    account: &AccountInfo,
+    owner: &Pubkey,
+    game_vault: Option<&GameVault>,
+) -> Result<()> {
+    let Some(game_vault) = game_vault else {
+        require!(account.key() == *owner, ErrorCode::Unauthorized);
+        return Ok(());
+    };
+
+    require!(
+        account.owner == &game_vault.token_program.key(),
+        ErrorCode::InvalidGameAccounts
+    );
+    let token_account = TokenAccount::try_deserialize(&mut &account.try_borrow_data()?[..])?;
+    require!(
+        token_account.owner == *owner && token_account.mint == game_vault.stake_mint.key(),
+        ErrorCode::Unauthorized
+    );
+    Ok(())
+}
+
+// Pays out of a game's stakes, from the game account itself for lamport games,
+// or from the vault for token games
+pub fn pay_from_game<'info>(
+    game: &Account<'info, Game>,
+    destination: &AccountInfo<'info>,
+    amount: u64,
+    game_vault: Option<&GameVault<'_, 'info>>,
+) -> Result<()> {
+    // The game account is owned by this program, so it can be debited directly
+    let Some(game_vault) = game_vault else {
+        game.sub_lamports(amount).map_err(|_| error!(ErrorCode::LamportsTransferFailed))?;
+        destination.add_lamports(amount)?;
+        return Ok(());
+    };
+
+    let seeds = [b"game".as_ref(), &game.id.to_le_bytes()[..], &[game.bump]];
+
+    // Payees are checked by check_game_payee rather than loaded as token accounts,
+    // so this calls transfer_checked directly instead of going through transfer_tokens
+    let transfer_accounts = TransferChecked {
+        from: game_vault.vault.to_account_info(),
+        mint: game_vault.stake_mint.to_account_info(),
+        to: destination.clone(),
+        authority: game.to_account_info(),
+    };
+    transfer_checked(
+        CpiContext::new_with_signer(
+            game_vault.token_program.to_account_info(),
+            transfer_accounts,
+            &[&seeds],
+        ),
+        amount,
+        game_vault.stake_mint.decimals,
+    )
+    .map_err(|_| error!(ErrorCode::FailedVaultWithdrawal))
+}
+
+// Closes a token game's vault once it's empty, sending the rent to `destination`
+pub fn close_game_vault<'info>(
+    game: &Account<'info, Game>,
+    game_vault: &GameVault<'_, 'info>,
+    destination: &AccountInfo<'info>,
+) -> Result<()> {
+    let seeds = [b"game".as_ref(), &game.id.to_le_bytes()[..], &[game.bump]];
+
+    close_token_account(
+        game_vault.vault,
+        destination,
+        &game.to_account_info(),
+        game_vault.token_program,
+        Some(&seeds),
+    )
+    .map_err(|_| error!(ErrorCode::FailedRefundClosure))
+}
+
+// Records how a game ended on its side pool, if spectators bet on it, so bets can be claimed.
+// `outcome` is the game's winner, or DRAW to refund every bet
+pub fn settle_side_pool(
+    game: &Game,
+    side_pool: Option<&mut Account<SidePool>>,
+    outcome: u8,
+) -> Result<()> {
+    if !game.has_side_pool {
+        return Ok(());
+    }
+    let Some(side_pool) = side_pool else {
+        return Err(error!(ErrorCode::InvalidGameAccounts));
+    };
+    side_pool.outcome = outcome;
+    Ok(())
+}
+
+// Splits an instruction's remaining accounts into where each player is paid and each player's
+// profile, both in slot order, and whatever the instruction takes after them
+pub fn split_player_accounts<'info>(
+    game: &Game,
+    remaining_accounts: &'info [AccountInfo<'info>],
+) -> Result<(
+    &'info [AccountInfo<'info>],
+    &'info [AccountInfo<'info>],
+    &'info [AccountInfo<'info>],
+)> {
+    let player_count = game.players.len();
+    require!(remaining_accounts.len() >= player_count * 2, ErrorCode::Unauthorized);
+    let (player_accounts, remaining_accounts) = remaining_accounts.split_at(player_count);
+    let (profile_accounts, remaining_accounts) = remaining_accounts.split_at(player_count);
+    Ok((player_accounts, profile_accounts, remaining_accounts))
+}
+
+// Pays out a game's result: each player's share of the pot, less the rake, to where they're paid
+// in `player_accounts` (in slot order), and the rake to the treasury, then records the game on
+// each player's profile and its winner on the game. Token games then have their vault closed,
+// with its rent sent to `rent_destination`. The game account itself is left open as a record
+// of the outcome until close_game
+pub fn pay_out_result<'info>(
+    game: &mut Account<'info, Game>,
+    result: &GameResult,
+    player_accounts: &[AccountInfo<'info>],
+    profile_accounts: &'info [AccountInfo<'info>],
+    treasury: &AccountInfo<'info>,
+    game_vault: Option<&GameVault<'_, 'info>>,
+    rent_destination: &AccountInfo<'info>,
+) -> Result<()> {
+    // Validate destination accounts
+    require!(player_accounts.len() == game.players.len(), ErrorCode::Unauthorized);
+    for (player_account, slot) in player_accounts.iter().zip(&game.players) {
+        check_game_payee(player_account, &slot.player, game_vault)?;
+    }
+    check_game_payee(treasury, &game.treasury, game_vault)?;
+
+    let (mut payouts, rake) = game.payouts(result)?;
+
+    // Anything else sent to the vault goes to first place, or slot 1 on a draw,
+    // so the vault is empty and can be closed
+    if let Some(game_vault) = game_vault {
+        let first = result.ranking.first().map_or(0, |slot| *slot as usize - 1);
+        let extra = game_vault
+            .vault
+            .amount
+            .checked_sub(game.total_staked())
+            .ok_or(ErrorCode::VaultShortfall)?;
+        payouts[first] += extra;
+    }
+
+    for (destination, amount) in player_accounts.iter().zip(payouts).chain([(treasury, rake)]) {
+        if amount == 0 {
+            continue;
+        }
+        pay_from_game(game, destination, amount, game_vault)?;
+    }
+
+    update_player_profiles(game, result, profile_accounts)?;
+
+    if let Some(game_vault) = game_vault {
+        close_game_vault(game, game_vault, rent_destination)?;
+    }
+
+    game.winner = Game::winner_for(result);
+
+    emit!(GameFinalized {
+        id: game.id,
+        winner: Game::winner_for(result),
+    });
+
+    Ok(())
+}
+
+// Games matched from a queue had their rent paid by their players' rent shares, so when the game
+// is closed, whatever the game account holds is split evenly between the players in
+// `player_accounts` (in slot order), rather than going to whoever closes it.
+// Queue games are staked in lamports, so `player_accounts` are the players' wallets
+pub fn refund_queue_rent<'info>(
+    game: &Account<'info, Game>,
+    player_accounts: &[AccountInfo<'info>],
+) -> Result<()> {
+    if !game.matched_from_queue {
+        return Ok(());
+    }
+
+    let rent = game.get_lamports();
+    let share = rent / player_accounts.len() as u64;
+    // Slot 1 gets any lamport left over from splitting the rent
+    let leftover = rent - share * player_accounts.len() as u64;
+    for (index, player_account) in player_accounts.iter().enumerate() {
+        let amount = if index == 0 { share + leftover } else { share };
+        pay_from_game(game, player_account, amount, None)?;
+    }
+
+    Ok(())
+}
+
+// Records a finalized game on each player's profile, passed in `profile_accounts` in slot order:
+// their record, what they wagered and won, and their new rating
+pub fn update_player_profiles<'info>(
+    game: &Game,
+    result: &GameResult,
+    profile_accounts: &'info [AccountInfo<'info>],
+) -> Result<()> {
+    require!(profile_accounts.len() == game.players.len(), ErrorCode::Unauthorized);
+    let mut profiles = Vec::with_capacity(profile_accounts.len());
+    for (profile_account, slot) in profile_accounts.iter().zip(&game.players) {
+        let profile = Account::<PlayerProfile>::try_from(profile_account)?;
+        require!(profile.player == slot.player, ErrorCode::Unauthorized);
+        profiles.push(profile);
+    }
+
+    // Unranked players share last place, and on a draw everyone shares first
+    let mut places = vec![result.ranking.len(); game.players.len()];
+    for (place, slot) in result.ranking.iter().enumerate() {
+        places[*slot as usize - 1] = place;
+    }
+    let ratings: Vec<i32> = profiles.iter().map(|profile| profile.rating).collect();
+    let rating_changes = rating_changes(&ratings, &places);
+
+    let (payouts, _rake) = game.payouts(result)?;
+    for (index, profile) in profiles.iter_mut().enumerate() {
+        let stake = game.players[index].stake;
+        profile.games_played += 1;
+        if result.ranking.is_empty() {
+            profile.draws += 1;
+        } else if places[index] == 0 {
+            profile.wins += 1;
+        } else {
+            profile.losses += 1;
+        }
+        profile.total_wagered = profile
+            .total_wagered
+            .checked_add(stake)
+            .ok_or(ErrorCode::InvalidAmount)?;
+        profile.net_profit += payouts[index] as i64 - stake as i64;
+        profile.rating += rating_changes[index];
+        profile.exit(&crate::ID)?;
+    }
+
+    Ok(())
+}
@@ -0,0 +1,61 @@
+use anchor_lang::prelude::*;
+
+use crate::error::ErrorCode;
+use crate::state::{Game, GameResult, VoteRecord};
+
+#[derive(Accounts)]
+#[instruction(result: GameResult)]
+pub struct VoteResult<'info> {
+    #[account(mut)]
+    pub referee: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+
+    #[account(
+        constraint = !game.is_over() @ ErrorCode::GameOver,
+        seeds = [b"game", game.id.to_le_bytes().as_ref()],
+        bump = game.bump,
+    )]
+    pub game: Account<'info, Game>,
+
+    // One record per result, so referees voting for different results don't mix
+    #[account(
+        init_if_needed,
+        payer = referee,
+        space = VoteRecord::DISCRIMINATOR.len() + VoteRecord::INIT_SPACE,
+        seeds = [
+            b"vote",
+            game.key().as_ref(),
+            game.nonce.to_le_bytes().as_ref(),
+            result.hash().as_ref()
+        ],
+        bump
+    )]
+    pub vote_record: Account<'info, VoteRecord>,
+}
+
+// Records a referee's vote for a result, for games finalized by a referee quorum
+pub fn vote_result(ctx: Context<VoteResult>, result: GameResult) -> Result<()> {
+    let game = &ctx.accounts.game;
+    let Some(referee_quorum) = &game.referee_quorum else {
+        return Err(error!(ErrorCode::QuorumNotEnabled));
+    };
+    let referee = ctx.accounts.referee.key();
+    require!(referee_quorum.referees.contains(&referee), ErrorCode::Unauthorized);
+
+    // Check the result can be paid out now, rather than when the game is finalized
+    game.payouts(&result)?;
+
+    let vote_record = &mut ctx.accounts.vote_record;
+    if vote_record.voters.is_empty() {
+        vote_record.game = game.key();
+        vote_record.game_nonce = game.nonce;
+        vote_record.result_hash = result.hash();
+        vote_record.rent_payer = referee;
+        vote_record.bump = ctx.bumps.vote_record;
+    }
+    require!(!vote_record.voters.contains(&referee), ErrorCode::AlreadyVoted);
+    vote_record.voters.push(referee);
+
+    Ok(())
+}
@@ -0,0 +1,97 @@
+use super::shared::{close_token_account, transfer_tokens};
+use crate::{error::ErrorCode, state::Htlc};
+use anchor_lang::prelude::*;
+use anchor_spl::token_interface::{Mint, TokenAccount, TokenInterface};
+use solana_sha256_hasher::hash;
+
+#[derive(Accounts)]
+pub struct ClaimHtlc<'info> {
+    // Work with either the classic token program or
+    // the newer token extensions program
+    pub token_program: Interface<'info, TokenInterface>,
+
+    #[account(mut)]
+    pub counterparty: Signer<'info>,
+
+    // Gets the vault rent back, since they made the vault
+    #[account(mut)]
+    pub maker: SystemAccount<'info>,
+
+    pub token_mint: InterfaceAccount<'info, Mint>,
+
+    // Where the tokens go, chosen by the counterparty
+    #[account(
+        mut,
+        token::mint = token_mint,
+        token::token_program = token_program
+    )]
+    pub recipient: InterfaceAccount<'info, TokenAccount>,
+
+    // Not closed, so the revealed preimage stays on-chain for the other leg of the swap
+    #[account(
+        mut,
+        has_one = maker,
+        has_one = counterparty,
+        has_one = token_mint,
+        constraint = !htlc.claimed @ ErrorCode::HtlcAlreadyClaimed,
+        seeds = [b"htlc", htlc.id.to_le_bytes().as_ref()],
+        bump = htlc.bump
+    )]
+    pub htlc: Account<'info, Htlc>,
+
+    #[account(
+        mut,
+        associated_token::mint = token_mint,
+        associated_token::authority = htlc,
+        associated_token::token_program = token_program,
+    )]
+    pub vault: InterfaceAccount<'info, TokenAccount>,
+}
+
+// Handle the claim HTLC instruction by:
+// 1. Checking the preimage against the hashlock and saving it to the HTLC account
+// 2. Withdrawing the tokens from the vault to the counterparty and closing the vault
+pub fn claim_htlc(context: Context<ClaimHtlc>, preimage: [u8; 32]) -> Result<()> {
+    let now = Clock::get()?.unix_timestamp;
+    require!(now <= context.accounts.htlc.timelock_ts, ErrorCode::HtlcExpired);
+
+    require!(
+        hash(&preimage).to_bytes() == context.accounts.htlc.hashlock,
+        ErrorCode::InvalidPreimage
+    );
+
+    let htlc_account_seeds = &[
+        b"htlc",
+        &context.accounts.htlc.id.to_le_bytes()[..],
+        &[context.accounts.htlc.bump],
+    ];
+    let signers_seeds = Some(&htlc_account_seeds[..]);
+
+    // Withdraw the tokens from the vault to the counterparty. We move everything in the
+    // vault, including anything else sent to it, so the vault is empty and can be closed
+    transfer_tokens(
+        &context.accounts.vault,
+        &context.accounts.recipient,
+        &context.accounts.vault.amount,
+        &context.accounts.token_mint,
+        &context.accounts.htlc.to_account_info(),
+        &context.accounts.token_program,
+        signers_seeds,
+    )
+    .map_err(|_| ErrorCode::FailedVaultWithdrawal)?;
+
+    // Close the vault and return the rent to the maker
+    close_token_account(
+        &context.accounts.vault,
+        &context.accounts.maker.to_account_info(),
+        &context.accounts.htlc.to_account_info(),
+        &context.accounts.token_program,
+        signers_seeds,
+    )
+    .map_err(|_| ErrorCode::FailedVaultClosure)?;
+
+    let htlc = &mut context.accounts.htlc;
+    htlc.preimage = preimage;
+    htlc.claimed = true;
+    Ok(())
+}
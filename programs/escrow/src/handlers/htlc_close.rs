@@ -0,0 +1,27 @@
+use crate::{error::ErrorCode, state::Htlc};
+use anchor_lang::prelude::*;
+
+#[derive(Accounts)]
+pub struct CloseHtlc<'info> {
+    #[account(mut)]
+    pub maker: Signer<'info>,
+
+    #[account(
+        mut,
+        close = maker,
+        has_one = maker,
+        constraint = htlc.claimed @ ErrorCode::HtlcNotClaimed,
+        seeds = [b"htlc", htlc.id.to_le_bytes().as_ref()],
+        bump = htlc.bump
+    )]
+    pub htlc: Account<'info, Htlc>,
+}
+
+// Handle the close HTLC instruction, which returns the rent for a claimed HTLC to the maker.
+// We wait until the timelock has passed, so the preimage stays readable on-chain
+// for as long as the other leg of the swap could need it
+pub fn close_htlc(context: Context<CloseHtlc>) -> Result<()> {
+    let now = Clock::get()?.unix_timestamp;
+    require!(now > context.accounts.htlc.timelock_ts, ErrorCode::HtlcNotExpired);
+    Ok(())
+}
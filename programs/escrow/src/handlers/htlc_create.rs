@@ -0,0 +1,98 @@
+use super::shared::transfer_tokens;
+use crate::{error::ErrorCode, state::Htlc};
+use anchor_lang::prelude::*;
+use anchor_spl::{
+    associated_token::AssociatedToken,
+    token_interface::{Mint, TokenAccount, TokenInterface},
+};
+
+#[derive(Accounts)]
+#[instruction(id: u64)]
+pub struct CreateHtlc<'info> {
+    // Used to manage associated token accounts
+    // ie where a wallet holds a specific type of token
+    pub associated_token_program: Program<'info, AssociatedToken>,
+
+    // Work with either the classic token program or
+    // the newer token extensions program
+    pub token_program: Interface<'info, TokenInterface>,
+
+    // Used to create accounts
+    pub system_program: Program<'info, System>,
+
+    #[account(mut)]
+    pub maker: Signer<'info>,
+
+    #[account(mint::token_program = token_program)]
+    pub token_mint: InterfaceAccount<'info, Mint>,
+
+    #[account(
+        mut,
+        associated_token::mint = token_mint,
+        associated_token::authority = maker,
+        associated_token::token_program = token_program
+    )]
+    pub maker_token_account: InterfaceAccount<'info, TokenAccount>,
+
+    #[account(
+        init,
+        payer = maker,
+        space = Htlc::DISCRIMINATOR.len() + Htlc::INIT_SPACE,
+        seeds = [b"htlc", id.to_le_bytes().as_ref()],
+        bump
+    )]
+    pub htlc: Account<'info, Htlc>,
+
+    #[account(
+        init,
+        payer = maker,
+        associated_token::mint = token_mint,
+        associated_token::authority = htlc,
+        associated_token::token_program = token_program
+    )]
+    pub vault: InterfaceAccount<'info, TokenAccount>,
+}
+
+// Handle the create HTLC instruction by:
+// 1. Moving the tokens from the maker's ATA to the vault
+// 2. Saving the details of the HTLC to the HTLC account
+pub fn create_htlc(
+    context: Context<CreateHtlc>,
+    id: u64,
+    counterparty: Pubkey,
+    amount: u64,
+    hashlock: [u8; 32],
+    timelock_ts: i64,
+) -> Result<()> {
+    require!(amount > 0, ErrorCode::InvalidAmount);
+
+    let now = Clock::get()?.unix_timestamp;
+    require!(timelock_ts > now, ErrorCode::HtlcExpired);
+
+    // Move the tokens from the maker's ATA to the vault
+    transfer_tokens(
+        &context.accounts.maker_token_account,
+        &context.accounts.vault,
+        &amount,
+        &context.accounts.token_mint,
+        &context.accounts.maker.to_account_info(),
+        &context.accounts.token_program,
+        None,
+    )
+    .map_err(|_| ErrorCode::InsufficientMakerBalance)?;
+
+    // Save the details of the HTLC to the HTLC account
+    context.accounts.htlc.set_inner(Htlc {
+        id,
+        maker: context.accounts.maker.key(),
+        counterparty,
+        token_mint: context.accounts.token_mint.key(),
+        amount,
+        hashlock,
+        timelock_ts,
+        preimage: [0; 32],
+        claimed: false,
+        bump: context.bumps.htlc,
+    });
+    Ok(())
+}
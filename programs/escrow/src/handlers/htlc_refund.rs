@@ -0,0 +1,82 @@
+use super::shared::{close_token_account, transfer_tokens};
+use crate::{error::ErrorCode, state::Htlc};
+use anchor_lang::prelude::*;
+use anchor_spl::token_interface::{Mint, TokenAccount, TokenInterface};
+
+#[derive(Accounts)]
+pub struct RefundHtlc<'info> {
+    // Work with either the classic token program or
+    // the newer token extensions program
+    pub token_program: Interface<'info, TokenInterface>,
+
+    #[account(mut)]
+    pub maker: Signer<'info>,
+
+    pub token_mint: InterfaceAccount<'info, Mint>,
+
+    #[account(
+        mut,
+        token::mint = token_mint,
+        token::token_program = token_program
+    )]
+    pub maker_token_account: InterfaceAccount<'info, TokenAccount>,
+
+    #[account(
+        mut,
+        close = maker,
+        has_one = maker,
+        has_one = token_mint,
+        constraint = !htlc.claimed @ ErrorCode::HtlcAlreadyClaimed,
+        seeds = [b"htlc", htlc.id.to_le_bytes().as_ref()],
+        bump = htlc.bump
+    )]
+    pub htlc: Account<'info, Htlc>,
+
+    #[account(
+        mut,
+        associated_token::mint = token_mint,
+        associated_token::authority = htlc,
+        associated_token::token_program = token_program,
+    )]
+    pub vault: InterfaceAccount<'info, TokenAccount>,
+}
+
+// Handle the refund HTLC instruction, once the timelock has passed without a claim, by:
+// 1. Returning the tokens from the vault to the maker
+// 2. Closing the vault and returning the rent to the maker
+pub fn refund_htlc(context: Context<RefundHtlc>) -> Result<()> {
+    let now = Clock::get()?.unix_timestamp;
+    require!(now > context.accounts.htlc.timelock_ts, ErrorCode::HtlcNotExpired);
+
+    let htlc_account_seeds = &[
+        b"htlc",
+        &context.accounts.htlc.id.to_le_bytes()[..],
+        &[context.accounts.htlc.bump],
+    ];
+    let signers_seeds = Some(&htlc_account_seeds[..]);
+
+    // Return the tokens from the vault to the maker. We move everything in the
+    // vault, including anything else sent to it, so the vault is empty and can be closed
+    transfer_tokens(
+        &context.accounts.vault,
+        &context.accounts.maker_token_account,
+        &context.accounts.vault.amount,
+        &context.accounts.token_mint,
+        &context.accounts.htlc.to_account_info(),
+        &context.accounts.token_program,
+        signers_seeds,
+    )
+    .map_err(|_| ErrorCode::FailedRefundTransfer)?;
+
+    // Close the vault and return the rent to the maker
+    close_token_account(
+        &context.accounts.vault,
+        &context.accounts.maker.to_account_info(),
+        &context.accounts.htlc.to_account_info(),
+        &context.accounts.token_program,
+        signers_seeds,
+    )
+    .map_err(|_| ErrorCode::FailedRefundClosure)?;
+
+    Ok(())
+}
@@ -0,0 +1,83 @@
+use super::shared::{close_token_account, transfer_tokens};
+use crate::{
+    error::ErrorCode,
+    state::{Listing, ListingStatus},
+};
+use anchor_lang::prelude::*;
+use anchor_spl::token_interface::{Mint, TokenAccount, TokenInterface};
+
+#[derive(Accounts)]
+pub struct CancelListing<'info> {
+    // Work with either the classic token program or
+    // the newer token extensions program
+    pub token_program: Interface<'info, TokenInterface>,
+
+    #[account(mut)]
+    pub seller: Signer<'info>,
+
+    pub token_mint: InterfaceAccount<'info, Mint>,
+
+    // Where anything sent to the vault goes
+    #[account(
+        mut,
+        token::mint = token_mint,
+        token::token_program = token_program
+    )]
+    pub seller_token_account: InterfaceAccount<'info, TokenAccount>,
+
+    #[account(
+        mut,
+        close = seller,
+        has_one = seller,
+        has_one = token_mint,
+        constraint = listing.status == ListingStatus::Listed @ ErrorCode::InvalidListingStatus,
+        seeds = [b"listing", listing.id.to_le_bytes().as_ref()],
+        bump = listing.bump
+    )]
+    pub listing: Account<'info, Listing>,
+
+    #[account(
+        mut,
+        associated_token::mint = token_mint,
+        associated_token::authority = listing,
+        associated_token::token_program = token_program
+    )]
+    pub vault: InterfaceAccount<'info, TokenAccount>,
+}
+
+// Handle the cancel listing instruction, before anyone has bought the item, by
+// closing the vault and listing accounts and returning the rent to the seller
+pub fn cancel_listing(context: Context<CancelListing>) -> Result<()> {
+    let listing_account_seeds = &[
+        b"listing",
+        &context.accounts.listing.id.to_le_bytes()[..],
+        &[context.accounts.listing.bump],
+    ];
+    let signers_seeds = Some(&listing_account_seeds[..]);
+
+    // Nobody has paid yet, but someone may have sent tokens to the vault,
+    // which has to be empty before it can be closed
+    if context.accounts.vault.amount > 0 {
+        transfer_tokens(
+            &context.accounts.vault,
+            &context.accounts.seller_token_account,
+            &context.accounts.vault.amount,
+            &context.accounts.token_mint,
+            &context.accounts.listing.to_account_info(),
+            &context.accounts.token_program,
+            signers_seeds,
+        )
+        .map_err(|_| ErrorCode::FailedRefundTransfer)?;
+    }
+
+    close_token_account(
+        &context.accounts.vault,
+        &context.accounts.seller.to_account_info(),
+        &context.accounts.listing.to_account_info(),
+        &context.accounts.token_program,
+        signers_seeds,
+    )
+    .map_err(|_| ErrorCode::FailedRefundClosure)?;
+
+    Ok(())
+}
@@ -0,0 +1,96 @@
+use super::shared::{close_token_account, transfer_tokens};
+use crate::{
+    error::ErrorCode,
+    state::{Listing, ListingStatus},
+};
+use anchor_lang::prelude::*;
+use anchor_spl::token_interface::{Mint, TokenAccount, TokenInterface};
+
+#[derive(Accounts)]
+pub struct ConfirmReceipt<'info> {
+    // Work with either the classic token program or
+    // the newer token extensions program
+    pub token_program: Interface<'info, TokenInterface>,
+
+    // The buyer, or anyone once the confirmation window has passed
+    pub caller: Signer<'info>,
+
+    // Gets the payment, and the rent for the listing and vault accounts
+    #[account(mut)]
+    pub seller: SystemAccount<'info>,
+
+    pub token_mint: InterfaceAccount<'info, Mint>,
+
+    #[account(
+        mut,
+        token::mint = token_mint,
+        token::authority = seller,
+        token::token_program = token_program
+    )]
+    pub seller_token_account: InterfaceAccount<'info, TokenAccount>,
+
+    #[account(
+        mut,
+        close = seller,
+        has_one = seller,
+        has_one = token_mint,
+        constraint = listing.status == ListingStatus::Shipped @ ErrorCode::InvalidListingStatus,
+        seeds = [b"listing", listing.id.to_le_bytes().as_ref()],
+        bump = listing.bump
+    )]
+    pub listing: Account<'info, Listing>,
+
+    #[account(
+        mut,
+        associated_token::mint = token_mint,
+        associated_token::authority = listing,
+        associated_token::token_program = token_program
+    )]
+    pub vault: InterfaceAccount<'info, TokenAccount>,
+}
+
+// Handle the confirm receipt instruction by:
+// 1. Checking the caller is the buyer, or the confirmation window has passed
+// 2. Releasing the payment from the vault to the seller
+// 3. Closing the vault and listing accounts and returning the rent to the seller
+pub fn confirm_receipt(context: Context<ConfirmReceipt>) -> Result<()> {
+    let listing = &context.accounts.listing;
+    if context.accounts.caller.key() != listing.buyer {
+        let now = Clock::get()?.unix_timestamp;
+        require!(
+            now > listing.confirmation_deadline_ts()?,
+            ErrorCode::ConfirmationWindowOpen
+        );
+    }
+
+    let listing_account_seeds = &[
+        b"listing",
+        &listing.id.to_le_bytes()[..],
+        &[listing.bump],
+    ];
+    let signers_seeds = Some(&listing_account_seeds[..]);
+
+    // We move everything in the vault, including anything else sent to it,
+    // so the vault is empty and can be closed
+    transfer_tokens(
+        &context.accounts.vault,
+        &context.accounts.seller_token_account,
+        &context.accounts.vault.amount,
+        &context.accounts.token_mint,
+        &listing.to_account_info(),
+        &context.accounts.token_program,
+        signers_seeds,
+    )
+    .map_err(|_| ErrorCode::FailedVaultWithdrawal)?;
+
+    close_token_account(
+        &context.accounts.vault,
+        &context.accounts.seller.to_account_info(),
+        &listing.to_account_info(),
+        &context.accounts.token_program,
+        signers_seeds,
+    )
+    .map_err(|_| ErrorCode::FailedVaultClosure)?;
+
+    Ok(())
+}
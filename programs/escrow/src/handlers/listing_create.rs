@@ -0,0 +1,86 @@
+use crate::{
+    constants::MAX_CONFIRMATION_WINDOW_SECS,
+    error::ErrorCode,
+    state::{Listing, ListingStatus},
+};
+use anchor_lang::prelude::*;
+use anchor_spl::{
+    associated_token::AssociatedToken,
+    token_interface::{Mint, TokenAccount, TokenInterface},
+};
+
+#[derive(Accounts)]
+#[instruction(id: u64)]
+pub struct CreateListing<'info> {
+    // Used to manage associated token accounts
+    // ie where a wallet holds a specific type of token
+    pub associated_token_program: Program<'info, AssociatedToken>,
+
+    // Work with either the classic token program or
+    // the newer token extensions program
+    pub token_program: Interface<'info, TokenInterface>,
+
+    // Used to create accounts
+    pub system_program: Program<'info, System>,
+
+    #[account(mut)]
+    pub seller: Signer<'info>,
+
+    #[account(mint::token_program = token_program)]
+    pub token_mint: InterfaceAccount<'info, Mint>,
+
+    #[account(
+        init,
+        payer = seller,
+        space = Listing::DISCRIMINATOR.len() + Listing::INIT_SPACE,
+        seeds = [b"listing", id.to_le_bytes().as_ref()],
+        bump
+    )]
+    pub listing: Account<'info, Listing>,
+
+    #[account(
+        init,
+        payer = seller,
+        associated_token::mint = token_mint,
+        associated_token::authority = listing,
+        associated_token::token_program = token_program
+    )]
+    pub vault: InterfaceAccount<'info, TokenAccount>,
+}
+
+// Handle the create listing instruction by saving the details of the listing
+// to the listing account. The vault stays empty until a buyer pays
+pub fn create_listing(
+    context: Context<CreateListing>,
+    id: u64,
+    price: u64,
+    arbiter: Pubkey,
+    confirmation_window_secs: i64,
+    response_window_secs: i64,
+) -> Result<()> {
+    require!(price > 0, ErrorCode::InvalidAmount);
+    require!(
+        confirmation_window_secs > 0 && confirmation_window_secs <= MAX_CONFIRMATION_WINDOW_SECS,
+        ErrorCode::InvalidAmount
+    );
+    require!(
+        response_window_secs > 0 && response_window_secs <= MAX_CONFIRMATION_WINDOW_SECS,
+        ErrorCode::InvalidAmount
+    );
+
+    context.accounts.listing.set_inner(Listing {
+        id,
+        seller: context.accounts.seller.key(),
+        buyer: Pubkey::default(),
+        arbiter,
+        token_mint: context.accounts.token_mint.key(),
+        price,
+        confirmation_window_secs,
+        response_window_secs,
+        status: ListingStatus::Listed,
+        shipped_ts: 0,
+        response_deadline_ts: 0,
+        bump: context.bumps.listing,
+    });
+    Ok(())
+}
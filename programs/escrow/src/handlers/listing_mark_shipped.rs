@@ -0,0 +1,27 @@
+use crate::{
+    error::ErrorCode,
+    state::{Listing, ListingStatus},
+};
+use anchor_lang::prelude::*;
+
+#[derive(Accounts)]
+pub struct MarkShipped<'info> {
+    pub seller: Signer<'info>,
+
+    #[account(
+        mut,
+        has_one = seller,
+        constraint = listing.status == ListingStatus::Paid @ ErrorCode::InvalidListingStatus,
+        seeds = [b"listing", listing.id.to_le_bytes().as_ref()],
+        bump = listing.bump
+    )]
+    pub listing: Account<'info, Listing>,
+}
+
+// Handle the mark shipped instruction by starting the buyer's confirmation window
+pub fn mark_shipped(context: Context<MarkShipped>) -> Result<()> {
+    let listing = &mut context.accounts.listing;
+    listing.status = ListingStatus::Shipped;
+    listing.shipped_ts = Clock::get()?.unix_timestamp;
+    Ok(())
+}
@@ -0,0 +1,38 @@
+use crate::{
+    error::ErrorCode,
+    state::{Listing, ListingStatus},
+};
+use anchor_lang::prelude::*;
+
+#[derive(Accounts)]
+pub struct OpenDispute<'info> {
+    pub buyer: Signer<'info>,
+
+    #[account(
+        mut,
+        has_one = buyer,
+        constraint = matches!(listing.status, ListingStatus::Paid | ListingStatus::Shipped)
+            @ ErrorCode::InvalidListingStatus,
+        seeds = [b"listing", listing.id.to_le_bytes().as_ref()],
+        bump = listing.bump
+    )]
+    pub listing: Account<'info, Listing>,
+}
+
+// Handle the open dispute instruction by freezing the payment until the arbiter resolves it,
+// or the arbiter's window to respond passes. The buyer can dispute before the item ships,
+// or during the confirmation window after it ships
+pub fn open_dispute(context: Context<OpenDispute>) -> Result<()> {
+    let listing = &mut context.accounts.listing;
+    let now = Clock::get()?.unix_timestamp;
+    if listing.status == ListingStatus::Shipped {
+        require!(
+            now <= listing.confirmation_deadline_ts()?,
+            ErrorCode::ConfirmationWindowClosed
+        );
+    }
+
+    listing.status = ListingStatus::Disputed;
+    listing.response_deadline_ts = listing.response_deadline_from(now)?;
+    Ok(())
+}
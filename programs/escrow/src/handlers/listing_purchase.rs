@@ -0,0 +1,65 @@
+use super::shared::transfer_tokens;
+use crate::{
+    error::ErrorCode,
+    state::{Listing, ListingStatus},
+};
+use anchor_lang::prelude::*;
+use anchor_spl::token_interface::{Mint, TokenAccount, TokenInterface};
+
+#[derive(Accounts)]
+pub struct PurchaseListing<'info> {
+    // Work with either the classic token program or
+    // the newer token extensions program
+    pub token_program: Interface<'info, TokenInterface>,
+
+    pub buyer: Signer<'info>,
+
+    pub token_mint: InterfaceAccount<'info, Mint>,
+
+    #[account(
+        mut,
+        associated_token::mint = token_mint,
+        associated_token::authority = buyer,
+        associated_token::token_program = token_program
+    )]
+    pub buyer_token_account: InterfaceAccount<'info, TokenAccount>,
+
+    #[account(
+        mut,
+        has_one = token_mint,
+        constraint = listing.status == ListingStatus::Listed @ ErrorCode::InvalidListingStatus,
+        constraint = listing.seller != buyer.key() @ ErrorCode::Unauthorized,
+        seeds = [b"listing", listing.id.to_le_bytes().as_ref()],
+        bump = listing.bump
+    )]
+    pub listing: Account<'info, Listing>,
+
+    #[account(
+        mut,
+        associated_token::mint = token_mint,
+        associated_token::authority = listing,
+        associated_token::token_program = token_program
+    )]
+    pub vault: InterfaceAccount<'info, TokenAccount>,
+}
+
+// Handle the purchase listing instruction by moving the price from the buyer's ATA
+// to the vault, recording the buyer, and starting the seller's window to ship
+pub fn purchase_listing(context: Context<PurchaseListing>) -> Result<()> {
+    transfer_tokens(
+        &context.accounts.buyer_token_account,
+        &context.accounts.vault,
+        &context.accounts.listing.price,
+        &context.accounts.token_mint,
+        &context.accounts.buyer.to_account_info(),
+        &context.accounts.token_program,
+        None,
+    )
+    .map_err(|_| ErrorCode::InsufficientTakerBalance)?;
+
+    let listing = &mut context.accounts.listing;
+    listing.buyer = context.accounts.buyer.key();
+    listing.status = ListingStatus::Paid;
+    listing.response_deadline_ts = listing.response_deadline_from(Clock::get()?.unix_timestamp)?;
+    Ok(())
+}
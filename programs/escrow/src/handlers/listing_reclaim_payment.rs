@@ -0,0 +1,97 @@
+use super::shared::{close_token_account, transfer_tokens};
+use crate::{
+    error::ErrorCode,
+    state::{Listing, ListingStatus},
+};
+use anchor_lang::prelude::*;
+use anchor_spl::token_interface::{Mint, TokenAccount, TokenInterface};
+
+#[derive(Accounts)]
+pub struct ReclaimPayment<'info> {
+    // Work with either the classic token program or
+    // the newer token extensions program
+    pub token_program: Interface<'info, TokenInterface>,
+
+    pub buyer: Signer<'info>,
+
+    // Gets the rent for the listing and vault accounts, since they paid for them
+    #[account(mut)]
+    pub seller: SystemAccount<'info>,
+
+    pub token_mint: InterfaceAccount<'info, Mint>,
+
+    #[account(
+        mut,
+        token::mint = token_mint,
+        token::authority = buyer,
+        token::token_program = token_program
+    )]
+    pub buyer_token_account: InterfaceAccount<'info, TokenAccount>,
+
+    #[account(
+        mut,
+        close = seller,
+        has_one = buyer,
+        has_one = seller,
+        has_one = token_mint,
+        constraint = matches!(listing.status, ListingStatus::Paid | ListingStatus::Disputed)
+            @ ErrorCode::InvalidListingStatus,
+        seeds = [b"listing", listing.id.to_le_bytes().as_ref()],
+        bump = listing.bump
+    )]
+    pub listing: Account<'info, Listing>,
+
+    #[account(
+        mut,
+        associated_token::mint = token_mint,
+        associated_token::authority = listing,
+        associated_token::token_program = token_program
+    )]
+    pub vault: InterfaceAccount<'info, TokenAccount>,
+}
+
+// Handle the reclaim payment instruction, for when the seller never ships the item
+// or the arbiter never resolves the dispute, by:
+// 1. Returning everything in the vault to the buyer
+// 2. Closing the vault and listing accounts and returning the rent to the seller
+pub fn reclaim_payment(context: Context<ReclaimPayment>) -> Result<()> {
+    let listing = &context.accounts.listing;
+    let now = Clock::get()?.unix_timestamp;
+    require!(
+        now > listing.response_deadline_ts,
+        ErrorCode::ResponseWindowOpen
+    );
+
+    let listing_account_seeds = &[
+        b"listing",
+        &listing.id.to_le_bytes()[..],
+        &[listing.bump],
+    ];
+    let signers_seeds = Some(&listing_account_seeds[..]);
+
+    // We move everything in the vault, including anything else sent to it,
+    // so the vault is empty and can be closed
+    if context.accounts.vault.amount > 0 {
+        transfer_tokens(
+            &context.accounts.vault,
+            &context.accounts.buyer_token_account,
+            &context.accounts.vault.amount,
+            &context.accounts.token_mint,
+            &listing.to_account_info(),
+            &context.accounts.token_program,
+            signers_seeds,
+        )
+        .map_err(|_| ErrorCode::FailedRefundTransfer)?;
+    }
+
+    close_token_account(
+        &context.accounts.vault,
+        &context.accounts.seller.to_account_info(),
+        &listing.to_account_info(),
+        &context.accounts.token_program,
+        signers_seeds,
+    )
+    .map_err(|_| ErrorCode::FailedRefundClosure)?;
+
+    Ok(())
+}
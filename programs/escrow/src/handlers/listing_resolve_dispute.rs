@@ -0,0 +1,118 @@
+use super::shared::{close_token_account, transfer_tokens};
+use crate::{
+    error::ErrorCode,
+    state::{Listing, ListingStatus},
+};
+use anchor_lang::prelude::*;
+use anchor_spl::token_interface::{Mint, TokenAccount, TokenInterface};
+
+#[derive(Accounts)]
+pub struct ResolveDispute<'info> {
+    // Work with either the classic token program or
+    // the newer token extensions program
+    pub token_program: Interface<'info, TokenInterface>,
+
+    pub arbiter: Signer<'info>,
+
+    // Gets the rest of the payment, and the rent for the listing and vault accounts
+    #[account(mut)]
+    pub seller: SystemAccount<'info>,
+
+    pub token_mint: InterfaceAccount<'info, Mint>,
+
+    #[account(
+        mut,
+        token::mint = token_mint,
+        token::authority = listing.buyer,
+        token::token_program = token_program
+    )]
+    pub buyer_token_account: InterfaceAccount<'info, TokenAccount>,
+
+    #[account(
+        mut,
+        token::mint = token_mint,
+        token::authority = seller,
+        token::token_program = token_program
+    )]
+    pub seller_token_account: InterfaceAccount<'info, TokenAccount>,
+
+    #[account(
+        mut,
+        close = seller,
+        has_one = arbiter,
+        has_one = seller,
+        has_one = token_mint,
+        constraint = listing.status == ListingStatus::Disputed @ ErrorCode::InvalidListingStatus,
+        seeds = [b"listing", listing.id.to_le_bytes().as_ref()],
+        bump = listing.bump
+    )]
+    pub listing: Account<'info, Listing>,
+
+    #[account(
+        mut,
+        associated_token::mint = token_mint,
+        associated_token::authority = listing,
+        associated_token::token_program = token_program
+    )]
+    pub vault: InterfaceAccount<'info, TokenAccount>,
+}
+
+// Handle the resolve dispute instruction by:
+// 1. Refunding the buyer the amount the arbiter decided on
+// 2. Paying the rest of the vault to the seller
+// 3. Closing the vault and listing accounts and returning the rent to the seller
+pub fn resolve_dispute(context: Context<ResolveDispute>, buyer_amount: u64) -> Result<()> {
+    let listing = &context.accounts.listing;
+    require!(buyer_amount <= listing.price, ErrorCode::InvalidAmount);
+
+    let listing_account_seeds = &[
+        b"listing",
+        &listing.id.to_le_bytes()[..],
+        &[listing.bump],
+    ];
+    let signers_seeds = Some(&listing_account_seeds[..]);
+
+    if buyer_amount > 0 {
+        transfer_tokens(
+            &context.accounts.vault,
+            &context.accounts.buyer_token_account,
+            &buyer_amount,
+            &context.accounts.token_mint,
+            &listing.to_account_info(),
+            &context.accounts.token_program,
+            signers_seeds,
+        )
+        .map_err(|_| ErrorCode::FailedRefundTransfer)?;
+    }
+
+    // The seller gets everything else in the vault, so the vault is empty and can be closed
+    let seller_amount = context
+        .accounts
+        .vault
+        .amount
+        .checked_sub(buyer_amount)
+        .ok_or(ErrorCode::FailedRefundTransfer)?;
+    if seller_amount > 0 {
+        transfer_tokens(
+            &context.accounts.vault,
+            &context.accounts.seller_token_account,
+            &seller_amount,
+            &context.accounts.token_mint,
+            &listing.to_account_info(),
+            &context.accounts.token_program,
+            signers_seeds,
+        )
+        .map_err(|_| ErrorCode::FailedVaultWithdrawal)?;
+    }
+
+    close_token_account(
+        &context.accounts.vault,
+        &context.accounts.seller.to_account_info(),
+        &listing.to_account_info(),
+        &context.accounts.token_program,
+        signers_seeds,
+    )
+    .map_err(|_| ErrorCode::FailedVaultClosure)?;
+
+    Ok(())
+}
@@ -0,0 +1,80 @@
+use anchor_lang::prelude::*;
+use anchor_spl::token_interface::{Mint, TokenAccount, TokenInterface};
+
+use super::shared::{close_token_account, transfer_tokens};
+use crate::{error::ErrorCode, state::Loan};
+
+#[derive(Accounts)]
+pub struct CancelLoan<'info> {
+    // Work with either the classic token program or
+    // the newer token extensions program
+    pub token_program: Interface<'info, TokenInterface>,
+
+    #[account(mut)]
+    pub borrower: Signer<'info>,
+
+    pub collateral_mint: InterfaceAccount<'info, Mint>,
+
+    #[account(
+        mut,
+        token::mint = collateral_mint,
+        token::token_program = token_program
+    )]
+    pub borrower_collateral_account: InterfaceAccount<'info, TokenAccount>,
+
+    #[account(
+        mut,
+        close = borrower,
+        has_one = borrower,
+        has_one = collateral_mint,
+        constraint = loan.lender == Pubkey::default() @ ErrorCode::LoanAlreadyFunded,
+        seeds = [b"loan", loan.id.to_le_bytes().as_ref()],
+        bump = loan.bump
+    )]
+    pub loan: Account<'info, Loan>,
+
+    #[account(
+        mut,
+        associated_token::mint = collateral_mint,
+        associated_token::authority = loan,
+        associated_token::token_program = token_program,
+    )]
+    pub vault: InterfaceAccount<'info, TokenAccount>,
+}
+
+// Handle the cancel loan instruction, for loans nobody has funded yet, by:
+// 1. Returning the collateral from the vault to the borrower
+// 2. Closing the vault and returning the rent to the borrower
+pub fn cancel_loan(context: Context<CancelLoan>) -> Result<()> {
+    let loan_account_seeds = &[
+        b"loan",
+        &context.accounts.loan.id.to_le_bytes()[..],
+        &[context.accounts.loan.bump],
+    ];
+    let signers_seeds = Some(&loan_account_seeds[..]);
+
+    // Return the collateral from the vault to the borrower. We move everything in the vault,
+    // including anything else sent to it, so the vault is empty and can be closed
+    transfer_tokens(
+        &context.accounts.vault,
+        &context.accounts.borrower_collateral_account,
+        &context.accounts.vault.amount,
+        &context.accounts.collateral_mint,
+        &context.accounts.loan.to_account_info(),
+        &context.accounts.token_program,
+        signers_seeds,
+    )
+    .map_err(|_| ErrorCode::FailedRefundTransfer)?;
+
+    // Close the vault and return the rent to the borrower
+    close_token_account(
+        &context.accounts.vault,
+        &context.accounts.borrower.to_account_info(),
+        &context.accounts.loan.to_account_info(),
+        &context.accounts.token_program,
+        signers_seeds,
+    )
+    .map_err(|_| ErrorCode::FailedRefundClosure)?;
+
+    Ok(())
+}
@@ -0,0 +1,92 @@
+use super::shared::{close_token_account, transfer_tokens};
+use crate::{error::ErrorCode, state::Loan};
+use anchor_lang::prelude::*;
+use anchor_spl::token_interface::{Mint, TokenAccount, TokenInterface};
+
+#[derive(Accounts)]
+pub struct ClaimCollateral<'info> {
+    // Work with either the classic token program or
+    // the newer token extensions program
+    pub token_program: Interface<'info, TokenInterface>,
+
+    #[account(mut)]
+    pub lender: Signer<'info>,
+
+    // Gets the rent back, since they made the loan and vault accounts
+    #[account(mut)]
+    pub borrower: SystemAccount<'info>,
+
+    pub collateral_mint: InterfaceAccount<'info, Mint>,
+
+    #[account(
+        mut,
+        token::mint = collateral_mint,
+        token::token_program = token_program
+    )]
+    pub lender_collateral_account: InterfaceAccount<'info, TokenAccount>,
+
+    #[account(
+        mut,
+        close = borrower,
+        has_one = borrower,
+        has_one = lender,
+        has_one = collateral_mint,
+        seeds = [b"loan", loan.id.to_le_bytes().as_ref()],
+        bump = loan.bump
+    )]
+    pub loan: Account<'info, Loan>,
+
+    #[account(
+        mut,
+        associated_token::mint = collateral_mint,
+        associated_token::authority = loan,
+        associated_token::token_program = token_program,
+    )]
+    pub vault: InterfaceAccount<'info, TokenAccount>,
+}
+
+// Handle the claim collateral instruction, for loans that weren't repaid in time, by:
+// 1. Sending the collateral from the vault to the lender
+// 2. Closing the vault and returning the rent to the borrower
+pub fn claim_collateral(context: Context<ClaimCollateral>) -> Result<()> {
+    // Unfunded loans have no lender yet
+    require!(
+        context.accounts.loan.lender != Pubkey::default(),
+        ErrorCode::LoanNotFunded
+    );
+
+    let now = Clock::get()?.unix_timestamp;
+    require!(now > context.accounts.loan.due_ts, ErrorCode::LoanNotOverdue);
+
+    let loan_account_seeds = &[
+        b"loan",
+        &context.accounts.loan.id.to_le_bytes()[..],
+        &[context.accounts.loan.bump],
+    ];
+    let signers_seeds = Some(&loan_account_seeds[..]);
+
+    // Send the collateral from the vault to the lender. We move everything in the vault,
+    // including anything else sent to it, so the vault is empty and can be closed
+    transfer_tokens(
+        &context.accounts.vault,
+        &context.accounts.lender_collateral_account,
+        &context.accounts.vault.amount,
+        &context.accounts.collateral_mint,
+        &context.accounts.loan.to_account_info(),
+        &context.accounts.token_program,
+        signers_seeds,
+    )
+    .map_err(|_| ErrorCode::FailedVaultWithdrawal)?;
+
+    // Close the vault and return the rent to the borrower
+    close_token_account(
+        &context.accounts.vault,
+        &context.accounts.borrower.to_account_info(),
+        &context.accounts.loan.to_account_info(),
+        &context.accounts.token_program,
+        signers_seeds,
+    )
+    .map_err(|_| ErrorCode::FailedVaultClosure)?;
+
+    Ok(())
+}
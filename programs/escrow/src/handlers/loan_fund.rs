@@ -0,0 +1,71 @@
+use super::shared::transfer_tokens;
+use crate::{error::ErrorCode, state::Loan};
+use anchor_lang::prelude::*;
+use anchor_spl::token_interface::{Mint, TokenAccount, TokenInterface};
+
+#[derive(Accounts)]
+pub struct FundLoan<'info> {
+    // Work with either the classic token program or
+    // the newer token extensions program
+    pub token_program: Interface<'info, TokenInterface>,
+
+    #[account(mut)]
+    pub lender: Signer<'info>,
+
+    pub borrower: SystemAccount<'info>,
+
+    pub principal_mint: InterfaceAccount<'info, Mint>,
+
+    #[account(
+        mut,
+        token::mint = principal_mint,
+        token::authority = lender,
+        token::token_program = token_program
+    )]
+    pub lender_principal_account: InterfaceAccount<'info, TokenAccount>,
+
+    // The principal goes straight to the borrower
+    #[account(
+        mut,
+        token::mint = principal_mint,
+        token::authority = borrower,
+        token::token_program = token_program
+    )]
+    pub borrower_principal_account: InterfaceAccount<'info, TokenAccount>,
+
+    #[account(
+        mut,
+        has_one = borrower,
+        has_one = principal_mint,
+        constraint = loan.lender == Pubkey::default() @ ErrorCode::LoanAlreadyFunded,
+        seeds = [b"loan", loan.id.to_le_bytes().as_ref()],
+        bump = loan.bump
+    )]
+    pub loan: Account<'info, Loan>,
+}
+
+// Handle the fund loan instruction by:
+// 1. Sending the principal from the lender to the borrower
+// 2. Recording the lender on the loan account and starting the clock on the loan
+pub fn fund_loan(context: Context<FundLoan>) -> Result<()> {
+    let now = Clock::get()?.unix_timestamp;
+    let due_ts = now
+        .checked_add(context.accounts.loan.duration)
+        .ok_or(ErrorCode::InvalidLoanDuration)?;
+
+    // Send the principal from the lender to the borrower
+    transfer_tokens(
+        &context.accounts.lender_principal_account,
+        &context.accounts.borrower_principal_account,
+        &context.accounts.loan.principal_amount,
+        &context.accounts.principal_mint,
+        &context.accounts.lender.to_account_info(),
+        &context.accounts.token_program,
+        None,
+    )
+    .map_err(|_| ErrorCode::InsufficientPrincipal)?;
+
+    context.accounts.loan.lender = context.accounts.lender.key();
+    context.accounts.loan.due_ts = due_ts;
+    Ok(())
+}
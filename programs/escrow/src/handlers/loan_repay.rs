@@ -0,0 +1,129 @@
+use super::shared::{close_token_account, transfer_tokens};
+use crate::{error::ErrorCode, state::Loan};
+use anchor_lang::prelude::*;
+use anchor_spl::token_interface::{Mint, TokenAccount, TokenInterface};
+
+#[derive(Accounts)]
+pub struct RepayLoan<'info> {
+    // Work with either the classic token program or
+    // the newer token extensions program
+    pub token_program: Interface<'info, TokenInterface>,
+
+    #[account(mut)]
+    pub borrower: Signer<'info>,
+
+    pub lender: SystemAccount<'info>,
+
+    pub collateral_mint: InterfaceAccount<'info, Mint>,
+
+    pub principal_mint: InterfaceAccount<'info, Mint>,
+
+    #[account(
+        mut,
+        token::mint = principal_mint,
+        token::authority = borrower,
+        token::token_program = token_program
+    )]
+    pub borrower_principal_account: InterfaceAccount<'info, TokenAccount>,
+
+    #[account(
+        mut,
+        token::mint = principal_mint,
+        token::authority = lender,
+        token::token_program = token_program
+    )]
+    pub lender_principal_account: InterfaceAccount<'info, TokenAccount>,
+
+    #[account(
+        mut,
+        token::mint = collateral_mint,
+        token::token_program = token_program
+    )]
+    pub borrower_collateral_account: InterfaceAccount<'info, TokenAccount>,
+
+    #[account(
+        mut,
+        close = borrower,
+        has_one = borrower,
+        has_one = lender,
+        has_one = collateral_mint,
+        has_one = principal_mint,
+        seeds = [b"loan", loan.id.to_le_bytes().as_ref()],
+        bump = loan.bump
+    )]
+    pub loan: Account<'info, Loan>,
+
+    #[account(
+        mut,
+        associated_token::mint = collateral_mint,
+        associated_token::authority = loan,
+        associated_token::token_program = token_program,
+    )]
+    pub vault: InterfaceAccount<'info, TokenAccount>,
+}
+
+// Handle the repay loan instruction by:
+// 1. Sending the principal plus interest from the borrower to the lender
+// 2. Returning the collateral from the vault to the borrower
+// 3. Closing the vault and returning the rent to the borrower
+pub fn repay_loan(context: Context<RepayLoan>) -> Result<()> {
+    // Unfunded loans have no lender yet
+    require!(
+        context.accounts.loan.lender != Pubkey::default(),
+        ErrorCode::LoanNotFunded
+    );
+
+    let now = Clock::get()?.unix_timestamp;
+    require!(now <= context.accounts.loan.due_ts, ErrorCode::LoanOverdue);
+
+    let repayment_amount = context
+        .accounts
+        .loan
+        .principal_amount
+        .checked_add(context.accounts.loan.interest_amount)
+        .ok_or(ErrorCode::InvalidAmount)?;
+
+    // Send the principal plus interest from the borrower to the lender
+    transfer_tokens(
+        &context.accounts.borrower_principal_account,
+        &context.accounts.lender_principal_account,
+        &repayment_amount,
+        &context.accounts.principal_mint,
+        &context.accounts.borrower.to_account_info(),
+        &context.accounts.token_program,
+        None,
+    )
+    .map_err(|_| ErrorCode::InsufficientRepayment)?;
+
+    let loan_account_seeds = &[
+        b"loan",
+        &context.accounts.loan.id.to_le_bytes()[..],
+        &[context.accounts.loan.bump],
+    ];
+    let signers_seeds = Some(&loan_account_seeds[..]);
+
+    // Return the collateral from the vault to the borrower. We move everything in the vault,
+    // including anything else sent to it, so the vault is empty and can be closed
+    transfer_tokens(
+        &context.accounts.vault,
+        &context.accounts.borrower_collateral_account,
+        &context.accounts.vault.amount,
+        &context.accounts.collateral_mint,
+        &context.accounts.loan.to_account_info(),
+        &context.accounts.token_program,
+        signers_seeds,
+    )
+    .map_err(|_| ErrorCode::FailedVaultWithdrawal)?;
+
+    // Close the vault and return the rent to the borrower, since they made this account
+    close_token_account(
+        &context.accounts.vault,
+        &context.accounts.borrower.to_account_info(),
+        &context.accounts.loan.to_account_info(),
+        &context.accounts.token_program,
+        signers_seeds,
+    )
+    .map_err(|_| ErrorCode::FailedVaultClosure)?;
+
+    Ok(())
+}
@@ -0,0 +1,110 @@
+use super::shared::transfer_tokens;
+use crate::{error::ErrorCode, state::Loan};
+use anchor_lang::prelude::*;
+use anchor_spl::{
+    associated_token::AssociatedToken,
+    token_interface::{Mint, TokenAccount, TokenInterface},
+};
+
+#[derive(Accounts)]
+#[instruction(id: u64)]
+pub struct RequestLoan<'info> {
+    // Used to manage associated token accounts
+    // ie where a wallet holds a specific type of token
+    pub associated_token_program: Program<'info, AssociatedToken>,
+
+    // Work with either the classic token program or
+    // the newer token extensions program
+    pub token_program: Interface<'info, TokenInterface>,
+
+    // Used to create accounts
+    pub system_program: Program<'info, System>,
+
+    #[account(mut)]
+    pub borrower: Signer<'info>,
+
+    #[account(mint::token_program = token_program)]
+    pub collateral_mint: InterfaceAccount<'info, Mint>,
+
+    #[account(mint::token_program = token_program)]
+    pub principal_mint: InterfaceAccount<'info, Mint>,
+
+    #[account(
+        mut,
+        associated_token::mint = collateral_mint,
+        associated_token::authority = borrower,
+        associated_token::token_program = token_program
+    )]
+    pub borrower_collateral_account: InterfaceAccount<'info, TokenAccount>,
+
+    #[account(
+        init,
+        payer = borrower,
+        space = Loan::DISCRIMINATOR.len() + Loan::INIT_SPACE,
+        seeds = [b"loan", id.to_le_bytes().as_ref()],
+        bump
+    )]
+    pub loan: Account<'info, Loan>,
+
+    #[account(
+        init,
+        payer = borrower,
+        associated_token::mint = collateral_mint,
+        associated_token::authority = loan,
+        associated_token::token_program = token_program
+    )]
+    pub vault: InterfaceAccount<'info, TokenAccount>,
+}
+
+// Handle the request loan instruction by:
+// 1. Moving the collateral from the borrower's ATA to the vault
+// 2. Saving the details of the loan to the loan account
+pub fn request_loan(
+    context: Context<RequestLoan>,
+    id: u64,
+    collateral_amount: u64,
+    principal_amount: u64,
+    interest_amount: u64,
+    duration: i64,
+) -> Result<()> {
+    // Validate amounts. Interest can be zero, for a loan between friends
+    require!(collateral_amount > 0, ErrorCode::InvalidAmount);
+    require!(principal_amount > 0, ErrorCode::InvalidAmount);
+
+    // Validate token mints are different
+    require!(
+        context.accounts.collateral_mint.key() != context.accounts.principal_mint.key(),
+        ErrorCode::InvalidTokenMint
+    );
+
+    // The due date is set when the loan is funded, so a lender can't fund
+    // just before it and claim the collateral straight away
+    require!(duration > 0, ErrorCode::InvalidLoanDuration);
+
+    // Move the collateral from the borrower's ATA to the vault
+    transfer_tokens(
+        &context.accounts.borrower_collateral_account,
+        &context.accounts.vault,
+        &collateral_amount,
+        &context.accounts.collateral_mint,
+        &context.accounts.borrower.to_account_info(),
+        &context.accounts.token_program,
+        None,
+    )
+    .map_err(|_| ErrorCode::InsufficientCollateral)?;
+
+    // Save the details of the loan to the loan account
+    context.accounts.loan.set_inner(Loan {
+        id,
+        borrower: context.accounts.borrower.key(),
+        lender: Pubkey::default(),
+        collateral_mint: context.accounts.collateral_mint.key(),
+        principal_mint: context.accounts.principal_mint.key(),
+        principal_amount,
+        interest_amount,
+        duration,
+        due_ts: 0,
+        bump: context.bumps.loan,
+    });
+    Ok(())
+}
@@ -1,9 +1,16 @@
-use super::shared::transfer_tokens;
+use super::shared::{
+    create_pda_account, mint_tokens, next_nonce, revoke_mint_authority, transfer_tokens,
+};
 use crate::{error::ErrorCode, state::Offer};
 use anchor_lang::prelude::*;
 use anchor_spl::{
-    associated_token::AssociatedToken,
-    token_interface::{Mint, TokenAccount, TokenInterface},
+    associated_token::{
+        create, get_associated_token_address_with_program_id, AssociatedToken, Create,
+    },
+    token_interface::{
+        find_mint_account_size, initialize_mint2, InitializeMint2, Mint, TokenAccount,
+        TokenInterface,
+    },
 };
 
 // See https://www.anchor-lang.com/docs/account-constraints#instruction-attribute
@@ -24,6 +31,11 @@ pub struct MakeOffer<'info> {
     #[account(mut)]
     pub maker: Signer<'info>,
 
+    // Pays the rent for the offer and vault accounts. Defaults to the maker,
+    // but a backend can sponsor the rent for makers who only hold the tokens they are trading
+    #[account(mut)]
+    pub rent_payer: Option<Signer<'info>>,
+
     #[account(mint::token_program = token_program)]
     pub token_mint_a: InterfaceAccount<'info, Mint>,
 
@@ -38,28 +50,76 @@ pub struct MakeOffer<'info> {
     )]
     pub maker_token_account_a: InterfaceAccount<'info, TokenAccount>,
 
+    // Any token b account, not necessarily the maker's ATA - it could be
+    // a PDA owned by another program
+    #[account(
+        token::mint = token_mint_b,
+        token::token_program = token_program
+    )]
+    pub proceeds_account: InterfaceAccount<'info, TokenAccount>,
+
+    // Where any extra token a sent to the vault goes when the offer is closed.
+    // Defaults to the maker's token a account
     #[account(
-        init,
-        payer = maker,
-        space = Offer::DISCRIMINATOR.len() + Offer::INIT_SPACE,
+        token::mint = token_mint_a,
+        token::token_program = token_program
+    )]
+    pub surplus_account: Option<InterfaceAccount<'info, TokenAccount>>,
+
+    // The offer and vault are created in the handler rather than with `init`,
+    // since Anchor can't use an optional account as the payer
+    /// CHECK: created by the handler at this address
+    #[account(
+        mut,
         seeds = [b"offer", id.to_le_bytes().as_ref()],
         bump
     )]
-    pub offer: Account<'info, Offer>,
+    pub offer: UncheckedAccount<'info>,
 
+    /// CHECK: created by the handler as the offer's token a ATA
     #[account(
-        init,
-        payer = maker,
-        associated_token::mint = token_mint_a,
-        associated_token::authority = offer,
-        associated_token::token_program = token_program
+        mut,
+        address = get_associated_token_address_with_program_id(
+            &offer.key(),
+            &token_mint_a.key(),
+            &token_program.key()
+        )
     )]
-    pub vault: InterfaceAccount<'info, TokenAccount>,
+    pub vault: UncheckedAccount<'info>,
+
+    // Hands out a nonce for the receipt mint's address, so reusing an offer id
+    // never collides with the receipt mint of an earlier offer with that id.
+    // Only needed with a receipt
+    /// CHECK: created or updated by the handler at this address
+    #[account(
+        mut,
+        seeds = [b"nonce_counter", offer.key().as_ref()],
+        bump
+    )]
+    pub nonce_counter: Option<UncheckedAccount<'info>>,
+
+    // Optional 1-of-1 receipt token representing the offer. Whoever holds it can refund
+    // the offer and receives the token b when it is taken, so offers can be transferred.
+    // The offer stays its freeze authority so the receipt can be frozen once the offer is taken,
+    // and thawed again when the holder closes it.
+    // Created by the handler like the offer, since its address depends on the nonce.
+    // The rent payer covers it along with the receipt account
+    /// CHECK: created by the handler at [b"receipt", offer, nonce]
+    #[account(mut)]
+    pub receipt_mint: Option<UncheckedAccount<'info>>,
+
+    // Where the receipt token is minted to
+    /// CHECK: created by the handler as the maker's receipt ATA, which the
+    /// associated token program checks the address of
+    #[account(mut)]
+    pub maker_receipt_account: Option<UncheckedAccount<'info>>,
 }
 
 // Handle the make offer instruction by:
-// 1. Moving the tokens from the maker's ATA to the vault
-// 2. Saving the details of the offer to the offer account
+// 1. Creating the offer and vault accounts, paid for by the rent payer
+// 2. Moving the tokens from the maker's ATA to the vault
+// 3. Creating the receipt and minting it to the maker, if they asked for one
+// 4. Saving the details of the offer to the offer account
 pub fn make_offer(
     context: Context<MakeOffer>,
     id: u64,
@@ -76,26 +136,157 @@ pub fn make_offer(
         ErrorCode::InvalidTokenMint
     );
 
+    let accounts = &context.accounts;
+    let rent_payer = match &accounts.rent_payer {
+        Some(rent_payer) => rent_payer.to_account_info(),
+        None => accounts.maker.to_account_info(),
+    };
+    let offer_account_seeds = &[b"offer", &id.to_le_bytes()[..], &[context.bumps.offer]];
+
+    create_pda_account(
+        &rent_payer,
+        &accounts.offer,
+        Offer::DISCRIMINATOR.len() + Offer::INIT_SPACE,
+        &crate::ID,
+        &accounts.system_program,
+        offer_account_seeds,
+    )?;
+
+    create(CpiContext::new(
+        accounts.associated_token_program.to_account_info(),
+        Create {
+            payer: rent_payer.clone(),
+            associated_token: accounts.vault.to_account_info(),
+            authority: accounts.offer.to_account_info(),
+            mint: accounts.token_mint_a.to_account_info(),
+            system_program: accounts.system_program.to_account_info(),
+            token_program: accounts.token_program.to_account_info(),
+        },
+    ))?;
+
     // Move the tokens from the maker's ATA to the vault
     transfer_tokens(
-        &context.accounts.maker_token_account_a,
-        &context.accounts.vault,
+        &accounts.maker_token_account_a,
+        &accounts.vault,
         &token_a_offered_amount,
-        &context.accounts.token_mint_a,
-        &context.accounts.maker.to_account_info(),
-        &context.accounts.token_program,
+        &accounts.token_mint_a,
+        &accounts.maker.to_account_info(),
+        &accounts.token_program,
         None,
     )
     .map_err(|_| ErrorCode::InsufficientMakerBalance)?;
 
+    // Mints with a transfer fee deliver less than was sent, so the offer is for
+    // whatever actually reached the vault
+    let token_a_offered_amount =
+        TokenAccount::try_deserialize(&mut &accounts.vault.try_borrow_data()?[..])?.amount;
+    require!(token_a_offered_amount > 0, ErrorCode::InvalidAmount);
+
+    // Create the receipt mint and the maker's receipt account, mint a single receipt
+    // token to the maker, then remove the mint authority so there can never be more than one
+    let (receipt_mint, nonce) = match (
+        &accounts.nonce_counter,
+        &accounts.receipt_mint,
+        &accounts.maker_receipt_account,
+    ) {
+        (Some(nonce_counter), Some(receipt_mint), Some(maker_receipt_account)) => {
+            let offer_key = accounts.offer.key();
+            let nonce = next_nonce(
+                &rent_payer,
+                nonce_counter,
+                &offer_key,
+                context.bumps.nonce_counter.ok_or(ErrorCode::InvalidReceipt)?,
+                &accounts.system_program,
+            )?;
+
+            let nonce_bytes = nonce.to_le_bytes();
+            let (receipt_mint_address, receipt_mint_bump) = Pubkey::find_program_address(
+                &[b"receipt", offer_key.as_ref(), &nonce_bytes],
+                &crate::ID,
+            );
+            require_keys_eq!(
+                receipt_mint.key(),
+                receipt_mint_address,
+                ErrorCode::InvalidReceipt
+            );
+
+            create_pda_account(
+                &rent_payer,
+                receipt_mint,
+                find_mint_account_size(None)?,
+                &accounts.token_program.key(),
+                &accounts.system_program,
+                &[b"receipt", offer_key.as_ref(), &nonce_bytes, &[receipt_mint_bump]],
+            )?;
+
+            initialize_mint2(
+                CpiContext::new(
+                    accounts.token_program.to_account_info(),
+                    InitializeMint2 {
+                        mint: receipt_mint.to_account_info(),
+                    },
+                ),
+                0,
+                &offer_key,
+                Some(&offer_key),
+            )?;
+
+            create(CpiContext::new(
+                accounts.associated_token_program.to_account_info(),
+                Create {
+                    payer: rent_payer.clone(),
+                    associated_token: maker_receipt_account.to_account_info(),
+                    authority: accounts.maker.to_account_info(),
+                    mint: receipt_mint.to_account_info(),
+                    system_program: accounts.system_program.to_account_info(),
+                    token_program: accounts.token_program.to_account_info(),
+                },
+            ))?;
+
+            let signers_seeds = Some(&offer_account_seeds[..]);
+
+            mint_tokens(
+                receipt_mint,
+                maker_receipt_account,
+                1,
+                &accounts.offer.to_account_info(),
+                &accounts.token_program,
+                signers_seeds,
+            )?;
+
+            revoke_mint_authority(
+                receipt_mint,
+                &accounts.offer.to_account_info(),
+                &accounts.token_program,
+                signers_seeds,
+            )?;
+
+            (receipt_mint.key(), nonce)
+        }
+        (None, None, None) => (Pubkey::default(), 0),
+        _ => return Err(error!(ErrorCode::InvalidReceipt)),
+    };
+
+    let surplus_account = match &accounts.surplus_account {
+        Some(surplus_account) => surplus_account.key(),
+        None => accounts.maker_token_account_a.key(),
+    };
+
     // Save the details of the offer to the offer account
-    context.accounts.offer.set_inner(Offer {
+    let offer = Offer {
         id,
-        maker: context.accounts.maker.key(),
-        token_mint_a: context.accounts.token_mint_a.key(),
-        token_mint_b: context.accounts.token_mint_b.key(),
+        maker: accounts.maker.key(),
+        token_mint_a: accounts.token_mint_a.key(),
+        token_mint_b: accounts.token_mint_b.key(),
+        token_a_offered_amount,
         token_b_wanted_amount,
+        proceeds_account: accounts.proceeds_account.key(),
+        rent_payer: rent_payer.key(),
+        surplus_account,
+        receipt_mint,
+        nonce,
         bump: context.bumps.offer,
-    });
+    };
+    offer.try_serialize(&mut &mut accounts.offer.try_borrow_mut_data()?[..])?;
     Ok(())
 }
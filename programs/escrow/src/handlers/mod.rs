@@ -7,6 +7,9 @@ pub use take_offer::*;
 pub mod refund_offer;
 pub use refund_offer::*;
 
+pub mod close_receipt;
+pub use close_receipt::*;
+
 pub mod shared;
 pub use shared::*;
 
@@ -21,3 +24,162 @@ pub use game_finalize::*;
 
 pub mod game_cancel;
 pub use game_cancel::*;
+
+pub mod game_shared;
+pub use game_shared::*;
+
+pub mod game_propose_result;
+pub use game_propose_result::*;
+
+pub mod game_challenge_result;
+pub use game_challenge_result::*;
+
+pub mod game_resolve_challenge;
+pub use game_resolve_challenge::*;
+
+pub mod game_settle;
+pub use game_settle::*;
+
+pub mod game_vote_result;
+pub use game_vote_result::*;
+
+pub mod game_close_vote_record;
+pub use game_close_vote_record::*;
+
+pub mod game_finalize_with_attestation;
+pub use game_finalize_with_attestation::*;
+
+pub mod game_record_round;
+pub use game_record_round::*;
+
+pub mod game_place_side_bet;
+pub use game_place_side_bet::*;
+
+pub mod game_claim_side_bet;
+pub use game_claim_side_bet::*;
+
+pub mod game_close;
+pub use game_close::*;
+
+pub mod queue_create;
+pub use queue_create::*;
+
+pub mod queue_enqueue;
+pub use queue_enqueue::*;
+
+pub mod queue_match_players;
+pub use queue_match_players::*;
+
+pub mod queue_dequeue;
+pub use queue_dequeue::*;
+
+pub mod queue_close;
+pub use queue_close::*;
+
+pub mod loan_request;
+pub use loan_request::*;
+
+pub mod loan_cancel;
+pub use loan_cancel::*;
+
+pub mod loan_fund;
+pub use loan_fund::*;
+
+pub mod loan_repay;
+pub use loan_repay::*;
+
+pub mod loan_claim_collateral;
+pub use loan_claim_collateral::*;
+
+pub mod option_write;
+pub use option_write::*;
+
+pub mod option_buy;
+pub use option_buy::*;
+
+pub mod option_exercise;
+pub use option_exercise::*;
+
+pub mod option_reclaim;
+pub use option_reclaim::*;
+
+pub mod htlc_create;
+pub use htlc_create::*;
+
+pub mod htlc_claim;
+pub use htlc_claim::*;
+
+pub mod htlc_refund;
+pub use htlc_refund::*;
+
+pub mod htlc_close;
+pub use htlc_close::*;
+
+pub mod stream_create;
+pub use stream_create::*;
+
+pub mod stream_withdraw;
+pub use stream_withdraw::*;
+
+pub mod stream_cancel;
+pub use stream_cancel::*;
+
+pub mod stream_top_up;
+pub use stream_top_up::*;
+
+pub mod campaign_create;
+pub use campaign_create::*;
+
+pub mod campaign_contribute;
+pub use campaign_contribute::*;
+
+pub mod campaign_withdraw;
+pub use campaign_withdraw::*;
+
+pub mod campaign_refund;
+pub use campaign_refund::*;
+
+pub mod campaign_close_contribution;
+pub use campaign_close_contribution::*;
+
+pub mod campaign_close;
+pub use campaign_close::*;
+
+pub mod bounty_create;
+pub use bounty_create::*;
+
+pub mod bounty_submit_claim;
+pub use bounty_submit_claim::*;
+
+pub mod bounty_award_claim;
+pub use bounty_award_claim::*;
+
+pub mod bounty_close;
+pub use bounty_close::*;
+
+pub mod bounty_close_claim;
+pub use bounty_close_claim::*;
+
+pub mod listing_create;
+pub use listing_create::*;
+
+pub mod listing_purchase;
+pub use listing_purchase::*;
+
+pub mod listing_mark_shipped;
+pub use listing_mark_shipped::*;
+
+pub mod listing_confirm_receipt;
+pub use listing_confirm_receipt::*;
+
+pub mod listing_open_dispute;
+pub use listing_open_dispute::*;
+
+pub mod listing_resolve_dispute;
+pub use listing_resolve_dispute::*;
+
+pub mod listing_cancel;
+pub use listing_cancel::*;
+
+pub mod listing_reclaim_payment;
+pub use listing_reclaim_payment::*;
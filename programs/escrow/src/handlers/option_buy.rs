@@ -0,0 +1,106 @@
+use super::shared::{mint_tokens, revoke_mint_authority, transfer_tokens};
+use crate::{error::ErrorCode, state::CallOption};
+use anchor_lang::prelude::*;
+use anchor_spl::token_interface::{Mint, TokenAccount, TokenInterface};
+
+#[derive(Accounts)]
+pub struct BuyOption<'info> {
+    // Work with either the classic token program or
+    // the newer token extensions program
+    pub token_program: Interface<'info, TokenInterface>,
+
+    #[account(mut)]
+    pub buyer: Signer<'info>,
+
+    pub quote_mint: InterfaceAccount<'info, Mint>,
+
+    #[account(
+        mut,
+        token::mint = quote_mint,
+        token::authority = buyer,
+        token::token_program = token_program
+    )]
+    pub buyer_quote_account: InterfaceAccount<'info, TokenAccount>,
+
+    // Where the option token is minted to
+    #[account(
+        mut,
+        token::mint = option_mint,
+        token::token_program = token_program
+    )]
+    pub buyer_option_account: InterfaceAccount<'info, TokenAccount>,
+
+    #[account(
+        mut,
+        token::mint = quote_mint,
+        token::token_program = token_program
+    )]
+    pub proceeds_account: InterfaceAccount<'info, TokenAccount>,
+
+    #[account(mut)]
+    pub option_mint: InterfaceAccount<'info, Mint>,
+
+    #[account(
+        mut,
+        has_one = quote_mint,
+        has_one = proceeds_account,
+        has_one = option_mint,
+        constraint = !call_option.sold @ ErrorCode::OptionAlreadySold,
+        seeds = [b"option", call_option.id.to_le_bytes().as_ref()],
+        bump = call_option.bump
+    )]
+    pub call_option: Account<'info, CallOption>,
+}
+
+// Handle the buy option instruction by:
+// 1. Sending the premium from the buyer to the writer's proceeds account
+// 2. Minting the only option token to the buyer
+pub fn buy_option(context: Context<BuyOption>) -> Result<()> {
+    let now = Clock::get()?.unix_timestamp;
+    require!(
+        now <= context.accounts.call_option.expiry_ts,
+        ErrorCode::OptionExpired
+    );
+
+    // Send the premium from the buyer to the writer's proceeds account
+    if context.accounts.call_option.premium_amount > 0 {
+        transfer_tokens(
+            &context.accounts.buyer_quote_account,
+            &context.accounts.proceeds_account,
+            &context.accounts.call_option.premium_amount,
+            &context.accounts.quote_mint,
+            &context.accounts.buyer.to_account_info(),
+            &context.accounts.token_program,
+            None,
+        )
+        .map_err(|_| ErrorCode::InsufficientTakerBalance)?;
+    }
+
+    let option_account_seeds = &[
+        b"option",
+        &context.accounts.call_option.id.to_le_bytes()[..],
+        &[context.accounts.call_option.bump],
+    ];
+    let signers_seeds = Some(&option_account_seeds[..]);
+
+    // Mint a single option token to the buyer, then remove the mint authority
+    // so there can never be more than one
+    mint_tokens(
+        &context.accounts.option_mint,
+        &context.accounts.buyer_option_account,
+        1,
+        &context.accounts.call_option.to_account_info(),
+        &context.accounts.token_program,
+        signers_seeds,
+    )?;
+
+    revoke_mint_authority(
+        &context.accounts.option_mint,
+        &context.accounts.call_option.to_account_info(),
+        &context.accounts.token_program,
+        signers_seeds,
+    )?;
+
+    context.accounts.call_option.sold = true;
+    Ok(())
+}
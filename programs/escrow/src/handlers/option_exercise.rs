@@ -0,0 +1,144 @@
+use super::shared::{burn_tokens, close_token_account, transfer_tokens};
+use crate::{error::ErrorCode, state::CallOption};
+use anchor_lang::prelude::*;
+use anchor_spl::token_interface::{Mint, TokenAccount, TokenInterface};
+
+#[derive(Accounts)]
+pub struct ExerciseOption<'info> {
+    // Work with either the classic token program or
+    // the newer token extensions program
+    pub token_program: Interface<'info, TokenInterface>,
+
+    // Whoever holds the option token, not necessarily the original buyer
+    #[account(mut)]
+    pub holder: Signer<'info>,
+
+    // Gets the rent back, since they made the option and vault accounts
+    #[account(mut)]
+    pub writer: SystemAccount<'info>,
+
+    pub underlying_mint: InterfaceAccount<'info, Mint>,
+
+    pub quote_mint: InterfaceAccount<'info, Mint>,
+
+    #[account(mut)]
+    pub option_mint: InterfaceAccount<'info, Mint>,
+
+    #[account(
+        mut,
+        token::mint = option_mint,
+        token::authority = holder,
+        token::token_program = token_program
+    )]
+    pub holder_option_account: InterfaceAccount<'info, TokenAccount>,
+
+    #[account(
+        mut,
+        token::mint = quote_mint,
+        token::authority = holder,
+        token::token_program = token_program
+    )]
+    pub holder_quote_account: InterfaceAccount<'info, TokenAccount>,
+
+    // Where the underlying tokens go, chosen by the holder
+    #[account(
+        mut,
+        token::mint = underlying_mint,
+        token::token_program = token_program
+    )]
+    pub holder_underlying_account: InterfaceAccount<'info, TokenAccount>,
+
+    #[account(
+        mut,
+        token::mint = quote_mint,
+        token::token_program = token_program
+    )]
+    pub proceeds_account: InterfaceAccount<'info, TokenAccount>,
+
+    #[account(
+        mut,
+        close = writer,
+        has_one = writer,
+        has_one = underlying_mint,
+        has_one = quote_mint,
+        has_one = option_mint,
+        has_one = proceeds_account,
+        seeds = [b"option", call_option.id.to_le_bytes().as_ref()],
+        bump = call_option.bump
+    )]
+    pub call_option: Account<'info, CallOption>,
+
+    #[account(
+        mut,
+        associated_token::mint = underlying_mint,
+        associated_token::authority = call_option,
+        associated_token::token_program = token_program,
+    )]
+    pub vault: InterfaceAccount<'info, TokenAccount>,
+}
+
+// Handle the exercise option instruction by:
+// 1. Burning the holder's option token
+// 2. Sending the strike from the holder to the writer's proceeds account
+// 3. Withdrawing the underlying tokens from the vault to the holder and closing the vault
+pub fn exercise_option(context: Context<ExerciseOption>) -> Result<()> {
+    let now = Clock::get()?.unix_timestamp;
+    require!(
+        now <= context.accounts.call_option.expiry_ts,
+        ErrorCode::OptionExpired
+    );
+
+    // Burning the option token proves the holder has it, and stops it being used again
+    burn_tokens(
+        &context.accounts.holder_option_account,
+        &context.accounts.option_mint,
+        1,
+        &context.accounts.holder.to_account_info(),
+        &context.accounts.token_program,
+        None,
+    )?;
+
+    // Send the strike from the holder to the writer's proceeds account
+    transfer_tokens(
+        &context.accounts.holder_quote_account,
+        &context.accounts.proceeds_account,
+        &context.accounts.call_option.strike_amount,
+        &context.accounts.quote_mint,
+        &context.accounts.holder.to_account_info(),
+        &context.accounts.token_program,
+        None,
+    )
+    .map_err(|_| ErrorCode::InsufficientTakerBalance)?;
+
+    let option_account_seeds = &[
+        b"option",
+        &context.accounts.call_option.id.to_le_bytes()[..],
+        &[context.accounts.call_option.bump],
+    ];
+    let signers_seeds = Some(&option_account_seeds[..]);
+
+    // Withdraw the underlying tokens from the vault to the holder. We move everything in the
+    // vault, including anything else sent to it, so the vault is empty and can be closed
+    transfer_tokens(
+        &context.accounts.vault,
+        &context.accounts.holder_underlying_account,
+        &context.accounts.vault.amount,
+        &context.accounts.underlying_mint,
+        &context.accounts.call_option.to_account_info(),
+        &context.accounts.token_program,
+        signers_seeds,
+    )
+    .map_err(|_| ErrorCode::FailedVaultWithdrawal)?;
+
+    // Close the vault and return the rent to the writer
+    close_token_account(
+        &context.accounts.vault,
+        &context.accounts.writer.to_account_info(),
+        &context.accounts.call_option.to_account_info(),
+        &context.accounts.token_program,
+        signers_seeds,
+    )
+    .map_err(|_| ErrorCode::FailedVaultClosure)?;
+
+    Ok(())
+}
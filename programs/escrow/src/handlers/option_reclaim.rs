@@ -0,0 +1,85 @@
+use super::shared::{close_token_account, transfer_tokens};
+use crate::{error::ErrorCode, state::CallOption};
+use anchor_lang::prelude::*;
+use anchor_spl::token_interface::{Mint, TokenAccount, TokenInterface};
+
+#[derive(Accounts)]
+pub struct ReclaimOption<'info> {
+    // Work with either the classic token program or
+    // the newer token extensions program
+    pub token_program: Interface<'info, TokenInterface>,
+
+    #[account(mut)]
+    pub writer: Signer<'info>,
+
+    pub underlying_mint: InterfaceAccount<'info, Mint>,
+
+    #[account(
+        mut,
+        token::mint = underlying_mint,
+        token::token_program = token_program
+    )]
+    pub writer_underlying_account: InterfaceAccount<'info, TokenAccount>,
+
+    #[account(
+        mut,
+        close = writer,
+        has_one = writer,
+        has_one = underlying_mint,
+        seeds = [b"option", call_option.id.to_le_bytes().as_ref()],
+        bump = call_option.bump
+    )]
+    pub call_option: Account<'info, CallOption>,
+
+    #[account(
+        mut,
+        associated_token::mint = underlying_mint,
+        associated_token::authority = call_option,
+        associated_token::token_program = token_program,
+    )]
+    pub vault: InterfaceAccount<'info, TokenAccount>,
+}
+
+// Handle the reclaim option instruction, for options nobody bought or that expired
+// without being exercised, by:
+// 1. Returning the underlying tokens from the vault to the writer
+// 2. Closing the vault and returning the rent to the writer
+pub fn reclaim_option(context: Context<ReclaimOption>) -> Result<()> {
+    let now = Clock::get()?.unix_timestamp;
+    require!(
+        !context.accounts.call_option.sold || now > context.accounts.call_option.expiry_ts,
+        ErrorCode::OptionNotExpired
+    );
+
+    let option_account_seeds = &[
+        b"option",
+        &context.accounts.call_option.id.to_le_bytes()[..],
+        &[context.accounts.call_option.bump],
+    ];
+    let signers_seeds = Some(&option_account_seeds[..]);
+
+    // Return the underlying tokens from the vault to the writer. We move everything in the
+    // vault, including anything else sent to it, so the vault is empty and can be closed
+    transfer_tokens(
+        &context.accounts.vault,
+        &context.accounts.writer_underlying_account,
+        &context.accounts.vault.amount,
+        &context.accounts.underlying_mint,
+        &context.accounts.call_option.to_account_info(),
+        &context.accounts.token_program,
+        signers_seeds,
+    )
+    .map_err(|_| ErrorCode::FailedRefundTransfer)?;
+
+    // Close the vault and return the rent to the writer
+    close_token_account(
+        &context.accounts.vault,
+        &context.accounts.writer.to_account_info(),
+        &context.accounts.call_option.to_account_info(),
+        &context.accounts.token_program,
+        signers_seeds,
+    )
+    .map_err(|_| ErrorCode::FailedRefundClosure)?;
+
+    Ok(())
+}
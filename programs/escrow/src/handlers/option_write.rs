@@ -0,0 +1,156 @@
+use super::shared::transfer_tokens;
+use crate::{
+    error::ErrorCode,
+    state::{CallOption, NonceCounter},
+};
+use anchor_lang::prelude::*;
+use anchor_spl::{
+    associated_token::AssociatedToken,
+    token_interface::{Mint, TokenAccount, TokenInterface},
+};
+
+#[derive(Accounts)]
+#[instruction(id: u64)]
+pub struct WriteOption<'info> {
+    // Used to manage associated token accounts
+    // ie where a wallet holds a specific type of token
+    pub associated_token_program: Program<'info, AssociatedToken>,
+
+    // Work with either the classic token program or
+    // the newer token extensions program
+    pub token_program: Interface<'info, TokenInterface>,
+
+    // Used to create accounts
+    pub system_program: Program<'info, System>,
+
+    #[account(mut)]
+    pub writer: Signer<'info>,
+
+    #[account(mint::token_program = token_program)]
+    pub underlying_mint: InterfaceAccount<'info, Mint>,
+
+    #[account(mint::token_program = token_program)]
+    pub quote_mint: InterfaceAccount<'info, Mint>,
+
+    #[account(
+        mut,
+        associated_token::mint = underlying_mint,
+        associated_token::authority = writer,
+        associated_token::token_program = token_program
+    )]
+    pub writer_underlying_account: InterfaceAccount<'info, TokenAccount>,
+
+    // Where the premium and strike are sent. Any quote token account,
+    // not necessarily the writer's ATA
+    #[account(
+        token::mint = quote_mint,
+        token::token_program = token_program
+    )]
+    pub proceeds_account: InterfaceAccount<'info, TokenAccount>,
+
+    #[account(
+        init,
+        payer = writer,
+        space = CallOption::DISCRIMINATOR.len() + CallOption::INIT_SPACE,
+        seeds = [b"option", id.to_le_bytes().as_ref()],
+        bump
+    )]
+    pub call_option: Account<'info, CallOption>,
+
+    // Hands out a nonce for the option mint's address, so reusing an option id
+    // never collides with the option mint of an earlier option with that id
+    #[account(
+        init_if_needed,
+        payer = writer,
+        space = NonceCounter::DISCRIMINATOR.len() + NonceCounter::INIT_SPACE,
+        seeds = [b"nonce_counter", call_option.key().as_ref()],
+        bump
+    )]
+    pub nonce_counter: Account<'info, NonceCounter>,
+
+    #[account(
+        init,
+        payer = writer,
+        associated_token::mint = underlying_mint,
+        associated_token::authority = call_option,
+        associated_token::token_program = token_program
+    )]
+    pub vault: InterfaceAccount<'info, TokenAccount>,
+
+    // The token representing the option. One is minted to the buyer, and it can be
+    // transferred like any other token, so whoever holds it can exercise.
+    // Its address includes the nonce the handler is about to take from the counter
+    #[account(
+        init,
+        payer = writer,
+        seeds = [
+            b"option_mint",
+            call_option.key().as_ref(),
+            nonce_counter.last_nonce.wrapping_add(1).to_le_bytes().as_ref()
+        ],
+        bump,
+        mint::decimals = 0,
+        mint::authority = call_option,
+        mint::token_program = token_program
+    )]
+    pub option_mint: InterfaceAccount<'info, Mint>,
+}
+
+// Handle the write option instruction by:
+// 1. Moving the underlying tokens from the writer's ATA to the vault
+// 2. Saving the details of the option to the option account
+pub fn write_option(
+    context: Context<WriteOption>,
+    id: u64,
+    underlying_amount: u64,
+    strike_amount: u64,
+    premium_amount: u64,
+    expiry_ts: i64,
+) -> Result<()> {
+    // Validate amounts. The premium can be zero, eg for options given as compensation
+    require!(underlying_amount > 0, ErrorCode::InvalidAmount);
+    require!(strike_amount > 0, ErrorCode::InvalidAmount);
+
+    // Validate token mints are different
+    require!(
+        context.accounts.underlying_mint.key() != context.accounts.quote_mint.key(),
+        ErrorCode::InvalidTokenMint
+    );
+
+    let now = Clock::get()?.unix_timestamp;
+    require!(expiry_ts > now, ErrorCode::OptionExpired);
+
+    // Move the underlying tokens from the writer's ATA to the vault
+    transfer_tokens(
+        &context.accounts.writer_underlying_account,
+        &context.accounts.vault,
+        &underlying_amount,
+        &context.accounts.underlying_mint,
+        &context.accounts.writer.to_account_info(),
+        &context.accounts.token_program,
+        None,
+    )
+    .map_err(|_| ErrorCode::InsufficientMakerBalance)?;
+
+    let nonce_counter = &mut context.accounts.nonce_counter;
+    nonce_counter.bump = context.bumps.nonce_counter;
+    let nonce = nonce_counter.next_nonce()?;
+
+    // Save the details of the option to the option account
+    context.accounts.call_option.set_inner(CallOption {
+        id,
+        writer: context.accounts.writer.key(),
+        underlying_mint: context.accounts.underlying_mint.key(),
+        quote_mint: context.accounts.quote_mint.key(),
+        underlying_amount,
+        strike_amount,
+        premium_amount,
+        expiry_ts,
+        proceeds_account: context.accounts.proceeds_account.key(),
+        option_mint: context.accounts.option_mint.key(),
+        nonce,
+        sold: false,
+        bump: context.bumps.call_option,
+    });
+    Ok(())
+}
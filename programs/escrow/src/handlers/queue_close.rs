@@ -0,0 +1,32 @@
+use anchor_lang::prelude::*;
+
+use crate::error::ErrorCode;
+use crate::state::MatchQueue;
+
+#[derive(Accounts)]
+pub struct CloseQueue<'info> {
+    #[account(mut)]
+    pub authority: Signer<'info>,
+
+    // Only an empty queue can be closed, so no player's stake is left behind
+    #[account(
+        mut,
+        close = authority,
+        has_one = authority,
+        constraint = queue.entries.is_empty() @ ErrorCode::QueueNotEmpty,
+        seeds = [
+            b"queue",
+            queue.authority.as_ref(),
+            &[queue.game_mode],
+            queue.stake_lamports.to_le_bytes().as_ref()
+        ],
+        bump = queue.bump,
+    )]
+    pub queue: Account<'info, MatchQueue>,
+}
+
+// Closes a queue nobody is waiting in, returning its rent to the authority that opened it.
+// Games already matched from it are unaffected
+pub fn close_queue(_ctx: Context<CloseQueue>) -> Result<()> {
+    Ok(())
+}
@@ -0,0 +1,60 @@
+use anchor_lang::prelude::*;
+
+use crate::constants::{MAX_GAME_DURATION_SECS, MAX_RAKE_BPS};
+use crate::error::ErrorCode;
+use crate::state::MatchQueue;
+
+#[derive(Accounts)]
+#[instruction(game_mode: u8, stake_lamports: u64)]
+pub struct CreateQueue<'info> {
+    #[account(mut)]
+    pub authority: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+
+    /// CHECK: only stored, as the account the rake is paid to
+    pub treasury: UncheckedAccount<'info>,
+
+    #[account(
+        init,
+        payer = authority,
+        space = MatchQueue::DISCRIMINATOR.len() + MatchQueue::INIT_SPACE,
+        seeds = [
+            b"queue",
+            authority.key().as_ref(),
+            &[game_mode],
+            stake_lamports.to_le_bytes().as_ref()
+        ],
+        bump
+    )]
+    pub queue: Account<'info, MatchQueue>,
+}
+
+// Opens a queue for one game mode and stake, whose matched games are refereed by the authority
+pub fn create_queue(
+    ctx: Context<CreateQueue>,
+    game_mode: u8,
+    stake_lamports: u64,
+    rake_bps: u16,
+    game_duration: i64,
+) -> Result<()> {
+    require!(stake_lamports > 0, ErrorCode::InvalidAmount);
+    require!(rake_bps <= MAX_RAKE_BPS, ErrorCode::RakeTooHigh);
+    require!(
+        (1..=MAX_GAME_DURATION_SECS).contains(&game_duration),
+        ErrorCode::InvalidGameDuration
+    );
+
+    ctx.accounts.queue.set_inner(MatchQueue {
+        authority: ctx.accounts.authority.key(),
+        game_mode,
+        stake_lamports,
+        rake_bps,
+        treasury: ctx.accounts.treasury.key(),
+        game_duration,
+        entries: Vec::new(),
+        bump: ctx.bumps.queue,
+    });
+
+    Ok(())
+}
@@ -0,0 +1,43 @@
+use anchor_lang::prelude::*;
+
+use crate::error::ErrorCode;
+use crate::state::MatchQueue;
+
+#[derive(Accounts)]
+pub struct Dequeue<'info> {
+    #[account(mut)]
+    pub player: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+
+    #[account(
+        mut,
+        seeds = [
+            b"queue",
+            queue.authority.as_ref(),
+            &[queue.game_mode],
+            queue.stake_lamports.to_le_bytes().as_ref()
+        ],
+        bump = queue.bump,
+    )]
+    pub queue: Account<'info, MatchQueue>,
+}
+
+// Leaves a queue before being matched, returning the player's stake and rent share
+pub fn dequeue(ctx: Context<Dequeue>) -> Result<()> {
+    let queue = &mut ctx.accounts.queue;
+    let player = ctx.accounts.player.key();
+    let index = queue
+        .entries
+        .iter()
+        .position(|entry| entry.player == player)
+        .ok_or(ErrorCode::NotQueued)?;
+    let entry = queue.entries.remove(index);
+
+    // The queue is owned by this program, so it can be debited directly
+    let refund = queue.stake_lamports + entry.rent_share;
+    queue.sub_lamports(refund).map_err(|_| ErrorCode::LamportsTransferFailed)?;
+    ctx.accounts.player.add_lamports(refund)?;
+
+    Ok(())
+}
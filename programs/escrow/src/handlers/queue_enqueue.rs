@@ -0,0 +1,69 @@
+use anchor_lang::prelude::*;
+
+use crate::constants::MAX_QUEUE_LENGTH;
+use crate::error::ErrorCode;
+use crate::handlers::transfer_lamports;
+use crate::state::{Game, MatchQueue, PlayerProfile, QueueEntry};
+
+#[derive(Accounts)]
+pub struct Enqueue<'info> {
+    #[account(mut)]
+    pub player: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+
+    #[account(
+        mut,
+        seeds = [
+            b"queue",
+            queue.authority.as_ref(),
+            &[queue.game_mode],
+            queue.stake_lamports.to_le_bytes().as_ref()
+        ],
+        bump = queue.bump,
+    )]
+    pub queue: Account<'info, MatchQueue>,
+
+    // Created the first time the player queues, as matched players never call deposit
+    #[account(
+        init_if_needed,
+        payer = player,
+        space = PlayerProfile::DISCRIMINATOR.len() + PlayerProfile::INIT_SPACE,
+        seeds = [b"profile", player.key().as_ref()],
+        bump
+    )]
+    pub player_profile: Account<'info, PlayerProfile>,
+}
+
+// Joins a queue, escrowing the player's stake and half the rent of the game they'll be
+// matched into, until they're matched or leave
+pub fn enqueue(ctx: Context<Enqueue>) -> Result<()> {
+    let queue = &mut ctx.accounts.queue;
+    let player = ctx.accounts.player.key();
+    require!(
+        !queue.entries.iter().any(|entry| entry.player == player),
+        ErrorCode::AlreadyQueued
+    );
+    require!(queue.entries.len() < MAX_QUEUE_LENGTH, ErrorCode::QueueFull);
+
+    let rent_share = Rent::get()?.minimum_balance(Game::space(2)).div_ceil(2);
+    transfer_lamports(
+        &ctx.accounts.player.to_account_info(),
+        &queue.to_account_info(),
+        queue.stake_lamports + rent_share,
+        &ctx.accounts.system_program,
+        None,
+    )
+    .map_err(|_| ErrorCode::LamportsTransferFailed)?;
+
+    queue.entries.push(QueueEntry {
+        player,
+        enqueued_ts: Clock::get()?.unix_timestamp,
+        rent_share,
+    });
+
+    let bump = ctx.bumps.player_profile;
+    ctx.accounts.player_profile.set_up(player, bump);
+
+    Ok(())
+}
@@ -0,0 +1,109 @@
+use anchor_lang::prelude::*;
+
+use crate::error::ErrorCode;
+use crate::state::{Game, MatchQueue, NonceCounter, PlayerSlot};
+
+// Anyone can crank a queue. Whoever does fronts the new game's rent and is paid back
+// from the matched players' rent shares, so cranking costs only the transaction fee, and the
+// rent of the id's nonce counter the first time the id is used.
+// The game's rent goes back to the players when the game is closed
+#[derive(Accounts)]
+#[instruction(id: u64)]
+pub struct MatchPlayers<'info> {
+    #[account(mut)]
+    pub cranker: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+
+    #[account(
+        mut,
+        seeds = [
+            b"queue",
+            queue.authority.as_ref(),
+            &[queue.game_mode],
+            queue.stake_lamports.to_le_bytes().as_ref()
+        ],
+        bump = queue.bump,
+    )]
+    pub queue: Account<'info, MatchQueue>,
+
+    #[account(
+        init,
+        payer = cranker,
+        space = Game::space(2),
+        seeds = [b"game", id.to_le_bytes().as_ref()],
+        bump
+    )]
+    pub game: Account<'info, Game>,
+
+    // Hands out the game's nonce
+    #[account(
+        init_if_needed,
+        payer = cranker,
+        space = NonceCounter::DISCRIMINATOR.len() + NonceCounter::INIT_SPACE,
+        seeds = [b"nonce_counter", game.key().as_ref()],
+        bump
+    )]
+    pub nonce_counter: Account<'info, NonceCounter>,
+}
+
+// Pairs the two players who have waited longest into a new duel, moving their
+// escrowed stakes into it so the game starts with every deposit made
+pub fn match_players(ctx: Context<MatchPlayers>, id: u64) -> Result<()> {
+    let queue = &mut ctx.accounts.queue;
+    require!(queue.entries.len() >= 2, ErrorCode::NotEnoughPlayersQueued);
+    let entries: Vec<_> = queue.entries.drain(..2).collect();
+
+    // The queue is owned by this program, so it can be debited directly
+    let stakes = queue.stake_lamports * 2;
+    let rent_shares: u64 = entries.iter().map(|entry| entry.rent_share).sum();
+    queue
+        .sub_lamports(stakes + rent_shares)
+        .map_err(|_| ErrorCode::LamportsTransferFailed)?;
+    ctx.accounts.game.add_lamports(stakes)?;
+    ctx.accounts.cranker.add_lamports(rent_shares)?;
+
+    let now = Clock::get()?.unix_timestamp;
+    let expiry_ts = now
+        .checked_add(queue.game_duration)
+        .ok_or(ErrorCode::InvalidGameDuration)?;
+
+    let nonce_counter = &mut ctx.accounts.nonce_counter;
+    nonce_counter.bump = ctx.bumps.nonce_counter;
+    let nonce = nonce_counter.next_nonce()?;
+
+    let bump = ctx.bumps.game;
+    ctx.accounts.game.set_inner(Game {
+        id,
+        players: entries
+            .into_iter()
+            .map(|entry| PlayerSlot {
+                player: entry.player,
+                stake: queue.stake_lamports,
+                deposited: true,
+                joined_ts: entry.enqueued_ts,
+            })
+            .collect(),
+        authority: queue.authority,
+        stake_mint: Pubkey::default(),
+        winner: 0,
+        rake_bps: queue.rake_bps,
+        treasury: queue.treasury,
+        expiry_ts,
+        deposit_deadline_ts: now,
+        nonce,
+        challenge_settings: None,
+        referee_quorum: None,
+        proposed_result: None,
+        challenge_deadline_ts: 0,
+        challenger: Pubkey::default(),
+        wins_required: 0,
+        round_winners: Vec::new(),
+        has_side_pool: false,
+        matched_from_queue: true,
+        attester: Pubkey::default(),
+        bump,
+    });
+
+    Ok(())
+}
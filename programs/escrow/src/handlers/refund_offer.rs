@@ -1,7 +1,7 @@
 use anchor_lang::prelude::*;
 use anchor_spl::token_interface::{Mint, TokenAccount, TokenInterface};
 
-use super::shared::{close_token_account, transfer_tokens};
+use super::shared::{burn_tokens, close_token_account, transfer_tokens};
 use crate::{error::ErrorCode, state::Offer};
 
 #[derive(Accounts)]
@@ -13,23 +13,40 @@ pub struct RefundOffer<'info> {
     // Used to create accounts
     pub system_program: Program<'info, System>,
 
+    // The maker, or for offers with a receipt, whoever holds the receipt
     #[account(mut)]
-    pub maker: Signer<'info>,
+    pub owner: Signer<'info>,
+
+    // Whoever paid the rent for the offer and vault accounts gets it back
+    #[account(mut)]
+    pub rent_payer: SystemAccount<'info>,
 
     pub token_mint_a: InterfaceAccount<'info, Mint>,
 
+    // Where the offered tokens are returned to, chosen by the owner
     #[account(
         mut,
-        associated_token::mint = token_mint_a,
-        associated_token::authority = maker,
-        associated_token::token_program = token_program
+        token::mint = token_mint_a,
+        token::token_program = token_program
+    )]
+    pub owner_token_account_a: InterfaceAccount<'info, TokenAccount>,
+
+    // Where any extra token a sent to the vault goes: the one chosen when making the offer,
+    // or any token a account owned by the maker, in case that one has been closed.
+    // Only needed when someone has sent extra tokens to the vault and it isn't the
+    // owner's token a account
+    #[account(
+        mut,
+        token::mint = token_mint_a,
+        token::token_program = token_program,
     )]
-    pub maker_token_account_a: InterfaceAccount<'info, TokenAccount>,
+    pub surplus_account: Option<InterfaceAccount<'info, TokenAccount>>,
 
     #[account(
         mut,
-        close = maker,
-        has_one = maker,
+        close = rent_payer,
+        constraint = offer.receipt_mint != Pubkey::default() || offer.maker == owner.key() @ ErrorCode::NotOfferOwner,
+        has_one = rent_payer,
         seeds = [b"offer", offer.id.to_le_bytes().as_ref()],
         bump = offer.bump
     )]
@@ -42,12 +59,49 @@ pub struct RefundOffer<'info> {
         associated_token::token_program = token_program,
     )]
     pub vault: InterfaceAccount<'info, TokenAccount>,
+
+    // Only needed for offers with a receipt, which is burned by the refund
+    #[account(mut)]
+    pub receipt_mint: Option<InterfaceAccount<'info, Mint>>,
+
+    #[account(mut)]
+    pub owner_receipt_account: Option<InterfaceAccount<'info, TokenAccount>>,
 }
 
 // Handle the refund offer instruction by:
-// 1. Returning the tokens from the vault to the maker's account
-// 2. Closing the vault and returning the rent to the maker
+// 1. Burning the receipt token, for offers with a receipt
+// 2. Returning the offered tokens from the vault to the owner's account
+// 3. Returning any extra tokens sent to the vault to the surplus account, or the maker
+// 4. Closing the vault and returning the rent to whoever paid for it
 pub fn refund_offer(context: Context<RefundOffer>) -> Result<()> {
+    // For offers with a receipt, only the receipt holder can refund, and the receipt is
+    // burned since the offer it represents is going away
+    let receipt_mint_key = context.accounts.offer.receipt_mint;
+    if receipt_mint_key != Pubkey::default() {
+        let (Some(receipt_mint), Some(owner_receipt_account)) = (
+            &context.accounts.receipt_mint,
+            &context.accounts.owner_receipt_account,
+        ) else {
+            return Err(error!(ErrorCode::InvalidReceipt));
+        };
+        require!(
+            receipt_mint.key() == receipt_mint_key
+                && owner_receipt_account.mint == receipt_mint_key
+                && owner_receipt_account.owner == context.accounts.owner.key()
+                && owner_receipt_account.amount == 1,
+            ErrorCode::InvalidReceipt
+        );
+
+        burn_tokens(
+            owner_receipt_account,
+            receipt_mint,
+            1,
+            &context.accounts.owner.to_account_info(),
+            &context.accounts.token_program,
+            None,
+        )?;
+    }
+
     let offer_account_seeds = &[
         b"offer",
         &context.accounts.offer.id.to_le_bytes()[..],
@@ -55,11 +109,20 @@ pub fn refund_offer(context: Context<RefundOffer>) -> Result<()> {
     ];
     let signers_seeds = Some(&offer_account_seeds[..]);
 
-    // Return the tokens from the vault to the maker's account
+    // Anyone can send tokens to the vault, so work out how much is there
+    // beyond what the maker offered before we move anything
+    let surplus = context
+        .accounts
+        .vault
+        .amount
+        .checked_sub(context.accounts.offer.token_a_offered_amount)
+        .ok_or(ErrorCode::FailedRefundTransfer)?;
+
+    // Return the offered tokens from the vault to the owner's account
     transfer_tokens(
         &context.accounts.vault,
-        &context.accounts.maker_token_account_a,
-        &context.accounts.vault.amount,
+        &context.accounts.owner_token_account_a,
+        &context.accounts.offer.token_a_offered_amount,
         &context.accounts.token_mint_a,
         &context.accounts.offer.to_account_info(),
         &context.accounts.token_program,
@@ -67,10 +130,35 @@ pub fn refund_offer(context: Context<RefundOffer>) -> Result<()> {
     )
     .map_err(|_| ErrorCode::FailedRefundTransfer)?;
 
-    // Close the vault and return the rent to the maker
+    // Return any extra tokens so the vault is empty and can be closed
+    if surplus > 0 {
+        let surplus_account = context
+            .accounts
+            .surplus_account
+            .as_ref()
+            .unwrap_or(&context.accounts.owner_token_account_a);
+        require!(
+            surplus_account.key() == context.accounts.offer.surplus_account
+                || surplus_account.owner == context.accounts.offer.maker,
+            ErrorCode::InvalidSurplusAccount
+        );
+
+        transfer_tokens(
+            &context.accounts.vault,
+            surplus_account,
+            &surplus,
+            &context.accounts.token_mint_a,
+            &context.accounts.offer.to_account_info(),
+            &context.accounts.token_program,
+            signers_seeds,
+        )
+        .map_err(|_| ErrorCode::FailedSurplusTransfer)?;
+    }
+
+    // Close the vault and return the rent to whoever paid for it
     close_token_account(
         &context.accounts.vault,
-        &context.accounts.maker.to_account_info(),
+        &context.accounts.rent_payer.to_account_info(),
         &context.accounts.offer.to_account_info(),
         &context.accounts.token_program,
         signers_seeds,
@@ -1,16 +1,25 @@
 use anchor_lang::prelude::*;
-use anchor_lang::system_program::{transfer, Transfer as SystemTransfer};
+use anchor_lang::system_program::{
+    allocate, assign, create_account, transfer, Allocate, Assign, CreateAccount,
+    Transfer as SystemTransfer,
+};
 
+use anchor_spl::token_2022::spl_token_2022::instruction::AuthorityType;
 use anchor_spl::token_interface::{
-    close_account, transfer_checked, CloseAccount, Mint, TokenAccount, TokenInterface,
-    TransferChecked,
+    burn, close_account, freeze_account, mint_to, set_authority, thaw_account, transfer_checked,
+    Burn, CloseAccount, FreezeAccount, Mint, MintTo, SetAuthority, ThawAccount, TokenAccount,
+    TokenInterface, TransferChecked,
 };
 
+use crate::state::NonceCounter;
+
 // Transfer tokens from one account to another
 // If transferring from a token account owned by a PDA, owning_pda_seeds must be provided.
+// `to` can be any account info, so tokens can be sent to an account created earlier in the
+// same instruction, which hasn't been loaded as a token account
 pub fn transfer_tokens<'info>(
     from: &InterfaceAccount<'info, TokenAccount>,
-    to: &InterfaceAccount<'info, TokenAccount>,
+    to: &impl ToAccountInfo<'info>,
     amount: &u64,
     mint: &InterfaceAccount<'info, Mint>,
     authority: &AccountInfo<'info>,
@@ -69,7 +78,145 @@ pub fn close_token_account<'info>(
     })
 }
 
+// Mint new tokens to a token account
+// If the mint authority is a PDA, owning_pda_seeds must be provided.
+// `mint` and `to` can be any account info, so tokens can be minted with a mint and token
+// account created earlier in the same instruction, which haven't been loaded as token accounts
+pub fn mint_tokens<'info>(
+    mint: &impl ToAccountInfo<'info>,
+    to: &impl ToAccountInfo<'info>,
+    amount: u64,
+    authority: &AccountInfo<'info>,
+    token_program: &Interface<'info, TokenInterface>,
+    owning_pda_seeds: Option<&[&[u8]]>,
+) -> Result<()> {
+    let mint_accounts = MintTo {
+        mint: mint.to_account_info(),
+        to: to.to_account_info(),
+        authority: authority.to_account_info(),
+    };
+
+    let signers_seeds = owning_pda_seeds.map(|seeds| [seeds]);
+
+    mint_to(
+        if let Some(seeds_arr) = signers_seeds.as_ref() {
+            CpiContext::new_with_signer(token_program.to_account_info(), mint_accounts, seeds_arr)
+        } else {
+            CpiContext::new(token_program.to_account_info(), mint_accounts)
+        },
+        amount,
+    )
+}
+
+// Remove the mint authority so no more tokens can ever be minted, fixing the supply
+// If the mint authority is a PDA, owning_pda_seeds must be provided.
+pub fn revoke_mint_authority<'info>(
+    mint: &impl ToAccountInfo<'info>,
+    authority: &AccountInfo<'info>,
+    token_program: &Interface<'info, TokenInterface>,
+    owning_pda_seeds: Option<&[&[u8]]>,
+) -> Result<()> {
+    let set_authority_accounts = SetAuthority {
+        current_authority: authority.to_account_info(),
+        account_or_mint: mint.to_account_info(),
+    };
+
+    let signers_seeds = owning_pda_seeds.map(|seeds| [seeds]);
+
+    set_authority(
+        if let Some(seeds_arr) = signers_seeds.as_ref() {
+            CpiContext::new_with_signer(
+                token_program.to_account_info(),
+                set_authority_accounts,
+                seeds_arr,
+            )
+        } else {
+            CpiContext::new(token_program.to_account_info(), set_authority_accounts)
+        },
+        AuthorityType::MintTokens,
+        None,
+    )
+}
+
+// Burn tokens from a token account
+// If the token account is owned by a PDA, owning_pda_seeds must be provided.
+pub fn burn_tokens<'info>(
+    from: &InterfaceAccount<'info, TokenAccount>,
+    mint: &InterfaceAccount<'info, Mint>,
+    amount: u64,
+    authority: &AccountInfo<'info>,
+    token_program: &Interface<'info, TokenInterface>,
+    owning_pda_seeds: Option<&[&[u8]]>,
+) -> Result<()> {
+    let burn_accounts = Burn {
+        mint: mint.to_account_info(),
+        from: from.to_account_info(),
+        authority: authority.to_account_info(),
+    };
+
+    let signers_seeds = owning_pda_seeds.map(|seeds| [seeds]);
+
+    burn(
+        if let Some(seeds_arr) = signers_seeds.as_ref() {
+            CpiContext::new_with_signer(token_program.to_account_info(), burn_accounts, seeds_arr)
+        } else {
+            CpiContext::new(token_program.to_account_info(), burn_accounts)
+        },
+        amount,
+    )
+}
+
+// Freeze a token account so its tokens can no longer be moved
+// If the freeze authority is a PDA, owning_pda_seeds must be provided.
+pub fn freeze_token_account<'info>(
+    token_account: &InterfaceAccount<'info, TokenAccount>,
+    mint: &InterfaceAccount<'info, Mint>,
+    authority: &AccountInfo<'info>,
+    token_program: &Interface<'info, TokenInterface>,
+    owning_pda_seeds: Option<&[&[u8]]>,
+) -> Result<()> {
+    let freeze_accounts = FreezeAccount {
+        account: token_account.to_account_info(),
+        mint: mint.to_account_info(),
+        authority: authority.to_account_info(),
+    };
+
+    let signers_seeds = owning_pda_seeds.map(|seeds| [seeds]);
+
+    freeze_account(if let Some(seeds_arr) = signers_seeds.as_ref() {
+        CpiContext::new_with_signer(token_program.to_account_info(), freeze_accounts, seeds_arr)
+    } else {
+        CpiContext::new(token_program.to_account_info(), freeze_accounts)
+    })
+}
+
+// Thaw a frozen token account so its tokens can be moved, burned or the account closed
+// If the freeze authority is a PDA, owning_pda_seeds must be provided.
+pub fn thaw_token_account<'info>(
+    token_account: &InterfaceAccount<'info, TokenAccount>,
+    mint: &InterfaceAccount<'info, Mint>,
+    authority: &AccountInfo<'info>,
+    token_program: &Interface<'info, TokenInterface>,
+    owning_pda_seeds: Option<&[&[u8]]>,
+) -> Result<()> {
+    let thaw_accounts = ThawAccount {
+        account: token_account.to_account_info(),
+        mint: mint.to_account_info(),
+        authority: authority.to_account_info(),
+    };
+
+    let signers_seeds = owning_pda_seeds.map(|seeds| [seeds]);
+
+    thaw_account(if let Some(seeds_arr) = signers_seeds.as_ref() {
+        CpiContext::new_with_signer(token_program.to_account_info(), thaw_accounts, seeds_arr)
+    } else {
+        CpiContext::new(token_program.to_account_info(), thaw_accounts)
+    })
+}
+
 // Transfer lamports from one account to another, optionally signing with PDA seeds when `from` is a PDA.
+// The system program can't move lamports out of accounts that hold data, so this program's own
+// accounts (like a game or stream PDA) are debited directly with sub_lamports instead.
 pub fn transfer_lamports<'info>(
     from: &AccountInfo<'info>,
     to: &AccountInfo<'info>,
@@ -98,3 +245,88 @@ pub fn transfer_lamports<'info>(
         )
     }
 }
+
+// Create a PDA owned by `owner`, with `payer` covering the rent.
+// Like Anchor's `init`, this still works if someone has already sent lamports to the address.
+pub fn create_pda_account<'info>(
+    payer: &AccountInfo<'info>,
+    account: &AccountInfo<'info>,
+    space: usize,
+    owner: &Pubkey,
+    system_program: &Program<'info, System>,
+    pda_seeds: &[&[u8]],
+) -> Result<()> {
+    let rent = Rent::get()?.minimum_balance(space);
+    let signers_seeds = [pda_seeds];
+
+    if account.lamports() == 0 {
+        return create_account(
+            CpiContext::new_with_signer(
+                system_program.to_account_info(),
+                CreateAccount {
+                    from: payer.to_account_info(),
+                    to: account.to_account_info(),
+                },
+                &signers_seeds,
+            ),
+            rent,
+            space as u64,
+            owner,
+        );
+    }
+
+    let shortfall = rent.saturating_sub(account.lamports());
+    if shortfall > 0 {
+        transfer_lamports(payer, account, shortfall, system_program, None)?;
+    }
+    allocate(
+        CpiContext::new_with_signer(
+            system_program.to_account_info(),
+            Allocate {
+                account_to_allocate: account.to_account_info(),
+            },
+            &signers_seeds,
+        ),
+        space as u64,
+    )?;
+    assign(
+        CpiContext::new_with_signer(
+            system_program.to_account_info(),
+            Assign {
+                account_to_assign: account.to_account_info(),
+            },
+            &signers_seeds,
+        ),
+        owner,
+    )
+}
+
+// Hand out the next nonce from the nonce counter for the account at `account`, creating the
+// counter with `payer` covering the rent the first time it's used. Like Anchor's
+// `init_if_needed`, for instructions whose payer is an optional account
+pub fn next_nonce<'info>(
+    payer: &AccountInfo<'info>,
+    nonce_counter: &AccountInfo<'info>,
+    account: &Pubkey,
+    bump: u8,
+    system_program: &Program<'info, System>,
+) -> Result<u64> {
+    let mut counter = if nonce_counter.data_is_empty() {
+        create_pda_account(
+            payer,
+            nonce_counter,
+            NonceCounter::DISCRIMINATOR.len() + NonceCounter::INIT_SPACE,
+            &crate::ID,
+            system_program,
+            &[b"nonce_counter", account.as_ref(), &[bump]],
+        )?;
+        NonceCounter { last_nonce: 0, bump }
+    } else {
+        // Only this program can create accounts at the counter's address
+        NonceCounter::try_deserialize(&mut &nonce_counter.try_borrow_data()?[..])?
+    };
+
+    let nonce = counter.next_nonce()?;
+    counter.try_serialize(&mut &mut nonce_counter.try_borrow_mut_data()?[..])?;
+    Ok(nonce)
+}
@@ -0,0 +1,163 @@
+use super::shared::{close_token_account, transfer_tokens};
+use crate::{error::ErrorCode, state::Stream};
+use anchor_lang::prelude::*;
+use anchor_spl::token_interface::{Mint, TokenAccount, TokenInterface};
+
+#[derive(Accounts)]
+pub struct CancelStream<'info> {
+    // Work with either the classic token program or
+    // the newer token extensions program
+    pub token_program: Option<Interface<'info, TokenInterface>>,
+
+    // Used to move lamports for lamport streams
+    pub system_program: Program<'info, System>,
+
+    // Either the sender or the recipient
+    pub caller: Signer<'info>,
+
+    // Gets back what hasn't accrued yet, and the rent. Not necessarily a wallet,
+    // so a stream paid by or to a PDA can still be cancelled
+    /// CHECK: checked against the stream's sender
+    #[account(mut, address = stream.sender @ ErrorCode::Unauthorized)]
+    pub sender: UncheckedAccount<'info>,
+
+    // Gets what has accrued and not been withdrawn yet
+    /// CHECK: checked against the stream's recipient
+    #[account(mut, address = stream.recipient @ ErrorCode::Unauthorized)]
+    pub recipient: UncheckedAccount<'info>,
+
+    #[account(
+        address = stream.token_mint @ ErrorCode::InvalidStreamAccounts,
+        mint::token_program = token_program
+    )]
+    pub token_mint: Option<InterfaceAccount<'info, Mint>>,
+
+    #[account(
+        mut,
+        token::mint = token_mint,
+        token::authority = sender,
+        token::token_program = token_program
+    )]
+    pub sender_token_account: Option<InterfaceAccount<'info, TokenAccount>>,
+
+    #[account(
+        mut,
+        token::mint = token_mint,
+        token::authority = recipient,
+        token::token_program = token_program
+    )]
+    pub recipient_token_account: Option<InterfaceAccount<'info, TokenAccount>>,
+
+    #[account(
+        mut,
+        close = sender,
+        constraint = caller.key() == stream.sender || caller.key() == stream.recipient
+            @ ErrorCode::Unauthorized,
+        seeds = [b"stream", stream.id.to_le_bytes().as_ref()],
+        bump = stream.bump
+    )]
+    pub stream: Account<'info, Stream>,
+
+    #[account(
+        mut,
+        associated_token::mint = token_mint,
+        associated_token::authority = stream,
+        associated_token::token_program = token_program
+    )]
+    pub vault: Option<InterfaceAccount<'info, TokenAccount>>,
+}
+
+// Handle the cancel stream instruction by:
+// 1. Sending the recipient everything that has accrued and not been withdrawn yet
+// 2. Returning everything else to the sender
+// 3. Closing the vault and stream accounts and returning the rent to the sender
+pub fn cancel_stream(context: Context<CancelStream>) -> Result<()> {
+    let now = Clock::get()?.unix_timestamp;
+    let stream = &context.accounts.stream;
+    let recipient_amount = stream.withdrawable_amount(now)?;
+    let sender_amount = stream.deposited_amount - stream.accrued_amount(now)?;
+
+    let stream_account_seeds = &[
+        b"stream",
+        &stream.id.to_le_bytes()[..],
+        &[stream.bump],
+    ];
+    let signers_seeds = Some(&stream_account_seeds[..]);
+
+    if stream.token_mint == Pubkey::default() {
+        // The stream account is owned by this program, so it can be debited directly.
+        // The rent is returned to the sender when the stream account is closed
+        for (destination, amount) in [
+            (&context.accounts.recipient, recipient_amount),
+            (&context.accounts.sender, sender_amount),
+        ] {
+            if amount > 0 {
+                stream
+                    .sub_lamports(amount)
+                    .map_err(|_| ErrorCode::LamportsTransferFailed)?;
+                destination.add_lamports(amount)?;
+            }
+        }
+        return Ok(());
+    }
+
+    let (
+        Some(token_mint),
+        Some(sender_token_account),
+        Some(recipient_token_account),
+        Some(vault),
+        Some(token_program),
+    ) = (
+        &context.accounts.token_mint,
+        &context.accounts.sender_token_account,
+        &context.accounts.recipient_token_account,
+        &context.accounts.vault,
+        &context.accounts.token_program,
+    )
+    else {
+        return Err(error!(ErrorCode::InvalidStreamAccounts));
+    };
+
+    if recipient_amount > 0 {
+        transfer_tokens(
+            vault,
+            recipient_token_account,
+            &recipient_amount,
+            token_mint,
+            &stream.to_account_info(),
+            token_program,
+            signers_seeds,
+        )
+        .map_err(|_| ErrorCode::FailedVaultWithdrawal)?;
+    }
+
+    // Return everything else in the vault to the sender, including anything else
+    // sent to it, so the vault is empty and can be closed
+    let remaining_amount = vault
+        .amount
+        .checked_sub(recipient_amount)
+        .ok_or(ErrorCode::VaultShortfall)?;
+    if remaining_amount > 0 {
+        transfer_tokens(
+            vault,
+            sender_token_account,
+            &remaining_amount,
+            token_mint,
+            &stream.to_account_info(),
+            token_program,
+            signers_seeds,
+        )
+        .map_err(|_| ErrorCode::FailedRefundTransfer)?;
+    }
+
+    close_token_account(
+        vault,
+        &context.accounts.sender.to_account_info(),
+        &stream.to_account_info(),
+        token_program,
+        signers_seeds,
+    )
+    .map_err(|_| ErrorCode::FailedRefundClosure)?;
+
+    Ok(())
+}
@@ -0,0 +1,132 @@
+use super::shared::{transfer_lamports, transfer_tokens};
+use crate::{error::ErrorCode, state::Stream};
+use anchor_lang::prelude::*;
+use anchor_spl::{
+    associated_token::AssociatedToken,
+    token_interface::{Mint, TokenAccount, TokenInterface},
+};
+
+#[derive(Accounts)]
+#[instruction(id: u64)]
+pub struct CreateStream<'info> {
+    // Used to manage associated token accounts
+    // ie where a wallet holds a specific type of token
+    pub associated_token_program: Option<Program<'info, AssociatedToken>>,
+
+    // Work with either the classic token program or
+    // the newer token extensions program
+    pub token_program: Option<Interface<'info, TokenInterface>>,
+
+    // Used to create accounts, and to move lamports for lamport streams
+    pub system_program: Program<'info, System>,
+
+    #[account(mut)]
+    pub sender: Signer<'info>,
+
+    // The token being streamed. Leave this and the other token accounts out to stream lamports
+    #[account(mint::token_program = token_program)]
+    pub token_mint: Option<InterfaceAccount<'info, Mint>>,
+
+    #[account(
+        mut,
+        associated_token::mint = token_mint,
+        associated_token::authority = sender,
+        associated_token::token_program = token_program
+    )]
+    pub sender_token_account: Option<InterfaceAccount<'info, TokenAccount>>,
+
+    // Also holds the escrowed lamports for lamport streams
+    #[account(
+        init,
+        payer = sender,
+        space = Stream::DISCRIMINATOR.len() + Stream::INIT_SPACE,
+        seeds = [b"stream", id.to_le_bytes().as_ref()],
+        bump
+    )]
+    pub stream: Account<'info, Stream>,
+
+    #[account(
+        init,
+        payer = sender,
+        associated_token::mint = token_mint,
+        associated_token::authority = stream,
+        associated_token::token_program = token_program
+    )]
+    pub vault: Option<InterfaceAccount<'info, TokenAccount>>,
+}
+
+// Handle the create stream instruction by:
+// 1. Moving the tokens from the sender's ATA to the vault, or the lamports to the stream account
+// 2. Saving the details of the stream to the stream account
+pub fn create_stream(
+    context: Context<CreateStream>,
+    id: u64,
+    recipient: Pubkey,
+    amount: u64,
+    start_ts: i64,
+    end_ts: i64,
+) -> Result<()> {
+    require!(amount > 0, ErrorCode::InvalidAmount);
+
+    let now = Clock::get()?.unix_timestamp;
+    require!(
+        start_ts >= 0 && end_ts > start_ts && end_ts > now,
+        ErrorCode::InvalidStreamSchedule
+    );
+
+    let (token_mint, deposited_amount) = match (
+        &context.accounts.token_mint,
+        &context.accounts.sender_token_account,
+        &mut context.accounts.vault,
+        &context.accounts.token_program,
+    ) {
+        (Some(token_mint), Some(sender_token_account), Some(vault), Some(token_program)) => {
+            // Move the tokens from the sender's ATA to the vault
+            transfer_tokens(
+                sender_token_account,
+                vault,
+                &amount,
+                token_mint,
+                &context.accounts.sender.to_account_info(),
+                token_program,
+                None,
+            )
+            .map_err(|_| ErrorCode::InsufficientMakerBalance)?;
+
+            // Mints with a transfer fee deliver less than was sent, so the stream
+            // pays out whatever actually reached the vault
+            vault.reload()?;
+            require!(vault.amount > 0, ErrorCode::InvalidAmount);
+            (token_mint.key(), vault.amount)
+        }
+        (None, None, None, None) => {
+            // Move the lamports from the sender to the stream account
+            transfer_lamports(
+                &context.accounts.sender.to_account_info(),
+                &context.accounts.stream.to_account_info(),
+                amount,
+                &context.accounts.system_program,
+                None,
+            )
+            .map_err(|_| ErrorCode::LamportsTransferFailed)?;
+            (Pubkey::default(), amount)
+        }
+        _ => return Err(error!(ErrorCode::InvalidStreamAccounts)),
+    };
+
+    // Save the details of the stream to the stream account
+    context.accounts.stream.set_inner(Stream {
+        id,
+        sender: context.accounts.sender.key(),
+        recipient,
+        token_mint,
+        deposited_amount,
+        withdrawn_amount: 0,
+        start_ts,
+        end_ts,
+        checkpoint_amount: 0,
+        checkpoint_ts: start_ts,
+        bump: context.bumps.stream,
+    });
+    Ok(())
+}
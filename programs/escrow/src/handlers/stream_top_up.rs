@@ -0,0 +1,109 @@
+use super::shared::{transfer_lamports, transfer_tokens};
+use crate::{error::ErrorCode, state::Stream};
+use anchor_lang::prelude::*;
+use anchor_spl::token_interface::{Mint, TokenAccount, TokenInterface};
+
+#[derive(Accounts)]
+pub struct TopUpStream<'info> {
+    // Work with either the classic token program or
+    // the newer token extensions program
+    pub token_program: Option<Interface<'info, TokenInterface>>,
+
+    // Used to move lamports for lamport streams
+    pub system_program: Program<'info, System>,
+
+    #[account(mut)]
+    pub sender: Signer<'info>,
+
+    #[account(
+        address = stream.token_mint @ ErrorCode::InvalidStreamAccounts,
+        mint::token_program = token_program
+    )]
+    pub token_mint: Option<InterfaceAccount<'info, Mint>>,
+
+    #[account(
+        mut,
+        associated_token::mint = token_mint,
+        associated_token::authority = sender,
+        associated_token::token_program = token_program
+    )]
+    pub sender_token_account: Option<InterfaceAccount<'info, TokenAccount>>,
+
+    #[account(
+        mut,
+        has_one = sender,
+        seeds = [b"stream", stream.id.to_le_bytes().as_ref()],
+        bump = stream.bump
+    )]
+    pub stream: Account<'info, Stream>,
+
+    #[account(
+        mut,
+        associated_token::mint = token_mint,
+        associated_token::authority = stream,
+        associated_token::token_program = token_program
+    )]
+    pub vault: Option<InterfaceAccount<'info, TokenAccount>>,
+}
+
+// Handle the top up stream instruction by:
+// 1. Moving the extra tokens or lamports into the vault or stream account
+// 2. Checkpointing what has accrued so far, so the extra amount is only
+//    spread over what is left of the stream, rather than paid out for time already passed
+pub fn top_up_stream(context: Context<TopUpStream>, amount: u64) -> Result<()> {
+    require!(amount > 0, ErrorCode::InvalidAmount);
+
+    let now = Clock::get()?.unix_timestamp;
+    require!(now < context.accounts.stream.end_ts, ErrorCode::StreamEnded);
+
+    let received_amount = if context.accounts.stream.token_mint == Pubkey::default() {
+        transfer_lamports(
+            &context.accounts.sender.to_account_info(),
+            &context.accounts.stream.to_account_info(),
+            amount,
+            &context.accounts.system_program,
+            None,
+        )
+        .map_err(|_| ErrorCode::LamportsTransferFailed)?;
+        amount
+    } else {
+        let (Some(token_mint), Some(sender_token_account), Some(vault), Some(token_program)) = (
+            &context.accounts.token_mint,
+            &context.accounts.sender_token_account,
+            &mut context.accounts.vault,
+            &context.accounts.token_program,
+        ) else {
+            return Err(error!(ErrorCode::InvalidStreamAccounts));
+        };
+
+        transfer_tokens(
+            sender_token_account,
+            vault,
+            &amount,
+            token_mint,
+            &context.accounts.sender.to_account_info(),
+            token_program,
+            None,
+        )
+        .map_err(|_| ErrorCode::InsufficientMakerBalance)?;
+
+        // Mints with a transfer fee deliver less than was sent, so only count what arrived
+        let balance_before = vault.amount;
+        vault.reload()?;
+        vault
+            .amount
+            .checked_sub(balance_before)
+            .ok_or(ErrorCode::VaultShortfall)?
+    };
+
+    let stream = &mut context.accounts.stream;
+    if now > stream.checkpoint_ts {
+        stream.checkpoint_amount = stream.accrued_amount(now)?;
+        stream.checkpoint_ts = now;
+    }
+    stream.deposited_amount = stream
+        .deposited_amount
+        .checked_add(received_amount)
+        .ok_or(ErrorCode::InvalidAmount)?;
+    Ok(())
+}
@@ -0,0 +1,100 @@
+use super::shared::transfer_tokens;
+use crate::{error::ErrorCode, state::Stream};
+use anchor_lang::prelude::*;
+use anchor_spl::token_interface::{Mint, TokenAccount, TokenInterface};
+
+#[derive(Accounts)]
+pub struct WithdrawStream<'info> {
+    // Work with either the classic token program or
+    // the newer token extensions program
+    pub token_program: Option<Interface<'info, TokenInterface>>,
+
+    // Used to move lamports for lamport streams
+    pub system_program: Program<'info, System>,
+
+    #[account(mut)]
+    pub recipient: Signer<'info>,
+
+    #[account(
+        address = stream.token_mint @ ErrorCode::InvalidStreamAccounts,
+        mint::token_program = token_program
+    )]
+    pub token_mint: Option<InterfaceAccount<'info, Mint>>,
+
+    // Where the tokens go, chosen by the recipient
+    #[account(
+        mut,
+        token::mint = token_mint,
+        token::token_program = token_program
+    )]
+    pub recipient_token_account: Option<InterfaceAccount<'info, TokenAccount>>,
+
+    #[account(
+        mut,
+        has_one = recipient,
+        seeds = [b"stream", stream.id.to_le_bytes().as_ref()],
+        bump = stream.bump
+    )]
+    pub stream: Account<'info, Stream>,
+
+    #[account(
+        mut,
+        associated_token::mint = token_mint,
+        associated_token::authority = stream,
+        associated_token::token_program = token_program
+    )]
+    pub vault: Option<InterfaceAccount<'info, TokenAccount>>,
+}
+
+// Handle the withdraw stream instruction by sending everything that has accrued
+// to the recipient, and not been withdrawn yet, from the vault or stream account
+pub fn withdraw_stream(context: Context<WithdrawStream>) -> Result<()> {
+    let now = Clock::get()?.unix_timestamp;
+    let amount = context.accounts.stream.withdrawable_amount(now)?;
+    require!(amount > 0, ErrorCode::NothingToWithdraw);
+
+    let stream_account_seeds = &[
+        b"stream",
+        &context.accounts.stream.id.to_le_bytes()[..],
+        &[context.accounts.stream.bump],
+    ];
+    let signers_seeds = Some(&stream_account_seeds[..]);
+
+    if context.accounts.stream.token_mint == Pubkey::default() {
+        // The stream account is owned by this program, so it can be debited directly
+        context
+            .accounts
+            .stream
+            .sub_lamports(amount)
+            .map_err(|_| ErrorCode::LamportsTransferFailed)?;
+        context.accounts.recipient.add_lamports(amount)?;
+    } else {
+        let (Some(token_mint), Some(recipient_token_account), Some(vault), Some(token_program)) = (
+            &context.accounts.token_mint,
+            &context.accounts.recipient_token_account,
+            &context.accounts.vault,
+            &context.accounts.token_program,
+        ) else {
+            return Err(error!(ErrorCode::InvalidStreamAccounts));
+        };
+
+        transfer_tokens(
+            vault,
+            recipient_token_account,
+            &amount,
+            token_mint,
+            &context.accounts.stream.to_account_info(),
+            token_program,
+            signers_seeds,
+        )
+        .map_err(|_| ErrorCode::FailedVaultWithdrawal)?;
+    }
+
+    context.accounts.stream.withdrawn_amount = context
+        .accounts
+        .stream
+        .withdrawn_amount
+        .checked_add(amount)
+        .ok_or(ErrorCode::InvalidAmount)?;
+    Ok(())
+}
@@ -1,17 +1,10 @@
-use super::shared::{close_token_account, transfer_tokens};
+use super::shared::{close_token_account, freeze_token_account, transfer_tokens};
 use crate::{error::ErrorCode, state::Offer};
 use anchor_lang::prelude::*;
-use anchor_spl::{
-    associated_token::AssociatedToken,
-    token_interface::{Mint, TokenAccount, TokenInterface},
-};
+use anchor_spl::token_interface::{Mint, TokenAccount, TokenInterface};
 
 #[derive(Accounts)]
 pub struct TakeOffer<'info> {
-    // Used to manage associated token accounts
-    // ie where a wallet holds a specific type of token
-    pub associated_token_program: Program<'info, AssociatedToken>,
-
     // Work with either the classic token program or
     // the newer token extensions program
     pub token_program: Interface<'info, TokenInterface>,
@@ -22,21 +15,24 @@ pub struct TakeOffer<'info> {
     #[account(mut)]
     pub taker: Signer<'info>,
 
-    #[account(mut)]
     pub maker: SystemAccount<'info>,
 
+    // Whoever paid the rent for the offer and vault accounts gets it back
+    #[account(mut)]
+    pub rent_payer: SystemAccount<'info>,
+
     pub token_mint_a: InterfaceAccount<'info, Mint>,
 
     pub token_mint_b: InterfaceAccount<'info, Mint>,
 
+    // Where the offered token a goes. Any token a account the taker chooses,
+    // not necessarily their ATA - it could be a PDA owned by another program
     #[account(
-        init_if_needed,
-        payer = taker,
-        associated_token::mint = token_mint_a,
-        associated_token::authority = taker,
-        associated_token::token_program = token_program,
+        mut,
+        token::mint = token_mint_a,
+        token::token_program = token_program,
     )]
-    pub taker_token_account_a: InterfaceAccount<'info, TokenAccount>,
+    pub recipient: InterfaceAccount<'info, TokenAccount>,
 
     #[account(
         mut,
@@ -46,20 +42,38 @@ pub struct TakeOffer<'info> {
     )]
     pub taker_token_account_b: InterfaceAccount<'info, TokenAccount>,
 
+    // The token b account the maker chose when making the offer, or for offers
+    // with a receipt, any token b account owned by whoever holds the receipt
     #[account(
-        init_if_needed,
-        payer = taker,
-        associated_token::mint = token_mint_b,
+        mut,
+        token::mint = token_mint_b,
+        token::token_program = token_program,
+    )]
+    pub proceeds_account: InterfaceAccount<'info, TokenAccount>,
+
+    // Where any extra token a sent to the vault goes, chosen when making the offer.
+    // Only needed when someone has sent extra tokens to the vault
+    /// CHECK: must be the offer's surplus account, which the handler checks. It's unchecked
+    /// since it may have been closed since the offer was made
+    #[account(mut)]
+    pub surplus_account: Option<UncheckedAccount<'info>>,
+
+    // The maker's token a ATA, where extra tokens go instead if the offer's
+    // surplus account has been closed
+    #[account(
+        mut,
+        associated_token::mint = token_mint_a,
         associated_token::authority = maker,
         associated_token::token_program = token_program,
     )]
-    pub maker_token_account_b: InterfaceAccount<'info, TokenAccount>,
+    pub maker_token_account_a: Option<InterfaceAccount<'info, TokenAccount>>,
 
     #[account(
         mut,
-        close = maker,
+        close = rent_payer,
         has_one = maker,
         has_one = token_mint_b,
+        has_one = rent_payer,
         seeds = [b"offer", offer.id.to_le_bytes().as_ref()],
         bump = offer.bump
     )]
@@ -72,12 +86,57 @@ pub struct TakeOffer<'info> {
         associated_token::token_program = token_program,
     )]
     pub vault: InterfaceAccount<'info, TokenAccount>,
+
+    // The token account holding the receipt, only needed for offers with a receipt.
+    // It is frozen once the offer is taken, so the spent receipt can't be passed on.
+    // The holder can then close it with close_receipt to get its rent back
+    #[account(mut)]
+    pub receipt_account: Option<InterfaceAccount<'info, TokenAccount>>,
+
+    // The offer's receipt mint, only needed for offers with a receipt
+    pub receipt_mint: Option<InterfaceAccount<'info, Mint>>,
 }
 
 // Handle the take offer instruction by:
-// 1. Withdrawing the offered tokens from the vault to the taker and closing the vault
-// 2. Sending the wanted tokens from the taker to the maker
+// 1. Withdrawing the offered tokens from the vault to the taker's recipient account
+// 2. Returning any extra tokens sent to the vault to the surplus account, or the maker's ATA
+//    if the surplus account has been closed, and closing the vault
+// 3. Sending the wanted tokens from the taker to the maker's proceeds account
+// 4. Freezing the receipt token, if the offer has one
 pub fn take_offer(context: Context<TakeOffer>) -> Result<()> {
+    // Token b goes to whoever owns the offer: the maker's chosen proceeds account,
+    // or for offers with a receipt, an account owned by the current receipt holder
+    let offer = &context.accounts.offer;
+    if offer.receipt_mint == Pubkey::default() {
+        require_keys_eq!(
+            context.accounts.proceeds_account.key(),
+            offer.proceeds_account,
+            ErrorCode::InvalidProceedsAccount
+        );
+    } else {
+        let receipt_account = context
+            .accounts
+            .receipt_account
+            .as_ref()
+            .ok_or(ErrorCode::InvalidReceipt)?;
+        let receipt_mint = context
+            .accounts
+            .receipt_mint
+            .as_ref()
+            .ok_or(ErrorCode::InvalidReceipt)?;
+        require!(
+            receipt_mint.key() == offer.receipt_mint
+                && receipt_account.mint == offer.receipt_mint
+                && receipt_account.amount == 1,
+            ErrorCode::InvalidReceipt
+        );
+        require_keys_eq!(
+            context.accounts.proceeds_account.owner,
+            receipt_account.owner,
+            ErrorCode::InvalidProceedsAccount
+        );
+    }
+
     // Since the Offer account owns the Vault, we will say
     // there is one signer (the offer), with the seeds of the specific offer account
     // We can use these signer seeds to withdraw the token from the vault
@@ -88,11 +147,20 @@ pub fn take_offer(context: Context<TakeOffer>) -> Result<()> {
     ];
     let signers_seeds = Some(&offer_account_seeds[..]);
 
-    // Withdraw the offered tokens from the vault to the taker
+    // Anyone can send tokens to the vault, so work out how much is there
+    // beyond what the maker offered before we move anything
+    let surplus = context
+        .accounts
+        .vault
+        .amount
+        .checked_sub(context.accounts.offer.token_a_offered_amount)
+        .ok_or(ErrorCode::FailedVaultWithdrawal)?;
+
+    // Withdraw the offered tokens from the vault to the taker's recipient account
     transfer_tokens(
         &context.accounts.vault,
-        &context.accounts.taker_token_account_a,
-        &context.accounts.vault.amount,
+        &context.accounts.recipient,
+        &context.accounts.offer.token_a_offered_amount,
         &context.accounts.token_mint_a,
         &context.accounts.offer.to_account_info(),
         &context.accounts.token_program,
@@ -100,21 +168,59 @@ pub fn take_offer(context: Context<TakeOffer>) -> Result<()> {
     )
     .map_err(|_| ErrorCode::FailedVaultWithdrawal)?;
 
-    // Close the vault and return the rent to the maker
+    // Return any extra tokens so the vault is empty and can be closed
+    if surplus > 0 {
+        let surplus_account = context
+            .accounts
+            .surplus_account
+            .as_ref()
+            .ok_or(ErrorCode::InvalidSurplusAccount)?;
+        require_keys_eq!(
+            surplus_account.key(),
+            context.accounts.offer.surplus_account,
+            ErrorCode::InvalidSurplusAccount
+        );
+
+        // If the surplus account has been closed, the taker can't be stuck,
+        // so the extra tokens go to the maker's ATA instead
+        let surplus_account = if surplus_account.data_is_empty() {
+            context
+                .accounts
+                .maker_token_account_a
+                .as_ref()
+                .ok_or(ErrorCode::InvalidSurplusAccount)?
+                .to_account_info()
+        } else {
+            surplus_account.to_account_info()
+        };
+
+        transfer_tokens(
+            &context.accounts.vault,
+            &surplus_account,
+            &surplus,
+            &context.accounts.token_mint_a,
+            &context.accounts.offer.to_account_info(),
+            &context.accounts.token_program,
+            signers_seeds,
+        )
+        .map_err(|_| ErrorCode::FailedSurplusTransfer)?;
+    }
+
+    // Close the vault and return the rent to whoever paid for it
     close_token_account(
         &context.accounts.vault,
-        // Return the rent to the maker, since they made this account in the first place
-        &context.accounts.maker.to_account_info(),
+        // Return the rent to the rent payer, since they paid for this account in the first place
+        &context.accounts.rent_payer.to_account_info(),
         &context.accounts.offer.to_account_info(),
         &context.accounts.token_program,
         signers_seeds,
     )
     .map_err(|_| ErrorCode::FailedVaultClosure)?;
 
-    // Send the wanted tokens from the taker to the maker
+    // Send the wanted tokens from the taker to the maker's proceeds account
     transfer_tokens(
         &context.accounts.taker_token_account_b,
-        &context.accounts.maker_token_account_b,
+        &context.accounts.proceeds_account,
         &context.accounts.offer.token_b_wanted_amount,
         &context.accounts.token_mint_b,
         &context.accounts.taker.to_account_info(),
@@ -123,5 +229,20 @@ pub fn take_offer(context: Context<TakeOffer>) -> Result<()> {
     )
     .map_err(|_| ErrorCode::InsufficientTakerBalance)?;
 
+    // The offer is gone, so freeze the receipt rather than leave it looking redeemable
+    if let (true, Some(receipt_account), Some(receipt_mint)) = (
+        context.accounts.offer.receipt_mint != Pubkey::default(),
+        &context.accounts.receipt_account,
+        &context.accounts.receipt_mint,
+    ) {
+        freeze_token_account(
+            receipt_account,
+            receipt_mint,
+            &context.accounts.offer.to_account_info(),
+            &context.accounts.token_program,
+            signers_seeds,
+        )?;
+    }
+
     Ok(())
 }
@@ -1,8 +1,10 @@
 use anchor_lang::prelude::*;
 use handlers::*;
+use state::{GameOptions, GameResult};
 
 pub mod constants;
 pub mod error;
+pub mod events;
 pub mod handlers;
 pub mod state;
 
@@ -29,29 +31,363 @@ pub mod escrow {
         handlers::refund_offer::refund_offer(context)
     }
 
+    pub fn close_receipt(context: Context<CloseReceipt>, offer_id: u64) -> Result<()> {
+        handlers::close_receipt::close_receipt(context, offer_id)
+    }
+
     // Native SOL duel escrow instructions
     pub fn create_game(
         context: Context<CreateGame>,
         id: u64,
-        player_a: Pubkey,
-        player_b: Pubkey,
-        stake_lamports: u64,
+        players: Vec<Pubkey>,
+        stake_amount: u64,
         expiry_ts: i64,
+        rake_bps: u16,
+        options: GameOptions,
     ) -> Result<()> {
-        handlers::game_create::create_game(context, id, player_a, player_b, stake_lamports, expiry_ts)
+        handlers::game_create::create_game(
+            context,
+            id,
+            players,
+            stake_amount,
+            expiry_ts,
+            rake_bps,
+            options,
+        )
     }
 
     pub fn deposit(context: Context<Deposit>, amount: u64) -> Result<()> {
         handlers::game_deposit::deposit(context, amount)
     }
 
-    pub fn finalize_game(context: Context<FinalizeGame>, winner: u8) -> Result<()> {
-        handlers::game_finalize::finalize_game(context, winner)
+    pub fn finalize_game<'info>(
+        context: Context<'_, '_, 'info, 'info, FinalizeGame<'info>>,
+        result: GameResult,
+    ) -> Result<()> {
+        handlers::game_finalize::finalize_game(context, result)
     }
 
-    pub fn cancel_game(context: Context<CancelGame>) -> Result<()> {
+    pub fn cancel_game<'info>(
+        context: Context<'_, '_, 'info, 'info, CancelGame<'info>>,
+    ) -> Result<()> {
         handlers::game_cancel::cancel_game(context)
     }
+
+    pub fn finalize_with_attestation<'info>(
+        context: Context<'_, '_, 'info, 'info, FinalizeWithAttestation<'info>>,
+        result: GameResult,
+    ) -> Result<()> {
+        handlers::game_finalize_with_attestation::finalize_with_attestation(context, result)
+    }
+
+    pub fn propose_result(context: Context<ProposeResult>, result: GameResult) -> Result<()> {
+        handlers::game_propose_result::propose_result(context, result)
+    }
+
+    pub fn challenge_result(context: Context<ChallengeResult>) -> Result<()> {
+        handlers::game_challenge_result::challenge_result(context)
+    }
+
+    pub fn resolve_challenge<'info>(
+        context: Context<'_, '_, 'info, 'info, ResolveChallenge<'info>>,
+        result: GameResult,
+    ) -> Result<()> {
+        handlers::game_resolve_challenge::resolve_challenge(context, result)
+    }
+
+    pub fn vote_result(context: Context<VoteResult>, result: GameResult) -> Result<()> {
+        handlers::game_vote_result::vote_result(context, result)
+    }
+
+    pub fn close_vote_record(context: Context<CloseVoteRecord>) -> Result<()> {
+        handlers::game_close_vote_record::close_vote_record(context)
+    }
+
+    pub fn settle_game<'info>(
+        context: Context<'_, '_, 'info, 'info, SettleGame<'info>>,
+    ) -> Result<()> {
+        handlers::game_settle::settle_game(context)
+    }
+
+    pub fn record_round<'info>(
+        context: Context<'_, '_, 'info, 'info, RecordRound<'info>>,
+        winner: u8,
+    ) -> Result<()> {
+        handlers::game_record_round::record_round(context, winner)
+    }
+
+    pub fn place_side_bet(context: Context<PlaceSideBet>, slot: u8, amount: u64) -> Result<()> {
+        handlers::game_place_side_bet::place_side_bet(context, slot, amount)
+    }
+
+    pub fn claim_side_bet(context: Context<ClaimSideBet>) -> Result<()> {
+        handlers::game_claim_side_bet::claim_side_bet(context)
+    }
+
+    pub fn close_game<'info>(
+        context: Context<'_, '_, 'info, 'info, CloseGame<'info>>,
+    ) -> Result<()> {
+        handlers::game_close::close_game(context)
+    }
+
+    // Matchmaking queue instructions, which start duels between queued players
+    pub fn create_queue(
+        context: Context<CreateQueue>,
+        game_mode: u8,
+        stake_lamports: u64,
+        rake_bps: u16,
+        game_duration: i64,
+    ) -> Result<()> {
+        handlers::queue_create::create_queue(
+            context,
+            game_mode,
+            stake_lamports,
+            rake_bps,
+            game_duration,
+        )
+    }
+
+    pub fn enqueue(context: Context<Enqueue>) -> Result<()> {
+        handlers::queue_enqueue::enqueue(context)
+    }
+
+    pub fn match_players(context: Context<MatchPlayers>, id: u64) -> Result<()> {
+        handlers::queue_match_players::match_players(context, id)
+    }
+
+    pub fn dequeue(context: Context<Dequeue>) -> Result<()> {
+        handlers::queue_dequeue::dequeue(context)
+    }
+
+    pub fn close_queue(context: Context<CloseQueue>) -> Result<()> {
+        handlers::queue_close::close_queue(context)
+    }
+
+    // Peer-to-peer collateralized loan instructions
+    pub fn request_loan(
+        context: Context<RequestLoan>,
+        id: u64,
+        collateral_amount: u64,
+        principal_amount: u64,
+        interest_amount: u64,
+        duration: i64,
+    ) -> Result<()> {
+        handlers::loan_request::request_loan(
+            context,
+            id,
+            collateral_amount,
+            principal_amount,
+            interest_amount,
+            duration,
+        )
+    }
+
+    pub fn cancel_loan(context: Context<CancelLoan>) -> Result<()> {
+        handlers::loan_cancel::cancel_loan(context)
+    }
+
+    pub fn fund_loan(context: Context<FundLoan>) -> Result<()> {
+        handlers::loan_fund::fund_loan(context)
+    }
+
+    pub fn repay_loan(context: Context<RepayLoan>) -> Result<()> {
+        handlers::loan_repay::repay_loan(context)
+    }
+
+    pub fn claim_collateral(context: Context<ClaimCollateral>) -> Result<()> {
+        handlers::loan_claim_collateral::claim_collateral(context)
+    }
+
+    // Covered call option instructions
+    pub fn write_option(
+        context: Context<WriteOption>,
+        id: u64,
+        underlying_amount: u64,
+        strike_amount: u64,
+        premium_amount: u64,
+        expiry_ts: i64,
+    ) -> Result<()> {
+        handlers::option_write::write_option(
+            context,
+            id,
+            underlying_amount,
+            strike_amount,
+            premium_amount,
+            expiry_ts,
+        )
+    }
+
+    pub fn buy_option(context: Context<BuyOption>) -> Result<()> {
+        handlers::option_buy::buy_option(context)
+    }
+
+    pub fn exercise_option(context: Context<ExerciseOption>) -> Result<()> {
+        handlers::option_exercise::exercise_option(context)
+    }
+
+    pub fn reclaim_option(context: Context<ReclaimOption>) -> Result<()> {
+        handlers::option_reclaim::reclaim_option(context)
+    }
+
+    // Hash time-locked contract instructions, for atomic swaps with other chains
+    pub fn create_htlc(
+        context: Context<CreateHtlc>,
+        id: u64,
+        counterparty: Pubkey,
+        amount: u64,
+        hashlock: [u8; 32],
+        timelock_ts: i64,
+    ) -> Result<()> {
+        handlers::htlc_create::create_htlc(context, id, counterparty, amount, hashlock, timelock_ts)
+    }
+
+    pub fn claim_htlc(context: Context<ClaimHtlc>, preimage: [u8; 32]) -> Result<()> {
+        handlers::htlc_claim::claim_htlc(context, preimage)
+    }
+
+    pub fn refund_htlc(context: Context<RefundHtlc>) -> Result<()> {
+        handlers::htlc_refund::refund_htlc(context)
+    }
+
+    pub fn close_htlc(context: Context<CloseHtlc>) -> Result<()> {
+        handlers::htlc_close::close_htlc(context)
+    }
+
+    // Streaming payment instructions, in tokens or lamports
+    pub fn create_stream(
+        context: Context<CreateStream>,
+        id: u64,
+        recipient: Pubkey,
+        amount: u64,
+        start_ts: i64,
+        end_ts: i64,
+    ) -> Result<()> {
+        handlers::stream_create::create_stream(context, id, recipient, amount, start_ts, end_ts)
+    }
+
+    pub fn withdraw_stream(context: Context<WithdrawStream>) -> Result<()> {
+        handlers::stream_withdraw::withdraw_stream(context)
+    }
+
+    pub fn cancel_stream(context: Context<CancelStream>) -> Result<()> {
+        handlers::stream_cancel::cancel_stream(context)
+    }
+
+    pub fn top_up_stream(context: Context<TopUpStream>, amount: u64) -> Result<()> {
+        handlers::stream_top_up::top_up_stream(context, amount)
+    }
+
+    // Crowdfunding campaign instructions
+    pub fn create_campaign(
+        context: Context<CreateCampaign>,
+        id: u64,
+        target_amount: u64,
+        deadline_ts: i64,
+    ) -> Result<()> {
+        handlers::campaign_create::create_campaign(context, id, target_amount, deadline_ts)
+    }
+
+    pub fn contribute(context: Context<Contribute>, amount: u64) -> Result<()> {
+        handlers::campaign_contribute::contribute(context, amount)
+    }
+
+    pub fn withdraw_campaign_funds(context: Context<WithdrawCampaignFunds>) -> Result<()> {
+        handlers::campaign_withdraw::withdraw_campaign_funds(context)
+    }
+
+    pub fn refund_contribution(context: Context<RefundContribution>) -> Result<()> {
+        handlers::campaign_refund::refund_contribution(context)
+    }
+
+    pub fn close_contribution(context: Context<CloseContribution>) -> Result<()> {
+        handlers::campaign_close_contribution::close_contribution(context)
+    }
+
+    pub fn close_campaign(context: Context<CloseCampaign>) -> Result<()> {
+        handlers::campaign_close::close_campaign(context)
+    }
+
+    // Bounty instructions
+    pub fn create_bounty(
+        context: Context<CreateBounty>,
+        id: u64,
+        judge: Pubkey,
+        reward_amount: u64,
+        deadline_ts: i64,
+        award_deadline_ts: i64,
+    ) -> Result<()> {
+        handlers::bounty_create::create_bounty(
+            context,
+            id,
+            judge,
+            reward_amount,
+            deadline_ts,
+            award_deadline_ts,
+        )
+    }
+
+    pub fn submit_claim(context: Context<SubmitClaim>, submission: String) -> Result<()> {
+        handlers::bounty_submit_claim::submit_claim(context, submission)
+    }
+
+    pub fn award_claim(context: Context<AwardClaim>, amount: u64) -> Result<()> {
+        handlers::bounty_award_claim::award_claim(context, amount)
+    }
+
+    pub fn close_bounty(context: Context<CloseBounty>) -> Result<()> {
+        handlers::bounty_close::close_bounty(context)
+    }
+
+    pub fn close_claim(context: Context<CloseClaim>) -> Result<()> {
+        handlers::bounty_close_claim::close_claim(context)
+    }
+
+    // Marketplace escrow instructions
+    pub fn create_listing(
+        context: Context<CreateListing>,
+        id: u64,
+        price: u64,
+        arbiter: Pubkey,
+        confirmation_window_secs: i64,
+        response_window_secs: i64,
+    ) -> Result<()> {
+        handlers::listing_create::create_listing(
+            context,
+            id,
+            price,
+            arbiter,
+            confirmation_window_secs,
+            response_window_secs,
+        )
+    }
+
+    pub fn purchase_listing(context: Context<PurchaseListing>) -> Result<()> {
+        handlers::listing_purchase::purchase_listing(context)
+    }
+
+    pub fn mark_shipped(context: Context<MarkShipped>) -> Result<()> {
+        handlers::listing_mark_shipped::mark_shipped(context)
+    }
+
+    pub fn confirm_receipt(context: Context<ConfirmReceipt>) -> Result<()> {
+        handlers::listing_confirm_receipt::confirm_receipt(context)
+    }
+
+    pub fn open_dispute(context: Context<OpenDispute>) -> Result<()> {
+        handlers::listing_open_dispute::open_dispute(context)
+    }
+
+    pub fn resolve_dispute(context: Context<ResolveDispute>, buyer_amount: u64) -> Result<()> {
+        handlers::listing_resolve_dispute::resolve_dispute(context, buyer_amount)
+    }
+
+    pub fn cancel_listing(context: Context<CancelListing>) -> Result<()> {
+        handlers::listing_cancel::cancel_listing(context)
+    }
+
+    pub fn reclaim_payment(context: Context<ReclaimPayment>) -> Result<()> {
+        handlers::listing_reclaim_payment::reclaim_payment(context)
+    }
 }
 
 #[cfg(test)]
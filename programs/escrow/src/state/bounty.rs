@@ -0,0 +1,49 @@
+use crate::constants::MAX_SUBMISSION_LENGTH;
+use anchor_lang::prelude::*;
+
+// Stores details of a bounty, where the sponsor escrows a token reward
+// that the sponsor or judge can award to one or more claims
+#[account]
+#[derive(InitSpace)]
+pub struct Bounty {
+    // Identifier of the bounty
+    pub id: u64,
+    // Who escrowed the reward
+    pub sponsor: Pubkey,
+    // Who else can award claims, besides the sponsor
+    pub judge: Pubkey,
+    // The token mint of the reward held in the vault
+    pub token_mint: Pubkey,
+    // The amount escrowed as the reward
+    pub reward_amount: u64,
+    // How much of the reward has been awarded so far
+    pub awarded_amount: u64,
+    // Unix timestamp after which nobody can submit claims
+    pub deadline_ts: i64,
+    // Unix timestamp after which claims can no longer be awarded, and the sponsor can take
+    // back whatever hasn't been. Later than deadline_ts, so claims submitted near the
+    // deadline can still be judged
+    pub award_deadline_ts: i64,
+    // Handed out by the nonce counter, so claims left over from an earlier
+    // bounty with the same id can't be awarded from this one
+    pub nonce: u64,
+    // Used to calculate the address for this account, we save it as a performance optimization
+    pub bump: u8,
+}
+
+// A claimant's submission for a bounty
+#[account]
+#[derive(InitSpace)]
+pub struct BountyClaim {
+    // The bounty being claimed
+    pub bounty: Pubkey,
+    // Who submitted the claim, and gets paid if it is awarded
+    pub claimant: Pubkey,
+    // A hash of the work, or a URI pointing to it
+    #[max_len(MAX_SUBMISSION_LENGTH)]
+    pub submission: String,
+    // The nonce of the bounty this claim was for
+    pub bounty_nonce: u64,
+    // Used to calculate the address for this account, we save it as a performance optimization
+    pub bump: u8,
+}
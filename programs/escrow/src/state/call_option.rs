@@ -0,0 +1,35 @@
+use anchor_lang::prelude::*;
+
+// Stores details of a covered call option. The writer escrows token a, and whoever holds
+// the option token can buy it for the strike amount of token b until the expiry
+#[account]
+#[derive(InitSpace)]
+pub struct CallOption {
+    // Identifier of the option
+    pub id: u64,
+    // Who wrote the option and escrowed the underlying tokens
+    pub writer: Pubkey,
+    // The token mint of the underlying token held in the vault
+    pub underlying_mint: Pubkey,
+    // The token mint the premium and strike are paid in
+    pub quote_mint: Pubkey,
+    // The amount of the underlying token in the vault
+    pub underlying_amount: u64,
+    // The amount of quote token the holder pays to exercise
+    pub strike_amount: u64,
+    // The amount of quote token the buyer pays up front for the option
+    pub premium_amount: u64,
+    // Unix timestamp after which the option can no longer be exercised,
+    // and the writer can reclaim the underlying tokens
+    pub expiry_ts: i64,
+    // Where the premium and strike are sent, chosen by the writer
+    pub proceeds_account: Pubkey,
+    // Mint of the 1-of-1 token representing the option
+    pub option_mint: Pubkey,
+    // Nonce in the option mint's address
+    pub nonce: u64,
+    // True once someone has bought the option
+    pub sold: bool,
+    // Used to calculate the address for this account, we save it as a performance optimization
+    pub bump: u8,
+}
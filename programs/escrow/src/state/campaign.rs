@@ -0,0 +1,42 @@
+use anchor_lang::prelude::*;
+
+// Stores details of a crowdfunding campaign, where contributors deposit tokens
+// that the creator can withdraw if the target is reached by the deadline
+#[account]
+#[derive(InitSpace)]
+pub struct Campaign {
+    // Identifier of the campaign
+    pub id: u64,
+    // Who is raising the funds
+    pub creator: Pubkey,
+    // The token mint of the tokens being raised
+    pub token_mint: Pubkey,
+    // How much the campaign needs to raise
+    pub target_amount: u64,
+    // How much has been contributed so far, less anything refunded
+    pub raised_amount: u64,
+    // Unix timestamp after which nobody can contribute, and contributors
+    // can get refunds if the target wasn't reached
+    pub deadline_ts: i64,
+    // Handed out by the nonce counter, so receipts left over from an earlier
+    // campaign with the same id can't be used against this one
+    pub nonce: u64,
+    // Used to calculate the address for this account, we save it as a performance optimization
+    pub bump: u8,
+}
+
+// Receipt for everything one contributor has put into a campaign
+#[account]
+#[derive(InitSpace)]
+pub struct Contribution {
+    // The campaign contributed to
+    pub campaign: Pubkey,
+    // Who contributed
+    pub contributor: Pubkey,
+    // The total amount contributed
+    pub amount: u64,
+    // The nonce of the campaign this receipt was for
+    pub campaign_nonce: u64,
+    // Used to calculate the address for this account, we save it as a performance optimization
+    pub bump: u8,
+}
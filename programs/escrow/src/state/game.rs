@@ -1,24 +1,238 @@
 use anchor_lang::prelude::*;
 
+use solana_sha256_hasher::hash;
+
+use crate::constants::{DRAW, MAX_PLAYERS, MAX_REFEREES, MAX_ROUNDS};
+use crate::error::ErrorCode;
+
+// One player's seat in a game
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, InitSpace)]
+pub struct PlayerSlot {
+    // The player's wallet, or the default pubkey for an open seat nobody has taken yet
+    pub player: Pubkey,
+    // What the seat stakes, in lamports, or in the stake mint's smallest unit for token games.
+    // For token games this becomes what the vault actually received once the player deposits
+    pub stake: u64,
+    // True once the player deposits
+    pub deposited: bool,
+    // Unix timestamp the player deposited at, 0 until they do
+    pub joined_ts: i64,
+}
+
+impl PlayerSlot {
+    // Open seats are taken by the first players to deposit who don't already have one
+    pub fn is_open(&self) -> bool {
+        self.player == Pubkey::default()
+    }
+}
+
+// The outcome of a game, as decided by the referee
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, InitSpace, PartialEq)]
+pub struct GameResult {
+    // Player slots in finishing order, starting with first place. Slots are numbered from 1,
+    // in the order the players were given to create_game. Empty for a draw
+    #[max_len(MAX_PLAYERS)]
+    pub ranking: Vec<u8>,
+    // Share of the pot for each place in the ranking, in basis points adding up to 10_000.
+    // For example [6_000, 3_000, 1_000] pays 60/30/10 to the top three
+    #[max_len(MAX_PLAYERS)]
+    pub payout_bps: Vec<u16>,
+}
+
+// Settings for games settled optimistically: the authority proposes a result, which either
+// player can challenge during a window by posting a bond, handing the decision to the arbiter
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, InitSpace)]
+pub struct ChallengeSettings {
+    // Decides the result of a challenged game
+    pub arbiter: Pubkey,
+    // How long players have to challenge a proposed result, in seconds
+    pub challenge_period: i64,
+    // Lamports a player must post to challenge. Returned if the arbiter overturns the
+    // proposed result, otherwise kept by the authority
+    pub challenge_bond: u64,
+}
+
+// A set of referees, any `threshold` of whom must agree on a result before it's paid out,
+// so no single compromised referee can decide where the pot goes
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, InitSpace)]
+pub struct RefereeQuorum {
+    #[max_len(MAX_REFEREES)]
+    pub referees: Vec<Pubkey>,
+    pub threshold: u8,
+}
+
+// What a game's attester signs off-chain to attest to its result, so anyone can submit it
+#[derive(AnchorSerialize, AnchorDeserialize)]
+pub struct ResultAttestation {
+    // Always RESULT_ATTESTATION_TAG
+    pub tag: [u8; 25],
+    // This program's id, so an attestation can't be replayed against another deployment
+    pub program_id: Pubkey,
+    pub game_id: u64,
+    // Must match the game's nonce
+    pub nonce: u64,
+    pub result: GameResult,
+}
+
+// Optional settings for a new game, leave them as None for a game finalized by its authority alone
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Default)]
+pub struct GameOptions {
+    // When players must have deposited by, defaulting to the expiry
+    pub deposit_deadline_ts: Option<i64>,
+    // Each slot's stake, in slot order, so a stronger player can lay odds against
+    // a weaker one. Every slot stakes stake_amount if this is left out
+    pub stakes: Option<Vec<u64>>,
+    pub challenge_settings: Option<ChallengeSettings>,
+    pub referee_quorum: Option<RefereeQuorum>,
+    // Plays the game as a series, paying the pot to the first player to win this many rounds
+    pub wins_required: Option<u8>,
+    // The key that signs result attestations, kept off-chain. Games without one
+    // can't be finalized with an attestation
+    pub attester: Option<Pubkey>,
+}
+
+// Once paid out, the game account is left open as a record of the outcome in `winner`,
+// until the authority closes it with close_game. Cancelled games are closed straight away
 #[account]
 #[derive(InitSpace)]
 pub struct Game {
     // Identifier for the game
     pub id: u64,
-    // Both players' wallets
-    pub player_a: Pubkey,
-    pub player_b: Pubkey,
+    // Every player's seat, in slot order. A duel has two, player A and player B
+    #[max_len(MAX_PLAYERS)]
+    pub players: Vec<PlayerSlot>,
     // Authority that can finalize/cancel (could be your backend/referee)
     pub authority: Pubkey,
-    // Stake in lamports per player
-    pub stake_lamports: u64,
-    // True once each player deposits
-    pub a_deposited: bool,
-    pub b_deposited: bool,
-    // 0 = none, 1 = A wins, 2 = B wins
+    // The mint of the tokens staked, held in the vault, or the default pubkey
+    // if the game is staked in lamports held in the game account itself
+    pub stake_mint: Pubkey,
+    // 0 while the game is being played, then the slot of the player who came first, or DRAW
     pub winner: u8,
+    // Share of the pot paid to the treasury when the game is finalized, in basis points.
+    // Stored on the game so players can see it before depositing
+    pub rake_bps: u16,
+    // Where the rake goes
+    pub treasury: Pubkey,
     // Unix timestamp after which timeout cancel can be executed
     pub expiry_ts: i64,
+    // Unix timestamp after which no more deposits are accepted. If a player hasn't deposited by
+    // then, the game can be cancelled straight away, rather than waiting for the expiry
+    pub deposit_deadline_ts: i64,
+    // Handed out by the nonce counter when the game is created. Included in result attestations,
+    // so an attestation can't be replayed against a later game that reuses this game's id
+    pub nonce: u64,
+    // Set for games settled optimistically, rather than finalized directly by the authority
+    pub challenge_settings: Option<ChallengeSettings>,
+    // Set for games whose results must be agreed by a quorum of referees
+    pub referee_quorum: Option<RefereeQuorum>,
+    // The result proposed by the authority, waiting to be settled
+    pub proposed_result: Option<GameResult>,
+    // Unix timestamp the proposed result can be challenged until
+    pub challenge_deadline_ts: i64,
+    // The player who challenged the proposed result, or the default pubkey if nobody has
+    pub challenger: Pubkey,
+    // Rounds a player must win to take the pot, for games played as a series, otherwise 0
+    pub wins_required: u8,
+    // The slot of each round's winner so far, in the order the rounds were played
+    #[max_len(MAX_ROUNDS)]
+    pub round_winners: Vec<u8>,
+    // True once a spectator bets on the game, so its side pool must be settled along with it
+    pub has_side_pool: bool,
+    // True for games matched from a queue, whose players paid the game's rent,
+    // so it goes back to them when the game is closed
+    pub matched_from_queue: bool,
+    // Signs result attestations, or the default pubkey if the game can't be finalized with one
+    pub attester: Pubkey,
     // Bump for PDA
     pub bump: u8,
 }
+
+impl GameResult {
+    // Identifies a result, so votes for it can be found
+    pub fn hash(&self) -> [u8; 32] {
+        let mut data = Vec::new();
+        self.serialize(&mut data).unwrap();
+        hash(&data).to_bytes()
+    }
+}
+
+impl Game {
+    // Space for a game with `player_count` players. The account is sized to fit
+    // its players, so a duel doesn't pay rent for MAX_PLAYERS seats. Too many players
+    // gets room for MAX_PLAYERS, since the account is sized before the handler rejects them
+    pub fn space(player_count: usize) -> usize {
+        Game::DISCRIMINATOR.len() + Game::INIT_SPACE
+            - MAX_PLAYERS.saturating_sub(player_count) * PlayerSlot::INIT_SPACE
+    }
+
+    pub fn all_deposited(&self) -> bool {
+        self.players.iter().all(|slot| slot.deposited)
+    }
+
+    // Every player's stake added up, which is what the game holds once they've all deposited
+    pub fn total_staked(&self) -> u64 {
+        self.players.iter().map(|slot| slot.stake).sum()
+    }
+
+    // How many rounds the player in `slot` has won so far in a series
+    pub fn rounds_won(&self, slot: u8) -> usize {
+        self.round_winners.iter().filter(|winner| **winner == slot).count()
+    }
+
+    // True once the game has been paid out, after which it can only be closed
+    pub fn is_over(&self) -> bool {
+        self.winner != 0
+    }
+
+    // The value to record in `winner` for a result
+    pub fn winner_for(result: &GameResult) -> u8 {
+        result.ranking.first().copied().unwrap_or(DRAW)
+    }
+
+    // Works out how much each player slot is paid for a result, and the rake for the treasury
+    pub fn payouts(&self, result: &GameResult) -> Result<(Vec<u64>, u64)> {
+        let player_count = self.players.len();
+
+        // The rake comes out of each player's stake, so on a draw every player pays it
+        // in proportion to what they staked
+        let stakes_after_rake: Vec<u64> = self
+            .players
+            .iter()
+            .map(|slot| slot.stake - (slot.stake as u128 * self.rake_bps as u128 / 10_000) as u64)
+            .collect();
+        let pot: u64 = stakes_after_rake.iter().sum();
+        let rake = self.total_staked() - pot;
+
+        // A draw refunds every player's stake, less the rake
+        if result.ranking.is_empty() {
+            return Ok((stakes_after_rake, rake));
+        }
+
+        require!(
+            result.ranking.len() == result.payout_bps.len()
+                && result.payout_bps.iter().map(|bps| *bps as u32).sum::<u32>() == 10_000,
+            ErrorCode::InvalidResult
+        );
+
+        let mut payouts = vec![0; player_count];
+        let mut ranked = vec![false; player_count];
+        let mut paid = 0;
+        for (slot, bps) in result.ranking.iter().zip(&result.payout_bps) {
+            let index = (*slot as usize).wrapping_sub(1);
+            require!(
+                index < player_count && !ranked[index],
+                ErrorCode::InvalidResult
+            );
+            ranked[index] = true;
+
+            let amount = (pot as u128 * *bps as u128 / 10_000) as u64;
+            payouts[index] = amount;
+            paid += amount;
+        }
+
+        // Anything left over from rounding goes to first place
+        payouts[result.ranking[0] as usize - 1] += pot - paid;
+
+        Ok((payouts, rake))
+    }
+}
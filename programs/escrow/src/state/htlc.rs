@@ -0,0 +1,31 @@
+use anchor_lang::prelude::*;
+
+// Stores details of a hash time-locked contract. The maker escrows tokens that the
+// counterparty can claim by revealing the preimage of the hashlock before the timelock,
+// otherwise the maker can refund them after it
+#[account]
+#[derive(InitSpace)]
+pub struct Htlc {
+    // Identifier of the HTLC
+    pub id: u64,
+    // Who escrowed the tokens
+    pub maker: Pubkey,
+    // The only wallet that can claim the tokens
+    pub counterparty: Pubkey,
+    // The token mint of the tokens held in the vault
+    pub token_mint: Pubkey,
+    // The amount of tokens in the vault
+    pub amount: u64,
+    // sha256 of the secret preimage
+    pub hashlock: [u8; 32],
+    // Unix timestamp after which the counterparty can no longer claim,
+    // and the maker can refund instead
+    pub timelock_ts: i64,
+    // The revealed preimage, stored so the other chain's leg of the swap can use it.
+    // All zeros until the HTLC is claimed
+    pub preimage: [u8; 32],
+    // True once the counterparty has claimed
+    pub claimed: bool,
+    // Used to calculate the address for this account, we save it as a performance optimization
+    pub bump: u8,
+}
@@ -0,0 +1,64 @@
+use crate::error::ErrorCode;
+use anchor_lang::prelude::*;
+
+// Where a listing is in the sale
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq, InitSpace)]
+pub enum ListingStatus {
+    // Waiting for a buyer
+    Listed,
+    // The buyer's payment is in the vault, waiting for the seller to ship
+    Paid,
+    // Shipped, waiting for the buyer to confirm receipt
+    Shipped,
+    // Waiting for the arbiter to resolve the buyer's dispute
+    Disputed,
+}
+
+// Stores details of a marketplace listing, where the buyer's payment is held
+// in a vault until they confirm the item arrived, or an arbiter resolves a dispute
+#[account]
+#[derive(InitSpace)]
+pub struct Listing {
+    // Identifier of the listing
+    pub id: u64,
+    // Who is selling the item
+    pub seller: Pubkey,
+    // Who bought the item, or the default pubkey until someone does
+    pub buyer: Pubkey,
+    // Who resolves disputes
+    pub arbiter: Pubkey,
+    // The token mint of the payment
+    pub token_mint: Pubkey,
+    // The price of the item
+    pub price: u64,
+    // How long after shipping the buyer has to confirm receipt or open a dispute,
+    // before anyone can release the payment to the seller
+    pub confirmation_window_secs: i64,
+    // How long the seller has to ship after the item is paid for, and the arbiter
+    // has to resolve a dispute after it is opened, before the buyer can take back their payment
+    pub response_window_secs: i64,
+    pub status: ListingStatus,
+    // Unix timestamp the seller marked the item shipped, or 0 until then
+    pub shipped_ts: i64,
+    // Unix timestamp after which the buyer can take back their payment, while the listing
+    // is paid for or disputed, or 0 until then
+    pub response_deadline_ts: i64,
+    // Used to calculate the address for this account, we save it as a performance optimization
+    pub bump: u8,
+}
+
+impl Listing {
+    // When the buyer can no longer dispute, and anyone can release the payment to the seller
+    pub fn confirmation_deadline_ts(&self) -> Result<i64> {
+        self.shipped_ts
+            .checked_add(self.confirmation_window_secs)
+            .ok_or(error!(ErrorCode::InvalidAmount))
+    }
+
+    // When the buyer can take back their payment if the seller or arbiter hasn't acted,
+    // counting from `now`
+    pub fn response_deadline_from(&self, now: i64) -> Result<i64> {
+        now.checked_add(self.response_window_secs)
+            .ok_or(error!(ErrorCode::InvalidAmount))
+    }
+}
@@ -0,0 +1,29 @@
+use anchor_lang::prelude::*;
+
+// Stores details of a peer-to-peer loan, where the borrower escrows token a as collateral
+// and borrows token b from a lender
+#[account]
+#[derive(InitSpace)]
+pub struct Loan {
+    // Identifier of the loan
+    pub id: u64,
+    // Who asked for the loan
+    pub borrower: Pubkey,
+    // Who funded the loan, or the default pubkey until someone does
+    pub lender: Pubkey,
+    // The token mint of the collateral held in the vault
+    pub collateral_mint: Pubkey,
+    // The token mint of the principal being borrowed
+    pub principal_mint: Pubkey,
+    // The amount being borrowed
+    pub principal_amount: u64,
+    // The amount the borrower pays the lender on top of the principal
+    pub interest_amount: u64,
+    // How many seconds the borrower has to repay, counted from when the loan is funded
+    pub duration: i64,
+    // Unix timestamp after which the loan can no longer be repaid, and the lender
+    // can claim the collateral instead. Zero until the loan is funded
+    pub due_ts: i64,
+    // Used to calculate the address for this account, we save it as a performance optimization
+    pub bump: u8,
+}
@@ -0,0 +1,37 @@
+use anchor_lang::prelude::*;
+
+use crate::constants::MAX_QUEUE_LENGTH;
+
+// A player waiting in a queue, with their stake and share of the game's rent already escrowed
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, InitSpace)]
+pub struct QueueEntry {
+    pub player: Pubkey,
+    // Unix timestamp the player joined the queue at
+    pub enqueued_ts: i64,
+    // The player's half of the rent for the game they'll be matched into, in lamports
+    pub rent_share: u64,
+}
+
+// Players waiting to be matched into duels for one game mode and stake.
+// Each authority has one queue per tier, and referees the games it creates
+#[account]
+#[derive(InitSpace)]
+pub struct MatchQueue {
+    // Becomes the authority of every game matched from this queue
+    pub authority: Pubkey,
+    // Identifies the kind of game being played, as the authority defines it
+    pub game_mode: u8,
+    // Stake per player, in lamports
+    pub stake_lamports: u64,
+    // Rake taken by games matched from this queue, in basis points
+    pub rake_bps: u16,
+    // Where the rake goes
+    pub treasury: Pubkey,
+    // How long matched games have to finish before they can be cancelled, in seconds
+    pub game_duration: i64,
+    // Players waiting, oldest first
+    #[max_len(MAX_QUEUE_LENGTH)]
+    pub entries: Vec<QueueEntry>,
+    // Used to calculate the address for this account, we save it as a performance optimization
+    pub bump: u8,
+}
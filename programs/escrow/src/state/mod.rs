@@ -4,3 +4,39 @@ pub use offer::*;
 
 pub mod game;
 pub use game::*;
+
+pub mod loan;
+pub use loan::*;
+
+pub mod call_option;
+pub use call_option::*;
+
+pub mod htlc;
+pub use htlc::*;
+
+pub mod stream;
+pub use stream::*;
+
+pub mod campaign;
+pub use campaign::*;
+
+pub mod bounty;
+pub use bounty::*;
+
+pub mod listing;
+pub use listing::*;
+
+pub mod vote_record;
+pub use vote_record::*;
+
+pub mod match_queue;
+pub use match_queue::*;
+
+pub mod player_profile;
+pub use player_profile::*;
+
+pub mod side_pool;
+pub use side_pool::*;
+
+pub mod nonce_counter;
+pub use nonce_counter::*;
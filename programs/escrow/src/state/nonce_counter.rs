@@ -0,0 +1,28 @@
+use anchor_lang::prelude::*;
+
+use crate::error::ErrorCode;
+
+// Hands out nonces to an account that can be closed and created again at the same address,
+// like an offer, option, campaign, bounty or game, at ["nonce_counter", account address].
+// Anything recorded against the account, like a receipt or option mint, includes its nonce,
+// so it can't be used against a later account at that address, even one created in the
+// same slot. Each address has its own counter, so creating one account never waits on a
+// lock held by the creation of another
+#[account]
+#[derive(InitSpace)]
+pub struct NonceCounter {
+    // The last nonce handed out. Nonces start from 1, so a nonce of zero never matches
+    pub last_nonce: u64,
+    // Used to calculate the address for this account, we save it as a performance optimization
+    pub bump: u8,
+}
+
+impl NonceCounter {
+    pub fn next_nonce(&mut self) -> Result<u64> {
+        self.last_nonce = self
+            .last_nonce
+            .checked_add(1)
+            .ok_or(ErrorCode::NoncesExhausted)?;
+        Ok(self.last_nonce)
+    }
+}
@@ -13,8 +13,22 @@ pub struct Offer {
     pub token_mint_a: Pubkey,
     // The token mint of the token wanted
     pub token_mint_b: Pubkey,
+    // The amount of token a that reached the vault when the offer was made, which is
+    // exactly what leaves the vault even if someone sends extra tokens to it
+    pub token_a_offered_amount: u64,
     // The amount of token b being wanted
     pub token_b_wanted_amount: u64,
+    // Where the taker's token b is sent, chosen by the maker when making the offer
+    pub proceeds_account: Pubkey,
+    // Who paid the rent for the offer and vault accounts, and gets it back when they are closed
+    pub rent_payer: Pubkey,
+    // Where any extra token a sent to the vault goes when the offer is closed
+    pub surplus_account: Pubkey,
+    // Mint of the 1-of-1 receipt token for this offer, or the default pubkey if there isn't one.
+    // When set, whoever holds the receipt owns the offer instead of the maker
+    pub receipt_mint: Pubkey,
+    // Nonce in the receipt mint's address, or 0 if there isn't a receipt
+    pub nonce: u64,
     // Used to calculate the address for this account, we save it as a performance optimization
     pub bump: u8,
 }
@@ -0,0 +1,95 @@
+use anchor_lang::prelude::*;
+
+use crate::constants::{RATING_K_FACTOR, STARTING_RATING};
+
+// A wallet's record across every game it has finished, kept on-chain so ratings can't be faked
+#[account]
+#[derive(InitSpace)]
+pub struct PlayerProfile {
+    // The wallet this profile belongs to
+    pub player: Pubkey,
+    // Finalized games played, which is wins + losses + draws
+    pub games_played: u32,
+    // Games finished in first place
+    pub wins: u32,
+    // Games finished anywhere but first place
+    pub losses: u32,
+    pub draws: u32,
+    // Every stake put into finalized games added up, in the smallest unit of whatever was staked
+    pub total_wagered: u64,
+    // What finalized games have paid out, less what was staked in them
+    pub net_profit: i64,
+    // Elo rating, starting at STARTING_RATING
+    pub rating: i32,
+    // Used to calculate the address for this account, we save it as a performance optimization
+    pub bump: u8,
+}
+
+impl PlayerProfile {
+    // Fills in a profile just created by init_if_needed, leaving existing profiles alone
+    pub fn set_up(&mut self, player: Pubkey, bump: u8) {
+        if self.player == Pubkey::default() {
+            self.player = player;
+            self.rating = STARTING_RATING;
+            self.bump = bump;
+        }
+    }
+}
+
+// Expected score in thousandths against an opponent rated 0, 25, 50, ... 800 points higher,
+// from the Elo formula 1 / (1 + 10^(difference / 400)). Ratings are worked out with
+// integers, interpolating between these, so every validator gets the same answer
+const EXPECTED_SCORES: [i64; 33] = [
+    500, 464, 429, 394, 360, 327, 297, 267, 240, 215, 192, 170, 151, 133, 118, 104, 91, 80, 70,
+    61, 53, 46, 40, 35, 31, 27, 23, 20, 17, 15, 13, 11, 10,
+];
+const EXPECTED_SCORE_STEP: i64 = 25;
+
+// Expected score in thousandths against an opponent rated `difference` points higher,
+// treating differences over 800 as 800
+fn expected_score(difference: i64) -> i64 {
+    if difference < 0 {
+        return 1_000 - expected_score(-difference);
+    }
+    let last = (EXPECTED_SCORES.len() - 1) as i64;
+    let difference = difference.min(last * EXPECTED_SCORE_STEP);
+    let index = difference / EXPECTED_SCORE_STEP;
+    let remainder = difference % EXPECTED_SCORE_STEP;
+    let lower = EXPECTED_SCORES[index as usize];
+    let upper = EXPECTED_SCORES[index.min(last - 1) as usize + 1];
+    lower - (lower - upper) * remainder / EXPECTED_SCORE_STEP
+}
+
+// Works out how much each player's rating moves after a game, given everyone's rating and
+// where they placed (0 for first). A game with more than two players is rated as if each
+// player played every other, so beating higher rated players gains more
+pub fn rating_changes(ratings: &[i32], places: &[usize]) -> Vec<i32> {
+    // Scores are in thousandths, and changes are rounded half away from zero
+    // so that a two player game moves both ratings by the same amount
+    let denominator = (ratings.len() as i64 - 1) * 1_000;
+    ratings
+        .iter()
+        .zip(places)
+        .enumerate()
+        .map(|(index, (rating, place))| {
+            let mut change = 0;
+            for (opponent_index, (opponent_rating, opponent_place)) in
+                ratings.iter().zip(places).enumerate()
+            {
+                if opponent_index == index {
+                    continue;
+                }
+                let expected = expected_score((opponent_rating - rating) as i64);
+                let score = match place.cmp(opponent_place) {
+                    std::cmp::Ordering::Less => 1_000,
+                    std::cmp::Ordering::Equal => 500,
+                    std::cmp::Ordering::Greater => 0,
+                };
+                change += score - expected;
+            }
+            let numerator = RATING_K_FACTOR * change;
+            let rounded = (numerator.abs() * 2 + denominator) / (denominator * 2);
+            (rounded * numerator.signum()) as i32
+        })
+        .collect()
+}
@@ -0,0 +1,43 @@
+use anchor_lang::prelude::*;
+
+use crate::constants::MAX_PLAYERS;
+
+// Spectators' parimutuel bets on who wins a game. Bettors on the winner
+// split the whole pool in proportion to what they bet
+#[account]
+#[derive(InitSpace)]
+pub struct SidePool {
+    // The game being bet on
+    pub game: Pubkey,
+    // The game's nonce, so a later game created with the same id gets a fresh pool
+    pub game_nonce: u64,
+    // Lamports bet on each player slot, in slot order
+    #[max_len(MAX_PLAYERS)]
+    pub slot_totals: Vec<u64>,
+    // 0 while the game is undecided, otherwise the slot of the game's winner, or DRAW
+    // if every bet is refunded, because the game was drawn or cancelled
+    pub outcome: u8,
+    // Side bets not yet claimed. The pool is closed once the last one is
+    pub open_bets: u32,
+    // The bettor who placed the first bet and paid for this account, which gets the rent back
+    // along with any lamports left over from rounding payouts down
+    pub rent_payer: Pubkey,
+    // Used to calculate the address for this account, we save it as a performance optimization
+    pub bump: u8,
+}
+
+// Everything one spectator has bet in a side pool, all on the same player slot
+#[account]
+#[derive(InitSpace)]
+pub struct SideBet {
+    // The side pool bet in
+    pub side_pool: Pubkey,
+    // Who placed the bet
+    pub bettor: Pubkey,
+    // The player slot backed, numbered from 1
+    pub slot: u8,
+    // Lamports bet
+    pub amount: u64,
+    // Used to calculate the address for this account, we save it as a performance optimization
+    pub bump: u8,
+}
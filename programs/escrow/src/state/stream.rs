@@ -0,0 +1,66 @@
+use anchor_lang::prelude::*;
+
+use crate::error::ErrorCode;
+
+// Stores details of a payment stream, where the sender escrows tokens (or lamports)
+// that the recipient can withdraw as they accrue, every second between start_ts and end_ts
+#[account]
+#[derive(InitSpace)]
+pub struct Stream {
+    // Identifier of the stream
+    pub id: u64,
+    // Who is paying
+    pub sender: Pubkey,
+    // Who is being paid
+    pub recipient: Pubkey,
+    // The token mint of the tokens held in the vault, or the default pubkey
+    // if the stream pays lamports held in the stream account itself
+    pub token_mint: Pubkey,
+    // Everything the sender has escrowed, including top ups
+    pub deposited_amount: u64,
+    // How much the recipient has withdrawn so far
+    pub withdrawn_amount: u64,
+    // Unix timestamp the stream starts accruing from
+    pub start_ts: i64,
+    // Unix timestamp by which everything deposited has accrued
+    pub end_ts: i64,
+    // How much had accrued at checkpoint_ts. Top ups move the checkpoint to the time of the
+    // top up, so only what hasn't accrued yet is spread over the rest of the stream
+    pub checkpoint_amount: u64,
+    pub checkpoint_ts: i64,
+    // Used to calculate the address for this account, we save it as a performance optimization
+    pub bump: u8,
+}
+
+impl Stream {
+    // How much has accrued to the recipient by `now`, including anything already withdrawn
+    pub fn accrued_amount(&self, now: i64) -> Result<u64> {
+        if now <= self.checkpoint_ts {
+            return Ok(self.checkpoint_amount);
+        }
+        if now >= self.end_ts {
+            return Ok(self.deposited_amount);
+        }
+
+        let remaining = self
+            .deposited_amount
+            .checked_sub(self.checkpoint_amount)
+            .ok_or(ErrorCode::InvalidStreamSchedule)? as u128;
+        let elapsed = now
+            .checked_sub(self.checkpoint_ts)
+            .ok_or(ErrorCode::InvalidStreamSchedule)? as u128;
+        let duration = self
+            .end_ts
+            .checked_sub(self.checkpoint_ts)
+            .ok_or(ErrorCode::InvalidStreamSchedule)? as u128;
+        Ok(self.checkpoint_amount + (remaining * elapsed / duration) as u64)
+    }
+
+    // Accrued but not yet withdrawn
+    pub fn withdrawable_amount(&self, now: i64) -> Result<u64> {
+        Ok(self
+            .accrued_amount(now)?
+            .checked_sub(self.withdrawn_amount)
+            .ok_or(ErrorCode::InvalidStreamSchedule)?)
+    }
+}
@@ -0,0 +1,24 @@
+use anchor_lang::prelude::*;
+
+use crate::constants::MAX_REFEREES;
+
+// Referees' votes for one result of a game, collected across transactions
+// until enough of them agree to finalize the game with it
+#[account]
+#[derive(InitSpace)]
+pub struct VoteRecord {
+    // The game being voted on
+    pub game: Pubkey,
+    // The game's nonce when the vote started, so votes don't carry over to a later game
+    // created with the same id
+    pub game_nonce: u64,
+    // Hash of the result voted for, see GameResult::hash
+    pub result_hash: [u8; 32],
+    // Every referee who has voted for the result
+    #[max_len(MAX_REFEREES)]
+    pub voters: Vec<Pubkey>,
+    // The referee who cast the first vote and paid for this account, refunded when it's closed
+    pub rent_payer: Pubkey,
+    // Used to calculate the address for this account, we save it as a performance optimization
+    pub bump: u8,
+}
@@ -1,13 +1,60 @@
+use anchor_lang::{AccountDeserialize, Discriminator, Space};
+use solana_program::program_pack::Pack;
+use solana_keypair::Keypair;
 use solana_signer::Signer;
 
+use crate::constants::DRAW;
+use crate::state::{ChallengeSettings, GameOptions, GameResult, RefereeQuorum};
 use crate::escrow_test_helpers::{
-    build_make_offer_accounts, build_make_offer_instruction, build_refund_offer_instruction,
-    build_take_offer_instruction, execute_make_offer, execute_refund_offer, execute_take_offer,
-    generate_offer_id, setup_escrow_test, RefundOfferAccounts, TakeOfferAccounts, TOKEN_A, TOKEN_B,
+    build_award_claim_instruction, build_buy_option_instruction, build_cancel_game_instruction,
+    build_cancel_stream_instruction, build_challenge_result_instruction,
+    build_claim_collateral_instruction, build_claim_htlc_instruction,
+    build_claim_side_bet_instruction, build_close_bounty_instruction,
+    build_close_campaign_instruction, build_close_claim_instruction,
+    build_close_contribution_instruction, build_close_game_instruction,
+    build_close_queue_instruction, build_close_receipt_instruction,
+    build_close_vote_record_instruction, build_confirm_receipt_instruction,
+    build_contribute_instruction, build_create_bounty_instruction,
+    build_create_campaign_instruction, build_create_game_instruction,
+    build_create_htlc_instruction, build_create_listing_instruction,
+    build_create_queue_instruction, build_create_stream_instruction, build_deposit_instruction,
+    build_dequeue_instruction, build_ed25519_instruction, build_enqueue_instruction,
+    build_exercise_option_instruction, build_finalize_game_instruction,
+    build_finalize_with_attestation_instruction, build_make_offer_accounts,
+    build_make_offer_instruction, build_mark_shipped_instruction, build_match_players_instruction,
+    build_open_dispute_instruction, build_place_side_bet_instruction,
+    build_propose_result_instruction, build_purchase_listing_instruction,
+    build_reclaim_option_instruction, build_reclaim_payment_instruction,
+    build_record_round_instruction, build_refund_contribution_instruction,
+    build_refund_htlc_instruction, build_refund_offer_instruction, build_repay_loan_instruction,
+    build_resolve_challenge_instruction, build_resolve_dispute_instruction,
+    build_settle_game_instruction, build_submit_claim_instruction, build_take_offer_instruction,
+    build_vote_result_instruction, build_withdraw_campaign_funds_instruction,
+    build_withdraw_stream_instruction, build_write_option_instruction,
+    create_token_extensions_account, create_transfer_fee_mint, execute_create_and_deposit_game,
+    execute_create_and_deposit_game_with_options, execute_create_and_deposit_token_game,
+    execute_create_and_purchase_listing, execute_fund_loan, execute_make_offer,
+    execute_refund_offer, execute_request_and_fund_loan, execute_request_loan, execute_take_offer,
+    generate_offer_id, get_game_nonce, get_game_winner, get_nonce_counter, get_option_mint,
+    get_player_profile, get_player_profiles, get_receipt_mint, get_side_bet_accounts,
+    get_token_extensions_balance, get_unix_timestamp, get_vote_record,
+    serialize_result_attestation, setup_escrow_test, warp_to_unix_timestamp, AwardClaimAccounts,
+    BuyOptionAccounts, CancelGameAccounts, CancelStreamAccounts, ChallengeResultAccounts,
+    ClaimCollateralAccounts, ClaimHtlcAccounts, CloseBountyAccounts, CloseCampaignAccounts,
+    CloseQueueAccounts, ConfirmReceiptAccounts, ContributeAccounts, CreateBountyAccounts,
+    CreateCampaignAccounts, CreateGameAccounts, CreateHtlcAccounts, CreateListingAccounts,
+    CreateQueueAccounts, CreateStreamAccounts, DepositAccounts, ExerciseOptionAccounts,
+    FinalizeGameAccounts, FinalizeWithAttestationAccounts, MarkShippedAccounts,
+    MatchPlayersAccounts, ProposeResultAccounts, PurchaseListingAccounts, QueuePlayerAccounts,
+    ReclaimOptionAccounts, ReclaimPaymentAccounts, RecordRoundAccounts, RefundContributionAccounts,
+    RefundHtlcAccounts, RefundOfferAccounts, RepayLoanAccounts, ResolveChallengeAccounts,
+    ResolveDisputeAccounts, SettleGameAccounts, SideBetAccounts, StreamTokenAccounts,
+    SubmitClaimAccounts, TakeOfferAccounts, VoteResultAccounts, WithdrawCampaignFundsAccounts,
+    WithdrawStreamAccounts, WriteOptionAccounts, TOKEN_A, TOKEN_B,
 };
 use solana_kite::{
-    assert_token_balance, check_account_is_closed, get_pda_and_bump, seeds,
-    send_transaction_from_instructions,
+    assert_token_balance, check_account_is_closed, create_associated_token_account,
+    get_pda_and_bump, mint_tokens_to_account, send_transaction_from_instructions,
 };
 
 #[test]
@@ -15,8 +62,10 @@ fn test_make_offer_succeeds() {
     let mut test_environment = setup_escrow_test();
 
     let offer_id = generate_offer_id();
-    let (offer_account, _offer_bump) =
-        get_pda_and_bump(&seeds!["offer", offer_id], &test_environment.program_id);
+    let (offer_account, _offer_bump) = get_pda_and_bump(
+        &["offer".into(), offer_id.into()],
+        &test_environment.program_id,
+    );
     let vault = anchor_spl::associated_token::get_associated_token_address(
         &offer_account,
         &test_environment.token_mint_a,
@@ -27,12 +76,13 @@ fn test_make_offer_succeeds() {
         test_environment.token_mint_a,
         test_environment.token_mint_b,
         test_environment.alice_token_account_a,
+        test_environment.alice_token_account_b,
         offer_account,
         vault,
     );
 
     let make_offer_instruction =
-        build_make_offer_instruction(offer_id, 1 * TOKEN_A, 1 * TOKEN_B, make_offer_accounts);
+        build_make_offer_instruction(offer_id, TOKEN_A, TOKEN_B, make_offer_accounts);
 
     let result = send_transaction_from_instructions(
         &mut test_environment.litesvm,
@@ -49,8 +99,10 @@ fn test_duplicate_offer_id_fails() {
     let mut test_environment = setup_escrow_test();
 
     let offer_id = generate_offer_id();
-    let (offer_account, _offer_bump) =
-        get_pda_and_bump(&seeds!["offer", offer_id], &test_environment.program_id);
+    let (offer_account, _offer_bump) = get_pda_and_bump(
+        &["offer".into(), offer_id.into()],
+        &test_environment.program_id,
+    );
     let vault = anchor_spl::associated_token::get_associated_token_address(
         &offer_account,
         &test_environment.token_mint_a,
@@ -61,12 +113,13 @@ fn test_duplicate_offer_id_fails() {
         test_environment.token_mint_a,
         test_environment.token_mint_b,
         test_environment.alice_token_account_a,
+        test_environment.alice_token_account_b,
         offer_account,
         vault,
     );
 
     let make_offer_instruction =
-        build_make_offer_instruction(offer_id, 1 * TOKEN_A, 1 * TOKEN_B, make_offer_accounts);
+        build_make_offer_instruction(offer_id, TOKEN_A, TOKEN_B, make_offer_accounts);
 
     let result = send_transaction_from_instructions(
         &mut test_environment.litesvm,
@@ -81,14 +134,15 @@ fn test_duplicate_offer_id_fails() {
         test_environment.token_mint_a,
         test_environment.token_mint_b,
         test_environment.bob_token_account_a,
+        test_environment.bob_token_account_b,
         offer_account,
         vault,
     );
 
     let make_offer_instruction_with_existing_offer_id = build_make_offer_instruction(
         offer_id,
-        1 * TOKEN_A,
-        1 * TOKEN_B,
+        TOKEN_A,
+        TOKEN_B,
         make_offer_accounts_with_existing_offer_id,
     );
 
@@ -107,8 +161,10 @@ fn test_insufficient_funds_fails() {
 
     // Try to create offer with more tokens than Alice owns
     let offer_id = generate_offer_id();
-    let (offer_account, _offer_bump) =
-        get_pda_and_bump(&seeds!["offer", offer_id], &test_environment.program_id);
+    let (offer_account, _offer_bump) = get_pda_and_bump(
+        &["offer".into(), offer_id.into()],
+        &test_environment.program_id,
+    );
     let vault = anchor_spl::associated_token::get_associated_token_address(
         &offer_account,
         &test_environment.token_mint_a,
@@ -119,6 +175,7 @@ fn test_insufficient_funds_fails() {
         test_environment.token_mint_a,
         test_environment.token_mint_b,
         test_environment.alice_token_account_a,
+        test_environment.alice_token_account_b,
         offer_account,
         vault,
     );
@@ -126,7 +183,7 @@ fn test_insufficient_funds_fails() {
     let make_offer_instruction = build_make_offer_instruction(
         offer_id,
         1000 * TOKEN_A, // Try to offer 1000 tokens (Alice only has 10)
-        1 * TOKEN_B,
+        TOKEN_B,
         make_offer_accounts,
     );
 
@@ -145,8 +202,10 @@ fn test_same_token_mints_fails() {
 
     // Try to create offer with same token mint for both token_a and token_b
     let offer_id = generate_offer_id();
-    let (offer_account, _offer_bump) =
-        get_pda_and_bump(&seeds!["offer", offer_id], &test_environment.program_id);
+    let (offer_account, _offer_bump) = get_pda_and_bump(
+        &["offer".into(), offer_id.into()],
+        &test_environment.program_id,
+    );
     let vault = anchor_spl::associated_token::get_associated_token_address(
         &offer_account,
         &test_environment.token_mint_a,
@@ -157,12 +216,13 @@ fn test_same_token_mints_fails() {
         test_environment.token_mint_a,
         test_environment.token_mint_a, // Same mint for both
         test_environment.alice_token_account_a,
+        test_environment.alice_token_account_a, // Proceeds account must match token_mint_b
         offer_account,
         vault,
     );
 
     let make_offer_instruction =
-        build_make_offer_instruction(offer_id, 1 * TOKEN_A, 1 * TOKEN_B, make_offer_accounts);
+        build_make_offer_instruction(offer_id, TOKEN_A, TOKEN_B, make_offer_accounts);
 
     let result = send_transaction_from_instructions(
         &mut test_environment.litesvm,
@@ -179,8 +239,10 @@ fn test_zero_token_b_wanted_amount_fails() {
 
     // Try to create offer with zero token_b_wanted_amount
     let offer_id = generate_offer_id();
-    let (offer_account, _offer_bump) =
-        get_pda_and_bump(&seeds!["offer", offer_id], &test_environment.program_id);
+    let (offer_account, _offer_bump) = get_pda_and_bump(
+        &["offer".into(), offer_id.into()],
+        &test_environment.program_id,
+    );
     let vault = anchor_spl::associated_token::get_associated_token_address(
         &offer_account,
         &test_environment.token_mint_a,
@@ -191,13 +253,14 @@ fn test_zero_token_b_wanted_amount_fails() {
         test_environment.token_mint_a,
         test_environment.token_mint_b,
         test_environment.alice_token_account_a,
+        test_environment.alice_token_account_b,
         offer_account,
         vault,
     );
 
     let make_offer_instruction = build_make_offer_instruction(
         offer_id,
-        1 * TOKEN_A,
+        TOKEN_A,
         0, // Zero wanted amount
         make_offer_accounts,
     );
@@ -220,8 +283,10 @@ fn test_zero_token_a_offered_amount_fails() {
 
     // Try to create offer with zero token_a_offered_amount
     let offer_id = generate_offer_id();
-    let (offer_account, _offer_bump) =
-        get_pda_and_bump(&seeds!["offer", offer_id], &test_environment.program_id);
+    let (offer_account, _offer_bump) = get_pda_and_bump(
+        &["offer".into(), offer_id.into()],
+        &test_environment.program_id,
+    );
     let vault = anchor_spl::associated_token::get_associated_token_address(
         &offer_account,
         &test_environment.token_mint_a,
@@ -232,6 +297,7 @@ fn test_zero_token_a_offered_amount_fails() {
         test_environment.token_mint_a,
         test_environment.token_mint_b,
         test_environment.alice_token_account_a,
+        test_environment.alice_token_account_b,
         offer_account,
         vault,
     );
@@ -239,7 +305,7 @@ fn test_zero_token_a_offered_amount_fails() {
     let make_offer_instruction = build_make_offer_instruction(
         offer_id,
         0, // Zero offered amount
-        1 * TOKEN_B,
+        TOKEN_B,
         make_offer_accounts,
     );
 
@@ -263,11 +329,13 @@ fn test_take_offer_success() {
     let offer_id = generate_offer_id();
     let alice = test_environment.alice.insecure_clone();
     let alice_token_account_a = test_environment.alice_token_account_a;
-    let (offer_account, vault) = execute_make_offer(
+    let alice_token_account_b = test_environment.alice_token_account_b;
+    let (offer_account, _vault) = execute_make_offer(
         &mut test_environment,
         offer_id,
         &alice,
         alice_token_account_a,
+        alice_token_account_b,
         3 * TOKEN_A,
         2 * TOKEN_B,
     )
@@ -277,7 +345,6 @@ fn test_take_offer_success() {
     let bob = test_environment.bob.insecure_clone();
     let bob_token_account_a = test_environment.bob_token_account_a;
     let bob_token_account_b = test_environment.bob_token_account_b;
-    let alice_token_account_b = test_environment.alice_token_account_b;
     execute_take_offer(
         &mut test_environment,
         &bob,
@@ -286,7 +353,6 @@ fn test_take_offer_success() {
         bob_token_account_b,
         alice_token_account_b,
         offer_account,
-        vault,
     )
     .unwrap();
 
@@ -325,290 +391,5329 @@ fn test_take_offer_success() {
 }
 
 #[test]
-fn test_refund_offer_success() {
+fn test_take_offer_ignores_vault_donations() {
     let mut test_environment = setup_escrow_test();
 
     // Alice creates an offer: 3 token A for 2 token B
     let offer_id = generate_offer_id();
     let alice = test_environment.alice.insecure_clone();
     let alice_token_account_a = test_environment.alice_token_account_a;
+    let alice_token_account_b = test_environment.alice_token_account_b;
     let (offer_account, vault) = execute_make_offer(
         &mut test_environment,
         offer_id,
         &alice,
         alice_token_account_a,
+        alice_token_account_b,
         3 * TOKEN_A,
         2 * TOKEN_B,
     )
     .unwrap();
 
-    // Check that Alice's balance decreased after creating the offer
-    assert_token_balance(
-        &test_environment.litesvm,
-        &test_environment.alice_token_account_a,
-        7 * TOKEN_A,
-        "Alice should have 7 token A left after creating offer",
-    );
+    // Someone sends an extra 1 token A straight to the vault
+    mint_tokens_to_account(
+        &mut test_environment.litesvm,
+        &test_environment.token_mint_a,
+        &vault,
+        TOKEN_A,
+        &test_environment.mint_authority,
+    )
+    .unwrap();
 
-    // Alice refunds the offer
-    execute_refund_offer(
+    // Bob takes the offer
+    let bob = test_environment.bob.insecure_clone();
+    let bob_token_account_a = test_environment.bob_token_account_a;
+    let bob_token_account_b = test_environment.bob_token_account_b;
+    execute_take_offer(
         &mut test_environment,
+        &bob,
         &alice,
-        alice_token_account_a,
+        bob_token_account_a,
+        bob_token_account_b,
+        alice_token_account_b,
         offer_account,
-        vault,
     )
     .unwrap();
 
-    // Check that Alice's balance is restored after refunding
+    // Bob receives exactly what was offered, and the donation goes back to Alice
+    assert_token_balance(
+        &test_environment.litesvm,
+        &test_environment.bob_token_account_a,
+        3 * TOKEN_A,
+        "Bob should have received exactly the 3 token A offered",
+    );
     assert_token_balance(
         &test_environment.litesvm,
         &test_environment.alice_token_account_a,
-        10 * TOKEN_A,
-        "Alice should have all 10 token A back after refunding",
+        8 * TOKEN_A,
+        "Alice should have 7 token A left plus the 1 token A donated to the vault",
     );
-
-    // Check that the offer account is closed
     check_account_is_closed(
         &test_environment.litesvm,
-        &offer_account,
-        "Offer account should be closed after refund",
+        &vault,
+        "Vault should be closed after being taken",
     );
 }
 
 #[test]
-fn test_non_maker_cannot_refund_offer() {
+fn test_surplus_account_needed_only_when_vault_has_surplus() {
     let mut test_environment = setup_escrow_test();
 
     // Alice creates an offer: 3 token A for 2 token B
     let offer_id = generate_offer_id();
-    let (offer_account, _offer_bump) =
-        get_pda_and_bump(&seeds!["offer", offer_id], &test_environment.program_id);
-    let vault = anchor_spl::associated_token::get_associated_token_address(
-        &offer_account,
-        &test_environment.token_mint_a,
-    );
-
-    let make_offer_accounts = build_make_offer_accounts(
-        test_environment.alice.pubkey(),
-        test_environment.token_mint_a,
-        test_environment.token_mint_b,
-        test_environment.alice_token_account_a,
-        offer_account,
-        vault,
-    );
-
-    let make_offer_instruction =
-        build_make_offer_instruction(offer_id, 3 * TOKEN_A, 2 * TOKEN_B, make_offer_accounts);
+    let alice = test_environment.alice.insecure_clone();
+    let alice_token_account_a = test_environment.alice_token_account_a;
+    let alice_token_account_b = test_environment.alice_token_account_b;
+    let (offer_account, vault) = execute_make_offer(
+        &mut test_environment,
+        offer_id,
+        &alice,
+        alice_token_account_a,
+        alice_token_account_b,
+        3 * TOKEN_A,
+        2 * TOKEN_B,
+    )
+    .unwrap();
 
-    let result = send_transaction_from_instructions(
+    // Someone sends an extra 1 token A straight to the vault
+    mint_tokens_to_account(
         &mut test_environment.litesvm,
-        vec![make_offer_instruction],
-        &[&test_environment.alice],
-        &test_environment.alice.pubkey(),
-    );
-    assert!(result.is_ok(), "Alice's offer should succeed");
+        &test_environment.token_mint_a,
+        &vault,
+        TOKEN_A,
+        &test_environment.mint_authority,
+    )
+    .unwrap();
 
-    // Bob tries to refund Alice's offer (should fail)
-    let refund_offer_accounts = RefundOfferAccounts {
+    let take_offer_accounts = |surplus_account| TakeOfferAccounts {
         token_program: anchor_spl::token::ID,
         system_program: anchor_lang::system_program::ID,
-        maker: test_environment.bob.pubkey(),
+        taker: test_environment.bob.pubkey(),
+        maker: alice.pubkey(),
+        rent_payer: alice.pubkey(),
         token_mint_a: test_environment.token_mint_a,
-        maker_token_account_a: test_environment.alice_token_account_a,
+        token_mint_b: test_environment.token_mint_b,
+        recipient: test_environment.bob_token_account_a,
+        taker_token_account_b: test_environment.bob_token_account_b,
+        proceeds_account: alice_token_account_b,
+        surplus_account,
+        maker_token_account_a: None,
         offer_account,
         vault,
+        receipt_account: None,
+        receipt_mint: None,
     };
+    let without_surplus_account = build_take_offer_instruction(take_offer_accounts(None));
+    let with_surplus_account =
+        build_take_offer_instruction(take_offer_accounts(Some(alice_token_account_a)));
 
-    let refund_instruction = build_refund_offer_instruction(refund_offer_accounts);
+    // Bob can't leave out the surplus account while the vault holds a donation
+    let bob = test_environment.bob.insecure_clone();
     let result = send_transaction_from_instructions(
         &mut test_environment.litesvm,
-        vec![refund_instruction],
-        &[&test_environment.bob],
-        &test_environment.bob.pubkey(),
+        vec![without_surplus_account],
+        &[&bob],
+        &bob.pubkey(),
     );
     assert!(
         result.is_err(),
-        "Non-maker should not be able to refund an offer"
+        "Taking an offer with a surplus but no surplus account should fail"
     );
 
-    // Verify that Alice's balance is still the same (offer not refunded)
+    send_transaction_from_instructions(
+        &mut test_environment.litesvm,
+        vec![with_surplus_account],
+        &[&bob],
+        &bob.pubkey(),
+    )
+    .unwrap();
+
     assert_token_balance(
         &test_environment.litesvm,
-        &test_environment.alice_token_account_a,
-        7 * TOKEN_A,
-        "Alice's balance should remain unchanged after failed refund attempt",
-    );
-
-    // Verify that the offer account still exists (invert the check)
-    let offer_account_data = test_environment.litesvm.get_account(&offer_account);
-    assert!(
-        offer_account_data.is_some() && !offer_account_data.unwrap().data.is_empty(),
-        "Offer account should still exist after failed refund attempt"
+        &alice_token_account_a,
+        8 * TOKEN_A,
+        "Alice should have 7 token A left plus the 1 token A donated to the vault",
     );
 }
 
 #[test]
-fn test_take_offer_insufficient_funds_fails() {
+fn test_surplus_goes_to_maker_when_surplus_account_closed() {
     let mut test_environment = setup_escrow_test();
+    let alice = test_environment.alice.insecure_clone();
+    let bob = test_environment.bob.insecure_clone();
 
-    // Create an offer from Alice for a large amount of token B
-    let large_token_b_amount = 1000 * TOKEN_B; // Much larger than Bob's balance (he has 5)
+    // Alice sends any surplus to an account she later closes
+    let surplus_owner = Keypair::new();
+    let surplus_account = create_associated_token_account(
+        &mut test_environment.litesvm,
+        &surplus_owner.pubkey(),
+        &test_environment.token_mint_a,
+        &alice,
+    )
+    .unwrap();
+
+    // Alice creates an offer: 3 token A for 2 token B
     let offer_id = generate_offer_id();
-    let (offer_account, _offer_bump) =
-        get_pda_and_bump(&seeds!["offer", offer_id], &test_environment.program_id);
+    let (offer_account, _offer_bump) = get_pda_and_bump(
+        &["offer".into(), offer_id.into()],
+        &test_environment.program_id,
+    );
     let vault = anchor_spl::associated_token::get_associated_token_address(
         &offer_account,
         &test_environment.token_mint_a,
     );
-
-    let make_offer_accounts = build_make_offer_accounts(
-        test_environment.alice.pubkey(),
+    let mut make_offer_accounts = build_make_offer_accounts(
+        alice.pubkey(),
         test_environment.token_mint_a,
         test_environment.token_mint_b,
         test_environment.alice_token_account_a,
+        test_environment.alice_token_account_b,
         offer_account,
         vault,
     );
-
-    let make_offer_instruction = build_make_offer_instruction(
-        offer_id,
-        1 * TOKEN_A,
-        large_token_b_amount,
-        make_offer_accounts,
-    );
-
-    let result = send_transaction_from_instructions(
+    make_offer_accounts.surplus_account = Some(surplus_account);
+    let make_offer_instruction =
+        build_make_offer_instruction(offer_id, 3 * TOKEN_A, 2 * TOKEN_B, make_offer_accounts);
+    send_transaction_from_instructions(
         &mut test_environment.litesvm,
         vec![make_offer_instruction],
-        &[&test_environment.alice],
-        &test_environment.alice.pubkey(),
-    );
-    assert!(result.is_ok(), "Alice's offer should succeed");
+        &[&alice],
+        &alice.pubkey(),
+    )
+    .unwrap();
 
-    // Try to take the offer with Bob who has insufficient token B
-    let take_offer_accounts = TakeOfferAccounts {
-        associated_token_program: anchor_spl::associated_token::ID,
+    // Someone sends an extra 1 token A straight to the vault, then the surplus account is closed
+    mint_tokens_to_account(
+        &mut test_environment.litesvm,
+        &test_environment.token_mint_a,
+        &vault,
+        TOKEN_A,
+        &test_environment.mint_authority,
+    )
+    .unwrap();
+    let close_surplus_instruction = spl_token::instruction::close_account(
+        &spl_token::ID,
+        &surplus_account,
+        &alice.pubkey(),
+        &surplus_owner.pubkey(),
+        &[],
+    )
+    .unwrap();
+    send_transaction_from_instructions(
+        &mut test_environment.litesvm,
+        vec![close_surplus_instruction],
+        &[&alice, &surplus_owner],
+        &alice.pubkey(),
+    )
+    .unwrap();
+
+    // Bob takes the offer, and the surplus goes to Alice's ATA instead
+    let take_offer_instruction = build_take_offer_instruction(TakeOfferAccounts {
         token_program: anchor_spl::token::ID,
         system_program: anchor_lang::system_program::ID,
-        taker: test_environment.bob.pubkey(),
-        maker: test_environment.alice.pubkey(),
+        taker: bob.pubkey(),
+        maker: alice.pubkey(),
+        rent_payer: alice.pubkey(),
         token_mint_a: test_environment.token_mint_a,
         token_mint_b: test_environment.token_mint_b,
-        taker_token_account_a: test_environment.bob_token_account_a,
+        recipient: test_environment.bob_token_account_a,
         taker_token_account_b: test_environment.bob_token_account_b,
-        maker_token_account_b: test_environment.alice_token_account_b,
+        proceeds_account: test_environment.alice_token_account_b,
+        surplus_account: Some(surplus_account),
+        maker_token_account_a: Some(test_environment.alice_token_account_a),
         offer_account,
         vault,
-    };
-
-    let take_offer_instruction = build_take_offer_instruction(take_offer_accounts);
+        receipt_account: None,
+        receipt_mint: None,
+    });
     let result = send_transaction_from_instructions(
         &mut test_environment.litesvm,
         vec![take_offer_instruction],
-        &[&test_environment.bob],
-        &test_environment.bob.pubkey(),
+        &[&bob],
+        &bob.pubkey(),
     );
     assert!(
-        result.is_err(),
-        "Take offer with insufficient funds should fail"
+        result.is_ok(),
+        "Taking an offer whose surplus account was closed should succeed"
+    );
+
+    assert_token_balance(
+        &test_environment.litesvm,
+        &test_environment.alice_token_account_a,
+        8 * TOKEN_A,
+        "Alice should have 7 token A left plus the 1 token A donated to the vault",
     );
 }
 
 #[test]
-fn test_duel_sol_flow_success() {
-    let mut env = setup_escrow_test();
-
-    // Use Alice as authority/referee for simplicity
-    let authority = env.alice.insecure_clone();
-    let player_a = env.alice.pubkey();
-    let player_b = env.bob.pubkey();
+fn test_take_offer_to_custom_recipients_success() {
+    let mut test_environment = setup_escrow_test();
 
-    let game_id = generate_offer_id();
-    let (game_pda, _bump) = get_pda_and_bump(&seeds!("game", game_id), &env.program_id);
+    // Proceeds and the taker's token a go to accounts owned by neither Alice nor Bob,
+    // eg PDAs of another program
+    let alice = test_environment.alice.insecure_clone();
+    let alice_vault_owner = Keypair::new();
+    let bob_vault_owner = Keypair::new();
+    let alice_proceeds_account = create_associated_token_account(
+        &mut test_environment.litesvm,
+        &alice_vault_owner.pubkey(),
+        &test_environment.token_mint_b,
+        &alice,
+    )
+    .unwrap();
+    let bob_recipient = create_associated_token_account(
+        &mut test_environment.litesvm,
+        &bob_vault_owner.pubkey(),
+        &test_environment.token_mint_a,
+        &alice,
+    )
+    .unwrap();
 
-    // Create game with small stake and near-future expiry
-    let stake = 100_000; // 0.0001 SOL
-    // Set a far-future expiry to avoid clock issues in simulated environment
-    let expiry_ts = 9_999_999_999i64;
+    // Alice creates an offer: 3 token A for 2 token B
+    let offer_id = generate_offer_id();
+    let alice_token_account_a = test_environment.alice_token_account_a;
+    let (offer_account, _vault) = execute_make_offer(
+        &mut test_environment,
+        offer_id,
+        &alice,
+        alice_token_account_a,
+        alice_proceeds_account,
+        3 * TOKEN_A,
+        2 * TOKEN_B,
+    )
+    .unwrap();
 
-    let create_ix = crate::escrow_test_helpers::build_create_game_instruction(
-        game_id,
-        player_a,
-        player_b,
-        stake,
-        expiry_ts,
-        crate::escrow_test_helpers::CreateGameAccounts {
-            authority: authority.pubkey(),
-            system_program: anchor_lang::system_program::ID,
-            game: game_pda,
-        },
+    // Bob can't redirect Alice's proceeds to one of Bob's accounts
+    let bob = test_environment.bob.insecure_clone();
+    let bob_token_account_b = test_environment.bob_token_account_b;
+    let result = execute_take_offer(
+        &mut test_environment,
+        &bob,
+        &alice,
+        bob_recipient,
+        bob_token_account_b,
+        bob_token_account_b,
+        offer_account,
     );
-
-    let res = send_transaction_from_instructions(
-        &mut env.litesvm,
-        vec![create_ix],
-        &[&authority],
-        &authority.pubkey(),
+    assert!(
+        result.is_err(),
+        "Take offer with a different proceeds account should fail"
     );
-    assert!(res.is_ok(), "create_game should succeed");
 
-    // Alice deposits
-    let deposit_a_ix = crate::escrow_test_helpers::build_deposit_instruction(
-        stake,
-        crate::escrow_test_helpers::DepositAccounts {
-            player: player_a,
-            system_program: anchor_lang::system_program::ID,
-            game: game_pda,
-        },
+    // Bob takes the offer
+    execute_take_offer(
+        &mut test_environment,
+        &bob,
+        &alice,
+        bob_recipient,
+        bob_token_account_b,
+        alice_proceeds_account,
+        offer_account,
+    )
+    .unwrap();
+
+    assert_token_balance(
+        &test_environment.litesvm,
+        &alice_proceeds_account,
+        2 * TOKEN_B,
+        "Alice's proceeds account should have received 2 token B",
     );
-    let res = send_transaction_from_instructions(
-        &mut env.litesvm,
-        vec![deposit_a_ix],
+    assert_token_balance(
+        &test_environment.litesvm,
+        &bob_recipient,
+        3 * TOKEN_A,
+        "Bob's recipient account should have received 3 token A",
+    );
+    assert_token_balance(
+        &test_environment.litesvm,
+        &test_environment.alice_token_account_b,
+        0,
+        "Alice's own token B account should be untouched",
+    );
+}
+
+#[test]
+fn test_refund_offer_success() {
+    let mut test_environment = setup_escrow_test();
+
+    // Alice creates an offer: 3 token A for 2 token B
+    let offer_id = generate_offer_id();
+    let alice = test_environment.alice.insecure_clone();
+    let alice_token_account_a = test_environment.alice_token_account_a;
+    let alice_token_account_b = test_environment.alice_token_account_b;
+    let (offer_account, vault) = execute_make_offer(
+        &mut test_environment,
+        offer_id,
+        &alice,
+        alice_token_account_a,
+        alice_token_account_b,
+        3 * TOKEN_A,
+        2 * TOKEN_B,
+    )
+    .unwrap();
+
+    // Check that Alice's balance decreased after creating the offer
+    assert_token_balance(
+        &test_environment.litesvm,
+        &test_environment.alice_token_account_a,
+        7 * TOKEN_A,
+        "Alice should have 7 token A left after creating offer",
+    );
+
+    // Alice refunds the offer
+    execute_refund_offer(
+        &mut test_environment,
+        &alice,
+        alice_token_account_a,
+        offer_account,
+        vault,
+    )
+    .unwrap();
+
+    // Check that Alice's balance is restored after refunding
+    assert_token_balance(
+        &test_environment.litesvm,
+        &test_environment.alice_token_account_a,
+        10 * TOKEN_A,
+        "Alice should have all 10 token A back after refunding",
+    );
+
+    // Check that the offer account is closed
+    check_account_is_closed(
+        &test_environment.litesvm,
+        &offer_account,
+        "Offer account should be closed after refund",
+    );
+}
+
+#[test]
+fn test_sponsored_rent_is_refunded_to_rent_payer() {
+    let mut test_environment = setup_escrow_test();
+
+    // A backend sponsors the rent (and fees) so Alice doesn't spend any SOL
+    let sponsor = Keypair::new();
+    test_environment
+        .litesvm
+        .airdrop(&sponsor.pubkey(), 1_000_000_000)
+        .unwrap();
+
+    let offer_id = generate_offer_id();
+    let (offer_account, _offer_bump) = get_pda_and_bump(
+        &["offer".into(), offer_id.into()],
+        &test_environment.program_id,
+    );
+    let vault = anchor_spl::associated_token::get_associated_token_address(
+        &offer_account,
+        &test_environment.token_mint_a,
+    );
+
+    let mut make_offer_accounts = build_make_offer_accounts(
+        test_environment.alice.pubkey(),
+        test_environment.token_mint_a,
+        test_environment.token_mint_b,
+        test_environment.alice_token_account_a,
+        test_environment.alice_token_account_b,
+        offer_account,
+        vault,
+    );
+    make_offer_accounts.rent_payer = Some(sponsor.pubkey());
+
+    let alice_lamports_before = test_environment
+        .litesvm
+        .get_balance(&test_environment.alice.pubkey())
+        .unwrap();
+
+    let make_offer_instruction =
+        build_make_offer_instruction(offer_id, 3 * TOKEN_A, 2 * TOKEN_B, make_offer_accounts);
+    let result = send_transaction_from_instructions(
+        &mut test_environment.litesvm,
+        vec![make_offer_instruction],
+        &[&test_environment.alice, &sponsor],
+        &sponsor.pubkey(),
+    );
+    assert!(result.is_ok(), "Sponsored offer should succeed");
+
+    let alice_lamports_after = test_environment
+        .litesvm
+        .get_balance(&test_environment.alice.pubkey())
+        .unwrap();
+    assert_eq!(
+        alice_lamports_before, alice_lamports_after,
+        "Alice should not pay any rent for a sponsored offer"
+    );
+
+    let sponsor_lamports_before_refund = test_environment
+        .litesvm
+        .get_balance(&sponsor.pubkey())
+        .unwrap();
+
+    // Alice refunds the offer, and the rent goes back to the sponsor
+    let refund_offer_accounts = RefundOfferAccounts {
+        token_program: anchor_spl::token::ID,
+        system_program: anchor_lang::system_program::ID,
+        owner: test_environment.alice.pubkey(),
+        rent_payer: sponsor.pubkey(),
+        token_mint_a: test_environment.token_mint_a,
+        owner_token_account_a: test_environment.alice_token_account_a,
+        surplus_account: None,
+        offer_account,
+        vault,
+        receipt_mint: None,
+        owner_receipt_account: None,
+    };
+    let refund_instruction = build_refund_offer_instruction(refund_offer_accounts);
+    let result = send_transaction_from_instructions(
+        &mut test_environment.litesvm,
+        vec![refund_instruction],
+        &[&test_environment.alice],
+        &test_environment.alice.pubkey(),
+    );
+    assert!(result.is_ok(), "Refund of a sponsored offer should succeed");
+
+    let sponsor_lamports_after_refund = test_environment
+        .litesvm
+        .get_balance(&sponsor.pubkey())
+        .unwrap();
+    assert!(
+        sponsor_lamports_after_refund > sponsor_lamports_before_refund,
+        "The sponsor should get the offer and vault rent back"
+    );
+}
+
+#[test]
+fn test_transfer_fee_offer_records_amount_received() {
+    let mut test_environment = setup_escrow_test();
+    let mint_authority = test_environment.mint_authority.insecure_clone();
+    let alice = test_environment.alice.insecure_clone();
+    let bob = test_environment.bob.insecure_clone();
+    let litesvm = &mut test_environment.litesvm;
+
+    // Token a charges 1% on every transfer, token b charges nothing
+    let token_mint_a = create_transfer_fee_mint(litesvm, &mint_authority, 100);
+    let token_mint_b = create_transfer_fee_mint(litesvm, &mint_authority, 0);
+    let alice_token_account_a = create_token_extensions_account(
+        litesvm,
+        &alice.pubkey(),
+        &token_mint_a,
+        &mint_authority,
+        10 * TOKEN_A,
+    );
+    let alice_token_account_b = create_token_extensions_account(
+        litesvm,
+        &alice.pubkey(),
+        &token_mint_b,
+        &mint_authority,
+        0,
+    );
+    let bob_token_account_a =
+        create_token_extensions_account(litesvm, &bob.pubkey(), &token_mint_a, &mint_authority, 0);
+    let bob_token_account_b = create_token_extensions_account(
+        litesvm,
+        &bob.pubkey(),
+        &token_mint_b,
+        &mint_authority,
+        5 * TOKEN_B,
+    );
+
+    let offer_id = generate_offer_id();
+    let (offer_account, _offer_bump) = get_pda_and_bump(
+        &["offer".into(), offer_id.into()],
+        &test_environment.program_id,
+    );
+    let vault = anchor_spl::associated_token::get_associated_token_address_with_program_id(
+        &offer_account,
+        &token_mint_a,
+        &anchor_spl::token_2022::ID,
+    );
+
+    let mut make_offer_accounts = build_make_offer_accounts(
+        alice.pubkey(),
+        token_mint_a,
+        token_mint_b,
+        alice_token_account_a,
+        alice_token_account_b,
+        offer_account,
+        vault,
+    );
+    make_offer_accounts.token_program = anchor_spl::token_2022::ID;
+    let make_offer_instruction =
+        build_make_offer_instruction(offer_id, TOKEN_A, TOKEN_B, make_offer_accounts);
+    let result = send_transaction_from_instructions(
+        litesvm,
+        vec![make_offer_instruction],
+        &[&alice],
+        &alice.pubkey(),
+    );
+    assert!(result.is_ok(), "Offer of a transfer fee token should succeed");
+
+    // The fee is taken on the way into the vault, so the offer is for what arrived
+    let amount_received = TOKEN_A - TOKEN_A / 100;
+    let offer_data = litesvm.get_account(&offer_account).unwrap().data;
+    let offer = crate::state::Offer::try_deserialize(&mut offer_data.as_slice()).unwrap();
+    assert_eq!(offer.token_a_offered_amount, amount_received);
+    assert_eq!(get_token_extensions_balance(litesvm, &vault), amount_received);
+
+    let take_offer_instruction = build_take_offer_instruction(TakeOfferAccounts {
+        token_program: anchor_spl::token_2022::ID,
+        system_program: anchor_lang::system_program::ID,
+        taker: bob.pubkey(),
+        maker: alice.pubkey(),
+        rent_payer: alice.pubkey(),
+        token_mint_a,
+        token_mint_b,
+        recipient: bob_token_account_a,
+        taker_token_account_b: bob_token_account_b,
+        proceeds_account: alice_token_account_b,
+        surplus_account: None,
+        maker_token_account_a: None,
+        offer_account,
+        vault,
+        receipt_account: None,
+        receipt_mint: None,
+    });
+    let result = send_transaction_from_instructions(
+        litesvm,
+        vec![take_offer_instruction],
+        &[&bob],
+        &bob.pubkey(),
+    );
+    assert!(result.is_ok(), "Taking a transfer fee offer should empty the vault");
+
+    // Bob pays the fee again on the way out of the vault
+    assert_eq!(
+        get_token_extensions_balance(litesvm, &bob_token_account_a),
+        amount_received - amount_received / 100
+    );
+    assert_eq!(get_token_extensions_balance(litesvm, &alice_token_account_b), TOKEN_B);
+    check_account_is_closed(litesvm, &vault, "Vault should be closed");
+    check_account_is_closed(litesvm, &offer_account, "Offer should be closed");
+}
+
+#[test]
+fn test_non_maker_cannot_refund_offer() {
+    let mut test_environment = setup_escrow_test();
+
+    // Alice creates an offer: 3 token A for 2 token B
+    let offer_id = generate_offer_id();
+    let (offer_account, _offer_bump) = get_pda_and_bump(
+        &["offer".into(), offer_id.into()],
+        &test_environment.program_id,
+    );
+    let vault = anchor_spl::associated_token::get_associated_token_address(
+        &offer_account,
+        &test_environment.token_mint_a,
+    );
+
+    let make_offer_accounts = build_make_offer_accounts(
+        test_environment.alice.pubkey(),
+        test_environment.token_mint_a,
+        test_environment.token_mint_b,
+        test_environment.alice_token_account_a,
+        test_environment.alice_token_account_b,
+        offer_account,
+        vault,
+    );
+
+    let make_offer_instruction =
+        build_make_offer_instruction(offer_id, 3 * TOKEN_A, 2 * TOKEN_B, make_offer_accounts);
+
+    let result = send_transaction_from_instructions(
+        &mut test_environment.litesvm,
+        vec![make_offer_instruction],
+        &[&test_environment.alice],
+        &test_environment.alice.pubkey(),
+    );
+    assert!(result.is_ok(), "Alice's offer should succeed");
+
+    // Bob tries to refund Alice's offer (should fail)
+    let refund_offer_accounts = RefundOfferAccounts {
+        token_program: anchor_spl::token::ID,
+        system_program: anchor_lang::system_program::ID,
+        owner: test_environment.bob.pubkey(),
+        rent_payer: test_environment.alice.pubkey(),
+        token_mint_a: test_environment.token_mint_a,
+        owner_token_account_a: test_environment.alice_token_account_a,
+        surplus_account: None,
+        offer_account,
+        vault,
+        receipt_mint: None,
+        owner_receipt_account: None,
+    };
+
+    let refund_instruction = build_refund_offer_instruction(refund_offer_accounts);
+    let result = send_transaction_from_instructions(
+        &mut test_environment.litesvm,
+        vec![refund_instruction],
+        &[&test_environment.bob],
+        &test_environment.bob.pubkey(),
+    );
+    assert!(
+        result.is_err(),
+        "Non-maker should not be able to refund an offer"
+    );
+
+    // Verify that Alice's balance is still the same (offer not refunded)
+    assert_token_balance(
+        &test_environment.litesvm,
+        &test_environment.alice_token_account_a,
+        7 * TOKEN_A,
+        "Alice's balance should remain unchanged after failed refund attempt",
+    );
+
+    // Verify that the offer account still exists (invert the check)
+    let offer_account_data = test_environment.litesvm.get_account(&offer_account);
+    assert!(
+        offer_account_data.is_some() && !offer_account_data.unwrap().data.is_empty(),
+        "Offer account should still exist after failed refund attempt"
+    );
+}
+
+#[test]
+fn test_receipt_holder_can_refund_transferred_offer() {
+    let mut test_environment = setup_escrow_test();
+
+    // Alice creates an offer with a receipt token: 3 token A for 2 token B
+    let offer_id = generate_offer_id();
+    let (offer_account, _offer_bump) = get_pda_and_bump(
+        &["offer".into(), offer_id.into()],
+        &test_environment.program_id,
+    );
+    let vault = anchor_spl::associated_token::get_associated_token_address(
+        &offer_account,
+        &test_environment.token_mint_a,
+    );
+    let receipt_mint = get_receipt_mint(&offer_account, 1);
+    let alice_receipt_account = anchor_spl::associated_token::get_associated_token_address(
+        &test_environment.alice.pubkey(),
+        &receipt_mint,
+    );
+
+    let mut make_offer_accounts = build_make_offer_accounts(
+        test_environment.alice.pubkey(),
+        test_environment.token_mint_a,
+        test_environment.token_mint_b,
+        test_environment.alice_token_account_a,
+        test_environment.alice_token_account_b,
+        offer_account,
+        vault,
+    );
+    make_offer_accounts.receipt_mint = Some(receipt_mint);
+    make_offer_accounts.maker_receipt_account = Some(alice_receipt_account);
+
+    let make_offer_instruction =
+        build_make_offer_instruction(offer_id, 3 * TOKEN_A, 2 * TOKEN_B, make_offer_accounts);
+    let result = send_transaction_from_instructions(
+        &mut test_environment.litesvm,
+        vec![make_offer_instruction],
+        &[&test_environment.alice],
+        &test_environment.alice.pubkey(),
+    );
+    assert!(result.is_ok(), "Offer with a receipt should succeed");
+    assert_token_balance(
+        &test_environment.litesvm,
+        &alice_receipt_account,
+        1,
+        "Alice should hold the receipt",
+    );
+
+    // Alice sells the receipt to Carol
+    let carol = Keypair::new();
+    test_environment
+        .litesvm
+        .airdrop(&carol.pubkey(), 1_000_000_000)
+        .unwrap();
+    let alice = test_environment.alice.insecure_clone();
+    let carol_receipt_account = create_associated_token_account(
+        &mut test_environment.litesvm,
+        &carol.pubkey(),
+        &receipt_mint,
+        &alice,
+    )
+    .unwrap();
+    let carol_token_account_a = create_associated_token_account(
+        &mut test_environment.litesvm,
+        &carol.pubkey(),
+        &test_environment.token_mint_a,
+        &alice,
+    )
+    .unwrap();
+    let transfer_receipt_instruction = spl_token::instruction::transfer(
+        &spl_token::ID,
+        &alice_receipt_account,
+        &carol_receipt_account,
+        &alice.pubkey(),
+        &[],
+        1,
+    )
+    .unwrap();
+    send_transaction_from_instructions(
+        &mut test_environment.litesvm,
+        vec![transfer_receipt_instruction],
+        &[&alice],
+        &alice.pubkey(),
+    )
+    .unwrap();
+
+    // Alice no longer owns the offer, so can't refund it
+    let alice_refund_instruction = build_refund_offer_instruction(RefundOfferAccounts {
+        token_program: anchor_spl::token::ID,
+        system_program: anchor_lang::system_program::ID,
+        owner: alice.pubkey(),
+        rent_payer: alice.pubkey(),
+        token_mint_a: test_environment.token_mint_a,
+        owner_token_account_a: test_environment.alice_token_account_a,
+        surplus_account: None,
+        offer_account,
+        vault,
+        receipt_mint: Some(receipt_mint),
+        owner_receipt_account: Some(alice_receipt_account),
+    });
+    let result = send_transaction_from_instructions(
+        &mut test_environment.litesvm,
+        vec![alice_refund_instruction],
+        &[&alice],
+        &alice.pubkey(),
+    );
+    assert!(
+        result.is_err(),
+        "Maker should not be able to refund after selling the receipt"
+    );
+
+    // Carol holds the receipt, so can refund and gets the offered tokens
+    let carol_refund_instruction = build_refund_offer_instruction(RefundOfferAccounts {
+        token_program: anchor_spl::token::ID,
+        system_program: anchor_lang::system_program::ID,
+        owner: carol.pubkey(),
+        rent_payer: alice.pubkey(),
+        token_mint_a: test_environment.token_mint_a,
+        owner_token_account_a: carol_token_account_a,
+        surplus_account: None,
+        offer_account,
+        vault,
+        receipt_mint: Some(receipt_mint),
+        owner_receipt_account: Some(carol_receipt_account),
+    });
+    let result = send_transaction_from_instructions(
+        &mut test_environment.litesvm,
+        vec![carol_refund_instruction],
+        &[&carol],
+        &carol.pubkey(),
+    );
+    assert!(result.is_ok(), "Receipt holder should be able to refund");
+
+    assert_token_balance(
+        &test_environment.litesvm,
+        &carol_token_account_a,
+        3 * TOKEN_A,
+        "Carol should have received the 3 token A offered",
+    );
+    assert_token_balance(
+        &test_environment.litesvm,
+        &carol_receipt_account,
+        0,
+        "The receipt should be burned by the refund",
+    );
+    check_account_is_closed(
+        &test_environment.litesvm,
+        &offer_account,
+        "Offer account should be closed after refund",
+    );
+}
+
+#[test]
+fn test_taking_offer_freezes_receipt() {
+    let mut test_environment = setup_escrow_test();
+    let alice = test_environment.alice.insecure_clone();
+    let bob = test_environment.bob.insecure_clone();
+
+    // Alice creates an offer with a receipt token: 3 token A for 2 token B
+    let offer_id = generate_offer_id();
+    let (offer_account, _offer_bump) = get_pda_and_bump(
+        &["offer".into(), offer_id.into()],
+        &test_environment.program_id,
+    );
+    let vault = anchor_spl::associated_token::get_associated_token_address(
+        &offer_account,
+        &test_environment.token_mint_a,
+    );
+    let receipt_mint = get_receipt_mint(&offer_account, 1);
+    let alice_receipt_account =
+        anchor_spl::associated_token::get_associated_token_address(&alice.pubkey(), &receipt_mint);
+
+    let mut make_offer_accounts = build_make_offer_accounts(
+        alice.pubkey(),
+        test_environment.token_mint_a,
+        test_environment.token_mint_b,
+        test_environment.alice_token_account_a,
+        test_environment.alice_token_account_b,
+        offer_account,
+        vault,
+    );
+    make_offer_accounts.receipt_mint = Some(receipt_mint);
+    make_offer_accounts.maker_receipt_account = Some(alice_receipt_account);
+    let make_offer_instruction =
+        build_make_offer_instruction(offer_id, 3 * TOKEN_A, 2 * TOKEN_B, make_offer_accounts);
+    send_transaction_from_instructions(
+        &mut test_environment.litesvm,
+        vec![make_offer_instruction],
+        &[&alice],
+        &alice.pubkey(),
+    )
+    .unwrap();
+
+    // Bob takes the offer, paying Alice as the receipt holder
+    let take_offer_instruction = build_take_offer_instruction(TakeOfferAccounts {
+        token_program: anchor_spl::token::ID,
+        system_program: anchor_lang::system_program::ID,
+        taker: bob.pubkey(),
+        maker: alice.pubkey(),
+        rent_payer: alice.pubkey(),
+        token_mint_a: test_environment.token_mint_a,
+        token_mint_b: test_environment.token_mint_b,
+        recipient: test_environment.bob_token_account_a,
+        taker_token_account_b: test_environment.bob_token_account_b,
+        proceeds_account: test_environment.alice_token_account_b,
+        surplus_account: None,
+        maker_token_account_a: None,
+        offer_account,
+        vault,
+        receipt_account: Some(alice_receipt_account),
+        receipt_mint: Some(receipt_mint),
+    });
+    let result = send_transaction_from_instructions(
+        &mut test_environment.litesvm,
+        vec![take_offer_instruction],
+        &[&bob],
+        &bob.pubkey(),
+    );
+    assert!(result.is_ok(), "Taking an offer with a receipt should succeed");
+    assert_token_balance(
+        &test_environment.litesvm,
+        &test_environment.alice_token_account_b,
+        2 * TOKEN_B,
+        "The receipt holder should receive the token B",
+    );
+
+    // The spent receipt is frozen, so it can't be passed on as if it were still good
+    let receipt_account_data = test_environment
+        .litesvm
+        .get_account(&alice_receipt_account)
+        .unwrap()
+        .data;
+    let receipt_account = spl_token::state::Account::unpack(&receipt_account_data).unwrap();
+    assert!(receipt_account.is_frozen(), "Receipt should be frozen");
+
+    let bob_receipt_account = create_associated_token_account(
+        &mut test_environment.litesvm,
+        &bob.pubkey(),
+        &receipt_mint,
+        &bob,
+    )
+    .unwrap();
+    let transfer_receipt_instruction = spl_token::instruction::transfer(
+        &spl_token::ID,
+        &alice_receipt_account,
+        &bob_receipt_account,
+        &alice.pubkey(),
+        &[],
+        1,
+    )
+    .unwrap();
+    let result = send_transaction_from_instructions(
+        &mut test_environment.litesvm,
+        vec![transfer_receipt_instruction],
+        &[&alice],
+        &alice.pubkey(),
+    );
+    assert!(result.is_err(), "A frozen receipt should not be transferable");
+
+    // Alice closes the spent receipt, which burns it and returns the account's rent to her
+    let receipt_account_rent = test_environment
+        .litesvm
+        .get_account(&alice_receipt_account)
+        .unwrap()
+        .lamports;
+    let alice_balance_before = test_environment.litesvm.get_balance(&alice.pubkey()).unwrap();
+    let close_receipt_instruction = build_close_receipt_instruction(
+        offer_id,
+        alice.pubkey(),
+        offer_account,
+        receipt_mint,
+        alice_receipt_account,
+    );
+    send_transaction_from_instructions(
+        &mut test_environment.litesvm,
+        vec![close_receipt_instruction],
+        &[&alice],
+        &alice.pubkey(),
+    )
+    .unwrap();
+
+    check_account_is_closed(
+        &test_environment.litesvm,
+        &alice_receipt_account,
+        "Receipt account should be closed",
+    );
+    let alice_balance_after = test_environment.litesvm.get_balance(&alice.pubkey()).unwrap();
+    assert!(
+        alice_balance_after > alice_balance_before + receipt_account_rent - 10_000,
+        "Alice should get the receipt account's rent back, less the transaction fee"
+    );
+}
+
+#[test]
+fn test_take_offer_insufficient_funds_fails() {
+    let mut test_environment = setup_escrow_test();
+
+    // Create an offer from Alice for a large amount of token B
+    let large_token_b_amount = 1000 * TOKEN_B; // Much larger than Bob's balance (he has 5)
+    let offer_id = generate_offer_id();
+    let (offer_account, _offer_bump) = get_pda_and_bump(
+        &["offer".into(), offer_id.into()],
+        &test_environment.program_id,
+    );
+    let vault = anchor_spl::associated_token::get_associated_token_address(
+        &offer_account,
+        &test_environment.token_mint_a,
+    );
+
+    let make_offer_accounts = build_make_offer_accounts(
+        test_environment.alice.pubkey(),
+        test_environment.token_mint_a,
+        test_environment.token_mint_b,
+        test_environment.alice_token_account_a,
+        test_environment.alice_token_account_b,
+        offer_account,
+        vault,
+    );
+
+    let make_offer_instruction =
+        build_make_offer_instruction(offer_id, TOKEN_A, large_token_b_amount, make_offer_accounts);
+
+    let result = send_transaction_from_instructions(
+        &mut test_environment.litesvm,
+        vec![make_offer_instruction],
+        &[&test_environment.alice],
+        &test_environment.alice.pubkey(),
+    );
+    assert!(result.is_ok(), "Alice's offer should succeed");
+
+    // Try to take the offer with Bob who has insufficient token B
+    let take_offer_accounts = TakeOfferAccounts {
+        token_program: anchor_spl::token::ID,
+        system_program: anchor_lang::system_program::ID,
+        taker: test_environment.bob.pubkey(),
+        maker: test_environment.alice.pubkey(),
+        rent_payer: test_environment.alice.pubkey(),
+        token_mint_a: test_environment.token_mint_a,
+        token_mint_b: test_environment.token_mint_b,
+        recipient: test_environment.bob_token_account_a,
+        taker_token_account_b: test_environment.bob_token_account_b,
+        proceeds_account: test_environment.alice_token_account_b,
+        surplus_account: None,
+        maker_token_account_a: None,
+        offer_account,
+        vault,
+        receipt_account: None,
+        receipt_mint: None,
+    };
+
+    let take_offer_instruction = build_take_offer_instruction(take_offer_accounts);
+    let result = send_transaction_from_instructions(
+        &mut test_environment.litesvm,
+        vec![take_offer_instruction],
+        &[&test_environment.bob],
+        &test_environment.bob.pubkey(),
+    );
+    assert!(
+        result.is_err(),
+        "Take offer with insufficient funds should fail"
+    );
+}
+
+#[test]
+fn test_duel_sol_flow_success() {
+    let mut env = setup_escrow_test();
+
+    // Use Alice as authority/referee for simplicity
+    let authority = env.alice.insecure_clone();
+    let player_a = env.alice.pubkey();
+    let player_b = env.bob.pubkey();
+
+    let game_id = generate_offer_id();
+    let (game_pda, _bump) = get_pda_and_bump(&["game".into(), game_id.into()], &env.program_id);
+
+    // Create game with small stake and near-future expiry
+    let stake = 100_000; // 0.0001 SOL
+    // Set a far-future expiry to avoid clock issues in simulated environment
+    let expiry_ts = 9_999_999_999i64;
+
+    let create_ix = crate::escrow_test_helpers::build_create_game_instruction(
+        game_id,
+        &[player_a, player_b],
+        stake,
+        expiry_ts,
+        0,
+        GameOptions::default(),
+        crate::escrow_test_helpers::CreateGameAccounts {
+            authority: authority.pubkey(),
+            system_program: anchor_lang::system_program::ID,
+            treasury: authority.pubkey(),
+            game: game_pda,
+            token_accounts: None,
+        },
+    );
+
+    let res = send_transaction_from_instructions(
+        &mut env.litesvm,
+        vec![create_ix],
+        &[&authority],
+        &authority.pubkey(),
+    );
+    assert!(res.is_ok(), "create_game should succeed");
+
+    // Alice deposits
+    let deposit_a_ix = crate::escrow_test_helpers::build_deposit_instruction(
+        stake,
+        crate::escrow_test_helpers::DepositAccounts {
+            player: player_a,
+            system_program: anchor_lang::system_program::ID,
+            game: game_pda,
+            token_accounts: None,
+            player_token_account: None,
+        },
+    );
+    let res = send_transaction_from_instructions(
+        &mut env.litesvm,
+        vec![deposit_a_ix],
+        &[&env.alice],
+        &player_a,
+    );
+    assert!(res.is_ok(), "alice deposit should succeed");
+
+    // Bob deposits
+    let deposit_b_ix = crate::escrow_test_helpers::build_deposit_instruction(
+        stake,
+        crate::escrow_test_helpers::DepositAccounts {
+            player: player_b,
+            system_program: anchor_lang::system_program::ID,
+            game: game_pda,
+            token_accounts: None,
+            player_token_account: None,
+        },
+    );
+    let res = send_transaction_from_instructions(
+        &mut env.litesvm,
+        vec![deposit_b_ix],
+        &[&env.bob],
+        &player_b,
+    );
+    assert!(res.is_ok(), "bob deposit should succeed");
+
+    // The game can't be closed before it's paid out
+    let res = send_transaction_from_instructions(
+        &mut env.litesvm,
+        vec![build_close_game_instruction(
+            authority.pubkey(),
+            game_pda,
+            vec![],
+        )],
+        &[&authority],
+        &authority.pubkey(),
+    );
+    assert!(
+        res.is_err(),
+        "close_game should fail before the game is paid out"
+    );
+
+    // Finalize: set Bob as winner (2)
+    let finalize_ix = crate::escrow_test_helpers::build_finalize_game_instruction(
+        &[2],
+        &[10_000],
+        crate::escrow_test_helpers::FinalizeGameAccounts {
+            authority: authority.pubkey(),
+            treasury: authority.pubkey(),
+            system_program: anchor_lang::system_program::ID,
+            game: game_pda,
+            token_accounts: None,
+            vote_record: None,
+            side_pool: None,
+            player_accounts: vec![player_a, player_b],
+            player_profiles: get_player_profiles(&[player_a, player_b]),
+            signing_referees: vec![],
+        },
+    );
+    let res = send_transaction_from_instructions(
+        &mut env.litesvm,
+        vec![finalize_ix],
+        &[&authority],
+        &authority.pubkey(),
+    );
+    assert!(res.is_ok(), "finalize_game should succeed");
+
+    // The game is left open as a record that Bob won, until the authority closes it
+    assert_eq!(
+        get_game_winner(&env.litesvm, &game_pda),
+        2,
+        "The game should record Bob as the winner"
+    );
+
+    // Nothing can change a game that's been paid out
+    let deposit_again_ix = crate::escrow_test_helpers::build_deposit_instruction(
+        stake,
+        crate::escrow_test_helpers::DepositAccounts {
+            player: player_a,
+            system_program: anchor_lang::system_program::ID,
+            game: game_pda,
+            token_accounts: None,
+            player_token_account: None,
+        },
+    );
+    let res = send_transaction_from_instructions(
+        &mut env.litesvm,
+        vec![deposit_again_ix],
         &[&env.alice],
         &player_a,
     );
-    assert!(res.is_ok(), "alice deposit should succeed");
+    assert!(
+        res.is_err(),
+        "deposit should fail once the game is paid out"
+    );
+
+    let res = send_transaction_from_instructions(
+        &mut env.litesvm,
+        vec![build_close_game_instruction(
+            authority.pubkey(),
+            game_pda,
+            vec![],
+        )],
+        &[&authority],
+        &authority.pubkey(),
+    );
+    assert!(
+        res.is_ok(),
+        "close_game should succeed once the game is paid out"
+    );
+    check_account_is_closed(
+        &env.litesvm,
+        &game_pda,
+        "Game account should be closed by close_game",
+    );
+}
+
+#[test]
+fn test_loan_repaid_before_due_date_returns_collateral() {
+    let mut test_environment = setup_escrow_test();
+
+    // Alice borrows 2 token B from Bob against 3 token A, paying 1 token B interest
+    let loan_id = generate_offer_id();
+    let (loan, vault) = execute_request_and_fund_loan(
+        &mut test_environment,
+        loan_id,
+        3 * TOKEN_A,
+        2 * TOKEN_B,
+        TOKEN_B,
+        60 * 60 * 24,
+    )
+    .unwrap();
+
+    assert_token_balance(
+        &test_environment.litesvm,
+        &test_environment.alice_token_account_b,
+        2 * TOKEN_B,
+        "Alice should have received the 2 token B principal",
+    );
+    assert_token_balance(
+        &test_environment.litesvm,
+        &vault,
+        3 * TOKEN_A,
+        "The vault should hold Alice's 3 token A collateral",
+    );
+
+    // Alice needs another token B to cover the interest
+    mint_tokens_to_account(
+        &mut test_environment.litesvm,
+        &test_environment.token_mint_b,
+        &test_environment.alice_token_account_b,
+        TOKEN_B,
+        &test_environment.mint_authority,
+    )
+    .unwrap();
+
+    let repay_loan_instruction = build_repay_loan_instruction(RepayLoanAccounts {
+        token_program: anchor_spl::token::ID,
+        borrower: test_environment.alice.pubkey(),
+        lender: test_environment.bob.pubkey(),
+        collateral_mint: test_environment.token_mint_a,
+        principal_mint: test_environment.token_mint_b,
+        borrower_principal_account: test_environment.alice_token_account_b,
+        lender_principal_account: test_environment.bob_token_account_b,
+        borrower_collateral_account: test_environment.alice_token_account_a,
+        loan,
+        vault,
+    });
+    let result = send_transaction_from_instructions(
+        &mut test_environment.litesvm,
+        vec![repay_loan_instruction],
+        &[&test_environment.alice],
+        &test_environment.alice.pubkey(),
+    );
+    assert!(result.is_ok(), "Repaying before the due date should succeed");
+
+    assert_token_balance(
+        &test_environment.litesvm,
+        &test_environment.alice_token_account_a,
+        10 * TOKEN_A,
+        "Alice should have the collateral back",
+    );
+    assert_token_balance(
+        &test_environment.litesvm,
+        &test_environment.bob_token_account_b,
+        6 * TOKEN_B,
+        "Bob should have the 5 token B lent plus 1 token B interest",
+    );
+    check_account_is_closed(
+        &test_environment.litesvm,
+        &loan,
+        "Loan account should be closed after repayment",
+    );
+}
+
+#[test]
+fn test_lender_claims_collateral_only_after_due_date() {
+    let mut test_environment = setup_escrow_test();
+
+    let loan_id = generate_offer_id();
+    let loan_duration = 60 * 60 * 24;
+
+    // Alice asks for the loan, but nobody funds it for two days
+    let (loan, vault) = execute_request_loan(
+        &mut test_environment,
+        loan_id,
+        3 * TOKEN_A,
+        2 * TOKEN_B,
+        TOKEN_B,
+        loan_duration,
+    )
+    .unwrap();
+    let funded_ts = get_unix_timestamp(&test_environment.litesvm) + 2 * loan_duration;
+    warp_to_unix_timestamp(&mut test_environment.litesvm, funded_ts);
+
+    // The due date counts from when Bob funds the loan, not when Alice asked for it
+    execute_fund_loan(&mut test_environment, loan).unwrap();
+    let due_ts = funded_ts + loan_duration;
+
+    let claim_instruction = build_claim_collateral_instruction(ClaimCollateralAccounts {
+        token_program: anchor_spl::token::ID,
+        lender: test_environment.bob.pubkey(),
+        borrower: test_environment.alice.pubkey(),
+        collateral_mint: test_environment.token_mint_a,
+        lender_collateral_account: test_environment.bob_token_account_a,
+        loan,
+        vault,
+    });
+
+    // Bob can't take the collateral while Alice still has time to repay
+    let result = send_transaction_from_instructions(
+        &mut test_environment.litesvm,
+        vec![claim_instruction.clone()],
+        &[&test_environment.bob],
+        &test_environment.bob.pubkey(),
+    );
+    assert!(
+        result.is_err(),
+        "Claiming collateral before the due date should fail"
+    );
+
+    // Alice misses the due date. We also expire the blockhash so the retried
+    // transaction gets a new signature
+    warp_to_unix_timestamp(&mut test_environment.litesvm, due_ts + 1);
+    test_environment.litesvm.expire_blockhash();
+
+    let result = send_transaction_from_instructions(
+        &mut test_environment.litesvm,
+        vec![claim_instruction],
+        &[&test_environment.bob],
+        &test_environment.bob.pubkey(),
+    );
+    assert!(
+        result.is_ok(),
+        "Claiming collateral after the due date should succeed"
+    );
+
+    assert_token_balance(
+        &test_environment.litesvm,
+        &test_environment.bob_token_account_a,
+        3 * TOKEN_A,
+        "Bob should have received Alice's 3 token A collateral",
+    );
+    check_account_is_closed(
+        &test_environment.litesvm,
+        &loan,
+        "Loan account should be closed after the collateral is claimed",
+    );
+}
+
+#[test]
+fn test_call_option_bought_and_exercised() {
+    let mut test_environment = setup_escrow_test();
+
+    // Alice writes a call on 3 token A with a strike of 2 token B and a premium of 1 token B
+    let option_id = generate_offer_id();
+    let (call_option, _option_bump) = get_pda_and_bump(
+        &["option".into(), option_id.into()],
+        &test_environment.program_id,
+    );
+    let vault = anchor_spl::associated_token::get_associated_token_address(
+        &call_option,
+        &test_environment.token_mint_a,
+    );
+    let option_mint = get_option_mint(&call_option, 1);
+    let expiry_ts = get_unix_timestamp(&test_environment.litesvm) + 60 * 60 * 24;
+
+    let write_option_instruction = build_write_option_instruction(
+        option_id,
+        3 * TOKEN_A,
+        2 * TOKEN_B,
+        TOKEN_B,
+        expiry_ts,
+        WriteOptionAccounts {
+            associated_token_program: anchor_spl::associated_token::ID,
+            token_program: anchor_spl::token::ID,
+            system_program: anchor_lang::system_program::ID,
+            writer: test_environment.alice.pubkey(),
+            underlying_mint: test_environment.token_mint_a,
+            quote_mint: test_environment.token_mint_b,
+            writer_underlying_account: test_environment.alice_token_account_a,
+            proceeds_account: test_environment.alice_token_account_b,
+            call_option,
+            vault,
+            option_mint,
+        },
+    );
+    let result = send_transaction_from_instructions(
+        &mut test_environment.litesvm,
+        vec![write_option_instruction],
+        &[&test_environment.alice],
+        &test_environment.alice.pubkey(),
+    );
+    assert!(result.is_ok(), "Writing the option should succeed");
+
+    // Bob buys the option, paying the premium
+    let bob = test_environment.bob.insecure_clone();
+    let bob_option_account = create_associated_token_account(
+        &mut test_environment.litesvm,
+        &bob.pubkey(),
+        &option_mint,
+        &bob,
+    )
+    .unwrap();
+    let buy_option_instruction = build_buy_option_instruction(BuyOptionAccounts {
+        token_program: anchor_spl::token::ID,
+        buyer: bob.pubkey(),
+        quote_mint: test_environment.token_mint_b,
+        buyer_quote_account: test_environment.bob_token_account_b,
+        buyer_option_account: bob_option_account,
+        proceeds_account: test_environment.alice_token_account_b,
+        option_mint,
+        call_option,
+    });
+    let result = send_transaction_from_instructions(
+        &mut test_environment.litesvm,
+        vec![buy_option_instruction],
+        &[&bob],
+        &bob.pubkey(),
+    );
+    assert!(result.is_ok(), "Buying the option should succeed");
+    assert_token_balance(
+        &test_environment.litesvm,
+        &bob_option_account,
+        1,
+        "Bob should hold the option token",
+    );
+    assert_token_balance(
+        &test_environment.litesvm,
+        &test_environment.alice_token_account_b,
+        TOKEN_B,
+        "Alice should have received the 1 token B premium",
+    );
+
+    // Bob exercises, paying the strike
+    let exercise_option_instruction = build_exercise_option_instruction(ExerciseOptionAccounts {
+        token_program: anchor_spl::token::ID,
+        holder: bob.pubkey(),
+        writer: test_environment.alice.pubkey(),
+        underlying_mint: test_environment.token_mint_a,
+        quote_mint: test_environment.token_mint_b,
+        option_mint,
+        holder_option_account: bob_option_account,
+        holder_quote_account: test_environment.bob_token_account_b,
+        holder_underlying_account: test_environment.bob_token_account_a,
+        proceeds_account: test_environment.alice_token_account_b,
+        call_option,
+        vault,
+    });
+    let result = send_transaction_from_instructions(
+        &mut test_environment.litesvm,
+        vec![exercise_option_instruction],
+        &[&bob],
+        &bob.pubkey(),
+    );
+    assert!(result.is_ok(), "Exercising the option should succeed");
+
+    assert_token_balance(
+        &test_environment.litesvm,
+        &test_environment.bob_token_account_a,
+        3 * TOKEN_A,
+        "Bob should have received the 3 token A underlying",
+    );
+    assert_token_balance(
+        &test_environment.litesvm,
+        &test_environment.bob_token_account_b,
+        2 * TOKEN_B,
+        "Bob should have 5 token B less the premium and strike",
+    );
+    assert_token_balance(
+        &test_environment.litesvm,
+        &test_environment.alice_token_account_b,
+        3 * TOKEN_B,
+        "Alice should have received the premium and strike",
+    );
+    check_account_is_closed(
+        &test_environment.litesvm,
+        &call_option,
+        "Option account should be closed after being exercised",
+    );
+}
+
+#[test]
+fn test_option_cannot_be_exercised_after_expiry_and_is_reclaimed() {
+    let mut test_environment = setup_escrow_test();
+
+    // Alice writes a call on 3 token A, expiring in a day
+    let option_id = generate_offer_id();
+    let (call_option, _option_bump) = get_pda_and_bump(
+        &["option".into(), option_id.into()],
+        &test_environment.program_id,
+    );
+    let vault = anchor_spl::associated_token::get_associated_token_address(
+        &call_option,
+        &test_environment.token_mint_a,
+    );
+    let option_mint = get_option_mint(&call_option, 1);
+    let expiry_ts = get_unix_timestamp(&test_environment.litesvm) + 60 * 60 * 24;
+
+    let write_option_instruction = build_write_option_instruction(
+        option_id,
+        3 * TOKEN_A,
+        2 * TOKEN_B,
+        TOKEN_B,
+        expiry_ts,
+        WriteOptionAccounts {
+            associated_token_program: anchor_spl::associated_token::ID,
+            token_program: anchor_spl::token::ID,
+            system_program: anchor_lang::system_program::ID,
+            writer: test_environment.alice.pubkey(),
+            underlying_mint: test_environment.token_mint_a,
+            quote_mint: test_environment.token_mint_b,
+            writer_underlying_account: test_environment.alice_token_account_a,
+            proceeds_account: test_environment.alice_token_account_b,
+            call_option,
+            vault,
+            option_mint,
+        },
+    );
+    let result = send_transaction_from_instructions(
+        &mut test_environment.litesvm,
+        vec![write_option_instruction],
+        &[&test_environment.alice],
+        &test_environment.alice.pubkey(),
+    );
+    assert!(result.is_ok(), "Writing the option should succeed");
+
+    // Bob buys the option
+    let bob = test_environment.bob.insecure_clone();
+    let bob_option_account = create_associated_token_account(
+        &mut test_environment.litesvm,
+        &bob.pubkey(),
+        &option_mint,
+        &bob,
+    )
+    .unwrap();
+    let buy_option_instruction = build_buy_option_instruction(BuyOptionAccounts {
+        token_program: anchor_spl::token::ID,
+        buyer: bob.pubkey(),
+        quote_mint: test_environment.token_mint_b,
+        buyer_quote_account: test_environment.bob_token_account_b,
+        buyer_option_account: bob_option_account,
+        proceeds_account: test_environment.alice_token_account_b,
+        option_mint,
+        call_option,
+    });
+    let result = send_transaction_from_instructions(
+        &mut test_environment.litesvm,
+        vec![buy_option_instruction],
+        &[&bob],
+        &bob.pubkey(),
+    );
+    assert!(result.is_ok(), "Buying the option should succeed");
+
+    // Alice can't take the underlying back while Bob can still exercise
+    let reclaim_accounts = || ReclaimOptionAccounts {
+        token_program: anchor_spl::token::ID,
+        writer: test_environment.alice.pubkey(),
+        underlying_mint: test_environment.token_mint_a,
+        writer_underlying_account: test_environment.alice_token_account_a,
+        call_option,
+        vault,
+    };
+    let result = send_transaction_from_instructions(
+        &mut test_environment.litesvm,
+        vec![build_reclaim_option_instruction(reclaim_accounts())],
+        &[&test_environment.alice],
+        &test_environment.alice.pubkey(),
+    );
+    assert!(
+        result.is_err(),
+        "Reclaiming a sold option before expiry should fail"
+    );
+
+    // Once the option expires, Bob can no longer exercise it
+    warp_to_unix_timestamp(&mut test_environment.litesvm, expiry_ts + 1);
+    let exercise_option_instruction = build_exercise_option_instruction(ExerciseOptionAccounts {
+        token_program: anchor_spl::token::ID,
+        holder: bob.pubkey(),
+        writer: test_environment.alice.pubkey(),
+        underlying_mint: test_environment.token_mint_a,
+        quote_mint: test_environment.token_mint_b,
+        option_mint,
+        holder_option_account: bob_option_account,
+        holder_quote_account: test_environment.bob_token_account_b,
+        holder_underlying_account: test_environment.bob_token_account_a,
+        proceeds_account: test_environment.alice_token_account_b,
+        call_option,
+        vault,
+    });
+    let result = send_transaction_from_instructions(
+        &mut test_environment.litesvm,
+        vec![exercise_option_instruction],
+        &[&bob],
+        &bob.pubkey(),
+    );
+    assert!(
+        result.is_err(),
+        "Exercising the option after expiry should fail"
+    );
+
+    // And Alice gets the underlying back
+    let result = send_transaction_from_instructions(
+        &mut test_environment.litesvm,
+        vec![build_reclaim_option_instruction(reclaim_accounts())],
+        &[&test_environment.alice],
+        &test_environment.alice.pubkey(),
+    );
+    assert!(
+        result.is_ok(),
+        "Reclaiming an expired option should succeed"
+    );
+    assert_token_balance(
+        &test_environment.litesvm,
+        &test_environment.alice_token_account_a,
+        10 * TOKEN_A,
+        "Alice should have her 3 token A underlying back",
+    );
+    check_account_is_closed(
+        &test_environment.litesvm,
+        &call_option,
+        "Option account should be closed after being reclaimed",
+    );
+}
+
+#[test]
+fn test_htlc_claimed_with_preimage_reveals_it() {
+    let mut test_environment = setup_escrow_test();
+
+    // Alice locks 3 token A for Bob behind the hash of a secret
+    let htlc_id = generate_offer_id();
+    let (htlc, _htlc_bump) = get_pda_and_bump(
+        &["htlc".into(), htlc_id.into()],
+        &test_environment.program_id,
+    );
+    let vault = anchor_spl::associated_token::get_associated_token_address(
+        &htlc,
+        &test_environment.token_mint_a,
+    );
+    let preimage = [7u8; 32];
+    let hashlock = solana_program::hash::hash(&preimage).to_bytes();
+    let timelock_ts = get_unix_timestamp(&test_environment.litesvm) + 60 * 60;
+
+    let create_htlc_instruction = build_create_htlc_instruction(
+        htlc_id,
+        test_environment.bob.pubkey(),
+        3 * TOKEN_A,
+        hashlock,
+        timelock_ts,
+        CreateHtlcAccounts {
+            associated_token_program: anchor_spl::associated_token::ID,
+            token_program: anchor_spl::token::ID,
+            system_program: anchor_lang::system_program::ID,
+            maker: test_environment.alice.pubkey(),
+            token_mint: test_environment.token_mint_a,
+            maker_token_account: test_environment.alice_token_account_a,
+            htlc,
+            vault,
+        },
+    );
+    let result = send_transaction_from_instructions(
+        &mut test_environment.litesvm,
+        vec![create_htlc_instruction],
+        &[&test_environment.alice],
+        &test_environment.alice.pubkey(),
+    );
+    assert!(result.is_ok(), "Creating the HTLC should succeed");
+
+    let claim_accounts = || ClaimHtlcAccounts {
+        token_program: anchor_spl::token::ID,
+        counterparty: test_environment.bob.pubkey(),
+        maker: test_environment.alice.pubkey(),
+        token_mint: test_environment.token_mint_a,
+        recipient: test_environment.bob_token_account_a,
+        htlc,
+        vault,
+    };
+
+    // Bob can't claim with the wrong secret
+    let wrong_claim_instruction = build_claim_htlc_instruction([8u8; 32], claim_accounts());
+    let result = send_transaction_from_instructions(
+        &mut test_environment.litesvm,
+        vec![wrong_claim_instruction],
+        &[&test_environment.bob],
+        &test_environment.bob.pubkey(),
+    );
+    assert!(result.is_err(), "Claiming with the wrong preimage should fail");
+
+    // Bob claims with the right secret
+    let claim_instruction = build_claim_htlc_instruction(preimage, claim_accounts());
+    let result = send_transaction_from_instructions(
+        &mut test_environment.litesvm,
+        vec![claim_instruction],
+        &[&test_environment.bob],
+        &test_environment.bob.pubkey(),
+    );
+    assert!(result.is_ok(), "Claiming with the preimage should succeed");
+
+    assert_token_balance(
+        &test_environment.litesvm,
+        &test_environment.bob_token_account_a,
+        3 * TOKEN_A,
+        "Bob should have received the 3 token A",
+    );
+    check_account_is_closed(
+        &test_environment.litesvm,
+        &vault,
+        "Vault should be closed after the HTLC is claimed",
+    );
+
+    // The preimage is now readable on-chain, for the other leg of the swap
+    let htlc_account = test_environment.litesvm.get_account(&htlc).unwrap();
+    let htlc_state =
+        crate::state::Htlc::try_deserialize(&mut htlc_account.data.as_slice()).unwrap();
+    assert!(htlc_state.claimed, "HTLC should be marked as claimed");
+    assert_eq!(htlc_state.preimage, preimage, "HTLC should store the revealed preimage");
+}
+
+#[test]
+fn test_htlc_refunded_to_maker_after_timelock() {
+    let mut test_environment = setup_escrow_test();
+
+    // Alice locks 3 token A for Bob behind the hash of a secret, for an hour
+    let htlc_id = generate_offer_id();
+    let (htlc, _htlc_bump) = get_pda_and_bump(
+        &["htlc".into(), htlc_id.into()],
+        &test_environment.program_id,
+    );
+    let vault = anchor_spl::associated_token::get_associated_token_address(
+        &htlc,
+        &test_environment.token_mint_a,
+    );
+    let preimage = [7u8; 32];
+    let hashlock = solana_program::hash::hash(&preimage).to_bytes();
+    let timelock_ts = get_unix_timestamp(&test_environment.litesvm) + 60 * 60;
+
+    let create_htlc_instruction = build_create_htlc_instruction(
+        htlc_id,
+        test_environment.bob.pubkey(),
+        3 * TOKEN_A,
+        hashlock,
+        timelock_ts,
+        CreateHtlcAccounts {
+            associated_token_program: anchor_spl::associated_token::ID,
+            token_program: anchor_spl::token::ID,
+            system_program: anchor_lang::system_program::ID,
+            maker: test_environment.alice.pubkey(),
+            token_mint: test_environment.token_mint_a,
+            maker_token_account: test_environment.alice_token_account_a,
+            htlc,
+            vault,
+        },
+    );
+    let result = send_transaction_from_instructions(
+        &mut test_environment.litesvm,
+        vec![create_htlc_instruction],
+        &[&test_environment.alice],
+        &test_environment.alice.pubkey(),
+    );
+    assert!(result.is_ok(), "Creating the HTLC should succeed");
+
+    // Alice can't take the tokens back while Bob can still claim them
+    let refund_accounts = || RefundHtlcAccounts {
+        token_program: anchor_spl::token::ID,
+        maker: test_environment.alice.pubkey(),
+        token_mint: test_environment.token_mint_a,
+        maker_token_account: test_environment.alice_token_account_a,
+        htlc,
+        vault,
+    };
+    let result = send_transaction_from_instructions(
+        &mut test_environment.litesvm,
+        vec![build_refund_htlc_instruction(refund_accounts())],
+        &[&test_environment.alice],
+        &test_environment.alice.pubkey(),
+    );
+    assert!(result.is_err(), "Refunding before the timelock should fail");
+
+    // Once the timelock passes, Bob can't claim, even with the right secret
+    warp_to_unix_timestamp(&mut test_environment.litesvm, timelock_ts + 1);
+    let claim_instruction = build_claim_htlc_instruction(
+        preimage,
+        ClaimHtlcAccounts {
+            token_program: anchor_spl::token::ID,
+            counterparty: test_environment.bob.pubkey(),
+            maker: test_environment.alice.pubkey(),
+            token_mint: test_environment.token_mint_a,
+            recipient: test_environment.bob_token_account_a,
+            htlc,
+            vault,
+        },
+    );
+    let result = send_transaction_from_instructions(
+        &mut test_environment.litesvm,
+        vec![claim_instruction],
+        &[&test_environment.bob],
+        &test_environment.bob.pubkey(),
+    );
+    assert!(result.is_err(), "Claiming after the timelock should fail");
+
+    // And Alice gets her tokens back
+    let result = send_transaction_from_instructions(
+        &mut test_environment.litesvm,
+        vec![build_refund_htlc_instruction(refund_accounts())],
+        &[&test_environment.alice],
+        &test_environment.alice.pubkey(),
+    );
+    assert!(
+        result.is_ok(),
+        "Refunding after the timelock should succeed"
+    );
+    assert_token_balance(
+        &test_environment.litesvm,
+        &test_environment.alice_token_account_a,
+        10 * TOKEN_A,
+        "Alice should have her 3 token A back",
+    );
+    check_account_is_closed(
+        &test_environment.litesvm,
+        &htlc,
+        "HTLC account should be closed after the refund",
+    );
+}
+
+#[test]
+fn test_lamport_stream_withdrawn_then_cancelled() {
+    let mut test_environment = setup_escrow_test();
+    let alice = test_environment.alice.insecure_clone();
+    let bob = test_environment.bob.insecure_clone();
+
+    // Alice streams 0.1 SOL to Bob over 100 seconds
+    let stream_id = generate_offer_id();
+    let (stream, _stream_bump) = get_pda_and_bump(
+        &["stream".into(), stream_id.into()],
+        &test_environment.program_id,
+    );
+    let start_ts = get_unix_timestamp(&test_environment.litesvm);
+    let end_ts = start_ts + 100;
+
+    let create_stream_instruction = build_create_stream_instruction(
+        stream_id,
+        bob.pubkey(),
+        100_000_000,
+        start_ts,
+        end_ts,
+        CreateStreamAccounts {
+            sender: alice.pubkey(),
+            stream,
+            token_accounts: None,
+        },
+    );
+    let result = send_transaction_from_instructions(
+        &mut test_environment.litesvm,
+        vec![create_stream_instruction],
+        &[&alice],
+        &alice.pubkey(),
+    );
+    assert!(result.is_ok(), "Creating the stream should succeed");
+
+    // A quarter of the way through, Bob withdraws what has accrued.
+    // Alice pays the transaction fee so Bob's balance only changes by the withdrawal
+    warp_to_unix_timestamp(&mut test_environment.litesvm, start_ts + 25);
+    let bob_lamports_before = test_environment.litesvm.get_balance(&bob.pubkey()).unwrap();
+    let withdraw_stream_instruction = build_withdraw_stream_instruction(WithdrawStreamAccounts {
+        recipient: bob.pubkey(),
+        stream,
+        token_accounts: None,
+    });
+    let result = send_transaction_from_instructions(
+        &mut test_environment.litesvm,
+        vec![withdraw_stream_instruction],
+        &[&bob, &alice],
+        &alice.pubkey(),
+    );
+    assert!(result.is_ok(), "Withdrawing from the stream should succeed");
+    let bob_lamports_after_withdrawal =
+        test_environment.litesvm.get_balance(&bob.pubkey()).unwrap();
+    assert_eq!(
+        bob_lamports_after_withdrawal - bob_lamports_before,
+        25_000_000,
+        "Bob should have received a quarter of the stream"
+    );
+
+    // Half way through, Alice cancels. Bob gets the second quarter that has accrued
+    warp_to_unix_timestamp(&mut test_environment.litesvm, start_ts + 50);
+    let cancel_stream_instruction = build_cancel_stream_instruction(CancelStreamAccounts {
+        caller: alice.pubkey(),
+        sender: alice.pubkey(),
+        recipient: bob.pubkey(),
+        stream,
+        token_accounts: None,
+    });
+    let result = send_transaction_from_instructions(
+        &mut test_environment.litesvm,
+        vec![cancel_stream_instruction],
+        &[&alice],
+        &alice.pubkey(),
+    );
+    assert!(result.is_ok(), "Cancelling the stream should succeed");
+    let bob_lamports_after_cancel = test_environment.litesvm.get_balance(&bob.pubkey()).unwrap();
+    assert_eq!(
+        bob_lamports_after_cancel - bob_lamports_after_withdrawal,
+        25_000_000,
+        "Bob should have received the second quarter of the stream"
+    );
+    check_account_is_closed(
+        &test_environment.litesvm,
+        &stream,
+        "Stream account should be closed after being cancelled",
+    );
+}
+
+#[test]
+fn test_stream_starting_before_epoch_fails() {
+    let mut test_environment = setup_escrow_test();
+    let alice = test_environment.alice.insecure_clone();
+    let bob = test_environment.bob.insecure_clone();
+
+    // Alice tries to stream 0.1 SOL to Bob starting before the Unix epoch
+    let stream_id = generate_offer_id();
+    let (stream, _stream_bump) = get_pda_and_bump(
+        &["stream".into(), stream_id.into()],
+        &test_environment.program_id,
+    );
+    let end_ts = get_unix_timestamp(&test_environment.litesvm) + 100;
+
+    let create_stream_instruction = build_create_stream_instruction(
+        stream_id,
+        bob.pubkey(),
+        100_000_000,
+        i64::MIN,
+        end_ts,
+        CreateStreamAccounts {
+            sender: alice.pubkey(),
+            stream,
+            token_accounts: None,
+        },
+    );
+    let result = send_transaction_from_instructions(
+        &mut test_environment.litesvm,
+        vec![create_stream_instruction],
+        &[&alice],
+        &alice.pubkey(),
+    );
+    assert!(result.is_err(), "Creating a stream that starts before the epoch should fail");
+}
+
+#[test]
+fn test_token_stream_cancelled_by_recipient_splits_balance() {
+    let mut test_environment = setup_escrow_test();
+
+    // Alice streams 4 token A to Bob over 100 seconds
+    let stream_id = generate_offer_id();
+    let (stream, _stream_bump) = get_pda_and_bump(
+        &["stream".into(), stream_id.into()],
+        &test_environment.program_id,
+    );
+    let vault = anchor_spl::associated_token::get_associated_token_address(
+        &stream,
+        &test_environment.token_mint_a,
+    );
+    let token_accounts = || StreamTokenAccounts {
+        token_program: anchor_spl::token::ID,
+        token_mint: test_environment.token_mint_a,
+        sender_token_account: test_environment.alice_token_account_a,
+        recipient_token_account: test_environment.bob_token_account_a,
+        vault,
+    };
+    let start_ts = get_unix_timestamp(&test_environment.litesvm);
+
+    let create_stream_instruction = build_create_stream_instruction(
+        stream_id,
+        test_environment.bob.pubkey(),
+        4 * TOKEN_A,
+        start_ts,
+        start_ts + 100,
+        CreateStreamAccounts {
+            sender: test_environment.alice.pubkey(),
+            stream,
+            token_accounts: Some(token_accounts()),
+        },
+    );
+    let result = send_transaction_from_instructions(
+        &mut test_environment.litesvm,
+        vec![create_stream_instruction],
+        &[&test_environment.alice],
+        &test_environment.alice.pubkey(),
+    );
+    assert!(result.is_ok(), "Creating the stream should succeed");
+
+    // A quarter of the way through, Bob cancels
+    warp_to_unix_timestamp(&mut test_environment.litesvm, start_ts + 25);
+    let cancel_stream_instruction = build_cancel_stream_instruction(CancelStreamAccounts {
+        caller: test_environment.bob.pubkey(),
+        sender: test_environment.alice.pubkey(),
+        recipient: test_environment.bob.pubkey(),
+        stream,
+        token_accounts: Some(token_accounts()),
+    });
+    let result = send_transaction_from_instructions(
+        &mut test_environment.litesvm,
+        vec![cancel_stream_instruction],
+        &[&test_environment.bob],
+        &test_environment.bob.pubkey(),
+    );
+    assert!(result.is_ok(), "Cancelling the stream should succeed");
+
+    assert_token_balance(
+        &test_environment.litesvm,
+        &test_environment.bob_token_account_a,
+        TOKEN_A,
+        "Bob should have received the quarter of the stream that accrued",
+    );
+    assert_token_balance(
+        &test_environment.litesvm,
+        &test_environment.alice_token_account_a,
+        9 * TOKEN_A,
+        "Alice should have the other 3 token A back",
+    );
+    check_account_is_closed(
+        &test_environment.litesvm,
+        &vault,
+        "Vault should be closed after the stream is cancelled",
+    );
+}
+
+#[test]
+fn test_contribution_refunded_when_campaign_misses_target() {
+    let mut test_environment = setup_escrow_test();
+
+    // Alice raises 5 token B
+    let campaign_id = generate_offer_id();
+    let (campaign, _campaign_bump) = get_pda_and_bump(
+        &["campaign".into(), campaign_id.into()],
+        &test_environment.program_id,
+    );
+    let vault = anchor_spl::associated_token::get_associated_token_address(
+        &campaign,
+        &test_environment.token_mint_b,
+    );
+    let deadline_ts = get_unix_timestamp(&test_environment.litesvm) + 60 * 60 * 24;
+
+    let create_campaign_instruction = build_create_campaign_instruction(
+        campaign_id,
+        5 * TOKEN_B,
+        deadline_ts,
+        CreateCampaignAccounts {
+            associated_token_program: anchor_spl::associated_token::ID,
+            token_program: anchor_spl::token::ID,
+            system_program: anchor_lang::system_program::ID,
+            creator: test_environment.alice.pubkey(),
+            token_mint: test_environment.token_mint_b,
+            campaign,
+            vault,
+        },
+    );
+    let result = send_transaction_from_instructions(
+        &mut test_environment.litesvm,
+        vec![create_campaign_instruction],
+        &[&test_environment.alice],
+        &test_environment.alice.pubkey(),
+    );
+    assert!(result.is_ok(), "Creating the campaign should succeed");
+
+    // Bob contributes 2 token B
+    let (contribution, _contribution_bump) = get_pda_and_bump(
+        &[
+            b"contribution".as_ref().into(),
+            campaign.as_ref().into(),
+            test_environment.bob.pubkey().as_ref().into(),
+        ],
+        &test_environment.program_id,
+    );
+    let contribute_instruction = build_contribute_instruction(
+        2 * TOKEN_B,
+        ContributeAccounts {
+            token_program: anchor_spl::token::ID,
+            system_program: anchor_lang::system_program::ID,
+            contributor: test_environment.bob.pubkey(),
+            token_mint: test_environment.token_mint_b,
+            contributor_token_account: test_environment.bob_token_account_b,
+            campaign,
+            contribution,
+            vault,
+        },
+    );
+    let result = send_transaction_from_instructions(
+        &mut test_environment.litesvm,
+        vec![contribute_instruction],
+        &[&test_environment.bob],
+        &test_environment.bob.pubkey(),
+    );
+    assert!(result.is_ok(), "Contributing should succeed");
+
+    // Alice can't withdraw, since the target hasn't been reached
+    let withdraw_instruction =
+        build_withdraw_campaign_funds_instruction(WithdrawCampaignFundsAccounts {
+            token_program: anchor_spl::token::ID,
+            creator: test_environment.alice.pubkey(),
+            token_mint: test_environment.token_mint_b,
+            proceeds_account: test_environment.alice_token_account_b,
+            campaign,
+            vault,
+        });
+    let result = send_transaction_from_instructions(
+        &mut test_environment.litesvm,
+        vec![withdraw_instruction],
+        &[&test_environment.alice],
+        &test_environment.alice.pubkey(),
+    );
+    assert!(
+        result.is_err(),
+        "Withdrawing should fail when the target hasn't been reached"
+    );
+
+    // After the deadline, Bob gets the contribution back
+    warp_to_unix_timestamp(&mut test_environment.litesvm, deadline_ts + 1);
+    let refund_instruction = build_refund_contribution_instruction(RefundContributionAccounts {
+        token_program: anchor_spl::token::ID,
+        contributor: test_environment.bob.pubkey(),
+        token_mint: test_environment.token_mint_b,
+        contributor_token_account: test_environment.bob_token_account_b,
+        creator: test_environment.alice.pubkey(),
+        campaign,
+        contribution,
+        vault,
+    });
+    let result = send_transaction_from_instructions(
+        &mut test_environment.litesvm,
+        vec![refund_instruction],
+        &[&test_environment.bob],
+        &test_environment.bob.pubkey(),
+    );
+    assert!(result.is_ok(), "Refunding the contribution should succeed");
+
+    assert_token_balance(
+        &test_environment.litesvm,
+        &test_environment.bob_token_account_b,
+        5 * TOKEN_B,
+        "Bob should have all 5 token B back",
+    );
+    check_account_is_closed(
+        &test_environment.litesvm,
+        &contribution,
+        "Contribution receipt should be closed after the refund",
+    );
+
+    // That was the only contribution, so the campaign is closed with it
+    check_account_is_closed(
+        &test_environment.litesvm,
+        &vault,
+        "Vault should be closed after the last refund",
+    );
+    check_account_is_closed(
+        &test_environment.litesvm,
+        &campaign,
+        "Campaign should be closed after the last refund",
+    );
+}
+
+#[test]
+fn test_transfer_fee_contribution_records_amount_received() {
+    let mut test_environment = setup_escrow_test();
+    let mint_authority = test_environment.mint_authority.insecure_clone();
+    let alice = test_environment.alice.insecure_clone();
+    let bob = test_environment.bob.insecure_clone();
+    let litesvm = &mut test_environment.litesvm;
+
+    // The token being raised charges 1% on every transfer
+    let token_mint = create_transfer_fee_mint(litesvm, &mint_authority, 100);
+    let bob_token_account = create_token_extensions_account(
+        litesvm,
+        &bob.pubkey(),
+        &token_mint,
+        &mint_authority,
+        5 * TOKEN_B,
+    );
+
+    let campaign_id = generate_offer_id();
+    let (campaign, _campaign_bump) = get_pda_and_bump(
+        &["campaign".into(), campaign_id.into()],
+        &test_environment.program_id,
+    );
+    let vault = anchor_spl::associated_token::get_associated_token_address_with_program_id(
+        &campaign,
+        &token_mint,
+        &anchor_spl::token_2022::ID,
+    );
+    let deadline_ts = get_unix_timestamp(litesvm) + 60 * 60 * 24;
+
+    let create_campaign_instruction = build_create_campaign_instruction(
+        campaign_id,
+        5 * TOKEN_B,
+        deadline_ts,
+        CreateCampaignAccounts {
+            associated_token_program: anchor_spl::associated_token::ID,
+            token_program: anchor_spl::token_2022::ID,
+            system_program: anchor_lang::system_program::ID,
+            creator: alice.pubkey(),
+            token_mint,
+            campaign,
+            vault,
+        },
+    );
+    let result = send_transaction_from_instructions(
+        litesvm,
+        vec![create_campaign_instruction],
+        &[&alice],
+        &alice.pubkey(),
+    );
+    assert!(result.is_ok(), "Creating the campaign should succeed");
+
+    // Bob contributes 2 tokens
+    let (contribution, _contribution_bump) = get_pda_and_bump(
+        &[
+            b"contribution".as_ref().into(),
+            campaign.as_ref().into(),
+            bob.pubkey().as_ref().into(),
+        ],
+        &test_environment.program_id,
+    );
+    let contribute_instruction = build_contribute_instruction(
+        2 * TOKEN_B,
+        ContributeAccounts {
+            token_program: anchor_spl::token_2022::ID,
+            system_program: anchor_lang::system_program::ID,
+            contributor: bob.pubkey(),
+            token_mint,
+            contributor_token_account: bob_token_account,
+            campaign,
+            contribution,
+            vault,
+        },
+    );
+    let result = send_transaction_from_instructions(
+        litesvm,
+        vec![contribute_instruction],
+        &[&bob],
+        &bob.pubkey(),
+    );
+    assert!(
+        result.is_ok(),
+        "Contributing a transfer fee token should succeed"
+    );
+
+    // The fee is taken on the way into the vault, so only what arrived is counted
+    let amount_received = 2 * TOKEN_B - 2 * TOKEN_B / 100;
+    assert_eq!(
+        get_token_extensions_balance(litesvm, &vault),
+        amount_received
+    );
+    let campaign_data = litesvm.get_account(&campaign).unwrap().data;
+    let campaign_state =
+        crate::state::Campaign::try_deserialize(&mut campaign_data.as_slice()).unwrap();
+    assert_eq!(
+        campaign_state.raised_amount, amount_received,
+        "The campaign should count what reached the vault"
+    );
+    let contribution_data = litesvm.get_account(&contribution).unwrap().data;
+    let contribution_state =
+        crate::state::Contribution::try_deserialize(&mut contribution_data.as_slice()).unwrap();
+    assert_eq!(
+        contribution_state.amount, amount_received,
+        "The receipt should record what reached the vault, so the refund can be covered"
+    );
+}
+
+#[test]
+fn test_creator_closes_campaign_nobody_contributed_to() {
+    let mut test_environment = setup_escrow_test();
+
+    // Alice raises 5 token B, but nobody contributes
+    let campaign_id = generate_offer_id();
+    let (campaign, _campaign_bump) = get_pda_and_bump(
+        &["campaign".into(), campaign_id.into()],
+        &test_environment.program_id,
+    );
+    let vault = anchor_spl::associated_token::get_associated_token_address(
+        &campaign,
+        &test_environment.token_mint_b,
+    );
+    let deadline_ts = get_unix_timestamp(&test_environment.litesvm) + 60 * 60 * 24;
+
+    let create_campaign_instruction = build_create_campaign_instruction(
+        campaign_id,
+        5 * TOKEN_B,
+        deadline_ts,
+        CreateCampaignAccounts {
+            associated_token_program: anchor_spl::associated_token::ID,
+            token_program: anchor_spl::token::ID,
+            system_program: anchor_lang::system_program::ID,
+            creator: test_environment.alice.pubkey(),
+            token_mint: test_environment.token_mint_b,
+            campaign,
+            vault,
+        },
+    );
+    let result = send_transaction_from_instructions(
+        &mut test_environment.litesvm,
+        vec![create_campaign_instruction],
+        &[&test_environment.alice],
+        &test_environment.alice.pubkey(),
+    );
+    assert!(result.is_ok(), "Creating the campaign should succeed");
+
+    let close_campaign_accounts = || CloseCampaignAccounts {
+        token_program: anchor_spl::token::ID,
+        creator: test_environment.alice.pubkey(),
+        token_mint: test_environment.token_mint_b,
+        proceeds_account: test_environment.alice_token_account_b,
+        campaign,
+        vault,
+    };
+
+    // Alice can't close the campaign while it's still running
+    let close_instruction = build_close_campaign_instruction(close_campaign_accounts());
+    let result = send_transaction_from_instructions(
+        &mut test_environment.litesvm,
+        vec![close_instruction],
+        &[&test_environment.alice],
+        &test_environment.alice.pubkey(),
+    );
+    assert!(
+        result.is_err(),
+        "Closing the campaign before the deadline should fail"
+    );
+
+    // After the deadline, there's no refund to close it, so Alice closes it herself
+    warp_to_unix_timestamp(&mut test_environment.litesvm, deadline_ts + 1);
+    let close_instruction = build_close_campaign_instruction(close_campaign_accounts());
+    let result = send_transaction_from_instructions(
+        &mut test_environment.litesvm,
+        vec![close_instruction],
+        &[&test_environment.alice],
+        &test_environment.alice.pubkey(),
+    );
+    assert!(result.is_ok(), "Closing the campaign should succeed");
+
+    check_account_is_closed(
+        &test_environment.litesvm,
+        &vault,
+        "Vault should be closed with the campaign",
+    );
+    check_account_is_closed(
+        &test_environment.litesvm,
+        &campaign,
+        "Campaign should be closed",
+    );
+}
+
+#[test]
+fn test_campaign_funds_withdrawn_when_target_reached() {
+    let mut test_environment = setup_escrow_test();
+
+    // Alice raises 5 token B
+    let campaign_id = generate_offer_id();
+    let (campaign, _campaign_bump) = get_pda_and_bump(
+        &["campaign".into(), campaign_id.into()],
+        &test_environment.program_id,
+    );
+    let vault = anchor_spl::associated_token::get_associated_token_address(
+        &campaign,
+        &test_environment.token_mint_b,
+    );
+    let deadline_ts = get_unix_timestamp(&test_environment.litesvm) + 60 * 60 * 24;
+
+    let create_campaign_instruction = build_create_campaign_instruction(
+        campaign_id,
+        5 * TOKEN_B,
+        deadline_ts,
+        CreateCampaignAccounts {
+            associated_token_program: anchor_spl::associated_token::ID,
+            token_program: anchor_spl::token::ID,
+            system_program: anchor_lang::system_program::ID,
+            creator: test_environment.alice.pubkey(),
+            token_mint: test_environment.token_mint_b,
+            campaign,
+            vault,
+        },
+    );
+    send_transaction_from_instructions(
+        &mut test_environment.litesvm,
+        vec![create_campaign_instruction],
+        &[&test_environment.alice],
+        &test_environment.alice.pubkey(),
+    )
+    .unwrap();
+
+    // Bob contributes 3 token B, then 2 more, reaching the target
+    let (contribution, _contribution_bump) = get_pda_and_bump(
+        &[
+            b"contribution".as_ref().into(),
+            campaign.as_ref().into(),
+            test_environment.bob.pubkey().as_ref().into(),
+        ],
+        &test_environment.program_id,
+    );
+    for amount in [3 * TOKEN_B, 2 * TOKEN_B] {
+        let contribute_instruction = build_contribute_instruction(
+            amount,
+            ContributeAccounts {
+                token_program: anchor_spl::token::ID,
+                system_program: anchor_lang::system_program::ID,
+                contributor: test_environment.bob.pubkey(),
+                token_mint: test_environment.token_mint_b,
+                contributor_token_account: test_environment.bob_token_account_b,
+                campaign,
+                contribution,
+                vault,
+            },
+        );
+        let result = send_transaction_from_instructions(
+            &mut test_environment.litesvm,
+            vec![contribute_instruction],
+            &[&test_environment.bob],
+            &test_environment.bob.pubkey(),
+        );
+        assert!(result.is_ok(), "Contributing should succeed");
+    }
+
+    // Bob can't close the receipt while the campaign is still open
+    let close_contribution_instruction =
+        build_close_contribution_instruction(test_environment.bob.pubkey(), campaign, contribution);
+    let result = send_transaction_from_instructions(
+        &mut test_environment.litesvm,
+        vec![close_contribution_instruction.clone()],
+        &[&test_environment.bob],
+        &test_environment.bob.pubkey(),
+    );
+    assert!(
+        result.is_err(),
+        "Closing the receipt should fail while the campaign is open"
+    );
+
+    // Only the creator can withdraw the funds
+    let bob_withdraw_instruction =
+        build_withdraw_campaign_funds_instruction(WithdrawCampaignFundsAccounts {
+            token_program: anchor_spl::token::ID,
+            creator: test_environment.bob.pubkey(),
+            token_mint: test_environment.token_mint_b,
+            proceeds_account: test_environment.bob_token_account_b,
+            campaign,
+            vault,
+        });
+    let result = send_transaction_from_instructions(
+        &mut test_environment.litesvm,
+        vec![bob_withdraw_instruction],
+        &[&test_environment.bob],
+        &test_environment.bob.pubkey(),
+    );
+    assert!(result.is_err(), "Only the creator should be able to withdraw");
+
+    // The target has been reached, so Alice can withdraw before the deadline
+    let withdraw_instruction =
+        build_withdraw_campaign_funds_instruction(WithdrawCampaignFundsAccounts {
+            token_program: anchor_spl::token::ID,
+            creator: test_environment.alice.pubkey(),
+            token_mint: test_environment.token_mint_b,
+            proceeds_account: test_environment.alice_token_account_b,
+            campaign,
+            vault,
+        });
+    let result = send_transaction_from_instructions(
+        &mut test_environment.litesvm,
+        vec![withdraw_instruction],
+        &[&test_environment.alice],
+        &test_environment.alice.pubkey(),
+    );
+    assert!(result.is_ok(), "Withdrawing should succeed once the target is reached");
+
+    assert_token_balance(
+        &test_environment.litesvm,
+        &test_environment.alice_token_account_b,
+        5 * TOKEN_B,
+        "Alice should have received the 5 token B raised",
+    );
+    check_account_is_closed(
+        &test_environment.litesvm,
+        &vault,
+        "Vault should be closed after the withdrawal",
+    );
+    check_account_is_closed(
+        &test_environment.litesvm,
+        &campaign,
+        "Campaign should be closed after the withdrawal",
+    );
+
+    // Now the campaign is finished, Bob can close the receipt and get the rent back
+    test_environment.litesvm.expire_blockhash();
+    let result = send_transaction_from_instructions(
+        &mut test_environment.litesvm,
+        vec![close_contribution_instruction],
+        &[&test_environment.bob],
+        &test_environment.bob.pubkey(),
+    );
+    assert!(result.is_ok(), "Closing the receipt should succeed after the withdrawal");
+    check_account_is_closed(
+        &test_environment.litesvm,
+        &contribution,
+        "Contribution receipt should be closed",
+    );
+}
+
+#[test]
+fn test_bounty_awarded_by_judge_and_remainder_returned() {
+    let mut test_environment = setup_escrow_test();
+    let judge = Keypair::new();
+
+    // Alice sponsors a 4 token A bounty, judged by someone else
+    let bounty_id = generate_offer_id();
+    let (bounty, _bounty_bump) = get_pda_and_bump(
+        &["bounty".into(), bounty_id.into()],
+        &test_environment.program_id,
+    );
+    let vault = anchor_spl::associated_token::get_associated_token_address(
+        &bounty,
+        &test_environment.token_mint_a,
+    );
+    let deadline_ts = get_unix_timestamp(&test_environment.litesvm) + 60 * 60 * 24;
+    let award_deadline_ts = deadline_ts + 60 * 60 * 24;
+
+    let create_bounty_instruction = build_create_bounty_instruction(
+        bounty_id,
+        judge.pubkey(),
+        4 * TOKEN_A,
+        deadline_ts,
+        award_deadline_ts,
+        CreateBountyAccounts {
+            associated_token_program: anchor_spl::associated_token::ID,
+            token_program: anchor_spl::token::ID,
+            system_program: anchor_lang::system_program::ID,
+            sponsor: test_environment.alice.pubkey(),
+            token_mint: test_environment.token_mint_a,
+            sponsor_token_account: test_environment.alice_token_account_a,
+            bounty,
+            vault,
+        },
+    );
+    let result = send_transaction_from_instructions(
+        &mut test_environment.litesvm,
+        vec![create_bounty_instruction],
+        &[&test_environment.alice],
+        &test_environment.alice.pubkey(),
+    );
+    assert!(result.is_ok(), "Creating the bounty should succeed");
+
+    // Bob submits a claim
+    let (claim, _claim_bump) = get_pda_and_bump(
+        &[
+            b"claim".as_ref().into(),
+            bounty.as_ref().into(),
+            test_environment.bob.pubkey().as_ref().into(),
+        ],
+        &test_environment.program_id,
+    );
+    let submit_claim_instruction = build_submit_claim_instruction(
+        "https://github.com/bob/hackathon-entry",
+        SubmitClaimAccounts {
+            system_program: anchor_lang::system_program::ID,
+            claimant: test_environment.bob.pubkey(),
+            bounty,
+            claim,
+        },
+    );
+    let result = send_transaction_from_instructions(
+        &mut test_environment.litesvm,
+        vec![submit_claim_instruction],
+        &[&test_environment.bob],
+        &test_environment.bob.pubkey(),
+    );
+    assert!(result.is_ok(), "Submitting a claim should succeed");
+
+    // Carol submits a claim too, which won't be awarded
+    let carol = Keypair::new();
+    test_environment
+        .litesvm
+        .airdrop(&carol.pubkey(), 1_000_000_000)
+        .unwrap();
+    let (carol_claim, _carol_claim_bump) = get_pda_and_bump(
+        &[
+            b"claim".as_ref().into(),
+            bounty.as_ref().into(),
+            carol.pubkey().as_ref().into(),
+        ],
+        &test_environment.program_id,
+    );
+    let submit_claim_instruction = build_submit_claim_instruction(
+        "https://github.com/carol/hackathon-entry",
+        SubmitClaimAccounts {
+            system_program: anchor_lang::system_program::ID,
+            claimant: carol.pubkey(),
+            bounty,
+            claim: carol_claim,
+        },
+    );
+    let result = send_transaction_from_instructions(
+        &mut test_environment.litesvm,
+        vec![submit_claim_instruction],
+        &[&carol],
+        &carol.pubkey(),
+    );
+    assert!(result.is_ok(), "Submitting a second claim should succeed");
+
+    // Carol can't close the claim while it could still be awarded
+    let close_claim_instruction =
+        build_close_claim_instruction(carol.pubkey(), bounty, carol_claim);
+    let result = send_transaction_from_instructions(
+        &mut test_environment.litesvm,
+        vec![close_claim_instruction.clone()],
+        &[&carol],
+        &carol.pubkey(),
+    );
+    assert!(result.is_err(), "Closing a claim should fail while the bounty is open");
+
+    // The judge awards Bob 3 token A. Alice pays the transaction fee
+    let award_claim_instruction = build_award_claim_instruction(
+        3 * TOKEN_A,
+        AwardClaimAccounts {
+            token_program: anchor_spl::token::ID,
+            awarder: judge.pubkey(),
+            claimant: test_environment.bob.pubkey(),
+            token_mint: test_environment.token_mint_a,
+            claimant_token_account: test_environment.bob_token_account_a,
+            bounty,
+            claim,
+            vault,
+        },
+    );
+    let result = send_transaction_from_instructions(
+        &mut test_environment.litesvm,
+        vec![award_claim_instruction],
+        &[&judge, &test_environment.alice],
+        &test_environment.alice.pubkey(),
+    );
+    assert!(result.is_ok(), "The judge should be able to award the claim");
+    assert_token_balance(
+        &test_environment.litesvm,
+        &test_environment.bob_token_account_a,
+        3 * TOKEN_A,
+        "Bob should have received the 3 token A award",
+    );
+
+    // Alice can't close the bounty once submissions end, while the judge can still award claims
+    warp_to_unix_timestamp(&mut test_environment.litesvm, deadline_ts + 1);
+    let close_bounty_instruction = build_close_bounty_instruction(CloseBountyAccounts {
+        token_program: anchor_spl::token::ID,
+        sponsor: test_environment.alice.pubkey(),
+        token_mint: test_environment.token_mint_a,
+        sponsor_token_account: test_environment.alice_token_account_a,
+        bounty,
+        vault,
+    });
+    let result = send_transaction_from_instructions(
+        &mut test_environment.litesvm,
+        vec![close_bounty_instruction.clone()],
+        &[&test_environment.alice],
+        &test_environment.alice.pubkey(),
+    );
+    assert!(
+        result.is_err(),
+        "Closing the bounty should fail before the award deadline"
+    );
+
+    // After the award deadline, Alice takes back the 1 token A that wasn't awarded
+    warp_to_unix_timestamp(&mut test_environment.litesvm, award_deadline_ts + 1);
+    test_environment.litesvm.expire_blockhash();
+    let result = send_transaction_from_instructions(
+        &mut test_environment.litesvm,
+        vec![close_bounty_instruction],
+        &[&test_environment.alice],
+        &test_environment.alice.pubkey(),
+    );
+    assert!(result.is_ok(), "Closing the bounty should succeed");
+    assert_token_balance(
+        &test_environment.litesvm,
+        &test_environment.alice_token_account_a,
+        7 * TOKEN_A,
+        "Alice should have the unawarded token A back",
+    );
+    check_account_is_closed(
+        &test_environment.litesvm,
+        &bounty,
+        "Bounty account should be closed",
+    );
+
+    // Now the bounty is closed, Carol can close the unawarded claim and get the rent back
+    test_environment.litesvm.expire_blockhash();
+    let result = send_transaction_from_instructions(
+        &mut test_environment.litesvm,
+        vec![close_claim_instruction],
+        &[&carol],
+        &carol.pubkey(),
+    );
+    assert!(result.is_ok(), "Closing the claim should succeed after the bounty is closed");
+    check_account_is_closed(
+        &test_environment.litesvm,
+        &carol_claim,
+        "Unawarded claim should be closed",
+    );
+}
+
+#[test]
+fn test_transfer_fee_bounty_records_reward_received() {
+    let mut test_environment = setup_escrow_test();
+    let mint_authority = test_environment.mint_authority.insecure_clone();
+    let alice = test_environment.alice.insecure_clone();
+    let judge = Keypair::new();
+    let litesvm = &mut test_environment.litesvm;
+
+    // The reward token charges 1% on every transfer
+    let token_mint = create_transfer_fee_mint(litesvm, &mint_authority, 100);
+    let alice_token_account = create_token_extensions_account(
+        litesvm,
+        &alice.pubkey(),
+        &token_mint,
+        &mint_authority,
+        10 * TOKEN_A,
+    );
+
+    // Alice sponsors a 4 token bounty
+    let bounty_id = generate_offer_id();
+    let (bounty, _bounty_bump) = get_pda_and_bump(
+        &["bounty".into(), bounty_id.into()],
+        &test_environment.program_id,
+    );
+    let vault = anchor_spl::associated_token::get_associated_token_address_with_program_id(
+        &bounty,
+        &token_mint,
+        &anchor_spl::token_2022::ID,
+    );
+    let deadline_ts = get_unix_timestamp(litesvm) + 60 * 60 * 24;
+
+    let create_bounty_instruction = build_create_bounty_instruction(
+        bounty_id,
+        judge.pubkey(),
+        4 * TOKEN_A,
+        deadline_ts,
+        deadline_ts + 60 * 60 * 24,
+        CreateBountyAccounts {
+            associated_token_program: anchor_spl::associated_token::ID,
+            token_program: anchor_spl::token_2022::ID,
+            system_program: anchor_lang::system_program::ID,
+            sponsor: alice.pubkey(),
+            token_mint,
+            sponsor_token_account: alice_token_account,
+            bounty,
+            vault,
+        },
+    );
+    let result = send_transaction_from_instructions(
+        litesvm,
+        vec![create_bounty_instruction],
+        &[&alice],
+        &alice.pubkey(),
+    );
+    assert!(
+        result.is_ok(),
+        "Creating a bounty with a transfer fee token should succeed"
+    );
+
+    // The fee is taken on the way into the vault, so the reward is what arrived
+    let reward_received = 4 * TOKEN_A - 4 * TOKEN_A / 100;
+    let bounty_data = litesvm.get_account(&bounty).unwrap().data;
+    let bounty_state = crate::state::Bounty::try_deserialize(&mut bounty_data.as_slice()).unwrap();
+    assert_eq!(
+        bounty_state.reward_amount, reward_received,
+        "The bounty should record the reward that reached the vault"
+    );
+}
+
+#[test]
+fn test_listing_payment_auto_releases_after_confirmation_window() {
+    let mut test_environment = setup_escrow_test();
+
+    // Alice lists an item for 2 token B, with a one day confirmation window
+    let listing_id = generate_offer_id();
+    let (listing, _listing_bump) = get_pda_and_bump(
+        &["listing".into(), listing_id.into()],
+        &test_environment.program_id,
+    );
+    let vault = anchor_spl::associated_token::get_associated_token_address(
+        &listing,
+        &test_environment.token_mint_b,
+    );
+    let confirmation_window_secs = 60 * 60 * 24;
+
+    let create_listing_instruction = build_create_listing_instruction(
+        listing_id,
+        2 * TOKEN_B,
+        Keypair::new().pubkey(),
+        confirmation_window_secs,
+        60 * 60 * 24,
+        CreateListingAccounts {
+            associated_token_program: anchor_spl::associated_token::ID,
+            token_program: anchor_spl::token::ID,
+            system_program: anchor_lang::system_program::ID,
+            seller: test_environment.alice.pubkey(),
+            token_mint: test_environment.token_mint_b,
+            listing,
+            vault,
+        },
+    );
+    let result = send_transaction_from_instructions(
+        &mut test_environment.litesvm,
+        vec![create_listing_instruction],
+        &[&test_environment.alice],
+        &test_environment.alice.pubkey(),
+    );
+    assert!(result.is_ok(), "Creating the listing should succeed");
+
+    // Bob buys it
+    let purchase_listing_instruction =
+        build_purchase_listing_instruction(PurchaseListingAccounts {
+            token_program: anchor_spl::token::ID,
+            buyer: test_environment.bob.pubkey(),
+            token_mint: test_environment.token_mint_b,
+            buyer_token_account: test_environment.bob_token_account_b,
+            listing,
+            vault,
+        });
+    let result = send_transaction_from_instructions(
+        &mut test_environment.litesvm,
+        vec![purchase_listing_instruction],
+        &[&test_environment.bob],
+        &test_environment.bob.pubkey(),
+    );
+    assert!(result.is_ok(), "Purchasing the listing should succeed");
+
+    // Alice ships it
+    let mark_shipped_instruction = build_mark_shipped_instruction(MarkShippedAccounts {
+        seller: test_environment.alice.pubkey(),
+        listing,
+    });
+    let result = send_transaction_from_instructions(
+        &mut test_environment.litesvm,
+        vec![mark_shipped_instruction],
+        &[&test_environment.alice],
+        &test_environment.alice.pubkey(),
+    );
+    assert!(result.is_ok(), "Marking the listing shipped should succeed");
+    let shipped_ts = get_unix_timestamp(&test_environment.litesvm);
+
+    let confirm_receipt_instruction = || {
+        build_confirm_receipt_instruction(ConfirmReceiptAccounts {
+            token_program: anchor_spl::token::ID,
+            caller: test_environment.alice.pubkey(),
+            seller: test_environment.alice.pubkey(),
+            token_mint: test_environment.token_mint_b,
+            seller_token_account: test_environment.alice_token_account_b,
+            listing,
+            vault,
+        })
+    };
+
+    // Bob never confirms. Alice can't release the payment during the window
+    let result = send_transaction_from_instructions(
+        &mut test_environment.litesvm,
+        vec![confirm_receipt_instruction()],
+        &[&test_environment.alice],
+        &test_environment.alice.pubkey(),
+    );
+    assert!(
+        result.is_err(),
+        "Only the buyer should be able to release payment during the confirmation window"
+    );
+
+    // But can once it has passed. We also expire the blockhash so the retried
+    // transaction gets a new signature
+    warp_to_unix_timestamp(
+        &mut test_environment.litesvm,
+        shipped_ts + confirmation_window_secs + 1,
+    );
+    test_environment.litesvm.expire_blockhash();
+    let result = send_transaction_from_instructions(
+        &mut test_environment.litesvm,
+        vec![confirm_receipt_instruction()],
+        &[&test_environment.alice],
+        &test_environment.alice.pubkey(),
+    );
+    assert!(
+        result.is_ok(),
+        "Anyone should be able to release payment after the confirmation window"
+    );
+
+    assert_token_balance(
+        &test_environment.litesvm,
+        &test_environment.alice_token_account_b,
+        2 * TOKEN_B,
+        "Alice should have been paid 2 token B",
+    );
+    check_account_is_closed(
+        &test_environment.litesvm,
+        &listing,
+        "Listing account should be closed after payment is released",
+    );
+}
+
+#[test]
+fn test_listing_dispute_split_by_arbiter() {
+    let mut test_environment = setup_escrow_test();
+    let arbiter = Keypair::new();
+    let (listing, vault) =
+        execute_create_and_purchase_listing(&mut test_environment, arbiter.pubkey());
+
+    // Only the buyer can open a dispute
+    let alice_dispute_instruction =
+        build_open_dispute_instruction(test_environment.alice.pubkey(), listing);
+    let result = send_transaction_from_instructions(
+        &mut test_environment.litesvm,
+        vec![alice_dispute_instruction],
+        &[&test_environment.alice],
+        &test_environment.alice.pubkey(),
+    );
+    assert!(result.is_err(), "Only the buyer should be able to open a dispute");
+
+    // Bob disputes before the item ships
+    let open_dispute_instruction =
+        build_open_dispute_instruction(test_environment.bob.pubkey(), listing);
+    let result = send_transaction_from_instructions(
+        &mut test_environment.litesvm,
+        vec![open_dispute_instruction],
+        &[&test_environment.bob],
+        &test_environment.bob.pubkey(),
+    );
+    assert!(result.is_ok(), "The buyer should be able to dispute before shipping");
+
+    // The payment is frozen, so Alice can't mark the item shipped any more
+    let mark_shipped_instruction = build_mark_shipped_instruction(MarkShippedAccounts {
+        seller: test_environment.alice.pubkey(),
+        listing,
+    });
+    let result = send_transaction_from_instructions(
+        &mut test_environment.litesvm,
+        vec![mark_shipped_instruction],
+        &[&test_environment.alice],
+        &test_environment.alice.pubkey(),
+    );
+    assert!(result.is_err(), "A disputed listing should not be marked shipped");
+
+    let resolve_dispute_instruction = |arbiter, buyer_amount| {
+        build_resolve_dispute_instruction(
+            buyer_amount,
+            ResolveDisputeAccounts {
+                token_program: anchor_spl::token::ID,
+                arbiter,
+                seller: test_environment.alice.pubkey(),
+                token_mint: test_environment.token_mint_b,
+                buyer_token_account: test_environment.bob_token_account_b,
+                seller_token_account: test_environment.alice_token_account_b,
+                listing,
+                vault,
+            },
+        )
+    };
+
+    // Only the arbiter can resolve it
+    let result = send_transaction_from_instructions(
+        &mut test_environment.litesvm,
+        vec![resolve_dispute_instruction(test_environment.alice.pubkey(), 0)],
+        &[&test_environment.alice],
+        &test_environment.alice.pubkey(),
+    );
+    assert!(result.is_err(), "Only the arbiter should be able to resolve a dispute");
+
+    // The arbiter can't refund more than the price
+    let result = send_transaction_from_instructions(
+        &mut test_environment.litesvm,
+        vec![resolve_dispute_instruction(arbiter.pubkey(), 3 * TOKEN_B)],
+        &[&arbiter, &test_environment.alice],
+        &test_environment.alice.pubkey(),
+    );
+    assert!(result.is_err(), "Refunding more than the price should fail");
+
+    // The arbiter refunds Bob 1.5 token B, and Alice gets the other 0.5
+    let result = send_transaction_from_instructions(
+        &mut test_environment.litesvm,
+        vec![resolve_dispute_instruction(arbiter.pubkey(), 3 * TOKEN_B / 2)],
+        &[&arbiter, &test_environment.alice],
+        &test_environment.alice.pubkey(),
+    );
+    assert!(result.is_ok(), "The arbiter should be able to resolve the dispute");
+
+    assert_token_balance(
+        &test_environment.litesvm,
+        &test_environment.bob_token_account_b,
+        3 * TOKEN_B + 3 * TOKEN_B / 2,
+        "Bob should have been refunded 1.5 token B",
+    );
+    assert_token_balance(
+        &test_environment.litesvm,
+        &test_environment.alice_token_account_b,
+        TOKEN_B / 2,
+        "Alice should have been paid 0.5 token B",
+    );
+    check_account_is_closed(
+        &test_environment.litesvm,
+        &vault,
+        "Vault should be closed once the dispute is resolved",
+    );
+    check_account_is_closed(
+        &test_environment.litesvm,
+        &listing,
+        "Listing account should be closed once the dispute is resolved",
+    );
+}
+
+#[test]
+fn test_listing_dispute_after_confirmation_window_fails() {
+    let mut test_environment = setup_escrow_test();
+    let (listing, _vault) =
+        execute_create_and_purchase_listing(&mut test_environment, Keypair::new().pubkey());
+
+    let mark_shipped_instruction = build_mark_shipped_instruction(MarkShippedAccounts {
+        seller: test_environment.alice.pubkey(),
+        listing,
+    });
+    send_transaction_from_instructions(
+        &mut test_environment.litesvm,
+        vec![mark_shipped_instruction],
+        &[&test_environment.alice],
+        &test_environment.alice.pubkey(),
+    )
+    .unwrap();
+    let shipped_ts = get_unix_timestamp(&test_environment.litesvm);
+
+    // Once the confirmation window has passed, Bob can no longer dispute
+    warp_to_unix_timestamp(&mut test_environment.litesvm, shipped_ts + 60 * 60 * 24 + 1);
+    let open_dispute_instruction =
+        build_open_dispute_instruction(test_environment.bob.pubkey(), listing);
+    let result = send_transaction_from_instructions(
+        &mut test_environment.litesvm,
+        vec![open_dispute_instruction],
+        &[&test_environment.bob],
+        &test_environment.bob.pubkey(),
+    );
+    assert!(
+        result.is_err(),
+        "Disputing after the confirmation window should fail"
+    );
+}
+
+#[test]
+fn test_buyer_reclaims_payment_when_seller_never_ships() {
+    let mut test_environment = setup_escrow_test();
+    let (listing, vault) =
+        execute_create_and_purchase_listing(&mut test_environment, Keypair::new().pubkey());
+    let purchased_ts = get_unix_timestamp(&test_environment.litesvm);
+
+    let reclaim_payment_instruction = build_reclaim_payment_instruction(ReclaimPaymentAccounts {
+        token_program: anchor_spl::token::ID,
+        buyer: test_environment.bob.pubkey(),
+        seller: test_environment.alice.pubkey(),
+        token_mint: test_environment.token_mint_b,
+        buyer_token_account: test_environment.bob_token_account_b,
+        listing,
+        vault,
+    });
+
+    // Bob can't take his payment back while Alice still has time to ship
+    let result = send_transaction_from_instructions(
+        &mut test_environment.litesvm,
+        vec![reclaim_payment_instruction.clone()],
+        &[&test_environment.bob],
+        &test_environment.bob.pubkey(),
+    );
+    assert!(
+        result.is_err(),
+        "Reclaiming the payment should fail before the response window passes"
+    );
+
+    // Alice never ships, so once the response window passes Bob takes his payment back
+    warp_to_unix_timestamp(&mut test_environment.litesvm, purchased_ts + 60 * 60 * 24 + 1);
+    test_environment.litesvm.expire_blockhash();
+    let result = send_transaction_from_instructions(
+        &mut test_environment.litesvm,
+        vec![reclaim_payment_instruction],
+        &[&test_environment.bob],
+        &test_environment.bob.pubkey(),
+    );
+    assert!(
+        result.is_ok(),
+        "Reclaiming the payment should succeed once the seller has missed the response window"
+    );
+
+    assert_token_balance(
+        &test_environment.litesvm,
+        &test_environment.bob_token_account_b,
+        5 * TOKEN_B,
+        "Bob should have all 5 token B back",
+    );
+    check_account_is_closed(
+        &test_environment.litesvm,
+        &listing,
+        "Listing account should be closed after the payment is reclaimed",
+    );
+}
+
+#[test]
+fn test_listing_with_overlong_confirmation_window_fails() {
+    let mut test_environment = setup_escrow_test();
+
+    let listing_id = generate_offer_id();
+    let (listing, _listing_bump) = get_pda_and_bump(
+        &["listing".into(), listing_id.into()],
+        &test_environment.program_id,
+    );
+    let vault = anchor_spl::associated_token::get_associated_token_address(
+        &listing,
+        &test_environment.token_mint_b,
+    );
+    let create_listing_instruction = build_create_listing_instruction(
+        listing_id,
+        2 * TOKEN_B,
+        Keypair::new().pubkey(),
+        i64::MAX,
+        60 * 60 * 24,
+        CreateListingAccounts {
+            associated_token_program: anchor_spl::associated_token::ID,
+            token_program: anchor_spl::token::ID,
+            system_program: anchor_lang::system_program::ID,
+            seller: test_environment.alice.pubkey(),
+            token_mint: test_environment.token_mint_b,
+            listing,
+            vault,
+        },
+    );
+    let result = send_transaction_from_instructions(
+        &mut test_environment.litesvm,
+        vec![create_listing_instruction],
+        &[&test_environment.alice],
+        &test_environment.alice.pubkey(),
+    );
+    assert!(
+        result.is_err(),
+        "A confirmation window longer than the maximum should be rejected"
+    );
+}
+
+#[test]
+fn test_draw_refunds_both_stakes() {
+    let mut test_environment = setup_escrow_test();
+    let alice = test_environment.alice.pubkey();
+    let bob = test_environment.bob.pubkey();
+    let referee = test_environment.mint_authority.insecure_clone();
+
+    let stake = 100_000;
+    let players = [
+        test_environment.alice.insecure_clone(),
+        test_environment.bob.insecure_clone(),
+    ];
+    let game = execute_create_and_deposit_game(
+        &mut test_environment,
+        generate_offer_id(),
+        &players,
+        stake,
+        9_999_999_999,
+        0,
+        referee.pubkey(),
+    )
+    .unwrap();
+
+    let alice_lamports_before = test_environment.litesvm.get_balance(&alice).unwrap();
+    let bob_lamports_before = test_environment.litesvm.get_balance(&bob).unwrap();
+
+    // The referee finalizes the game as a draw, by not ranking anyone
+    let finalize_instruction = build_finalize_game_instruction(
+        &[],
+        &[],
+        FinalizeGameAccounts {
+            authority: referee.pubkey(),
+            treasury: referee.pubkey(),
+            system_program: anchor_lang::system_program::ID,
+            game,
+            token_accounts: None,
+            vote_record: None,
+            side_pool: None,
+            player_accounts: vec![alice, bob],
+            player_profiles: get_player_profiles(&[alice, bob]),
+            signing_referees: vec![],
+        },
+    );
+    let result = send_transaction_from_instructions(
+        &mut test_environment.litesvm,
+        vec![finalize_instruction],
+        &[&referee],
+        &referee.pubkey(),
+    );
+    assert!(result.is_ok(), "Finalizing the game as a draw should succeed");
+
+    assert_eq!(
+        test_environment.litesvm.get_balance(&alice).unwrap() - alice_lamports_before,
+        stake,
+        "Alice should have the stake back"
+    );
+    assert_eq!(
+        test_environment.litesvm.get_balance(&bob).unwrap() - bob_lamports_before,
+        stake,
+        "Bob should have the stake back"
+    );
+    assert_eq!(
+        get_game_winner(&test_environment.litesvm, &game),
+        DRAW,
+        "The game should record the draw, so it can be told apart from a cancel"
+    );
+}
+
+#[test]
+fn test_finalize_pays_rake_to_treasury() {
+    let mut test_environment = setup_escrow_test();
+    let bob = test_environment.bob.pubkey();
+    let referee = test_environment.mint_authority.insecure_clone();
+    let treasury = Keypair::new();
+    test_environment
+        .litesvm
+        .airdrop(&treasury.pubkey(), 1_000_000_000)
+        .unwrap();
+
+    // A 5% rake on a game where each player stakes 0.001 SOL
+    let stake = 1_000_000;
+    let players = [
+        test_environment.alice.insecure_clone(),
+        test_environment.bob.insecure_clone(),
+    ];
+    let game = execute_create_and_deposit_game(
+        &mut test_environment,
+        generate_offer_id(),
+        &players,
+        stake,
+        9_999_999_999,
+        500,
+        treasury.pubkey(),
+    )
+    .unwrap();
+
+    let bob_lamports_before = test_environment.litesvm.get_balance(&bob).unwrap();
+    let treasury_lamports_before = test_environment
+        .litesvm
+        .get_balance(&treasury.pubkey())
+        .unwrap();
+
+    // Bob wins
+    let finalize_instruction = build_finalize_game_instruction(
+        &[2],
+        &[10_000],
+        FinalizeGameAccounts {
+            authority: referee.pubkey(),
+            treasury: treasury.pubkey(),
+            system_program: anchor_lang::system_program::ID,
+            game,
+            token_accounts: None,
+            vote_record: None,
+            side_pool: None,
+            player_accounts: vec![test_environment.alice.pubkey(), bob],
+            player_profiles: get_player_profiles(&[test_environment.alice.pubkey(), bob]),
+            signing_referees: vec![],
+        },
+    );
+    let result = send_transaction_from_instructions(
+        &mut test_environment.litesvm,
+        vec![finalize_instruction],
+        &[&referee],
+        &referee.pubkey(),
+    );
+    assert!(result.is_ok(), "Finalizing the game should succeed");
+
+    assert_eq!(
+        test_environment.litesvm.get_balance(&bob).unwrap() - bob_lamports_before,
+        1_900_000,
+        "Bob should receive the pot less the 5% rake"
+    );
+    assert_eq!(
+        test_environment
+            .litesvm
+            .get_balance(&treasury.pubkey())
+            .unwrap()
+            - treasury_lamports_before,
+        100_000,
+        "The treasury should receive the 5% rake"
+    );
+}
+
+#[test]
+fn test_multiplayer_game_pays_out_by_ranking() {
+    let mut test_environment = setup_escrow_test();
+    let referee = test_environment.mint_authority.insecure_clone();
+
+    // Four players, each staking 0.001 SOL
+    let mut players = vec![
+        test_environment.alice.insecure_clone(),
+        test_environment.bob.insecure_clone(),
+    ];
+    for _ in 0..2 {
+        let player = Keypair::new();
+        test_environment
+            .litesvm
+            .airdrop(&player.pubkey(), 1_000_000_000)
+            .unwrap();
+        players.push(player);
+    }
+    let player_pubkeys: Vec<_> = players.iter().map(|player| player.pubkey()).collect();
+
+    let stake = 1_000_000;
+    let game = execute_create_and_deposit_game(
+        &mut test_environment,
+        generate_offer_id(),
+        &players,
+        stake,
+        9_999_999_999,
+        0,
+        referee.pubkey(),
+    )
+    .unwrap();
+
+    let lamports_before: Vec<u64> = player_pubkeys
+        .iter()
+        .map(|player| test_environment.litesvm.get_balance(player).unwrap())
+        .collect();
+
+    // Slot 4 wins, then slot 2, then slot 1, paying 60/30/10
+    let finalize_instruction = build_finalize_game_instruction(
+        &[4, 2, 1],
+        &[6_000, 3_000, 1_000],
+        FinalizeGameAccounts {
+            authority: referee.pubkey(),
+            treasury: referee.pubkey(),
+            system_program: anchor_lang::system_program::ID,
+            game,
+            token_accounts: None,
+            vote_record: None,
+            side_pool: None,
+            player_accounts: player_pubkeys.clone(),
+            player_profiles: get_player_profiles(&player_pubkeys),
+            signing_referees: vec![],
+        },
+    );
+    let result = send_transaction_from_instructions(
+        &mut test_environment.litesvm,
+        vec![finalize_instruction],
+        &[&referee],
+        &referee.pubkey(),
+    );
+    assert!(result.is_ok(), "Finalizing the game should succeed");
+
+    let expected_winnings = [400_000, 1_200_000, 0, 2_400_000];
+    for (index, player) in player_pubkeys.iter().enumerate() {
+        assert_eq!(
+            test_environment.litesvm.get_balance(player).unwrap() - lamports_before[index],
+            expected_winnings[index],
+            "Player in slot {} should be paid their share of the pot",
+            index + 1
+        );
+    }
+}
+
+#[test]
+fn test_create_game_with_too_many_players_fails() {
+    let mut test_environment = setup_escrow_test();
+    let referee = test_environment.mint_authority.insecure_clone();
+
+    // One more player than a game has seats for
+    let players: Vec<_> = (0..9).map(|_| Keypair::new().pubkey()).collect();
+    let game_id = generate_offer_id();
+    let (game, _game_bump) = get_pda_and_bump(
+        &[b"game".as_ref().into(), game_id.to_le_bytes().as_ref().into()],
+        &test_environment.program_id,
+    );
+
+    let create_game_instruction = build_create_game_instruction(
+        game_id,
+        &players,
+        1_000_000,
+        9_999_999_999,
+        0,
+        GameOptions::default(),
+        CreateGameAccounts {
+            authority: referee.pubkey(),
+            system_program: anchor_lang::system_program::ID,
+            treasury: referee.pubkey(),
+            game,
+            token_accounts: None,
+        },
+    );
+    let result = send_transaction_from_instructions(
+        &mut test_environment.litesvm,
+        vec![create_game_instruction],
+        &[&referee],
+        &referee.pubkey(),
+    );
+    assert!(result.is_err(), "Creating a game with 9 players should fail");
+}
+
+#[test]
+fn test_token_game_pays_winner_and_closes_vault() {
+    let mut test_environment = setup_escrow_test();
+    let referee = test_environment.mint_authority.insecure_clone();
+
+    // Bob needs some token A to stake
+    mint_tokens_to_account(
+        &mut test_environment.litesvm,
+        &test_environment.token_mint_a,
+        &test_environment.bob_token_account_a,
+        TOKEN_A,
+        &referee,
+    )
+    .unwrap();
+
+    // Alice and Bob each stake 1 token A
+    let players = [
+        test_environment.alice.insecure_clone(),
+        test_environment.bob.insecure_clone(),
+    ];
+    let token_mint_a = test_environment.token_mint_a;
+    let (game, token_accounts) = execute_create_and_deposit_token_game(
+        &mut test_environment,
+        generate_offer_id(),
+        &players,
+        token_mint_a,
+        TOKEN_A,
+        9_999_999_999,
+    )
+    .unwrap();
+    assert_token_balance(
+        &test_environment.litesvm,
+        &token_accounts.vault,
+        2 * TOKEN_A,
+        "Vault should hold both stakes",
+    );
+
+    // The treasury takes any rake in token A, so it needs a token A account
+    let treasury_token_account = create_associated_token_account(
+        &mut test_environment.litesvm,
+        &referee.pubkey(),
+        &token_mint_a,
+        &referee,
+    )
+    .unwrap();
+
+    // Bob wins
+    let finalize_instruction = build_finalize_game_instruction(
+        &[2],
+        &[10_000],
+        FinalizeGameAccounts {
+            authority: referee.pubkey(),
+            treasury: treasury_token_account,
+            system_program: anchor_lang::system_program::ID,
+            game,
+            token_accounts: Some(token_accounts),
+            vote_record: None,
+            side_pool: None,
+            player_accounts: vec![
+                test_environment.alice_token_account_a,
+                test_environment.bob_token_account_a,
+            ],
+            player_profiles: get_player_profiles(&[
+                test_environment.alice.pubkey(),
+                test_environment.bob.pubkey(),
+            ]),
+            signing_referees: vec![],
+        },
+    );
+    let result = send_transaction_from_instructions(
+        &mut test_environment.litesvm,
+        vec![finalize_instruction],
+        &[&referee],
+        &referee.pubkey(),
+    );
+    assert!(result.is_ok(), "Finalizing a token game should succeed");
+
+    assert_token_balance(
+        &test_environment.litesvm,
+        &test_environment.alice_token_account_a,
+        9 * TOKEN_A,
+        "Alice should have lost the stake",
+    );
+    assert_token_balance(
+        &test_environment.litesvm,
+        &test_environment.bob_token_account_a,
+        2 * TOKEN_A,
+        "Bob should have won the pot",
+    );
+    check_account_is_closed(
+        &test_environment.litesvm,
+        &token_accounts.vault,
+        "Vault should be closed after the game is finalized",
+    );
+    assert_eq!(
+        get_game_winner(&test_environment.litesvm, &game),
+        2,
+        "The game should record Bob as the winner"
+    );
+}
+
+#[test]
+fn test_token_game_cancel_refunds_stakes() {
+    let mut test_environment = setup_escrow_test();
+
+    mint_tokens_to_account(
+        &mut test_environment.litesvm,
+        &test_environment.token_mint_a,
+        &test_environment.bob_token_account_a,
+        TOKEN_A,
+        &test_environment.mint_authority,
+    )
+    .unwrap();
+
+    let players = [
+        test_environment.alice.insecure_clone(),
+        test_environment.bob.insecure_clone(),
+    ];
+    let token_mint_a = test_environment.token_mint_a;
+    let expiry_ts = get_unix_timestamp(&test_environment.litesvm) + 60;
+    let (game, token_accounts) = execute_create_and_deposit_token_game(
+        &mut test_environment,
+        generate_offer_id(),
+        &players,
+        token_mint_a,
+        TOKEN_A,
+        expiry_ts,
+    )
+    .unwrap();
+
+    // The referee never finalizes, so Bob cancels once the game expires
+    warp_to_unix_timestamp(&mut test_environment.litesvm, expiry_ts + 1);
+    let cancel_instruction = build_cancel_game_instruction(CancelGameAccounts {
+        caller: test_environment.bob.pubkey(),
+        system_program: anchor_lang::system_program::ID,
+        game,
+        token_accounts: Some(token_accounts),
+        challenger: None,
+        side_pool: None,
+        player_accounts: vec![
+            test_environment.alice_token_account_a,
+            test_environment.bob_token_account_a,
+        ],
+    });
+    let result = send_transaction_from_instructions(
+        &mut test_environment.litesvm,
+        vec![cancel_instruction],
+        &[&test_environment.bob],
+        &test_environment.bob.pubkey(),
+    );
+    assert!(result.is_ok(), "Cancelling an expired token game should succeed");
+
+    assert_token_balance(
+        &test_environment.litesvm,
+        &test_environment.alice_token_account_a,
+        10 * TOKEN_A,
+        "Alice should have the stake back",
+    );
+    assert_token_balance(
+        &test_environment.litesvm,
+        &test_environment.bob_token_account_a,
+        TOKEN_A,
+        "Bob should have the stake back",
+    );
+    check_account_is_closed(
+        &test_environment.litesvm,
+        &token_accounts.vault,
+        "Vault should be closed after the game is cancelled",
+    );
+}
+
+#[test]
+fn test_unchallenged_result_settles_after_window() {
+    let mut test_environment = setup_escrow_test();
+    let referee = test_environment.mint_authority.insecure_clone();
+    let alice = test_environment.alice.pubkey();
+    let bob = test_environment.bob.pubkey();
+
+    let stake = 1_000_000;
+    let players = [
+        test_environment.alice.insecure_clone(),
+        test_environment.bob.insecure_clone(),
+    ];
+    let game = execute_create_and_deposit_game_with_options(
+        &mut test_environment,
+        generate_offer_id(),
+        &players,
+        stake,
+        9_999_999_999,
+        GameOptions {
+            challenge_settings: Some(ChallengeSettings {
+                arbiter: Keypair::new().pubkey(),
+                challenge_period: 60,
+                challenge_bond: 500_000,
+            }),
+            ..Default::default()
+        },
+    )
+    .unwrap();
+
+    // The referee can't finalize the game directly, only propose a result
+    let finalize_instruction = build_finalize_game_instruction(
+        &[2],
+        &[10_000],
+        FinalizeGameAccounts {
+            authority: referee.pubkey(),
+            treasury: referee.pubkey(),
+            system_program: anchor_lang::system_program::ID,
+            game,
+            token_accounts: None,
+            vote_record: None,
+            side_pool: None,
+            player_accounts: vec![alice, bob],
+            player_profiles: get_player_profiles(&[alice, bob]),
+            signing_referees: vec![],
+        },
+    );
+    let result = send_transaction_from_instructions(
+        &mut test_environment.litesvm,
+        vec![finalize_instruction],
+        &[&referee],
+        &referee.pubkey(),
+    );
+    assert!(result.is_err(), "Optimistic games shouldn't be finalized directly");
+
+    let propose_instruction = build_propose_result_instruction(
+        &[2],
+        &[10_000],
+        ProposeResultAccounts {
+            authority: referee.pubkey(),
+            game,
+        },
+    );
+    let result = send_transaction_from_instructions(
+        &mut test_environment.litesvm,
+        vec![propose_instruction],
+        &[&referee],
+        &referee.pubkey(),
+    );
+    assert!(result.is_ok(), "Proposing a result should succeed");
+
+    let settle_instruction = || {
+        build_settle_game_instruction(SettleGameAccounts {
+            caller: referee.pubkey(),
+            authority: referee.pubkey(),
+            treasury: referee.pubkey(),
+            system_program: anchor_lang::system_program::ID,
+            game,
+            token_accounts: None,
+            player_accounts: vec![alice, bob],
+            player_profiles: get_player_profiles(&[alice, bob]),
+        })
+    };
+    let result = send_transaction_from_instructions(
+        &mut test_environment.litesvm,
+        vec![settle_instruction()],
+        &[&referee],
+        &referee.pubkey(),
+    );
+    assert!(result.is_err(), "Settling during the challenge window should fail");
+
+    // Once the window passes, Alice can no longer challenge the result
+    let now = get_unix_timestamp(&test_environment.litesvm);
+    warp_to_unix_timestamp(&mut test_environment.litesvm, now + 61);
+    test_environment.litesvm.expire_blockhash();
+    let challenge_instruction = build_challenge_result_instruction(ChallengeResultAccounts {
+        player: alice,
+        system_program: anchor_lang::system_program::ID,
+        game,
+    });
+    let result = send_transaction_from_instructions(
+        &mut test_environment.litesvm,
+        vec![challenge_instruction],
+        &[&test_environment.alice],
+        &alice,
+    );
+    assert!(result.is_err(), "Challenging after the window should fail");
+
+    // And the proposed result is paid out
+    let bob_lamports_before = test_environment.litesvm.get_balance(&bob).unwrap();
+    let result = send_transaction_from_instructions(
+        &mut test_environment.litesvm,
+        vec![settle_instruction()],
+        &[&referee],
+        &referee.pubkey(),
+    );
+    assert!(result.is_ok(), "Settling after the challenge window should succeed");
+
+    assert_eq!(
+        test_environment.litesvm.get_balance(&bob).unwrap() - bob_lamports_before,
+        2 * stake,
+        "Bob should receive the pot"
+    );
+    assert_eq!(
+        get_game_winner(&test_environment.litesvm, &game),
+        2,
+        "The game should record Bob as the winner"
+    );
+}
+
+#[test]
+fn test_challenged_result_overturned_by_arbiter_returns_bond() {
+    let mut test_environment = setup_escrow_test();
+    let referee = test_environment.mint_authority.insecure_clone();
+    let arbiter = Keypair::new();
+    test_environment
+        .litesvm
+        .airdrop(&arbiter.pubkey(), 1_000_000_000)
+        .unwrap();
+    let alice = test_environment.alice.pubkey();
+    let bob = test_environment.bob.pubkey();
+
+    let stake = 1_000_000;
+    let bond = 500_000;
+    let players = [
+        test_environment.alice.insecure_clone(),
+        test_environment.bob.insecure_clone(),
+    ];
+    let game = execute_create_and_deposit_game_with_options(
+        &mut test_environment,
+        generate_offer_id(),
+        &players,
+        stake,
+        9_999_999_999,
+        GameOptions {
+            challenge_settings: Some(ChallengeSettings {
+                arbiter: arbiter.pubkey(),
+                challenge_period: 60,
+                challenge_bond: bond,
+            }),
+            ..Default::default()
+        },
+    )
+    .unwrap();
+
+    // The referee wrongly says Alice won
+    let propose_instruction = build_propose_result_instruction(
+        &[1],
+        &[10_000],
+        ProposeResultAccounts {
+            authority: referee.pubkey(),
+            game,
+        },
+    );
+    let result = send_transaction_from_instructions(
+        &mut test_environment.litesvm,
+        vec![propose_instruction],
+        &[&referee],
+        &referee.pubkey(),
+    );
+    assert!(result.is_ok(), "Proposing a result should succeed");
+
+    // Bob challenges, posting the bond
+    let challenge_instruction = build_challenge_result_instruction(ChallengeResultAccounts {
+        player: bob,
+        system_program: anchor_lang::system_program::ID,
+        game,
+    });
+    let result = send_transaction_from_instructions(
+        &mut test_environment.litesvm,
+        vec![challenge_instruction],
+        &[&test_environment.bob],
+        &bob,
+    );
+    assert!(result.is_ok(), "Challenging during the window should succeed");
+
+    // A challenged result can't be settled, even after the window
+    let now = get_unix_timestamp(&test_environment.litesvm);
+    warp_to_unix_timestamp(&mut test_environment.litesvm, now + 61);
+    let settle_instruction = build_settle_game_instruction(SettleGameAccounts {
+        caller: referee.pubkey(),
+        authority: referee.pubkey(),
+        treasury: referee.pubkey(),
+        system_program: anchor_lang::system_program::ID,
+        game,
+        token_accounts: None,
+        player_accounts: vec![alice, bob],
+        player_profiles: get_player_profiles(&[alice, bob]),
+    });
+    let result = send_transaction_from_instructions(
+        &mut test_environment.litesvm,
+        vec![settle_instruction],
+        &[&referee],
+        &referee.pubkey(),
+    );
+    assert!(result.is_err(), "Settling a challenged result should fail");
+
+    // The arbiter decides Bob won
+    let bob_lamports_before = test_environment.litesvm.get_balance(&bob).unwrap();
+    let resolve_instruction = build_resolve_challenge_instruction(
+        &[2],
+        &[10_000],
+        ResolveChallengeAccounts {
+            arbiter: arbiter.pubkey(),
+            authority: referee.pubkey(),
+            challenger: bob,
+            treasury: referee.pubkey(),
+            system_program: anchor_lang::system_program::ID,
+            game,
+            token_accounts: None,
+            player_accounts: vec![alice, bob],
+            player_profiles: get_player_profiles(&[alice, bob]),
+        },
+    );
+    let result = send_transaction_from_instructions(
+        &mut test_environment.litesvm,
+        vec![resolve_instruction],
+        &[&arbiter],
+        &arbiter.pubkey(),
+    );
+    assert!(result.is_ok(), "Resolving the challenge should succeed");
+
+    assert_eq!(
+        test_environment.litesvm.get_balance(&bob).unwrap() - bob_lamports_before,
+        2 * stake + bond,
+        "Bob should receive the pot and the bond back"
+    );
+    assert_eq!(
+        get_game_winner(&test_environment.litesvm, &game),
+        2,
+        "The game should record the arbiter's result"
+    );
+}
+
+#[test]
+fn test_referee_quorum_finalizes_with_votes_across_transactions() {
+    let mut test_environment = setup_escrow_test();
+    let authority = test_environment.mint_authority.insecure_clone();
+    let referees: Vec<Keypair> = (0..3).map(|_| Keypair::new()).collect();
+    for referee in &referees {
+        test_environment
+            .litesvm
+            .airdrop(&referee.pubkey(), 1_000_000_000)
+            .unwrap();
+    }
+    let alice = test_environment.alice.pubkey();
+    let bob = test_environment.bob.pubkey();
+
+    // Any 2 of the 3 referees must agree on the result
+    let stake = 1_000_000;
+    let players = [
+        test_environment.alice.insecure_clone(),
+        test_environment.bob.insecure_clone(),
+    ];
+    let game = execute_create_and_deposit_game_with_options(
+        &mut test_environment,
+        generate_offer_id(),
+        &players,
+        stake,
+        9_999_999_999,
+        GameOptions {
+            referee_quorum: Some(RefereeQuorum {
+                referees: referees.iter().map(|referee| referee.pubkey()).collect(),
+                threshold: 2,
+            }),
+            ..Default::default()
+        },
+    )
+    .unwrap();
+
+    let bob_wins = GameResult {
+        ranking: vec![2],
+        payout_bps: vec![10_000],
+    };
+    let vote_record = get_vote_record(&test_environment.litesvm, &game, &bob_wins.hash());
+    let alice_wins = GameResult {
+        ranking: vec![1],
+        payout_bps: vec![10_000],
+    };
+    let losing_vote_record =
+        get_vote_record(&test_environment.litesvm, &game, &alice_wins.hash());
+    let finalize_instruction = |vote_record, signing_referees| {
+        build_finalize_game_instruction(
+            &[2],
+            &[10_000],
+            FinalizeGameAccounts {
+                authority: authority.pubkey(),
+                treasury: authority.pubkey(),
+                system_program: anchor_lang::system_program::ID,
+                game,
+                token_accounts: None,
+                vote_record,
+                side_pool: None,
+                player_accounts: vec![alice, bob],
+                player_profiles: get_player_profiles(&[alice, bob]),
+                signing_referees,
+            },
+        )
+    };
+
+    // The first referee votes on their own
+    let vote_instruction = build_vote_result_instruction(
+        &[2],
+        &[10_000],
+        VoteResultAccounts {
+            referee: referees[0].pubkey(),
+            system_program: anchor_lang::system_program::ID,
+            game,
+            vote_record,
+        },
+    );
+    let result = send_transaction_from_instructions(
+        &mut test_environment.litesvm,
+        vec![vote_instruction],
+        &[&referees[0]],
+        &referees[0].pubkey(),
+    );
+    assert!(result.is_ok(), "Referee should be able to vote");
+
+    // The third referee votes for a different result, which never wins
+    let losing_vote_instruction = build_vote_result_instruction(
+        &[1],
+        &[10_000],
+        VoteResultAccounts {
+            referee: referees[2].pubkey(),
+            system_program: anchor_lang::system_program::ID,
+            game,
+            vote_record: losing_vote_record,
+        },
+    );
+    let result = send_transaction_from_instructions(
+        &mut test_environment.litesvm,
+        vec![losing_vote_instruction],
+        &[&referees[2]],
+        &referees[2].pubkey(),
+    );
+    assert!(result.is_ok(), "Referee should be able to vote for another result");
+
+    // Vote records can't be closed while the game can still be finalized
+    let result = send_transaction_from_instructions(
+        &mut test_environment.litesvm,
+        vec![build_close_vote_record_instruction(
+            referees[0].pubkey(),
+            game,
+            vote_record,
+        )],
+        &[&authority],
+        &authority.pubkey(),
+    );
+    assert!(result.is_err(), "Closing a vote record for a live game should fail");
+
+    // One vote isn't enough
+    let result = send_transaction_from_instructions(
+        &mut test_environment.litesvm,
+        vec![finalize_instruction(Some(vote_record), vec![])],
+        &[&authority],
+        &authority.pubkey(),
+    );
+    assert!(result.is_err(), "Finalizing with one referee vote should fail");
+
+    // The second referee signs the finalize transaction, making two
+    let bob_lamports_before = test_environment.litesvm.get_balance(&bob).unwrap();
+    let result = send_transaction_from_instructions(
+        &mut test_environment.litesvm,
+        vec![finalize_instruction(Some(vote_record), vec![referees[1].pubkey()])],
+        &[&authority, &referees[1]],
+        &authority.pubkey(),
+    );
+    assert!(result.is_ok(), "Finalizing with two referees should succeed");
+
+    assert_eq!(
+        test_environment.litesvm.get_balance(&bob).unwrap() - bob_lamports_before,
+        2 * stake,
+        "Bob should receive the pot"
+    );
+
+    // Once the game is over, both vote records can be closed, refunding the referees who paid
+    for (referee, record) in [(&referees[0], vote_record), (&referees[2], losing_vote_record)] {
+        let referee_lamports_before =
+            test_environment.litesvm.get_balance(&referee.pubkey()).unwrap();
+        let result = send_transaction_from_instructions(
+            &mut test_environment.litesvm,
+            vec![build_close_vote_record_instruction(referee.pubkey(), game, record)],
+            &[&authority],
+            &authority.pubkey(),
+        );
+        assert!(result.is_ok(), "Closing a vote record after the game should succeed");
+        check_account_is_closed(
+            &test_environment.litesvm,
+            &record,
+            "Vote record should be closed after the game is finalized",
+        );
+        assert!(
+            test_environment.litesvm.get_balance(&referee.pubkey()).unwrap()
+                > referee_lamports_before,
+            "Referee should get the vote record's rent back"
+        );
+    }
+}
+
+#[test]
+fn test_referee_quorum_finalizes_in_one_transaction() {
+    let mut test_environment = setup_escrow_test();
+    let authority = test_environment.mint_authority.insecure_clone();
+    let referees: Vec<Keypair> = (0..3).map(|_| Keypair::new()).collect();
+    let alice = test_environment.alice.pubkey();
+    let bob = test_environment.bob.pubkey();
+
+    let players = [
+        test_environment.alice.insecure_clone(),
+        test_environment.bob.insecure_clone(),
+    ];
+    let game = execute_create_and_deposit_game_with_options(
+        &mut test_environment,
+        generate_offer_id(),
+        &players,
+        1_000_000,
+        9_999_999_999,
+        GameOptions {
+            referee_quorum: Some(RefereeQuorum {
+                referees: referees.iter().map(|referee| referee.pubkey()).collect(),
+                threshold: 2,
+            }),
+            ..Default::default()
+        },
+    )
+    .unwrap();
+
+    // Only the authority, who isn't a referee, and one referee sign
+    let finalize_instruction = |signing_referees| {
+        build_finalize_game_instruction(
+            &[1],
+            &[10_000],
+            FinalizeGameAccounts {
+                authority: authority.pubkey(),
+                treasury: authority.pubkey(),
+                system_program: anchor_lang::system_program::ID,
+                game,
+                token_accounts: None,
+                vote_record: None,
+                side_pool: None,
+                player_accounts: vec![alice, bob],
+                player_profiles: get_player_profiles(&[alice, bob]),
+                signing_referees,
+            },
+        )
+    };
+    let result = send_transaction_from_instructions(
+        &mut test_environment.litesvm,
+        vec![finalize_instruction(vec![referees[0].pubkey()])],
+        &[&authority, &referees[0]],
+        &authority.pubkey(),
+    );
+    assert!(result.is_err(), "Finalizing with one referee should fail");
+
+    let result = send_transaction_from_instructions(
+        &mut test_environment.litesvm,
+        vec![finalize_instruction(vec![
+            referees[0].pubkey(),
+            referees[2].pubkey(),
+        ])],
+        &[&authority, &referees[0], &referees[2]],
+        &authority.pubkey(),
+    );
+    assert!(result.is_ok(), "Finalizing with two referees signing should succeed");
+    assert_eq!(
+        get_game_winner(&test_environment.litesvm, &game),
+        1,
+        "The game should record Alice as the winner"
+    );
+}
+
+#[test]
+fn test_challenge_settings_and_referee_quorum_cannot_be_combined() {
+    let mut test_environment = setup_escrow_test();
+    let players = [
+        test_environment.alice.insecure_clone(),
+        test_environment.bob.insecure_clone(),
+    ];
+
+    // A quorum's votes finalize the game directly, so a challenge window would never open
+    let result = execute_create_and_deposit_game_with_options(
+        &mut test_environment,
+        generate_offer_id(),
+        &players,
+        1_000_000,
+        9_999_999_999,
+        GameOptions {
+            challenge_settings: Some(ChallengeSettings {
+                arbiter: Keypair::new().pubkey(),
+                challenge_period: 60,
+                challenge_bond: 500_000,
+            }),
+            referee_quorum: Some(RefereeQuorum {
+                referees: vec![Keypair::new().pubkey(), Keypair::new().pubkey()],
+                threshold: 2,
+            }),
+            ..Default::default()
+        },
+    );
+    assert!(
+        result.is_err(),
+        "A game should not have both challenge settings and a referee quorum"
+    );
+}
+
+#[test]
+fn test_signed_result_attestation_relayed_by_winner() {
+    let mut test_environment = setup_escrow_test();
+    let alice = test_environment.alice.pubkey();
+    let bob = test_environment.bob.pubkey();
+
+    // The referee creates the game, naming an attester whose key only signs off-chain
+    let referee = test_environment.mint_authority.insecure_clone();
+    let attester = Keypair::new();
+    let game_id = generate_offer_id();
+    let stake = 1_000_000;
+    let players = [
+        test_environment.alice.insecure_clone(),
+        test_environment.bob.insecure_clone(),
+    ];
+    let game = execute_create_and_deposit_game_with_options(
+        &mut test_environment,
+        game_id,
+        &players,
+        stake,
+        9_999_999_999,
+        GameOptions {
+            attester: Some(attester.pubkey()),
+            ..Default::default()
+        },
+    )
+    .unwrap();
+
+    let game_account = test_environment.litesvm.get_account(&game).unwrap();
+    let game_state =
+        crate::state::Game::try_deserialize(&mut game_account.data.as_slice()).unwrap();
+    let finalize_instruction = || {
+        build_finalize_with_attestation_instruction(
+            &[2],
+            &[10_000],
+            FinalizeWithAttestationAccounts {
+                relayer: bob,
+                authority: referee.pubkey(),
+                treasury: referee.pubkey(),
+                system_program: anchor_lang::system_program::ID,
+                game,
+                token_accounts: None,
+                side_pool: None,
+                player_accounts: vec![alice, bob],
+                player_profiles: get_player_profiles(&[alice, bob]),
+            },
+        )
+    };
+
+    // An attestation signed by anyone but the attester is rejected, even the referee
+    let message = serialize_result_attestation(game_id, game_state.nonce, &[2], &[10_000]);
+    for signer in [&test_environment.bob, &referee] {
+        let result = send_transaction_from_instructions(
+            &mut test_environment.litesvm,
+            vec![build_ed25519_instruction(signer, &message), finalize_instruction()],
+            &[&test_environment.bob],
+            &bob,
+        );
+        assert!(result.is_err(), "Attestation signed by the wrong key should be rejected");
+    }
+
+    // Finalizing without an attestation fails
+    let result = send_transaction_from_instructions(
+        &mut test_environment.litesvm,
+        vec![finalize_instruction()],
+        &[&test_environment.bob],
+        &bob,
+    );
+    assert!(
+        result.is_err(),
+        "Finalizing without an attestation should fail"
+    );
+
+    // As does an attestation that isn't the instruction right before
+    let transfer_instruction =
+        anchor_lang::solana_program::system_instruction::transfer(&bob, &alice, 1);
+    let result = send_transaction_from_instructions(
+        &mut test_environment.litesvm,
+        vec![
+            build_ed25519_instruction(&attester, &message),
+            transfer_instruction,
+            finalize_instruction(),
+        ],
+        &[&test_environment.bob],
+        &bob,
+    );
+    assert!(
+        result.is_err(),
+        "Attestation that isn't immediately before should be rejected"
+    );
+
+    // So is the attester's attestation for a different result
+    let alice_wins = serialize_result_attestation(game_id, game_state.nonce, &[1], &[10_000]);
+    let result = send_transaction_from_instructions(
+        &mut test_environment.litesvm,
+        vec![
+            build_ed25519_instruction(&attester, &alice_wins),
+            finalize_instruction(),
+        ],
+        &[&test_environment.bob],
+        &bob,
+    );
+    assert!(result.is_err(), "Attestation for a different result should be rejected");
+
+    // Bob relays the attester's attestation that Bob won, paying the transaction fee
+    let bob_lamports_before = test_environment.litesvm.get_balance(&bob).unwrap();
+    let result = send_transaction_from_instructions(
+        &mut test_environment.litesvm,
+        vec![
+            build_ed25519_instruction(&attester, &message),
+            finalize_instruction(),
+        ],
+        &[&test_environment.bob],
+        &bob,
+    );
+    assert!(result.is_ok(), "Relaying the attester's attestation should succeed");
+
+    let bob_lamports_after = test_environment.litesvm.get_balance(&bob).unwrap();
+    assert!(
+        bob_lamports_after > bob_lamports_before + 2 * stake - 100_000,
+        "Bob should receive the pot, less transaction fees"
+    );
+    assert_eq!(
+        get_game_winner(&test_environment.litesvm, &game),
+        2,
+        "The game should record Bob as the winner"
+    );
+}
+
+#[test]
+fn test_missed_deposit_deadline_lets_depositor_reclaim_before_expiry() {
+    let mut test_environment = setup_escrow_test();
+    let referee = test_environment.mint_authority.insecure_clone();
+    let alice = test_environment.alice.pubkey();
+    let bob = test_environment.bob.pubkey();
+
+    // Players have a minute to deposit, and the referee an hour to finalize
+    let game_id = generate_offer_id();
+    let (game, _game_bump) = get_pda_and_bump(
+        &["game".into(), game_id.into()],
+        &test_environment.program_id,
+    );
+    let now = get_unix_timestamp(&test_environment.litesvm);
+    let stake = 1_000_000;
+    let create_game_instruction = build_create_game_instruction(
+        game_id,
+        &[alice, bob],
+        stake,
+        now + 60 * 60,
+        0,
+        GameOptions {
+            deposit_deadline_ts: Some(now + 60),
+            ..Default::default()
+        },
+        CreateGameAccounts {
+            authority: referee.pubkey(),
+            system_program: anchor_lang::system_program::ID,
+            treasury: referee.pubkey(),
+            game,
+            token_accounts: None,
+        },
+    );
+    let result = send_transaction_from_instructions(
+        &mut test_environment.litesvm,
+        vec![create_game_instruction],
+        &[&referee],
+        &referee.pubkey(),
+    );
+    assert!(result.is_ok(), "Creating the game should succeed");
+
+    let deposit_instruction = |player| {
+        build_deposit_instruction(
+            stake,
+            DepositAccounts {
+                player,
+                system_program: anchor_lang::system_program::ID,
+                game,
+                token_accounts: None,
+                player_token_account: None,
+            },
+        )
+    };
+    let result = send_transaction_from_instructions(
+        &mut test_environment.litesvm,
+        vec![deposit_instruction(alice)],
+        &[&test_environment.alice],
+        &alice,
+    );
+    assert!(result.is_ok(), "Alice should be able to deposit before the deadline");
+
+    // Bob never shows up in time
+    warp_to_unix_timestamp(&mut test_environment.litesvm, now + 61);
+    let result = send_transaction_from_instructions(
+        &mut test_environment.litesvm,
+        vec![deposit_instruction(bob)],
+        &[&test_environment.bob],
+        &bob,
+    );
+    assert!(result.is_err(), "Deposits after the deadline should be rejected");
+
+    // Alice gets the stake back without waiting for the expiry
+    let alice_lamports_before = test_environment.litesvm.get_balance(&alice).unwrap();
+    let cancel_instruction = build_cancel_game_instruction(CancelGameAccounts {
+        caller: referee.pubkey(),
+        system_program: anchor_lang::system_program::ID,
+        game,
+        token_accounts: None,
+        challenger: None,
+        side_pool: None,
+        player_accounts: vec![alice, bob],
+    });
+    let result = send_transaction_from_instructions(
+        &mut test_environment.litesvm,
+        vec![cancel_instruction],
+        &[&referee],
+        &referee.pubkey(),
+    );
+    assert!(result.is_ok(), "Cancelling after a missed deposit deadline should succeed");
+
+    assert_eq!(
+        test_environment.litesvm.get_balance(&alice).unwrap() - alice_lamports_before,
+        stake,
+        "Alice should have the stake back"
+    );
+    check_account_is_closed(
+        &test_environment.litesvm,
+        &game,
+        "Game should be closed after it is cancelled",
+    );
+}
+
+#[test]
+fn test_open_game_seats_first_player_to_deposit() {
+    let mut test_environment = setup_escrow_test();
+    let referee = test_environment.mint_authority.insecure_clone();
+    let alice = test_environment.alice.pubkey();
+    let bob = test_environment.bob.pubkey();
+    let latecomer = Keypair::new();
+    test_environment
+        .litesvm
+        .airdrop(&latecomer.pubkey(), 1_000_000_000)
+        .unwrap();
+
+    // Alice publishes a challenge, leaving the second seat open
+    let game_id = generate_offer_id();
+    let (game, _game_bump) = get_pda_and_bump(
+        &["game".into(), game_id.into()],
+        &test_environment.program_id,
+    );
+    let stake = 1_000_000;
+    let create_game_instruction = build_create_game_instruction(
+        game_id,
+        &[alice, solana_pubkey::Pubkey::default()],
+        stake,
+        9_999_999_999,
+        0,
+        GameOptions::default(),
+        CreateGameAccounts {
+            authority: referee.pubkey(),
+            system_program: anchor_lang::system_program::ID,
+            treasury: referee.pubkey(),
+            game,
+            token_accounts: None,
+        },
+    );
+    let result = send_transaction_from_instructions(
+        &mut test_environment.litesvm,
+        vec![create_game_instruction],
+        &[&referee],
+        &referee.pubkey(),
+    );
+    assert!(result.is_ok(), "Creating an open game should succeed");
+
+    let deposit_instruction = |player| {
+        build_deposit_instruction(
+            stake,
+            DepositAccounts {
+                player,
+                system_program: anchor_lang::system_program::ID,
+                game,
+                token_accounts: None,
+                player_token_account: None,
+            },
+        )
+    };
+
+    // Bob accepts the challenge by depositing, and takes the open seat
+    let join_ts = get_unix_timestamp(&test_environment.litesvm) + 10;
+    warp_to_unix_timestamp(&mut test_environment.litesvm, join_ts);
+    let result = send_transaction_from_instructions(
+        &mut test_environment.litesvm,
+        vec![deposit_instruction(bob)],
+        &[&test_environment.bob],
+        &bob,
+    );
+    assert!(result.is_ok(), "Bob should be able to take the open seat");
+
+    let game_account = test_environment.litesvm.get_account(&game).unwrap();
+    let game_state =
+        crate::state::Game::try_deserialize(&mut game_account.data.as_slice()).unwrap();
+    assert_eq!(game_state.players[1].player, bob, "Bob should have the second seat");
+    assert_eq!(game_state.players[1].joined_ts, join_ts, "Bob's join time should be recorded");
+
+    // Nobody else can join once every seat is taken
+    let result = send_transaction_from_instructions(
+        &mut test_environment.litesvm,
+        vec![deposit_instruction(latecomer.pubkey())],
+        &[&latecomer],
+        &latecomer.pubkey(),
+    );
+    assert!(result.is_err(), "Deposits should be rejected once the game is full");
+
+    let result = send_transaction_from_instructions(
+        &mut test_environment.litesvm,
+        vec![deposit_instruction(alice)],
+        &[&test_environment.alice],
+        &alice,
+    );
+    assert!(result.is_ok(), "Alice should be able to deposit");
+
+    // Bob is paid like any other player
+    let bob_lamports_before = test_environment.litesvm.get_balance(&bob).unwrap();
+    let finalize_instruction = build_finalize_game_instruction(
+        &[2],
+        &[10_000],
+        FinalizeGameAccounts {
+            authority: referee.pubkey(),
+            treasury: referee.pubkey(),
+            system_program: anchor_lang::system_program::ID,
+            game,
+            token_accounts: None,
+            vote_record: None,
+            side_pool: None,
+            player_accounts: vec![alice, bob],
+            player_profiles: get_player_profiles(&[alice, bob]),
+            signing_referees: vec![],
+        },
+    );
+    let result = send_transaction_from_instructions(
+        &mut test_environment.litesvm,
+        vec![finalize_instruction],
+        &[&referee],
+        &referee.pubkey(),
+    );
+    assert!(result.is_ok(), "Finalizing the game should succeed");
+    assert_eq!(
+        test_environment.litesvm.get_balance(&bob).unwrap() - bob_lamports_before,
+        2 * stake,
+        "Bob should receive the pot"
+    );
+}
+
+#[test]
+fn test_match_players_from_queue() {
+    let mut test_environment = setup_escrow_test();
+    let referee = test_environment.mint_authority.insecure_clone();
+    let alice = test_environment.alice.pubkey();
+    let bob = test_environment.bob.pubkey();
+    let impatient_player = Keypair::new();
+    test_environment
+        .litesvm
+        .airdrop(&impatient_player.pubkey(), 1_000_000_000)
+        .unwrap();
+
+    // The referee opens a queue for 1_000_000 lamport duels in game mode 1
+    let game_mode = 1u8;
+    let stake = 1_000_000u64;
+    let (queue, _queue_bump) = get_pda_and_bump(
+        &[
+            b"queue".as_ref().into(),
+            referee.pubkey().as_ref().into(),
+            [game_mode].as_ref().into(),
+            stake.to_le_bytes().as_ref().into(),
+        ],
+        &test_environment.program_id,
+    );
+    let create_queue_instruction = build_create_queue_instruction(
+        game_mode,
+        stake,
+        0,
+        3_600,
+        CreateQueueAccounts {
+            authority: referee.pubkey(),
+            system_program: anchor_lang::system_program::ID,
+            treasury: referee.pubkey(),
+            queue,
+        },
+    );
+    let result = send_transaction_from_instructions(
+        &mut test_environment.litesvm,
+        vec![create_queue_instruction],
+        &[&referee],
+        &referee.pubkey(),
+    );
+    assert!(result.is_ok(), "Creating the queue should succeed");
 
-    // Bob deposits
-    let deposit_b_ix = crate::escrow_test_helpers::build_deposit_instruction(
+    let queue_accounts = |player| QueuePlayerAccounts {
+        player,
+        system_program: anchor_lang::system_program::ID,
+        queue,
+    };
+    let game_id = generate_offer_id();
+    let (game, _game_bump) = get_pda_and_bump(
+        &["game".into(), game_id.into()],
+        &test_environment.program_id,
+    );
+    let match_players_instruction = build_match_players_instruction(
+        game_id,
+        MatchPlayersAccounts {
+            cranker: referee.pubkey(),
+            system_program: anchor_lang::system_program::ID,
+            queue,
+            game,
+        },
+    );
+
+    // Alice joins the queue, but can't be matched while nobody else is waiting
+    let result = send_transaction_from_instructions(
+        &mut test_environment.litesvm,
+        vec![build_enqueue_instruction(queue_accounts(alice))],
+        &[&test_environment.alice],
+        &alice,
+    );
+    assert!(result.is_ok(), "Alice should be able to join the queue");
+    let result = send_transaction_from_instructions(
+        &mut test_environment.litesvm,
+        vec![match_players_instruction.clone()],
+        &[&referee],
+        &referee.pubkey(),
+    );
+    assert!(result.is_err(), "Matching should fail with one player waiting");
+
+    // The queue can't be closed while Alice's stake is in it
+    let close_queue_instruction = build_close_queue_instruction(CloseQueueAccounts {
+        authority: referee.pubkey(),
+        queue,
+    });
+    let result = send_transaction_from_instructions(
+        &mut test_environment.litesvm,
+        vec![close_queue_instruction.clone()],
+        &[&referee],
+        &referee.pubkey(),
+    );
+    assert!(result.is_err(), "A queue with players waiting should not be closable");
+
+    // Another player joins, then leaves before being matched, and gets their stake back.
+    // Their profile's rent is spent, so check nothing is left behind in the queue
+    let queue_lamports_before = test_environment.litesvm.get_balance(&queue).unwrap();
+    let result = send_transaction_from_instructions(
+        &mut test_environment.litesvm,
+        vec![
+            build_enqueue_instruction(queue_accounts(impatient_player.pubkey())),
+            build_dequeue_instruction(queue_accounts(impatient_player.pubkey())),
+        ],
+        &[&impatient_player],
+        &impatient_player.pubkey(),
+    );
+    assert!(result.is_ok(), "Joining and leaving the queue should succeed");
+    assert_eq!(
+        test_environment.litesvm.get_balance(&queue).unwrap(),
+        queue_lamports_before,
+        "The stake and rent share should be returned"
+    );
+
+    let result = send_transaction_from_instructions(
+        &mut test_environment.litesvm,
+        vec![build_enqueue_instruction(queue_accounts(bob))],
+        &[&test_environment.bob],
+        &bob,
+    );
+    assert!(result.is_ok(), "Bob should be able to join the queue");
+
+    // The crank pairs Alice and Bob into a game with both stakes already deposited,
+    // and the players' rent shares pay the cranker back for the game's rent
+    test_environment.litesvm.expire_blockhash();
+    // The game's nonce counter is created the first time its id is used
+    let nonce_counter_rent = match test_environment.litesvm.get_account(&get_nonce_counter(&game)) {
+        Some(_) => 0,
+        None => test_environment.litesvm.minimum_balance_for_rent_exemption(
+            crate::state::NonceCounter::DISCRIMINATOR.len()
+                + crate::state::NonceCounter::INIT_SPACE,
+        ),
+    };
+    let referee_lamports_before =
+        test_environment.litesvm.get_balance(&referee.pubkey()).unwrap();
+    let result = send_transaction_from_instructions(
+        &mut test_environment.litesvm,
+        vec![match_players_instruction],
+        &[&referee],
+        &referee.pubkey(),
+    );
+    assert!(result.is_ok(), "Matching Alice and Bob should succeed");
+    let referee_lamports_after = test_environment.litesvm.get_balance(&referee.pubkey()).unwrap();
+    assert!(
+        referee_lamports_before.saturating_sub(referee_lamports_after)
+            <= 5_000 + nonce_counter_rent,
+        "The cranker should only pay the transaction fee, and for the nonce counter if it's new"
+    );
+
+    let game_account = test_environment.litesvm.get_account(&game).unwrap();
+    let game_state =
+        crate::state::Game::try_deserialize(&mut game_account.data.as_slice()).unwrap();
+    assert_eq!(game_state.players[0].player, alice, "Alice waited longest, so has slot 1");
+    assert_eq!(game_state.players[1].player, bob, "Bob should have slot 2");
+    assert!(game_state.all_deposited(), "Both stakes should be in the game");
+    let queue_account = test_environment.litesvm.get_account(&queue).unwrap();
+    let queue_state =
+        crate::state::MatchQueue::try_deserialize(&mut queue_account.data.as_slice()).unwrap();
+    assert!(queue_state.entries.is_empty(), "The queue should be empty");
+
+    // The referee finalizes the game like any other
+    let alice_lamports_before = test_environment.litesvm.get_balance(&alice).unwrap();
+    let finalize_instruction = build_finalize_game_instruction(
+        &[1],
+        &[10_000],
+        FinalizeGameAccounts {
+            authority: referee.pubkey(),
+            treasury: referee.pubkey(),
+            system_program: anchor_lang::system_program::ID,
+            game,
+            token_accounts: None,
+            vote_record: None,
+            side_pool: None,
+            player_accounts: vec![alice, bob],
+            player_profiles: get_player_profiles(&[alice, bob]),
+            signing_referees: vec![],
+        },
+    );
+    let result = send_transaction_from_instructions(
+        &mut test_environment.litesvm,
+        vec![finalize_instruction],
+        &[&referee],
+        &referee.pubkey(),
+    );
+    assert!(result.is_ok(), "Finalizing the matched game should succeed");
+    assert_eq!(
+        test_environment.litesvm.get_balance(&alice).unwrap() - alice_lamports_before,
+        2 * stake,
+        "Alice should receive the pot"
+    );
+
+    // When the referee closes the game, its rent goes back to the players who paid for it
+    let rent = test_environment
+        .litesvm
+        .minimum_balance_for_rent_exemption(crate::state::Game::space(2));
+    let alice_lamports_before = test_environment.litesvm.get_balance(&alice).unwrap();
+    let bob_lamports_before = test_environment.litesvm.get_balance(&bob).unwrap();
+    let result = send_transaction_from_instructions(
+        &mut test_environment.litesvm,
+        vec![build_close_game_instruction(
+            referee.pubkey(),
+            game,
+            vec![alice, bob],
+        )],
+        &[&referee],
+        &referee.pubkey(),
+    );
+    assert!(result.is_ok(), "Closing the matched game should succeed");
+    assert_eq!(
+        test_environment.litesvm.get_balance(&alice).unwrap() - alice_lamports_before,
+        rent - rent / 2,
+        "Alice should get a share of the rent back"
+    );
+    assert_eq!(
+        test_environment.litesvm.get_balance(&bob).unwrap() - bob_lamports_before,
+        rent / 2,
+        "Bob should get a share of the rent back"
+    );
+
+    // Once the queue is empty, the referee can close it
+    test_environment.litesvm.expire_blockhash();
+    let result = send_transaction_from_instructions(
+        &mut test_environment.litesvm,
+        vec![close_queue_instruction],
+        &[&referee],
+        &referee.pubkey(),
+    );
+    assert!(result.is_ok(), "Closing the empty queue should succeed");
+    check_account_is_closed(&test_environment.litesvm, &queue, "The queue should be closed");
+}
+
+#[test]
+fn test_handicap_game_with_uneven_stakes() {
+    let mut test_environment = setup_escrow_test();
+    let referee = test_environment.mint_authority.insecure_clone();
+    let alice = test_environment.alice.pubkey();
+    let bob = test_environment.bob.pubkey();
+
+    // Alice is the stronger player, so lays 3 to 1 against Bob
+    let game_id = generate_offer_id();
+    let (game, _game_bump) = get_pda_and_bump(
+        &["game".into(), game_id.into()],
+        &test_environment.program_id,
+    );
+    let alice_stake = 3_000_000;
+    let bob_stake = 1_000_000;
+    let create_game_instruction = build_create_game_instruction(
+        game_id,
+        &[alice, bob],
+        0,
+        9_999_999_999,
+        0,
+        GameOptions {
+            stakes: Some(vec![alice_stake, bob_stake]),
+            ..Default::default()
+        },
+        CreateGameAccounts {
+            authority: referee.pubkey(),
+            system_program: anchor_lang::system_program::ID,
+            treasury: referee.pubkey(),
+            game,
+            token_accounts: None,
+        },
+    );
+    let result = send_transaction_from_instructions(
+        &mut test_environment.litesvm,
+        vec![create_game_instruction],
+        &[&referee],
+        &referee.pubkey(),
+    );
+    assert!(result.is_ok(), "Creating a game with uneven stakes should succeed");
+
+    let deposit_instruction = |player, amount| {
+        build_deposit_instruction(
+            amount,
+            DepositAccounts {
+                player,
+                system_program: anchor_lang::system_program::ID,
+                game,
+                token_accounts: None,
+                player_token_account: None,
+            },
+        )
+    };
+
+    // Each player must deposit their own stake
+    let result = send_transaction_from_instructions(
+        &mut test_environment.litesvm,
+        vec![deposit_instruction(bob, alice_stake)],
+        &[&test_environment.bob],
+        &bob,
+    );
+    assert!(result.is_err(), "Bob shouldn't be able to deposit Alice's stake");
+
+    let result = send_transaction_from_instructions(
+        &mut test_environment.litesvm,
+        vec![deposit_instruction(bob, bob_stake)],
+        &[&test_environment.bob],
+        &bob,
+    );
+    assert!(result.is_ok(), "Bob should be able to deposit the smaller stake");
+
+    let result = send_transaction_from_instructions(
+        &mut test_environment.litesvm,
+        vec![deposit_instruction(alice, alice_stake)],
+        &[&test_environment.alice],
+        &alice,
+    );
+    assert!(result.is_ok(), "Alice should be able to deposit the larger stake");
+
+    // Bob pulls off the upset and takes the whole pot
+    let bob_lamports_before = test_environment.litesvm.get_balance(&bob).unwrap();
+    let finalize_instruction = build_finalize_game_instruction(
+        &[2],
+        &[10_000],
+        FinalizeGameAccounts {
+            authority: referee.pubkey(),
+            treasury: referee.pubkey(),
+            system_program: anchor_lang::system_program::ID,
+            game,
+            token_accounts: None,
+            vote_record: None,
+            side_pool: None,
+            player_accounts: vec![alice, bob],
+            player_profiles: get_player_profiles(&[alice, bob]),
+            signing_referees: vec![],
+        },
+    );
+    let result = send_transaction_from_instructions(
+        &mut test_environment.litesvm,
+        vec![finalize_instruction],
+        &[&referee],
+        &referee.pubkey(),
+    );
+    assert!(result.is_ok(), "Finalizing the game should succeed");
+    assert_eq!(
+        test_environment.litesvm.get_balance(&bob).unwrap() - bob_lamports_before,
+        alice_stake + bob_stake,
+        "Bob should receive both stakes"
+    );
+}
+
+#[test]
+fn test_best_of_three_series() {
+    let mut test_environment = setup_escrow_test();
+    let referee = test_environment.mint_authority.insecure_clone();
+    let alice = test_environment.alice.pubkey();
+    let bob = test_environment.bob.pubkey();
+
+    let stake = 1_000_000;
+    let players = [
+        test_environment.alice.insecure_clone(),
+        test_environment.bob.insecure_clone(),
+    ];
+    let game = execute_create_and_deposit_game_with_options(
+        &mut test_environment,
+        generate_offer_id(),
+        &players,
         stake,
-        crate::escrow_test_helpers::DepositAccounts {
-            player: player_b,
+        9_999_999_999,
+        GameOptions {
+            wins_required: Some(2),
+            ..Default::default()
+        },
+    )
+    .unwrap();
+
+    // The referee can't skip the rounds and finalize the series directly
+    let finalize_instruction = build_finalize_game_instruction(
+        &[1],
+        &[10_000],
+        FinalizeGameAccounts {
+            authority: referee.pubkey(),
+            treasury: referee.pubkey(),
             system_program: anchor_lang::system_program::ID,
-            game: game_pda,
+            game,
+            token_accounts: None,
+            vote_record: None,
+            side_pool: None,
+            player_accounts: vec![alice, bob],
+            player_profiles: get_player_profiles(&[alice, bob]),
+            signing_referees: vec![],
         },
     );
-    let res = send_transaction_from_instructions(
-        &mut env.litesvm,
-        vec![deposit_b_ix],
-        &[&env.bob],
-        &player_b,
+    let result = send_transaction_from_instructions(
+        &mut test_environment.litesvm,
+        vec![finalize_instruction],
+        &[&referee],
+        &referee.pubkey(),
     );
-    assert!(res.is_ok(), "bob deposit should succeed");
+    assert!(result.is_err(), "Series shouldn't be finalized directly");
 
-    // Finalize: set Bob as winner (2)
-    let finalize_ix = crate::escrow_test_helpers::build_finalize_game_instruction(
-        2,
-        crate::escrow_test_helpers::FinalizeGameAccounts {
-            authority: authority.pubkey(),
-            winner_account: player_b,
+    let record_round_instruction = |winner, player_accounts, player_profiles| {
+        build_record_round_instruction(
+            winner,
+            RecordRoundAccounts {
+                authority: referee.pubkey(),
+                treasury: referee.pubkey(),
+                system_program: anchor_lang::system_program::ID,
+                game,
+                token_accounts: None,
+                player_accounts,
+                player_profiles,
+            },
+        )
+    };
+
+    // Alice and Bob win a round each
+    let result = send_transaction_from_instructions(
+        &mut test_environment.litesvm,
+        vec![
+            record_round_instruction(1, vec![], vec![]),
+            record_round_instruction(2, vec![], vec![]),
+        ],
+        &[&referee],
+        &referee.pubkey(),
+    );
+    assert!(result.is_ok(), "Recording the first two rounds should succeed");
+
+    let game_account = test_environment.litesvm.get_account(&game).unwrap();
+    let game_state =
+        crate::state::Game::try_deserialize(&mut game_account.data.as_slice()).unwrap();
+    assert_eq!(game_state.round_winners, vec![1, 2], "Both rounds should be recorded");
+
+    // Alice takes the deciding round, and with it the pot
+    let alice_lamports_before = test_environment.litesvm.get_balance(&alice).unwrap();
+    let result = send_transaction_from_instructions(
+        &mut test_environment.litesvm,
+        vec![record_round_instruction(
+            1,
+            vec![alice, bob],
+            get_player_profiles(&[alice, bob]),
+        )],
+        &[&referee],
+        &referee.pubkey(),
+    );
+    assert!(result.is_ok(), "Recording the deciding round should succeed");
+    assert_eq!(
+        test_environment.litesvm.get_balance(&alice).unwrap() - alice_lamports_before,
+        2 * stake,
+        "Alice should receive the pot"
+    );
+    assert_eq!(
+        get_game_winner(&test_environment.litesvm, &game),
+        1,
+        "The game should record Alice as the series winner"
+    );
+
+    // The series counts as one game on each player's profile
+    let profile_account = test_environment
+        .litesvm
+        .get_account(&get_player_profile(&alice))
+        .unwrap();
+    let alice_profile =
+        crate::state::PlayerProfile::try_deserialize(&mut profile_account.data.as_slice())
+            .unwrap();
+    assert_eq!(alice_profile.games_played, 1, "Alice should have played one game");
+    assert_eq!(alice_profile.wins, 1, "Alice should have one win");
+}
+
+#[test]
+fn test_finalize_updates_player_profiles() {
+    let mut test_environment = setup_escrow_test();
+    let referee = test_environment.mint_authority.insecure_clone();
+    let alice = test_environment.alice.pubkey();
+    let bob = test_environment.bob.pubkey();
+
+    // Depositing creates each player's profile
+    let stake = 1_000_000;
+    let players = [
+        test_environment.alice.insecure_clone(),
+        test_environment.bob.insecure_clone(),
+    ];
+    let game = execute_create_and_deposit_game(
+        &mut test_environment,
+        generate_offer_id(),
+        &players,
+        stake,
+        9_999_999_999,
+        0,
+        referee.pubkey(),
+    )
+    .unwrap();
+
+    let finalize_instruction = |player_profiles| {
+        build_finalize_game_instruction(
+            &[1],
+            &[10_000],
+            FinalizeGameAccounts {
+                authority: referee.pubkey(),
+                treasury: referee.pubkey(),
+                system_program: anchor_lang::system_program::ID,
+                game,
+                token_accounts: None,
+                vote_record: None,
+                side_pool: None,
+                player_accounts: vec![alice, bob],
+                player_profiles,
+                signing_referees: vec![],
+            },
+        )
+    };
+
+    // Profiles must be passed in slot order, so a result can't be credited to the wrong player
+    let result = send_transaction_from_instructions(
+        &mut test_environment.litesvm,
+        vec![finalize_instruction(get_player_profiles(&[bob, alice]))],
+        &[&referee],
+        &referee.pubkey(),
+    );
+    assert!(result.is_err(), "Finalizing with the profiles swapped should fail");
+
+    let result = send_transaction_from_instructions(
+        &mut test_environment.litesvm,
+        vec![finalize_instruction(get_player_profiles(&[alice, bob]))],
+        &[&referee],
+        &referee.pubkey(),
+    );
+    assert!(result.is_ok(), "Finalizing the game should succeed");
+
+    let get_profile = |player| {
+        let profile_account = test_environment
+            .litesvm
+            .get_account(&get_player_profile(&player))
+            .unwrap();
+        crate::state::PlayerProfile::try_deserialize(&mut profile_account.data.as_slice())
+            .unwrap()
+    };
+
+    // Evenly rated players swap 16 points
+    let alice_profile = get_profile(alice);
+    assert_eq!(alice_profile.games_played, 1, "Alice should have played one game");
+    assert_eq!(alice_profile.wins, 1, "Alice should have one win");
+    assert_eq!(alice_profile.total_wagered, stake, "Alice should have wagered the stake");
+    assert_eq!(alice_profile.net_profit, stake as i64, "Alice should have won Bob's stake");
+    assert_eq!(alice_profile.rating, 1_216, "Alice's rating should go up");
+
+    let bob_profile = get_profile(bob);
+    assert_eq!(bob_profile.games_played, 1, "Bob should have played one game");
+    assert_eq!(bob_profile.losses, 1, "Bob should have one loss");
+    assert_eq!(bob_profile.net_profit, -(stake as i64), "Bob should have lost the stake");
+    assert_eq!(bob_profile.rating, 1_184, "Bob's rating should go down");
+}
+
+#[test]
+fn test_side_bets_pay_backers_of_the_winner() {
+    let mut test_environment = setup_escrow_test();
+    let referee = test_environment.mint_authority.insecure_clone();
+    let alice = test_environment.alice.pubkey();
+    let bob = test_environment.bob.pubkey();
+    let big_alice_backer = Keypair::new();
+    let small_alice_backer = Keypair::new();
+    let bob_backer = Keypair::new();
+    for spectator in [&big_alice_backer, &small_alice_backer, &bob_backer] {
+        test_environment
+            .litesvm
+            .airdrop(&spectator.pubkey(), 1_000_000_000)
+            .unwrap();
+    }
+
+    let game_id = generate_offer_id();
+    let (game, _game_bump) = get_pda_and_bump(
+        &["game".into(), game_id.into()],
+        &test_environment.program_id,
+    );
+    let stake = 1_000_000;
+    let create_game_instruction = build_create_game_instruction(
+        game_id,
+        &[alice, bob],
+        stake,
+        9_999_999_999,
+        0,
+        GameOptions::default(),
+        CreateGameAccounts {
+            authority: referee.pubkey(),
             system_program: anchor_lang::system_program::ID,
-            game: game_pda,
+            treasury: referee.pubkey(),
+            game,
+            token_accounts: None,
         },
     );
-    let res = send_transaction_from_instructions(
-        &mut env.litesvm,
-        vec![finalize_ix],
-        &[&authority],
-        &authority.pubkey(),
+    let result = send_transaction_from_instructions(
+        &mut test_environment.litesvm,
+        vec![create_game_instruction],
+        &[&referee],
+        &referee.pubkey(),
     );
-    assert!(res.is_ok(), "finalize_game should succeed");
+    assert!(result.is_ok(), "Creating the game should succeed");
+
+    let game_nonce = get_game_nonce(&test_environment.litesvm, &game);
+    let side_bet_accounts = |bettor| SideBetAccounts {
+        bettor,
+        system_program: anchor_lang::system_program::ID,
+        game,
+        game_nonce,
+    };
+
+    // Spectators back Alice with 3_000_000 and 1_000_000 lamports, and Bob with 2_000_000
+    for (spectator, slot, amount) in [
+        (&big_alice_backer, 1, 3_000_000),
+        (&small_alice_backer, 1, 1_000_000),
+        (&bob_backer, 2, 2_000_000),
+    ] {
+        let result = send_transaction_from_instructions(
+            &mut test_environment.litesvm,
+            vec![build_place_side_bet_instruction(
+                slot,
+                amount,
+                side_bet_accounts(spectator.pubkey()),
+            )],
+            &[spectator],
+            &spectator.pubkey(),
+        );
+        assert!(result.is_ok(), "Placing a side bet should succeed");
+    }
+
+    // Nobody can back the other player after betting on one
+    let result = send_transaction_from_instructions(
+        &mut test_environment.litesvm,
+        vec![build_place_side_bet_instruction(
+            2,
+            1_000_000,
+            side_bet_accounts(big_alice_backer.pubkey()),
+        )],
+        &[&big_alice_backer],
+        &big_alice_backer.pubkey(),
+    );
+    assert!(result.is_err(), "Backing both players should fail");
+
+    for player in [&test_environment.alice, &test_environment.bob] {
+        let deposit_instruction = build_deposit_instruction(
+            stake,
+            DepositAccounts {
+                player: player.pubkey(),
+                system_program: anchor_lang::system_program::ID,
+                game,
+                token_accounts: None,
+                player_token_account: None,
+            },
+        );
+        let result = send_transaction_from_instructions(
+            &mut test_environment.litesvm,
+            vec![deposit_instruction],
+            &[player],
+            &player.pubkey(),
+        );
+        assert!(result.is_ok(), "Depositing should succeed");
+    }
+
+    // Betting closes once the game starts
+    let result = send_transaction_from_instructions(
+        &mut test_environment.litesvm,
+        vec![build_place_side_bet_instruction(
+            1,
+            500_000,
+            side_bet_accounts(small_alice_backer.pubkey()),
+        )],
+        &[&small_alice_backer],
+        &small_alice_backer.pubkey(),
+    );
+    assert!(result.is_err(), "Betting after every player deposited should fail");
+
+    // The side pool has to be settled along with the game
+    let (side_pool, _side_bet) =
+        get_side_bet_accounts(&game, game_nonce, &big_alice_backer.pubkey());
+    let finalize_instruction = |side_pool| {
+        build_finalize_game_instruction(
+            &[1],
+            &[10_000],
+            FinalizeGameAccounts {
+                authority: referee.pubkey(),
+                treasury: referee.pubkey(),
+                system_program: anchor_lang::system_program::ID,
+                game,
+                token_accounts: None,
+                vote_record: None,
+                side_pool,
+                player_accounts: vec![alice, bob],
+                player_profiles: get_player_profiles(&[alice, bob]),
+                signing_referees: vec![],
+            },
+        )
+    };
+    let result = send_transaction_from_instructions(
+        &mut test_environment.litesvm,
+        vec![finalize_instruction(None)],
+        &[&referee],
+        &referee.pubkey(),
+    );
+    assert!(result.is_err(), "Finalizing without the side pool should fail");
+
+    let result = send_transaction_from_instructions(
+        &mut test_environment.litesvm,
+        vec![finalize_instruction(Some(side_pool))],
+        &[&referee],
+        &referee.pubkey(),
+    );
+    assert!(result.is_ok(), "Finalizing with the side pool should succeed");
 
-    // Check that the game account is closed
+    // Alice won, so Alice's backers split the 6_000_000 lamport pool 3 to 1. The referee
+    // pays the fees, so each bettor's balance only changes by their payout and the
+    // rent back from their closed bet. The first bettor paid for the pool, so gets its rent
+    // back when the last bet is claimed
+    for (spectator, payout) in [
+        (&big_alice_backer, 4_500_000),
+        (&small_alice_backer, 1_500_000),
+        (&bob_backer, 0),
+    ] {
+        let (_side_pool, side_bet) = get_side_bet_accounts(&game, game_nonce, &spectator.pubkey());
+        let side_bet_rent = test_environment.litesvm.get_balance(&side_bet).unwrap();
+        let lamports_before = test_environment.litesvm.get_balance(&spectator.pubkey()).unwrap();
+        let result = send_transaction_from_instructions(
+            &mut test_environment.litesvm,
+            vec![build_claim_side_bet_instruction(
+                side_bet_accounts(spectator.pubkey()),
+                big_alice_backer.pubkey(),
+            )],
+            &[spectator, &referee],
+            &referee.pubkey(),
+        );
+        assert!(result.is_ok(), "Claiming a side bet should succeed");
+        assert_eq!(
+            test_environment.litesvm.get_balance(&spectator.pubkey()).unwrap() - lamports_before,
+            payout + side_bet_rent,
+            "Each bettor should be paid their share of the pool"
+        );
+        check_account_is_closed(
+            &test_environment.litesvm,
+            &side_bet,
+            "The side bet should be closed once claimed",
+        );
+    }
     check_account_is_closed(
-        &env.litesvm,
-        &game_pda,
-        "Game account should be closed after finalize",
+        &test_environment.litesvm,
+        &side_pool,
+        "The side pool should be closed once every bet is claimed",
+    );
+}
+
+#[test]
+fn test_side_bets_refunded_when_game_cancelled() {
+    let mut test_environment = setup_escrow_test();
+    let referee = test_environment.mint_authority.insecure_clone();
+    let alice = test_environment.alice.pubkey();
+    let bob = test_environment.bob.pubkey();
+    let alice_backer = Keypair::new();
+    let bob_backer = Keypair::new();
+    for spectator in [&alice_backer, &bob_backer] {
+        test_environment
+            .litesvm
+            .airdrop(&spectator.pubkey(), 1_000_000_000)
+            .unwrap();
+    }
+
+    let game_id = generate_offer_id();
+    let (game, _game_bump) = get_pda_and_bump(
+        &["game".into(), game_id.into()],
+        &test_environment.program_id,
+    );
+    let expiry_ts = get_unix_timestamp(&test_environment.litesvm) + 60;
+    let create_game_instruction = build_create_game_instruction(
+        game_id,
+        &[alice, bob],
+        1_000_000,
+        expiry_ts,
+        0,
+        GameOptions::default(),
+        CreateGameAccounts {
+            authority: referee.pubkey(),
+            system_program: anchor_lang::system_program::ID,
+            treasury: referee.pubkey(),
+            game,
+            token_accounts: None,
+        },
+    );
+    let result = send_transaction_from_instructions(
+        &mut test_environment.litesvm,
+        vec![create_game_instruction],
+        &[&referee],
+        &referee.pubkey(),
+    );
+    assert!(result.is_ok(), "Creating the game should succeed");
+
+    let game_nonce = get_game_nonce(&test_environment.litesvm, &game);
+    let side_bet_accounts = |bettor| SideBetAccounts {
+        bettor,
+        system_program: anchor_lang::system_program::ID,
+        game,
+        game_nonce,
+    };
+
+    // Spectators back Alice with 3_000_000 lamports and Bob with 2_000_000
+    for (spectator, slot, amount) in [(&alice_backer, 1, 3_000_000), (&bob_backer, 2, 2_000_000)] {
+        let result = send_transaction_from_instructions(
+            &mut test_environment.litesvm,
+            vec![build_place_side_bet_instruction(
+                slot,
+                amount,
+                side_bet_accounts(spectator.pubkey()),
+            )],
+            &[spectator],
+            &spectator.pubkey(),
+        );
+        assert!(result.is_ok(), "Placing a side bet should succeed");
+    }
+
+    // Nobody deposits, so the game is cancelled once it expires, settling the side pool
+    warp_to_unix_timestamp(&mut test_environment.litesvm, expiry_ts + 1);
+    let (side_pool, _side_bet) = get_side_bet_accounts(&game, game_nonce, &alice_backer.pubkey());
+    let cancel_instruction = |side_pool| {
+        build_cancel_game_instruction(CancelGameAccounts {
+            caller: referee.pubkey(),
+            system_program: anchor_lang::system_program::ID,
+            game,
+            token_accounts: None,
+            challenger: None,
+            side_pool,
+            player_accounts: vec![alice, bob],
+        })
+    };
+    let result = send_transaction_from_instructions(
+        &mut test_environment.litesvm,
+        vec![cancel_instruction(None)],
+        &[&referee],
+        &referee.pubkey(),
+    );
+    assert!(
+        result.is_err(),
+        "Cancelling without the side pool should fail"
+    );
+
+    let result = send_transaction_from_instructions(
+        &mut test_environment.litesvm,
+        vec![cancel_instruction(Some(side_pool))],
+        &[&referee],
+        &referee.pubkey(),
+    );
+    assert!(
+        result.is_ok(),
+        "Cancelling with the side pool should succeed"
+    );
+
+    // Every bettor gets their bet back, along with the rent from their closed bet
+    for (spectator, amount) in [(&alice_backer, 3_000_000), (&bob_backer, 2_000_000)] {
+        let (_side_pool, side_bet) = get_side_bet_accounts(&game, game_nonce, &spectator.pubkey());
+        let side_bet_rent = test_environment.litesvm.get_balance(&side_bet).unwrap();
+        let lamports_before = test_environment
+            .litesvm
+            .get_balance(&spectator.pubkey())
+            .unwrap();
+        let result = send_transaction_from_instructions(
+            &mut test_environment.litesvm,
+            vec![build_claim_side_bet_instruction(
+                side_bet_accounts(spectator.pubkey()),
+                alice_backer.pubkey(),
+            )],
+            &[spectator, &referee],
+            &referee.pubkey(),
+        );
+        assert!(result.is_ok(), "Claiming a side bet should succeed");
+        assert_eq!(
+            test_environment
+                .litesvm
+                .get_balance(&spectator.pubkey())
+                .unwrap()
+                - lamports_before,
+            amount + side_bet_rent,
+            "Each bettor should get their bet back"
+        );
+    }
+    check_account_is_closed(
+        &test_environment.litesvm,
+        &side_pool,
+        "The side pool should be closed once every bet is claimed",
     );
 }
@@ -26,6 +26,7 @@ export async function createTestOffer(params: {
   tokenMintA: Address;
   tokenMintB: Address;
   makerTokenAccountA: Address;
+  proceedsAccount: Address;
   tokenAOfferedAmount: bigint;
   tokenBWantedAmount: bigint;
   offerId?: bigint;
@@ -36,6 +37,7 @@ export async function createTestOffer(params: {
     tokenMintA,
     tokenMintB,
     makerTokenAccountA,
+    proceedsAccount,
     tokenAOfferedAmount,
     tokenBWantedAmount,
     offerId = getRandomBigInt(),
@@ -46,10 +48,12 @@ export async function createTestOffer(params: {
   const vault = await connection.getTokenAccountAddress(offer, tokenMintA, true);
 
   const makeOfferInstruction = await programClient.getMakeOfferInstructionAsync({
+    // With no rentPayer the maker pays their own rent, but a separate signer could sponsor it
     maker,
     tokenMintA,
     tokenMintB,
     makerTokenAccountA,
+    proceedsAccount,
     offer,
     vault,
     id: offerId,
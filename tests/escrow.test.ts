@@ -7,8 +7,7 @@ import { type KeyPairSigner, type Address } from "@solana/kit";
 import { createTestOffer, getRandomBigInt, ONE_SOL } from "./escrow.test-helpers";
 
 const INSUFFICIENT_FUNDS_ERROR = "TokenzQdBNbLqP5VEhdkAS6EPFLC1PHnBqCXEpPxuEb.TransferChecked: insufficient funds";
-const REFUND_OFFER_ERROR =
-  "8jR5GeNzeweq35Uo84kGP3v1NcBaZWH5u62k7PxN4T2y.RefundOffer: A has one constraint was violated";
+const NOT_OFFER_OWNER_ERROR = "custom program error: #6017";
 const ACCOUNT_IN_USE_ERROR = "11111111111111111111111111111111.Allocate: account already in use";
 const INVALID_TOKEN_MINT_ERROR = "custom program error: #6002";
 const INVALID_AMOUNT_ERROR = "custom program error: #6003";
@@ -23,6 +22,7 @@ describe("Escrow", () => {
   let aliceTokenAccountA: Address;
   let bobTokenAccountA: Address;
   let aliceTokenAccountB: Address;
+  let bobTokenAccountB: Address;
 
   const tokenDecimals = 9;
 
@@ -35,6 +35,8 @@ describe("Escrow", () => {
   const bobInitialTokenAAmount = 1n;
   // Bob has 1 token of token B he will offer in exchange
   const bobInitialTokenBAmount = 1n * TOKEN;
+  // Offers send token B to a proceeds account that must already exist, so we give Alice a tiny amount (1 minor unit) of token B
+  const aliceInitialTokenBAmount = 1n;
 
   // Alice will offer 1 token of token A in exchange for 1 token of token B
   const tokenAOfferedAmount = 1n * TOKEN;
@@ -75,11 +77,13 @@ describe("Escrow", () => {
     await connection.mintTokens(tokenMintA, user, aliceInitialTokenAAmount, alice.address);
     await connection.mintTokens(tokenMintA, user, bobInitialTokenAAmount, bob.address);
     await connection.mintTokens(tokenMintB, user, bobInitialTokenBAmount, bob.address);
+    await connection.mintTokens(tokenMintB, user, aliceInitialTokenBAmount, alice.address);
 
     // Get the token accounts for alice and bob
     aliceTokenAccountA = await connection.getTokenAccountAddress(alice.address, tokenMintA, true);
     bobTokenAccountA = await connection.getTokenAccountAddress(bob.address, tokenMintA, true);
     aliceTokenAccountB = await connection.getTokenAccountAddress(alice.address, tokenMintB, true);
+    bobTokenAccountB = await connection.getTokenAccountAddress(bob.address, tokenMintB, true);
   });
 
   describe("makeOffer", () => {
@@ -90,6 +94,7 @@ describe("Escrow", () => {
         tokenMintA,
         tokenMintB,
         makerTokenAccountA: aliceTokenAccountA,
+        proceedsAccount: aliceTokenAccountB,
         tokenAOfferedAmount,
         tokenBWantedAmount,
       });
@@ -112,6 +117,7 @@ describe("Escrow", () => {
         tokenMintA,
         tokenMintB,
         makerTokenAccountA: aliceTokenAccountA,
+        proceedsAccount: aliceTokenAccountB,
         tokenAOfferedAmount,
         tokenBWantedAmount,
         offerId,
@@ -130,6 +136,7 @@ describe("Escrow", () => {
           tokenMintA,
           tokenMintB,
           makerTokenAccountA: bobTokenAccountA,
+          proceedsAccount: bobTokenAccountB,
           tokenAOfferedAmount: bobInitialTokenAAmount,
           tokenBWantedAmount,
           offerId, // Reusing the same offer ID
@@ -150,6 +157,7 @@ describe("Escrow", () => {
           tokenMintA,
           tokenMintB,
           makerTokenAccountA: aliceTokenAccountA,
+          proceedsAccount: aliceTokenAccountB,
           tokenAOfferedAmount: tooManyTokens,
           tokenBWantedAmount,
         });
@@ -171,6 +179,7 @@ describe("Escrow", () => {
           tokenMintA,
           tokenMintB: tokenMintA, // Using same mint
           makerTokenAccountA: aliceTokenAccountA,
+          proceedsAccount: aliceTokenAccountA, // Proceeds account must match tokenMintB
           tokenAOfferedAmount,
           tokenBWantedAmount,
         });
@@ -192,6 +201,7 @@ describe("Escrow", () => {
           tokenMintA,
           tokenMintB,
           makerTokenAccountA: aliceTokenAccountA,
+          proceedsAccount: aliceTokenAccountB,
           tokenAOfferedAmount,
           tokenBWantedAmount: 0n,
         });
@@ -210,6 +220,7 @@ describe("Escrow", () => {
           tokenMintA,
           tokenMintB,
           makerTokenAccountA: aliceTokenAccountA,
+          proceedsAccount: aliceTokenAccountB,
           tokenAOfferedAmount: 0n,
           tokenBWantedAmount,
         });
@@ -282,6 +293,7 @@ describe("Escrow", () => {
         tokenMintA,
         tokenMintB,
         makerTokenAccountA: aliceTokenAccountA,
+        proceedsAccount: aliceTokenAccountB,
         tokenAOfferedAmount,
         tokenBWantedAmount,
       });
@@ -293,10 +305,12 @@ describe("Escrow", () => {
       const takeOfferInstruction = await programClient.getTakeOfferInstructionAsync({
         taker: bob,
         maker: alice.address,
+        rentPayer: alice.address,
         tokenMintA,
         tokenMintB,
-        takerTokenAccountA: bobTokenAccountA,
-        makerTokenAccountB: aliceTokenAccountB,
+        recipient: bobTokenAccountA,
+        proceedsAccount: aliceTokenAccountB,
+        surplusAccount: aliceTokenAccountA,
         offer: testOffer,
         vault: testVault,
         tokenProgram: TOKEN_EXTENSIONS_PROGRAM,
@@ -324,7 +338,11 @@ describe("Escrow", () => {
         mint: tokenMintB,
         useTokenExtensions: true,
       });
-      assert.equal(aliceTokenBBalance.amount, tokenBWantedAmount, "Alice's token B balance should match wanted amount");
+      assert.equal(
+        aliceTokenBBalance.amount,
+        aliceInitialTokenBAmount + tokenBWantedAmount,
+        "Alice's token B balance should be initial + wanted amount",
+      );
     });
 
     test("fails when taker has insufficient token balance", async () => {
@@ -336,6 +354,7 @@ describe("Escrow", () => {
         tokenMintA,
         tokenMintB,
         makerTokenAccountA: aliceTokenAccountA,
+        proceedsAccount: aliceTokenAccountB,
         tokenAOfferedAmount,
         tokenBWantedAmount: largeTokenBAmount,
       });
@@ -343,10 +362,12 @@ describe("Escrow", () => {
       const takeOfferInstruction = await programClient.getTakeOfferInstructionAsync({
         taker: bob,
         maker: alice.address,
+        rentPayer: alice.address,
         tokenMintA,
         tokenMintB,
-        takerTokenAccountA: bobTokenAccountA,
-        makerTokenAccountB: aliceTokenAccountB,
+        recipient: bobTokenAccountA,
+        proceedsAccount: aliceTokenAccountB,
+        surplusAccount: aliceTokenAccountA,
         offer,
         vault,
         tokenProgram: TOKEN_EXTENSIONS_PROGRAM,
@@ -379,6 +400,7 @@ describe("Escrow", () => {
         tokenMintA,
         tokenMintB,
         makerTokenAccountA: aliceTokenAccountA,
+        proceedsAccount: aliceTokenAccountB,
         tokenAOfferedAmount,
         tokenBWantedAmount,
       });
@@ -394,9 +416,11 @@ describe("Escrow", () => {
       });
 
       const refundOfferInstruction = await programClient.getRefundOfferInstructionAsync({
-        maker: alice,
+        owner: alice,
+        rentPayer: alice.address,
         tokenMintA,
-        makerTokenAccountA: aliceTokenAccountA,
+        ownerTokenAccountA: aliceTokenAccountA,
+        surplusAccount: aliceTokenAccountA,
         offer: testOffer,
         vault: testVault,
         tokenProgram: TOKEN_EXTENSIONS_PROGRAM,
@@ -433,14 +457,17 @@ describe("Escrow", () => {
         tokenMintA,
         tokenMintB,
         makerTokenAccountA: aliceTokenAccountA,
+        proceedsAccount: aliceTokenAccountB,
         tokenAOfferedAmount,
         tokenBWantedAmount,
       });
 
       const refundOfferInstruction = await programClient.getRefundOfferInstructionAsync({
-        maker: bob,
+        owner: bob,
+        rentPayer: alice.address,
         tokenMintA,
-        makerTokenAccountA: bobTokenAccountA,
+        ownerTokenAccountA: bobTokenAccountA,
+        surplusAccount: aliceTokenAccountA,
         offer,
         vault,
         tokenProgram: TOKEN_EXTENSIONS_PROGRAM,
@@ -454,8 +481,75 @@ describe("Escrow", () => {
         assert.fail("Expected the refund to fail but it succeeded");
       } catch (thrownObject) {
         const error = thrownObject as ErrorWithTransaction;
-        assert.equal(error.message, REFUND_OFFER_ERROR, "Expected refund offer error");
+        assert(
+          error.message.includes(NOT_OFFER_OWNER_ERROR),
+          `Expected NotOfferOwner error but got: ${error.message}`,
+        );
       }
     });
   });
+
+  describe("lamport streams", () => {
+    const streamedAmount = ONE_SOL / 10n;
+
+    test("pays the recipient what has accrued, then refunds the rest when cancelled", async () => {
+      // The stream started a while ago, so part of it has already accrued to Bob
+      const now = BigInt(Math.floor(Date.now() / 1000));
+      const streamId = getRandomBigInt();
+      const { pda: stream } = await connection.getPDAAndBump(programClient.ESCROW_PROGRAM_ADDRESS, [
+        "stream",
+        streamId,
+      ]);
+
+      // Lamport streams leave every token account out
+      const createStreamInstruction = programClient.getCreateStreamInstruction({
+        sender: alice,
+        stream,
+        id: streamId,
+        recipient: bob.address,
+        amount: streamedAmount,
+        startTs: now - 1_000n,
+        endTs: now + 1_000n,
+      });
+      await connection.sendTransactionFromInstructions({
+        feePayer: alice,
+        instructions: [createStreamInstruction],
+      });
+
+      // Alice pays the fees, so Bob's balance only changes by what the stream pays
+      const bobBalanceBefore = await connection.getLamportBalance(bob.address);
+      const withdrawStreamInstruction = programClient.getWithdrawStreamInstruction({
+        recipient: bob,
+        stream,
+      });
+      await connection.sendTransactionFromInstructions({
+        feePayer: alice,
+        instructions: [withdrawStreamInstruction],
+      });
+      const bobBalanceAfterWithdrawal = await connection.getLamportBalance(bob.address);
+      assert.ok(
+        bobBalanceAfterWithdrawal > bobBalanceBefore,
+        "Bob should be paid what has accrued",
+      );
+
+      const cancelStreamInstruction = programClient.getCancelStreamInstruction({
+        caller: alice,
+        sender: alice.address,
+        recipient: bob.address,
+        stream,
+      });
+      await connection.sendTransactionFromInstructions({
+        feePayer: alice,
+        instructions: [cancelStreamInstruction],
+      });
+
+      const { value: streamAccount } = await connection.rpc.getAccountInfo(stream).send();
+      assert.equal(streamAccount, null, "Stream account should be closed after being cancelled");
+      const bobBalanceAfterCancel = await connection.getLamportBalance(bob.address);
+      assert.ok(
+        bobBalanceAfterCancel - bobBalanceBefore < streamedAmount,
+        "Bob should only be paid what accrued, with the rest refunded to Alice",
+      );
+    });
+  });
 });